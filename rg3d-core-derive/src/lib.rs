@@ -1,3 +1,4 @@
+mod reflect;
 mod visit;
 
 use proc_macro::TokenStream;
@@ -11,3 +12,12 @@ pub fn visit(input: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
     TokenStream::from(visit::impl_visit(ast))
 }
+
+/// Implements `Reflect` trait
+///
+/// User has to import `Reflect`.
+#[proc_macro_derive(Reflect, attributes(reflect))]
+pub fn reflect(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    TokenStream::from(reflect::impl_reflect(ast))
+}
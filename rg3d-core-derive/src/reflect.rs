@@ -0,0 +1,294 @@
+mod args;
+
+use std::collections::HashSet;
+
+use darling::*;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::*;
+use syn::*;
+
+// impl `#[derive(Reflect)]` for `struct` or `enum`
+pub fn impl_reflect(ast: DeriveInput) -> TokenStream2 {
+    let args = args::TypeArgs::from_derive_input(&ast).unwrap();
+    match &args.data {
+        ast::Data::Struct(ref field_args) => self::impl_reflect_struct(&args, field_args),
+        ast::Data::Enum(ref variants) => self::impl_reflect_enum(&args, variants),
+    }
+}
+
+fn create_generics<'a>(
+    generics: &Generics,
+    field_args: impl Iterator<Item = &'a args::FieldArgs>,
+) -> Generics {
+    let mut generics = generics.clone();
+
+    let type_params = generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect::<Vec<_>>();
+
+    let where_clause = generics.make_where_clause();
+
+    // `Reflect` requires `Any`
+    where_clause.predicates.extend(
+        type_params
+            .iter()
+            .map::<WherePredicate, _>(|ident| parse_quote! { #ident: 'static }),
+    );
+
+    // Add where clause for every reflected field of generic types only, otherwise recursive types
+    // like `struct Node { children: Vec<Node> }` would overflow trait resolution.
+    if type_params.is_empty() {
+        return generics;
+    }
+    where_clause.predicates.extend(
+        field_args
+            .filter(|f| !f.skip)
+            .map(|f| &f.ty)
+            .map::<WherePredicate, _>(|ty| parse_quote! { #ty: Reflect }),
+    );
+
+    generics
+}
+
+/// Name of each reflected field along with its binding: `self.field` for structs or
+/// `field`/`f0` for bound enum variants.
+fn reflected_fields(
+    fields: &ast::Fields<args::FieldArgs>,
+    is_self: bool,
+) -> Vec<(TokenStream2, String)> {
+    let reflected = fields
+        .iter()
+        .enumerate()
+        .filter(|(_, field)| !field.skip)
+        .map(|(field_index, field)| {
+            let (binding, name) = match fields.style {
+                // `NamedFields { a: f32, .. }`
+                ast::Style::Struct => {
+                    let ident = field.ident.as_ref().unwrap_or_else(|| unreachable!());
+                    let binding = if is_self {
+                        quote!(self.#ident)
+                    } else {
+                        quote!(#ident)
+                    };
+                    (binding, ident.to_string())
+                }
+                // `Tuple(f32, ..)`
+                ast::Style::Tuple => {
+                    let binding = if is_self {
+                        let index = Index::from(field_index);
+                        quote!(self.#index)
+                    } else {
+                        let ident = format_ident!("f{}", field_index);
+                        quote!(#ident)
+                    };
+                    (binding, field_index.to_string())
+                }
+                ast::Style::Unit => unreachable!(),
+            };
+
+            let name = match &field.rename {
+                Some(new_name) => {
+                    assert!(
+                        !new_name.is_empty(),
+                        "renaming to empty string doesn't make sense!"
+                    );
+                    new_name.clone()
+                }
+                None => name,
+            };
+
+            (binding, name)
+        })
+        .collect::<Vec<_>>();
+
+    let mut no_dup = HashSet::new();
+    for (_, name) in reflected.iter() {
+        if !no_dup.insert(name) {
+            panic!("duplicate reflected names detected!");
+        }
+    }
+
+    reflected
+}
+
+/// `vec![("name", &binding as &dyn Reflect), ..]` and its mutable counterpart
+fn create_field_lists(
+    fields: &[(TokenStream2, String)],
+    is_self: bool,
+) -> (TokenStream2, TokenStream2) {
+    let (bindings, names): (Vec<_>, Vec<_>) = fields.iter().cloned().unzip();
+
+    if is_self {
+        (
+            quote! { vec![#((#names, &#bindings as &dyn Reflect)),*] },
+            quote! { vec![#((#names, &mut #bindings as &mut dyn Reflect)),*] },
+        )
+    } else {
+        // Bindings of enum variants are already references.
+        (
+            quote! { vec![#((#names, #bindings as &dyn Reflect)),*] },
+            quote! { vec![#((#names, #bindings as &mut dyn Reflect)),*] },
+        )
+    }
+}
+
+/// `fn as_any(&self) -> &dyn Any { self }` and friends, same for every type
+fn create_casts() -> TokenStream2 {
+    quote! {
+        fn as_any(&self) -> &dyn ::std::any::Any {
+            self
+        }
+
+        fn as_any_mut(&mut self) -> &mut dyn ::std::any::Any {
+            self
+        }
+
+        fn as_reflect(&self) -> &dyn Reflect {
+            self
+        }
+
+        fn as_reflect_mut(&mut self) -> &mut dyn Reflect {
+            self
+        }
+    }
+}
+
+/// impl `Reflect` for `struct`
+fn impl_reflect_struct(
+    args: &args::TypeArgs,
+    field_args: &ast::Fields<args::FieldArgs>,
+) -> TokenStream2 {
+    let ty_ident = &args.ident;
+    let generics = self::create_generics(&args.generics, field_args.iter());
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let casts = self::create_casts();
+
+    let field_fns = if field_args.style == ast::Style::Unit {
+        quote! {}
+    } else {
+        let fields = self::reflected_fields(field_args, true);
+        let (fields, fields_mut) = self::create_field_lists(&fields, true);
+
+        quote! {
+            fn fields(&self) -> Vec<(&'static str, &dyn Reflect)> {
+                #fields
+            }
+
+            fn fields_mut(&mut self) -> Vec<(&'static str, &mut dyn Reflect)> {
+                #fields_mut
+            }
+        }
+    };
+
+    quote! {
+        impl #impl_generics Reflect for #ty_ident #ty_generics #where_clause {
+            #casts
+
+            #field_fns
+        }
+    }
+}
+
+/// impl `Reflect` for `enum`
+fn impl_reflect_enum(args: &args::TypeArgs, variants: &[args::VariantArgs]) -> TokenStream2 {
+    let ty_ident = &args.ident;
+
+    let generics = self::create_generics(
+        &args.generics,
+        variants.iter().flat_map(|variant| variant.fields.iter()),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let casts = self::create_casts();
+
+    let variant_names = variants
+        .iter()
+        .map(|variant| variant.ident.to_string())
+        .collect::<Vec<_>>();
+
+    // `Type::Variant { .. } => "Variant",`
+    let name_matchers = variants
+        .iter()
+        .zip(variant_names.iter())
+        .map(|(variant, name)| {
+            let variant_ident = &variant.ident;
+            match variant.fields.style {
+                ast::Style::Struct => quote! { #ty_ident::#variant_ident { .. } => #name, },
+                ast::Style::Tuple => quote! { #ty_ident::#variant_ident(..) => #name, },
+                ast::Style::Unit => quote! { #ty_ident::#variant_ident => #name, },
+            }
+        });
+
+    // `Type::Variant { a, .. } => vec![("a", a as &dyn Reflect)],`
+    let (field_matchers, field_mut_matchers): (Vec<_>, Vec<_>) = variants
+        .iter()
+        .map(|variant| {
+            let variant_ident = &variant.ident;
+            let fields = &variant.fields;
+
+            let pattern = match fields.style {
+                ast::Style::Struct => {
+                    let idents = fields
+                        .iter()
+                        .filter(|field| !field.skip)
+                        .map(|field| field.ident.as_ref().unwrap_or_else(|| unreachable!()));
+                    quote! { #ty_ident::#variant_ident { #(#idents,)* .. } }
+                }
+                ast::Style::Tuple => {
+                    let idents = fields.iter().enumerate().map(|(i, field)| {
+                        if field.skip {
+                            quote!(_)
+                        } else {
+                            let ident = format_ident!("f{}", i);
+                            quote!(#ident)
+                        }
+                    });
+                    quote! { #ty_ident::#variant_ident(#(#idents),*) }
+                }
+                ast::Style::Unit => quote! { #ty_ident::#variant_ident },
+            };
+
+            let (fields, fields_mut) = if fields.style == ast::Style::Unit {
+                (quote!(Vec::new()), quote!(Vec::new()))
+            } else {
+                let reflected = self::reflected_fields(fields, false);
+                self::create_field_lists(&reflected, false)
+            };
+
+            (
+                quote! { #pattern => #fields, },
+                quote! { #pattern => #fields_mut, },
+            )
+        })
+        .unzip();
+
+    quote! {
+        impl #impl_generics Reflect for #ty_ident #ty_generics #where_clause {
+            #casts
+
+            fn fields(&self) -> Vec<(&'static str, &dyn Reflect)> {
+                match self {
+                    #(#field_matchers)*
+                }
+            }
+
+            fn fields_mut(&mut self) -> Vec<(&'static str, &mut dyn Reflect)> {
+                match self {
+                    #(#field_mut_matchers)*
+                }
+            }
+
+            fn variant_name(&self) -> Option<&'static str> {
+                Some(match self {
+                    #(#name_matchers)*
+                })
+            }
+
+            fn variant_names(&self) -> &'static [&'static str] {
+                &[#(#variant_names),*]
+            }
+        }
+    }
+}
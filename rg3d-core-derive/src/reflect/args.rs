@@ -0,0 +1,33 @@
+use darling::*;
+use syn::*;
+
+#[derive(FromDeriveInput)]
+#[darling(attributes(reflect), supports(struct_any, enum_any))]
+pub struct TypeArgs {
+    pub ident: Ident,
+    pub generics: Generics,
+    pub data: ast::Data<VariantArgs, FieldArgs>,
+}
+
+/// Parsed from struct's or enum variant's field
+#[derive(FromField, Clone)]
+#[darling(attributes(reflect))]
+pub struct FieldArgs {
+    pub ident: Option<Ident>,
+    pub ty: Type,
+    // ---
+    /// `#[reflect(skip)]`: hide the field from reflection
+    #[darling(default)]
+    pub skip: bool,
+
+    /// `#[reflect(rename = "..")]`: expose the field with this name
+    #[darling(default)]
+    pub rename: Option<String>,
+}
+
+#[derive(FromVariant)]
+#[darling(attributes(reflect))]
+pub struct VariantArgs {
+    pub ident: Ident,
+    pub fields: ast::Fields<FieldArgs>,
+}
@@ -34,10 +34,41 @@ fn create_generics(
     generics
 }
 
+/// `visitor.enter_region(name)?;` or versioned variant of it, which also defines `__version` and
+/// `__outdated` locals used by field visits and migration.
+fn create_enter_region(args: &args::TypeArgs) -> TokenStream2 {
+    match args.version {
+        Some(version) => quote! {
+            let __version = visitor.enter_region_with_version(name, #version)?;
+            let __outdated = __version < #version;
+        },
+        None => quote! {
+            visitor.enter_region(name)?;
+        },
+    }
+}
+
+/// `if __outdated { migrate(self, __version, visitor)?; }`
+fn create_migration(args: &args::TypeArgs) -> TokenStream2 {
+    match (args.version, &args.migrate) {
+        (Some(_), Some(migrate)) => quote! {
+            if __outdated {
+                #migrate(self, __version, visitor)?;
+            }
+        },
+        (None, Some(_)) => panic!("`migrate` requires `version` to be specified!"),
+        _ => quote! {},
+    }
+}
+
 /// `<prefix>field.visit("name", visitor);`
 fn create_field_visits<'a>(
     // None or `f` when bindings tuple variants. NOTE: We can't use `prefix: Ident`
     prefix: Option<Ident>,
+    // true when visiting fields of a struct through `self`
+    is_self: bool,
+    // true when the type has `#[visit(version = ..)]`, fields of outdated regions may be missing
+    versioned: bool,
     fields: impl Iterator<Item = &'a args::FieldArgs>,
     field_style: ast::Style,
 ) -> Vec<TokenStream2> {
@@ -55,11 +86,15 @@ fn create_field_visits<'a>(
                 // `NamedFields { a: f32, .. }`
                 ast::Style::Struct => {
                     let ident = field.ident.as_ref().unwrap_or_else(|| unreachable!());
+                    let name = format!("{}", ident).to_case(Case::UpperCamel);
 
-                    (
-                        quote!(#ident),
-                        format!("{}", ident).to_case(Case::UpperCamel),
-                    )
+                    let ident = if is_self {
+                        quote!(self.#ident)
+                    } else {
+                        quote!(#ident)
+                    };
+
+                    (ident, name)
                 }
                 // `Tuple(f32, ..)`
                 ast::Style::Tuple => {
@@ -70,6 +105,7 @@ fn create_field_visits<'a>(
                             let ident = format_ident!("{}{}", prefix, ident);
                             quote!(#ident)
                         }
+                        None if is_self => quote!(self.#ident),
                         None => quote!(#ident),
                     };
 
@@ -108,6 +144,14 @@ fn create_field_visits<'a>(
                 quote! {
                     #ident.visit(#name, visitor).ok();
                 }
+            } else if versioned {
+                quote! {
+                    if __outdated {
+                        #ident.visit(#name, visitor).ok();
+                    } else {
+                        #ident.visit(#name, visitor)?;
+                    }
+                }
             } else {
                 quote! {
                     #ident.visit(#name, visitor)?;
@@ -130,12 +174,21 @@ fn impl_visit_struct(
         quote! { Ok(()) }
     } else {
         // `field.visit(..);` parts
-        let field_visits =
-            self::create_field_visits(None, field_args.fields.iter(), field_args.style);
+        let field_visits = self::create_field_visits(
+            None,
+            true,
+            args.version.is_some(),
+            field_args.fields.iter(),
+            field_args.style,
+        );
+
+        let enter_region = self::create_enter_region(args);
+        let migration = self::create_migration(args);
 
         quote! {
-            visitor.enter_region(name)?;
-            #(self.#field_visits)*
+            #enter_region
+            #(#field_visits)*
+            #migration
             visitor.leave_region()
         }
     };
@@ -264,7 +317,13 @@ fn impl_visit_enum(args: &args::TypeArgs, variants: &[args::VariantArgs]) -> Tok
 
         match style {
             ast::Style::Struct => {
-                let field_visits = self::create_field_visits(None, fields.iter(), style);
+                let field_visits = self::create_field_visits(
+                    None,
+                    false,
+                    args.version.is_some(),
+                    fields.iter(),
+                    style,
+                );
 
                 let idents = fields.iter().map(|field| {
                     let ident = &field.ident;
@@ -278,7 +337,13 @@ fn impl_visit_enum(args: &args::TypeArgs, variants: &[args::VariantArgs]) -> Tok
                 }
             }
             ast::Style::Tuple => {
-                let field_visits = self::create_field_visits(parse_quote!(f), fields.iter(), style);
+                let field_visits = self::create_field_visits(
+                    parse_quote!(f),
+                    false,
+                    args.version.is_some(),
+                    fields.iter(),
+                    style,
+                );
 
                 let idents = (0..fields.len()).map(|i| format_ident!("f{}", Index::from(i)));
 
@@ -305,6 +370,9 @@ fn impl_visit_enum(args: &args::TypeArgs, variants: &[args::VariantArgs]) -> Tok
 
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
+    let enter_region = self::create_enter_region(args);
+    let migration = self::create_migration(args);
+
     // plain-enum-only support
     quote! {
         impl #impl_generics Visit for #ty_ident #ty_generics #where_clause {
@@ -313,7 +381,7 @@ fn impl_visit_enum(args: &args::TypeArgs, variants: &[args::VariantArgs]) -> Tok
                 name: &str,
                 visitor: &mut Visitor,
             ) -> VisitResult {
-                visitor.enter_region(name)?;
+                #enter_region
 
                 let mut id = id(self);
                 id.visit("Id", visitor)?;
@@ -326,6 +394,8 @@ fn impl_visit_enum(args: &args::TypeArgs, variants: &[args::VariantArgs]) -> Tok
                     #(#variant_visits)*
                 }
 
+                #migration
+
                 return visitor.leave_region();
 
                 #fn_id
@@ -9,6 +9,15 @@ pub struct TypeArgs {
     pub generics: Generics,
     pub data: ast::Data<VariantArgs, FieldArgs>,
     // attrs: Vec<Attribute>
+    // ---
+    /// `#[visit(version = 3)]`: store version number in the region, reject newer versions on read
+    #[darling(default)]
+    pub version: Option<u32>,
+
+    /// `#[visit(migrate = "path::to::fn")]`: called after reading a region with older version as
+    /// `fn(&mut Self, old_version: u32, visitor: &mut Visitor) -> VisitResult`
+    #[darling(default)]
+    pub migrate: Option<Path>,
 }
 
 /// Parsed from struct's or enum variant's field
@@ -2,7 +2,7 @@
 
 mod utils;
 
-use rg3d_core::visitor::prelude::*;
+use rg3d_core::visitor::{prelude::*, VisitError};
 
 // Comment out to make sure it panics
 // #[derive(Debug, Clone, PartialEq, Visit)]
@@ -27,3 +27,134 @@ fn rename() {
 
     assert_eq!(data, data_default);
 }
+
+/// Writes `data` into a visitor and returns a visitor for reading the same data back.
+fn write_read<T: Visit>(data: &mut T) -> Visitor {
+    let mut visitor = Visitor::new();
+    data.visit("Data", &mut visitor).unwrap();
+    Visitor::load_from_memory(visitor.save_text().as_bytes()).unwrap()
+}
+
+#[derive(Debug, Clone, PartialEq, Visit)]
+pub struct PlayerV0 {
+    pub health: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Visit)]
+#[visit(version = 1, migrate = "PlayerV1::migrate")]
+pub struct PlayerV1 {
+    pub hit_points: f32,
+    pub max_hit_points: f32,
+}
+
+impl PlayerV1 {
+    fn migrate(&mut self, version: u32, visitor: &mut Visitor) -> VisitResult {
+        if version < 1 {
+            self.hit_points.visit("Health", visitor)?;
+            self.max_hit_points = 100.0;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Visit)]
+#[visit(version = 2)]
+pub struct PlayerV2 {
+    pub hit_points: f32,
+    pub max_hit_points: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Visit)]
+#[visit(version = 1, migrate = "migrate_shape")]
+pub enum Shape {
+    Circle { radius: f32 },
+    Square { side: f32 },
+}
+
+fn migrate_shape(shape: &mut Shape, version: u32, visitor: &mut Visitor) -> VisitResult {
+    if version < 1 {
+        if let Shape::Circle { radius } = shape {
+            // Diameter was stored in version 0.
+            radius.visit("Diameter", visitor)?;
+            *radius *= 0.5;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Visit)]
+pub enum ShapeV0 {
+    Circle { diameter: f32 },
+    Square { side: f32 },
+}
+
+#[test]
+fn migrate_struct() {
+    let mut visitor = write_read(&mut PlayerV0 { health: 50.0 });
+
+    let mut data = PlayerV1 {
+        hit_points: 0.0,
+        max_hit_points: 0.0,
+    };
+    data.visit("Data", &mut visitor).unwrap();
+
+    assert_eq!(
+        data,
+        PlayerV1 {
+            hit_points: 50.0,
+            max_hit_points: 100.0,
+        }
+    );
+}
+
+#[test]
+fn same_version() {
+    let mut data = PlayerV1 {
+        hit_points: 10.0,
+        max_hit_points: 20.0,
+    };
+    let mut visitor = write_read(&mut data);
+
+    let mut data_default = PlayerV1 {
+        hit_points: 0.0,
+        max_hit_points: 0.0,
+    };
+    data_default.visit("Data", &mut visitor).unwrap();
+
+    assert_eq!(data, data_default);
+}
+
+#[test]
+fn migrate_enum() {
+    let mut visitor = write_read(&mut ShapeV0::Circle { diameter: 4.0 });
+
+    let mut data = Shape::Square { side: 1.0 };
+    data.visit("Data", &mut visitor).unwrap();
+
+    assert_eq!(data, Shape::Circle { radius: 2.0 });
+}
+
+#[test]
+fn newer_version_is_rejected() {
+    let mut visitor = write_read(&mut PlayerV2 {
+        hit_points: 10.0,
+        max_hit_points: 20.0,
+    });
+
+    let mut data = PlayerV1 {
+        hit_points: 0.0,
+        max_hit_points: 0.0,
+    };
+    match data.visit("Data", &mut visitor) {
+        Err(VisitError::UnsupportedVersion {
+            region,
+            version,
+            supported,
+        }) => {
+            assert_eq!(region, "Data");
+            assert_eq!(version, 2);
+            assert_eq!(supported, 1);
+        }
+        other => panic!("expected UnsupportedVersion error, got {:?}", other),
+    }
+}
@@ -0,0 +1,68 @@
+use rg3d_core::{
+    algebra::Vector2,
+    reflect::{Reflect, ResolvePath},
+};
+
+#[derive(Debug, Default, PartialEq, Reflect)]
+struct Generics<T> {
+    items: Vec<T>,
+    #[reflect(skip)]
+    cache: Option<T>,
+}
+
+#[derive(Debug, Default, PartialEq, Reflect)]
+struct TupleStruct(f32, #[reflect(skip)] u32, Vector2<f32>);
+
+#[derive(Debug, PartialEq, Reflect)]
+enum Shape<T> {
+    Circle {
+        radius: T,
+        #[reflect(skip)]
+        area: T,
+    },
+    Segment(#[reflect(skip)] T, T),
+    Empty,
+}
+
+#[test]
+fn generics() {
+    let mut value = Generics {
+        items: vec![1u32, 2, 3],
+        cache: None,
+    };
+    value.set_field("items.2", 4u32).unwrap();
+    assert_eq!(value.items, [1, 2, 4]);
+    assert_eq!(value.fields().len(), 1);
+    assert_eq!(value.resolve_path("items").unwrap().items().len(), 3);
+}
+
+#[test]
+fn tuple_struct() {
+    let mut value = TupleStruct::default();
+    let names = value
+        .fields()
+        .into_iter()
+        .map(|(n, _)| n)
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["0", "2"]);
+    value.set_field("2.x", 1.0f32).unwrap();
+    assert_eq!(value.2.x, 1.0);
+}
+
+#[test]
+fn enum_with_skipped_fields() {
+    let mut shape = Shape::Circle {
+        radius: 1.0f32,
+        area: std::f32::consts::PI,
+    };
+    assert_eq!(shape.fields().len(), 1);
+    shape.set_field("radius", 2.0f32).unwrap();
+
+    let mut segment = Shape::Segment(1.0f32, 2.0);
+    assert!(segment.field("0").is_none());
+    segment.set_field("1", 3.0f32).unwrap();
+    assert_eq!(segment, Shape::Segment(1.0, 3.0));
+
+    assert_eq!(Shape::<f32>::Empty.variant_name(), Some("Empty"));
+    assert_eq!(shape.variant_names(), ["Circle", "Segment", "Empty"]);
+}
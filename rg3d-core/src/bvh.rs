@@ -0,0 +1,614 @@
+//! Dynamic bounding volume hierarchy.
+//!
+//! Unlike [`Octree`](crate::octree::Octree) and [`QuadTree`](crate::quadtree::QuadTree), which are
+//! built once and never change, dynamic BVH allows to insert, remove and move objects at any time.
+//! It is suitable for spatial queries on large amount of moving objects, for example frustum
+//! culling or picking of scene nodes.
+//!
+//! Every object is stored in a leaf with "fat" bounding box - a box inflated by some margin, so
+//! small movements of the object do not require changes in the hierarchy. Tree is kept balanced
+//! using tree rotations, so queries run in logarithmic time in most cases.
+
+use crate::{
+    algebra::Vector3,
+    math::{aabb::AxisAlignedBoundingBox, frustum::Frustum, ray::Ray},
+    pool::{Handle, Pool},
+};
+
+type NodeHandle<T> = Handle<BvhNode<T>>;
+
+/// Default margin that is used to inflate bounding boxes of objects.
+pub const DEFAULT_MARGIN: f32 = 0.1;
+
+/// Kind of a node of the hierarchy.
+#[derive(Clone, Debug)]
+pub enum BvhNodeKind<T> {
+    /// Leaf node that holds user data.
+    Leaf(T),
+    /// Branch node, always has exactly two children.
+    Branch {
+        /// Handle of the left child.
+        left: Handle<BvhNode<T>>,
+        /// Handle of the right child.
+        right: Handle<BvhNode<T>>,
+    },
+}
+
+/// Node of the hierarchy.
+#[derive(Clone, Debug)]
+pub struct BvhNode<T> {
+    bounds: AxisAlignedBoundingBox,
+    parent: Handle<BvhNode<T>>,
+    height: u32,
+    kind: BvhNodeKind<T>,
+}
+
+impl<T> BvhNode<T> {
+    /// Returns bounds of the node. Bounds of a leaf are fat bounds of its object.
+    pub fn bounds(&self) -> &AxisAlignedBoundingBox {
+        &self.bounds
+    }
+
+    /// Returns handle of parent node.
+    pub fn parent(&self) -> Handle<BvhNode<T>> {
+        self.parent
+    }
+
+    /// Returns height of the node, leaves have height of zero.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns kind of the node.
+    pub fn kind(&self) -> &BvhNodeKind<T> {
+        &self.kind
+    }
+
+    /// Returns true if the node is leaf.
+    pub fn is_leaf(&self) -> bool {
+        matches!(self.kind, BvhNodeKind::Leaf(_))
+    }
+
+    /// Returns user data of a leaf node, `None` for branches.
+    pub fn data(&self) -> Option<&T> {
+        match &self.kind {
+            BvhNodeKind::Leaf(data) => Some(data),
+            BvhNodeKind::Branch { .. } => None,
+        }
+    }
+
+    fn children(&self) -> Option<(NodeHandle<T>, NodeHandle<T>)> {
+        match self.kind {
+            BvhNodeKind::Leaf(_) => None,
+            BvhNodeKind::Branch { left, right } => Some((left, right)),
+        }
+    }
+}
+
+fn merge(a: &AxisAlignedBoundingBox, b: &AxisAlignedBoundingBox) -> AxisAlignedBoundingBox {
+    let mut merged = *a;
+    merged.add_box(*b);
+    merged
+}
+
+fn surface_area(aabb: &AxisAlignedBoundingBox) -> f32 {
+    let d = aabb.max - aabb.min;
+    2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+}
+
+fn contains(outer: &AxisAlignedBoundingBox, inner: &AxisAlignedBoundingBox) -> bool {
+    outer.min.x <= inner.min.x
+        && outer.min.y <= inner.min.y
+        && outer.min.z <= inner.min.z
+        && outer.max.x >= inner.max.x
+        && outer.max.y >= inner.max.y
+        && outer.max.z >= inner.max.z
+}
+
+/// See module docs.
+#[derive(Clone, Debug)]
+pub struct DynamicBvh<T> {
+    nodes: Pool<BvhNode<T>>,
+    root: Handle<BvhNode<T>>,
+    margin: f32,
+    leaf_count: usize,
+}
+
+impl<T> Default for DynamicBvh<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> DynamicBvh<T> {
+    /// Creates new empty hierarchy with default margin.
+    pub fn new() -> Self {
+        Self::with_margin(DEFAULT_MARGIN)
+    }
+
+    /// Creates new empty hierarchy with given margin. Margin defines how much bounds of objects
+    /// will be inflated, larger values mean less updates of hierarchy for moving objects, but
+    /// less precise queries.
+    pub fn with_margin(margin: f32) -> Self {
+        Self {
+            nodes: Pool::new(),
+            root: Handle::NONE,
+            margin,
+            leaf_count: 0,
+        }
+    }
+
+    /// Inserts new object with given bounds and returns handle of its leaf (proxy). The handle
+    /// stays valid until the object is removed, even if the object is moved.
+    pub fn insert(&mut self, bounds: AxisAlignedBoundingBox, data: T) -> Handle<BvhNode<T>> {
+        let leaf = self.nodes.spawn(BvhNode {
+            bounds: self.fatten(bounds),
+            parent: Handle::NONE,
+            height: 0,
+            kind: BvhNodeKind::Leaf(data),
+        });
+        self.insert_leaf(leaf);
+        self.leaf_count += 1;
+        leaf
+    }
+
+    /// Removes object by its proxy handle and returns its data.
+    ///
+    /// # Panics
+    ///
+    /// Panics if handle is invalid or it is not a leaf.
+    pub fn remove(&mut self, proxy: Handle<BvhNode<T>>) -> T {
+        assert!(self.nodes[proxy].is_leaf(), "BVH proxy must be a leaf!");
+        self.remove_leaf(proxy);
+        self.leaf_count -= 1;
+        match self.nodes.free(proxy).kind {
+            BvhNodeKind::Leaf(data) => data,
+            BvhNodeKind::Branch { .. } => unreachable!(),
+        }
+    }
+
+    /// Sets new bounds of an object. Hierarchy will be changed only if new bounds are not
+    /// inside fat bounds of the object. Returns true if the object was re-inserted in hierarchy.
+    ///
+    /// # Panics
+    ///
+    /// Panics if handle is invalid or it is not a leaf.
+    pub fn refit(&mut self, proxy: Handle<BvhNode<T>>, bounds: AxisAlignedBoundingBox) -> bool {
+        assert!(self.nodes[proxy].is_leaf(), "BVH proxy must be a leaf!");
+        if contains(&self.nodes[proxy].bounds, &bounds) {
+            return false;
+        }
+        self.remove_leaf(proxy);
+        self.nodes[proxy].bounds = self.fatten(bounds);
+        self.insert_leaf(proxy);
+        true
+    }
+
+    /// Removes every object from the hierarchy.
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.root = Handle::NONE;
+        self.leaf_count = 0;
+    }
+
+    /// Returns amount of objects in the hierarchy.
+    pub fn len(&self) -> usize {
+        self.leaf_count
+    }
+
+    /// Returns true if there are no objects in the hierarchy.
+    pub fn is_empty(&self) -> bool {
+        self.leaf_count == 0
+    }
+
+    /// Returns handle of root node, it is [`Handle::NONE`] if the hierarchy is empty.
+    pub fn root(&self) -> Handle<BvhNode<T>> {
+        self.root
+    }
+
+    /// Returns height of the hierarchy.
+    pub fn height(&self) -> u32 {
+        self.nodes
+            .try_borrow(self.root)
+            .map_or(0, |root| root.height)
+    }
+
+    /// Returns reference to a node.
+    pub fn node(&self, handle: Handle<BvhNode<T>>) -> &BvhNode<T> {
+        &self.nodes[handle]
+    }
+
+    /// Returns user data of an object. Returns `None` if handle is invalid or it is not a leaf.
+    pub fn get(&self, proxy: Handle<BvhNode<T>>) -> Option<&T> {
+        self.nodes.try_borrow(proxy).and_then(|node| node.data())
+    }
+
+    /// Returns user data of an object. Returns `None` if handle is invalid or it is not a leaf.
+    pub fn get_mut(&mut self, proxy: Handle<BvhNode<T>>) -> Option<&mut T> {
+        self.nodes
+            .try_borrow_mut(proxy)
+            .and_then(|node| match &mut node.kind {
+                BvhNodeKind::Leaf(data) => Some(data),
+                BvhNodeKind::Branch { .. } => None,
+            })
+    }
+
+    /// Traverses the hierarchy and calls `func` for every object whose fat bounds satisfy given
+    /// predicate. Predicate is also used to skip whole sub-trees, so it must return true for
+    /// any box that encloses a box that passes the test.
+    pub fn query<P, F>(&self, mut predicate: P, mut func: F)
+    where
+        P: FnMut(&AxisAlignedBoundingBox) -> bool,
+        F: FnMut(Handle<BvhNode<T>>, &T),
+    {
+        if self.root.is_none() {
+            return;
+        }
+
+        let mut stack = vec![self.root];
+        while let Some(handle) = stack.pop() {
+            let node = &self.nodes[handle];
+            if predicate(&node.bounds) {
+                match &node.kind {
+                    BvhNodeKind::Leaf(data) => func(handle, data),
+                    BvhNodeKind::Branch { left, right } => {
+                        stack.push(*left);
+                        stack.push(*right);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Collects proxies of every object that intersects given frustum.
+    pub fn frustum_query(&self, frustum: &Frustum, buffer: &mut Vec<Handle<BvhNode<T>>>) {
+        buffer.clear();
+        self.query(
+            |bounds| frustum.is_intersects_aabb(bounds),
+            |handle, _| buffer.push(handle),
+        );
+    }
+
+    /// Collects proxies of every object that intersects given ray.
+    pub fn ray_query(&self, ray: &Ray, buffer: &mut Vec<Handle<BvhNode<T>>>) {
+        buffer.clear();
+        self.query(
+            |bounds| ray.box_intersection(&bounds.min, &bounds.max).is_some(),
+            |handle, _| buffer.push(handle),
+        );
+    }
+
+    /// Collects proxies of every object that intersects given sphere.
+    pub fn sphere_query(
+        &self,
+        position: Vector3<f32>,
+        radius: f32,
+        buffer: &mut Vec<Handle<BvhNode<T>>>,
+    ) {
+        buffer.clear();
+        self.query(
+            |bounds| bounds.is_intersects_sphere(position, radius),
+            |handle, _| buffer.push(handle),
+        );
+    }
+
+    /// Collects proxies of every object that intersects given box.
+    pub fn aabb_query(&self, aabb: &AxisAlignedBoundingBox, buffer: &mut Vec<Handle<BvhNode<T>>>) {
+        buffer.clear();
+        self.query(
+            |bounds| bounds.intersect_aabb(aabb),
+            |handle, _| buffer.push(handle),
+        );
+    }
+
+    fn fatten(&self, mut bounds: AxisAlignedBoundingBox) -> AxisAlignedBoundingBox {
+        bounds.inflate(Vector3::repeat(2.0 * self.margin));
+        bounds
+    }
+
+    fn insert_leaf(&mut self, leaf: Handle<BvhNode<T>>) {
+        if self.root.is_none() {
+            self.root = leaf;
+            self.nodes[leaf].parent = Handle::NONE;
+            return;
+        }
+
+        // Find best sibling using surface area heuristic.
+        let leaf_bounds = self.nodes[leaf].bounds;
+        let mut sibling = self.root;
+        while let Some((left, right)) = self.nodes[sibling].children() {
+            let bounds = &self.nodes[sibling].bounds;
+            let area = surface_area(bounds);
+            let combined_area = surface_area(&merge(bounds, &leaf_bounds));
+
+            // Cost of creating new parent for this node and the new leaf.
+            let cost = 2.0 * combined_area;
+            // Minimum cost of pushing the leaf further down the tree.
+            let inheritance_cost = 2.0 * (combined_area - area);
+
+            let child_cost = |child: Handle<BvhNode<T>>| {
+                let child = &self.nodes[child];
+                let merged_area = surface_area(&merge(&child.bounds, &leaf_bounds));
+                if child.is_leaf() {
+                    merged_area + inheritance_cost
+                } else {
+                    merged_area - surface_area(&child.bounds) + inheritance_cost
+                }
+            };
+            let left_cost = child_cost(left);
+            let right_cost = child_cost(right);
+
+            if cost < left_cost && cost < right_cost {
+                break;
+            }
+
+            sibling = if left_cost < right_cost { left } else { right };
+        }
+
+        // Create new parent for the sibling and the leaf.
+        let old_parent = self.nodes[sibling].parent;
+        let new_parent = self.nodes.spawn(BvhNode {
+            bounds: merge(&leaf_bounds, &self.nodes[sibling].bounds),
+            parent: old_parent,
+            height: self.nodes[sibling].height + 1,
+            kind: BvhNodeKind::Branch {
+                left: sibling,
+                right: leaf,
+            },
+        });
+        self.nodes[sibling].parent = new_parent;
+        self.nodes[leaf].parent = new_parent;
+
+        if old_parent.is_some() {
+            self.replace_child(old_parent, sibling, new_parent);
+        } else {
+            self.root = new_parent;
+        }
+
+        self.fix_upwards(old_parent);
+    }
+
+    fn remove_leaf(&mut self, leaf: Handle<BvhNode<T>>) {
+        if leaf == self.root {
+            self.root = Handle::NONE;
+            return;
+        }
+
+        let parent = self.nodes[leaf].parent;
+        let grand_parent = self.nodes[parent].parent;
+        let (left, right) = self.nodes[parent]
+            .children()
+            .expect("parent of a leaf must be a branch");
+        let sibling = if left == leaf { right } else { left };
+
+        self.nodes[sibling].parent = grand_parent;
+        self.nodes[leaf].parent = Handle::NONE;
+        self.nodes.free(parent);
+
+        if grand_parent.is_some() {
+            self.replace_child(grand_parent, parent, sibling);
+            self.fix_upwards(grand_parent);
+        } else {
+            self.root = sibling;
+        }
+    }
+
+    fn replace_child(
+        &mut self,
+        parent: Handle<BvhNode<T>>,
+        old_child: Handle<BvhNode<T>>,
+        new_child: Handle<BvhNode<T>>,
+    ) {
+        if let BvhNodeKind::Branch { left, right } = &mut self.nodes[parent].kind {
+            if *left == old_child {
+                *left = new_child;
+            } else {
+                *right = new_child;
+            }
+        }
+    }
+
+    fn update_node(&mut self, handle: Handle<BvhNode<T>>) {
+        if let Some((left, right)) = self.nodes[handle].children() {
+            let left = &self.nodes[left];
+            let right = &self.nodes[right];
+            let height = 1 + left.height.max(right.height);
+            let bounds = merge(&left.bounds, &right.bounds);
+            let node = &mut self.nodes[handle];
+            node.height = height;
+            node.bounds = bounds;
+        }
+    }
+
+    // Walks up from given node, re-balances tree and recalculates bounds and heights.
+    fn fix_upwards(&mut self, mut handle: Handle<BvhNode<T>>) {
+        while handle.is_some() {
+            handle = self.balance(handle);
+            self.update_node(handle);
+            handle = self.nodes[handle].parent;
+        }
+    }
+
+    // Performs left or right rotation if node `a` is imbalanced. Returns new root of the sub-tree.
+    fn balance(&mut self, a: Handle<BvhNode<T>>) -> Handle<BvhNode<T>> {
+        let (b, c) = match self.nodes[a].children() {
+            Some(children) if self.nodes[a].height >= 2 => children,
+            _ => return a,
+        };
+
+        let balance = self.nodes[c].height as i32 - self.nodes[b].height as i32;
+        if balance > 1 {
+            self.rotate_up(a, c, b, true)
+        } else if balance < -1 {
+            self.rotate_up(a, b, c, false)
+        } else {
+            a
+        }
+    }
+
+    // Moves `child` of `a` one level up, so `a` becomes child of `child`. `other` is another
+    // child of `a`, `child_is_right` tells on which side `child` is.
+    fn rotate_up(
+        &mut self,
+        a: Handle<BvhNode<T>>,
+        child: Handle<BvhNode<T>>,
+        other: Handle<BvhNode<T>>,
+        child_is_right: bool,
+    ) -> Handle<BvhNode<T>> {
+        let (f, g) = self.nodes[child]
+            .children()
+            .expect("imbalanced child must be a branch");
+
+        // Replace `a` with `child` in hierarchy.
+        let a_parent = self.nodes[a].parent;
+        self.nodes[child].parent = a_parent;
+        self.nodes[a].parent = child;
+        if a_parent.is_some() {
+            self.replace_child(a_parent, a, child);
+        } else {
+            self.root = child;
+        }
+
+        // Higher grandchild stays with `child`, lower one goes to `a`.
+        let (keep, give) = if self.nodes[f].height > self.nodes[g].height {
+            (f, g)
+        } else {
+            (g, f)
+        };
+        self.nodes[give].parent = a;
+
+        self.nodes[a].kind = if child_is_right {
+            BvhNodeKind::Branch {
+                left: other,
+                right: give,
+            }
+        } else {
+            BvhNodeKind::Branch {
+                left: give,
+                right: other,
+            }
+        };
+        self.nodes[child].kind = BvhNodeKind::Branch {
+            left: a,
+            right: keep,
+        };
+
+        self.update_node(a);
+        self.update_node(child);
+
+        child
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algebra::{Matrix4, Point3, Vector3},
+        bvh::{BvhNode, BvhNodeKind, DynamicBvh},
+        math::{aabb::AxisAlignedBoundingBox, frustum::Frustum, ray::Ray},
+        pool::Handle,
+    };
+
+    fn unit_box_at(position: Vector3<f32>) -> AxisAlignedBoundingBox {
+        let mut aabb = AxisAlignedBoundingBox::unit();
+        aabb.offset(position);
+        aabb
+    }
+
+    fn validate(bvh: &DynamicBvh<usize>, handle: Handle<BvhNode<usize>>) -> u32 {
+        let node = bvh.node(handle);
+        match node.kind() {
+            BvhNodeKind::Leaf(_) => {
+                assert_eq!(node.height(), 0);
+                0
+            }
+            BvhNodeKind::Branch { left, right } => {
+                assert_eq!(bvh.node(*left).parent(), handle);
+                assert_eq!(bvh.node(*right).parent(), handle);
+                let left_height = validate(bvh, *left);
+                let right_height = validate(bvh, *right);
+                assert_eq!(node.height(), 1 + left_height.max(right_height));
+                node.height()
+            }
+        }
+    }
+
+    fn sorted(bvh: &DynamicBvh<usize>, buffer: &[Handle<BvhNode<usize>>]) -> Vec<usize> {
+        let mut result = buffer
+            .iter()
+            .map(|&h| *bvh.get(h).unwrap())
+            .collect::<Vec<_>>();
+        result.sort_unstable();
+        result
+    }
+
+    #[test]
+    fn bvh_insert_remove_refit() {
+        let mut bvh = DynamicBvh::with_margin(0.0);
+        let mut proxies = Vec::new();
+        for i in 0..100 {
+            let position = Vector3::new((i % 10) as f32 * 2.0, 0.0, (i / 10) as f32 * 2.0);
+            proxies.push(bvh.insert(unit_box_at(position), i));
+        }
+        assert_eq!(bvh.len(), 100);
+        validate(&bvh, bvh.root());
+        assert!(bvh.height() <= 10);
+
+        let mut buffer = Vec::new();
+        bvh.sphere_query(Vector3::new(0.0, 0.0, 0.0), 1.0, &mut buffer);
+        assert_eq!(sorted(&bvh, &buffer), vec![0]);
+
+        // Move first object far away.
+        assert!(bvh.refit(proxies[0], unit_box_at(Vector3::new(100.0, 0.0, 100.0))));
+        validate(&bvh, bvh.root());
+        bvh.sphere_query(Vector3::new(0.0, 0.0, 0.0), 1.0, &mut buffer);
+        assert!(buffer.is_empty());
+        bvh.aabb_query(&unit_box_at(Vector3::new(100.0, 0.0, 100.0)), &mut buffer);
+        assert_eq!(buffer, vec![proxies[0]]);
+
+        let ray = Ray::from_two_points(Vector3::new(-5.0, 0.0, 2.0), Vector3::new(50.0, 0.0, 2.0));
+        bvh.ray_query(&ray, &mut buffer);
+        assert_eq!(sorted(&bvh, &buffer), (10..20).collect::<Vec<_>>());
+
+        for proxy in proxies.drain(..50) {
+            bvh.remove(proxy);
+        }
+        assert_eq!(bvh.len(), 50);
+        validate(&bvh, bvh.root());
+        bvh.aabb_query(&unit_box_at(Vector3::new(100.0, 0.0, 100.0)), &mut buffer);
+        assert!(buffer.is_empty());
+
+        for proxy in proxies.drain(..) {
+            bvh.remove(proxy);
+        }
+        assert!(bvh.is_empty());
+        assert!(bvh.root().is_none());
+    }
+
+    #[test]
+    fn bvh_refit_inside_fat_bounds() {
+        let mut bvh = DynamicBvh::with_margin(0.5);
+        let proxy = bvh.insert(unit_box_at(Vector3::default()), 0);
+        assert!(!bvh.refit(proxy, unit_box_at(Vector3::new(0.2, 0.0, 0.0))));
+        assert!(bvh.refit(proxy, unit_box_at(Vector3::new(2.0, 0.0, 0.0))));
+        assert_eq!(bvh.get(proxy), Some(&0));
+    }
+
+    #[test]
+    fn bvh_frustum_query() {
+        let mut bvh = DynamicBvh::new();
+        let visible = bvh.insert(unit_box_at(Vector3::new(0.0, 0.0, 10.0)), 0);
+        bvh.insert(unit_box_at(Vector3::new(0.0, 0.0, -10.0)), 1);
+        let view = Matrix4::look_at_rh(
+            &Point3::new(0.0, 0.0, 0.0),
+            &Point3::new(0.0, 0.0, 1.0),
+            &Vector3::y(),
+        );
+        let projection = Matrix4::new_perspective(1.0, 1.0, 0.1, 100.0);
+        let frustum = Frustum::from(projection * view).unwrap();
+
+        let mut buffer = Vec::new();
+        bvh.frustum_query(&frustum, &mut buffer);
+        assert_eq!(buffer, vec![visible]);
+    }
+}
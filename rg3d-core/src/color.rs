@@ -1,7 +1,43 @@
+//! Colors in different color spaces.
+//!
+//! [`Color`] is an 8-bit RGBA color that is usually treated as sRGB (gamma-corrected) color, it
+//! is what users pick in editors. [`SrgbColor`] is the same color with float components, while
+//! [`LinearColor`] is a color in linear space with unbounded float components (so it can hold HDR
+//! values), this is what lighting calculations need. Conversions between sRGB and linear space use
+//! the exact sRGB transfer functions, except for `srgb_to_linear*` and `linear_to_srgb` methods
+//! of [`Color`] which approximate them with gamma 2.2 to match built-in shaders. [`Hsv`], [`Hsl`] and [`Oklab`] are alternative
+//! representations, Oklab is a perceptual color space which is good for blending colors.
+
 use crate::algebra::{Vector3, Vector4};
+use crate::reflect::Reflect;
 use crate::visitor::{Visit, VisitResult, Visitor};
 
-#[derive(Copy, Clone, Debug, PartialOrd, PartialEq)]
+/// Converts a color component from sRGB to linear space using the exact sRGB transfer function.
+#[inline]
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts a color component from linear to sRGB space using the exact sRGB transfer function.
+/// Values above 1.0 are extrapolated.
+#[inline]
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn float_to_u8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[derive(Copy, Clone, Debug, PartialOrd, PartialEq, Reflect)]
 #[repr(C)]
 pub struct Color {
     // Do not change order! OpenGL requires this order!
@@ -192,6 +228,8 @@ impl Color {
         Self { r, g, b, a }
     }
 
+    /// Converts the color from sRGB to linear space using gamma 2.2 approximation, use
+    /// [`Self::to_linear`] for exact conversion.
     #[must_use]
     pub fn srgb_to_linear(self) -> Self {
         let r = ((self.r as f32 / 255.0).powf(2.2).clamp(0.0, 1.0) * 255.0) as u8;
@@ -208,6 +246,8 @@ impl Color {
         Vector4::new(r, g, b, self.a as f32 / 255.0)
     }
 
+    /// Converts the color from linear to sRGB space using gamma 2.2 approximation, use
+    /// [`LinearColor::to_srgb`] for exact conversion.
     #[must_use]
     pub fn linear_to_srgb(self) -> Self {
         let r = ((self.r as f32 / 255.0).powf(1.0 / 2.2).clamp(0.0, 1.0) * 255.0) as u8;
@@ -216,6 +256,13 @@ impl Color {
         Self::from_rgba(r, g, b, self.a)
     }
 
+    /// Treats the color as sRGB color and converts it to linear space using the exact sRGB
+    /// transfer function.
+    #[must_use]
+    pub fn to_linear(self) -> LinearColor {
+        LinearColor::from(self)
+    }
+
     pub fn as_frgba(self) -> Vector4<f32> {
         Vector4::new(
             f32::from(self.r) / 255.0,
@@ -283,3 +330,429 @@ impl Visit for Color {
         visitor.leave_region()
     }
 }
+
+/// A color in sRGB space with float components in `[0; 1]` range. Alpha is always linear.
+#[derive(Copy, Clone, Debug, PartialEq, Reflect, Visit)]
+pub struct SrgbColor {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Default for SrgbColor {
+    fn default() -> Self {
+        Self::new(1.0, 1.0, 1.0, 1.0)
+    }
+}
+
+impl SrgbColor {
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Converts the color to linear space.
+    #[must_use]
+    pub fn to_linear(self) -> LinearColor {
+        LinearColor::new(
+            srgb_to_linear(self.r),
+            srgb_to_linear(self.g),
+            srgb_to_linear(self.b),
+            self.a,
+        )
+    }
+}
+
+impl From<Color> for SrgbColor {
+    fn from(color: Color) -> Self {
+        Self::new(
+            color.r as f32 / 255.0,
+            color.g as f32 / 255.0,
+            color.b as f32 / 255.0,
+            color.a as f32 / 255.0,
+        )
+    }
+}
+
+impl From<SrgbColor> for Color {
+    fn from(color: SrgbColor) -> Self {
+        Self::from_rgba(
+            float_to_u8(color.r),
+            float_to_u8(color.g),
+            float_to_u8(color.b),
+            float_to_u8(color.a),
+        )
+    }
+}
+
+impl From<LinearColor> for SrgbColor {
+    fn from(color: LinearColor) -> Self {
+        color.to_srgb()
+    }
+}
+
+/// A color in linear space. Components are not limited to `[0; 1]` range, so the color can be
+/// used for HDR values such as intensity of lights. Alpha is always linear.
+#[derive(Copy, Clone, Debug, PartialEq, Reflect, Visit)]
+pub struct LinearColor {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Default for LinearColor {
+    fn default() -> Self {
+        Self::WHITE
+    }
+}
+
+impl LinearColor {
+    pub const WHITE: Self = Self::new(1.0, 1.0, 1.0, 1.0);
+    pub const BLACK: Self = Self::new(0.0, 0.0, 0.0, 1.0);
+    pub const TRANSPARENT: Self = Self::new(0.0, 0.0, 0.0, 0.0);
+
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub const fn opaque(r: f32, g: f32, b: f32) -> Self {
+        Self { r, g, b, a: 1.0 }
+    }
+
+    /// Converts the color to sRGB space, HDR values are not clamped.
+    #[must_use]
+    pub fn to_srgb(self) -> SrgbColor {
+        SrgbColor::new(
+            linear_to_srgb(self.r),
+            linear_to_srgb(self.g),
+            linear_to_srgb(self.b),
+            self.a,
+        )
+    }
+
+    /// Multiplies color components (but not alpha) by given factor, it is useful to define
+    /// intensity of HDR colors.
+    #[must_use]
+    pub fn scale(self, factor: f32) -> Self {
+        Self::new(self.r * factor, self.g * factor, self.b * factor, self.a)
+    }
+
+    #[must_use]
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        Self::new(
+            self.r + (other.r - self.r) * t,
+            self.g + (other.g - self.g) * t,
+            self.b + (other.b - self.b) * t,
+            self.a + (other.a - self.a) * t,
+        )
+    }
+
+    /// Returns relative luminance of the color (Rec. 709 coefficients).
+    pub fn luminance(&self) -> f32 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
+    /// Returns the largest of color components, alpha is ignored.
+    pub fn max_component(&self) -> f32 {
+        self.r.max(self.g).max(self.b)
+    }
+
+    pub fn as_frgb(self) -> Vector3<f32> {
+        Vector3::new(self.r, self.g, self.b)
+    }
+}
+
+impl From<Color> for LinearColor {
+    fn from(color: Color) -> Self {
+        SrgbColor::from(color).to_linear()
+    }
+}
+
+impl From<SrgbColor> for LinearColor {
+    fn from(color: SrgbColor) -> Self {
+        color.to_linear()
+    }
+}
+
+impl From<LinearColor> for Color {
+    fn from(color: LinearColor) -> Self {
+        Self::from(color.to_srgb())
+    }
+}
+
+impl From<LinearColor> for Vector4<f32> {
+    fn from(color: LinearColor) -> Self {
+        Vector4::new(color.r, color.g, color.b, color.a)
+    }
+}
+
+impl From<Vector4<f32>> for LinearColor {
+    fn from(v: Vector4<f32>) -> Self {
+        Self::new(v.x, v.y, v.z, v.w)
+    }
+}
+
+impl From<Vector3<f32>> for LinearColor {
+    fn from(v: Vector3<f32>) -> Self {
+        Self::opaque(v.x, v.y, v.z)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Hsl {
+    /// [0; 360] range
+    hue: f32,
+    /// [0; 100] range
+    saturation: f32,
+    /// [0; 100] range
+    lightness: f32,
+}
+
+impl Hsl {
+    pub fn new(hue: f32, saturation: f32, lightness: f32) -> Self {
+        Self {
+            hue: hue.clamp(0.0, 360.0),
+            saturation: saturation.clamp(0.0, 100.0),
+            lightness: lightness.clamp(0.0, 100.0),
+        }
+    }
+
+    pub fn hue(&self) -> f32 {
+        self.hue
+    }
+
+    pub fn set_hue(&mut self, hue: f32) {
+        self.hue = hue.clamp(0.0, 360.0);
+    }
+
+    pub fn saturation(&self) -> f32 {
+        self.saturation
+    }
+
+    pub fn set_saturation(&mut self, saturation: f32) {
+        self.saturation = saturation.clamp(0.0, 100.0);
+    }
+
+    pub fn lightness(&self) -> f32 {
+        self.lightness
+    }
+
+    pub fn set_lightness(&mut self, lightness: f32) {
+        self.lightness = lightness.clamp(0.0, 100.0);
+    }
+}
+
+impl From<SrgbColor> for Hsl {
+    fn from(color: SrgbColor) -> Self {
+        let r = color.r.clamp(0.0, 1.0);
+        let g = color.g.clamp(0.0, 1.0);
+        let b = color.b.clamp(0.0, 1.0);
+
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+        let lightness = (max + min) / 2.0;
+
+        if delta <= f32::EPSILON {
+            return Self::new(0.0, 0.0, lightness * 100.0);
+        }
+
+        let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+        let hue = if max.eq(&r) {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max.eq(&g) {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+
+        Self::new(hue, saturation * 100.0, lightness * 100.0)
+    }
+}
+
+impl From<Hsl> for SrgbColor {
+    fn from(hsl: Hsl) -> Self {
+        let saturation = hsl.saturation / 100.0;
+        let lightness = hsl.lightness / 100.0;
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        let sector = (hsl.hue / 60.0) % 6.0;
+        let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+        let (r, g, b) = match sector as i32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            5 => (chroma, 0.0, x),
+            _ => unreachable!(),
+        };
+        let m = lightness - chroma / 2.0;
+        Self::new(r + m, g + m, b + m, 1.0)
+    }
+}
+
+impl From<Color> for Hsl {
+    fn from(color: Color) -> Self {
+        Self::from(SrgbColor::from(color))
+    }
+}
+
+impl From<Hsl> for Color {
+    fn from(hsl: Hsl) -> Self {
+        Self::from(SrgbColor::from(hsl))
+    }
+}
+
+/// A color in Oklab perceptual color space (see <https://bottosson.github.io/posts/oklab/>).
+/// `l` is perceived lightness in `[0; 1]` range, `a` and `b` are green-red and blue-yellow axes.
+/// Euclidean distance between Oklab colors matches perceived difference, so interpolation in this
+/// space gives smooth transitions without dark or oversaturated areas in the middle.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Oklab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+impl Oklab {
+    pub const fn new(l: f32, a: f32, b: f32) -> Self {
+        Self { l, a, b }
+    }
+
+    #[must_use]
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        Self::new(
+            self.l + (other.l - self.l) * t,
+            self.a + (other.a - self.a) * t,
+            self.b + (other.b - self.b) * t,
+        )
+    }
+
+    /// Converts the color to linear space with given alpha.
+    pub fn to_linear(self, alpha: f32) -> LinearColor {
+        let l = self.l + 0.396_337_78 * self.a + 0.215_803_76 * self.b;
+        let m = self.l - 0.105_561_346 * self.a - 0.063_854_17 * self.b;
+        let s = self.l - 0.089_484_18 * self.a - 1.291_485_5 * self.b;
+
+        let l = l * l * l;
+        let m = m * m * m;
+        let s = s * s * s;
+
+        LinearColor::new(
+            4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s,
+            -1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s,
+            -0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s,
+            alpha,
+        )
+    }
+}
+
+impl From<LinearColor> for Oklab {
+    fn from(color: LinearColor) -> Self {
+        let l = 0.412_221_46 * color.r + 0.536_332_55 * color.g + 0.051_445_995 * color.b;
+        let m = 0.211_903_5 * color.r + 0.680_699_5 * color.g + 0.107_396_96 * color.b;
+        let s = 0.088_302_46 * color.r + 0.281_718_85 * color.g + 0.629_978_7 * color.b;
+
+        let l = l.cbrt();
+        let m = m.cbrt();
+        let s = s.cbrt();
+
+        Self::new(
+            0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+            1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+            0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+        )
+    }
+}
+
+impl From<Oklab> for LinearColor {
+    fn from(color: Oklab) -> Self {
+        color.to_linear(1.0)
+    }
+}
+
+impl From<Color> for Oklab {
+    fn from(color: Color) -> Self {
+        Self::from(LinearColor::from(color))
+    }
+}
+
+impl From<Oklab> for Color {
+    fn from(color: Oklab) -> Self {
+        Self::from(color.to_linear(1.0))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::color::{linear_to_srgb, srgb_to_linear, Color, Hsl, LinearColor, Oklab, SrgbColor};
+
+    fn approx(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1.0e-4
+    }
+
+    #[test]
+    fn test_transfer_functions() {
+        assert!(approx(srgb_to_linear(0.5), 0.214_041));
+        assert!(approx(linear_to_srgb(0.214_041), 0.5));
+        assert_eq!(srgb_to_linear(0.0), 0.0);
+        assert!(approx(srgb_to_linear(1.0), 1.0));
+        // Linear part of the curve.
+        assert!(approx(srgb_to_linear(0.02), 0.02 / 12.92));
+
+        for value in 0..=255u8 {
+            let color = Color::opaque(value, value, value);
+            assert_eq!(Color::from(color.to_linear()), color);
+        }
+
+        // HDR values survive round trip.
+        let hdr = LinearColor::opaque(4.0, 2.0, 0.5);
+        let back = SrgbColor::from(hdr).to_linear();
+        assert!(approx(back.r, 4.0) && approx(back.g, 2.0) && approx(back.b, 0.5));
+    }
+
+    #[test]
+    fn test_hsl() {
+        let hsl = Hsl::from(Color::opaque(255, 0, 0));
+        assert_eq!(
+            (hsl.hue(), hsl.saturation(), hsl.lightness()),
+            (0.0, 100.0, 50.0)
+        );
+        assert_eq!(
+            Color::from(Hsl::new(120.0, 100.0, 25.0)),
+            Color::opaque(0, 128, 0)
+        );
+        assert_eq!(Color::from(Hsl::new(0.0, 0.0, 100.0)), Color::WHITE);
+
+        for color in [
+            Color::opaque(12, 200, 77),
+            Color::opaque(250, 10, 190),
+            Color::opaque(30, 60, 90),
+        ] {
+            assert_eq!(Color::from(Hsl::from(color)), color);
+        }
+    }
+
+    #[test]
+    fn test_oklab() {
+        let white = Oklab::from(Color::WHITE);
+        assert!(approx(white.l, 1.0) && approx(white.a, 0.0) && approx(white.b, 0.0));
+        let black = Oklab::from(Color::BLACK);
+        assert!(approx(black.l, 0.0));
+
+        // Reference value from the Oklab article.
+        let red = Oklab::from(Color::RED);
+        assert!((red.l - 0.627_955).abs() < 1.0e-3);
+        assert!((red.a - 0.224_863).abs() < 1.0e-3);
+        assert!((red.b - 0.125_846).abs() < 1.0e-3);
+
+        for color in [
+            Color::opaque(12, 200, 77),
+            Color::opaque(250, 10, 190),
+            Color::BLUE,
+        ] {
+            assert_eq!(Color::from(Oklab::from(color)), color);
+        }
+    }
+}
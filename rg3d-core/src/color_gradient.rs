@@ -1,9 +1,42 @@
 use crate::{
-    color::Color,
+    color::{Color, LinearColor, Oklab},
     visitor::{Visit, VisitResult, Visitor},
 };
 use std::cmp::Ordering;
 
+/// Color space in which colors of neighbouring gradient points are interpolated.
+#[derive(Visit, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GradientInterpolation {
+    /// Components of sRGB colors are interpolated directly, it is the cheapest mode, but
+    /// transitions between saturated colors look dark in the middle.
+    Srgb,
+    /// Colors are interpolated in linear space, which is physically correct blending of light.
+    Linear,
+    /// Colors are interpolated in Oklab perceptual space, it gives the most even transitions.
+    Oklab,
+}
+
+impl Default for GradientInterpolation {
+    fn default() -> Self {
+        Self::Srgb
+    }
+}
+
+impl GradientInterpolation {
+    /// Interpolates between two colors in the color space, alpha is always interpolated linearly.
+    pub fn interpolate(self, a: Color, b: Color, t: f32) -> Color {
+        match self {
+            Self::Srgb => a.lerp(b, t),
+            Self::Linear => Color::from(LinearColor::from(a).lerp(LinearColor::from(b), t)),
+            Self::Oklab => {
+                let alpha = a.a as f32 + (b.a as f32 - a.a as f32) * t;
+                Color::from(Oklab::from(a).lerp(Oklab::from(b), t))
+                    .with_new_alpha(alpha.round().clamp(0.0, 255.0) as u8)
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct GradientPoint {
     location: f32,
@@ -48,12 +81,14 @@ impl Clone for GradientPoint {
 #[derive(Debug)]
 pub struct ColorGradient {
     points: Vec<GradientPoint>,
+    interpolation: GradientInterpolation,
 }
 
 impl Clone for ColorGradient {
     fn clone(&self) -> Self {
         Self {
             points: self.points.clone(),
+            interpolation: self.interpolation,
         }
     }
 }
@@ -63,6 +98,7 @@ impl Visit for ColorGradient {
         visitor.enter_region(name)?;
 
         self.points.visit("Points", visitor)?;
+        let _ = self.interpolation.visit("Interpolation", visitor); // Backward compatibility.
 
         visitor.leave_region()
     }
@@ -76,7 +112,18 @@ impl Default for ColorGradient {
 
 impl ColorGradient {
     pub fn new() -> Self {
-        Self { points: Vec::new() }
+        Self {
+            points: Vec::new(),
+            interpolation: Default::default(),
+        }
+    }
+
+    pub fn set_interpolation(&mut self, interpolation: GradientInterpolation) {
+        self.interpolation = interpolation;
+    }
+
+    pub fn interpolation(&self) -> GradientInterpolation {
+        self.interpolation
     }
 
     pub fn add_point(&mut self, pt: GradientPoint) {
@@ -103,7 +150,7 @@ impl ColorGradient {
                 // linear interpolation
                 let span = pt_b.location - pt_a.location;
                 let t = (location - pt_a.location) / span;
-                return pt_a.color.lerp(pt_b.color, t);
+                return self.interpolation.interpolate(pt_a.color, pt_b.color, t);
             } else if location < pt_a.location {
                 return pt_a.color;
             } else {
@@ -135,7 +182,7 @@ impl ColorGradient {
             // linear interpolation
             let span = pt_b.location - pt_a.location;
             let t = (location - pt_a.location) / span;
-            pt_a.color.lerp(pt_b.color, t)
+            self.interpolation.interpolate(pt_a.color, pt_b.color, t)
         }
     }
 
@@ -147,15 +194,22 @@ impl ColorGradient {
 #[derive(Default)]
 pub struct ColorGradientBuilder {
     points: Vec<GradientPoint>,
+    interpolation: GradientInterpolation,
 }
 
 impl ColorGradientBuilder {
     pub fn new() -> Self {
         Self {
             points: Default::default(),
+            interpolation: Default::default(),
         }
     }
 
+    pub fn with_interpolation(mut self, interpolation: GradientInterpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
     pub fn with_point(mut self, point: GradientPoint) -> Self {
         self.points.push(point);
         self
@@ -170,6 +224,53 @@ impl ColorGradientBuilder {
 
         ColorGradient {
             points: self.points,
+            interpolation: self.interpolation,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        color::{Color, Oklab},
+        color_gradient::{ColorGradientBuilder, GradientInterpolation, GradientPoint},
+    };
+
+    #[test]
+    fn test_interpolation() {
+        let gradient = |interpolation| {
+            ColorGradientBuilder::new()
+                .with_interpolation(interpolation)
+                .with_point(GradientPoint::new(0.0, Color::RED))
+                .with_point(GradientPoint::new(1.0, Color::GREEN.with_new_alpha(0)))
+                .build()
+        };
+
+        let srgb = gradient(GradientInterpolation::Srgb).get_color(0.5);
+        let linear = gradient(GradientInterpolation::Linear).get_color(0.5);
+        let oklab = gradient(GradientInterpolation::Oklab).get_color(0.5);
+        assert_eq!(srgb, Color::from_rgba(128, 127, 0, 128));
+        // Linear blend is brighter than sRGB one.
+        assert_eq!(linear, Color::from_rgba(188, 188, 0, 128));
+        assert_eq!(oklab.a, 128);
+
+        // Oklab midpoint is perceptually halfway between ends.
+        let (a, b, mid) = (
+            Oklab::from(Color::RED),
+            Oklab::from(Color::GREEN),
+            Oklab::from(oklab.with_new_alpha(255)),
+        );
+        assert!((mid.l - (a.l + b.l) / 2.0).abs() < 0.01);
+
+        for interpolation in [
+            GradientInterpolation::Srgb,
+            GradientInterpolation::Linear,
+            GradientInterpolation::Oklab,
+        ] {
+            let gradient = gradient(interpolation);
+            assert_eq!(gradient.get_color(-1.0), Color::RED);
+            assert_eq!(gradient.get_color(0.0), Color::RED);
+            assert_eq!(gradient.get_color(1.0), Color::GREEN.with_new_alpha(0));
         }
     }
 }
@@ -0,0 +1,812 @@
+use crate::{
+    math::{cubicf, cubicf_derivative, lerpf},
+    visitor::prelude::*,
+};
+use std::cmp::Ordering;
+use uuid::Uuid;
+
+pub mod quaternion;
+pub mod vector;
+
+/// Default weight of Bezier tangents. Bezier segment with such weights is the same as cubic
+/// Hermite spline with the same tangents.
+pub const DEFAULT_TANGENT_WEIGHT: f32 = 1.0 / 3.0;
+
+fn stepf(p0: f32, p1: f32, t: f32) -> f32 {
+    if t.eq(&1.0) {
+        p1
+    } else {
+        p0
+    }
+}
+
+// Abscissas and weights of 5-point Gauss-Legendre quadrature on [0; 1] range, it is exact
+// for polynomials up to 9th degree so it is exact for every segment except Bezier ones.
+const GAUSS_LEGENDRE: [(f32, f32); 5] = [
+    (0.046_910_077, 0.118_463_44),
+    (0.230_765_34, 0.239_314_34),
+    (0.5, 0.284_444_45),
+    (0.769_234_66, 0.239_314_34),
+    (0.953_089_9, 0.118_463_44),
+];
+
+// Cubic Bezier with end points 0 and 1.
+fn bezier(p1: f32, p2: f32, s: f32) -> f32 {
+    let r = 1.0 - s;
+    3.0 * r * r * s * p1 + 3.0 * r * s * s * p2 + s * s * s
+}
+
+fn bezier_full(p0: f32, p1: f32, p2: f32, p3: f32, s: f32) -> f32 {
+    let r = 1.0 - s;
+    r * r * r * p0 + 3.0 * r * r * s * p1 + 3.0 * r * s * s * p2 + s * s * s * p3
+}
+
+fn bezier_derivative(p0: f32, p1: f32, p2: f32, p3: f32, s: f32) -> f32 {
+    let r = 1.0 - s;
+    3.0 * r * r * (p1 - p0) + 6.0 * r * s * (p2 - p1) + 3.0 * s * s * (p3 - p2)
+}
+
+// Finds Bezier parameter for given x. x(s) is monotonic when handles are in [0; 1] range, so
+// Newton's method is safe to use as long as it stays in the bracket, bisection is used otherwise.
+fn solve_bezier(x1: f32, x2: f32, x: f32) -> f32 {
+    let (mut lo, mut hi) = (0.0, 1.0);
+    let mut s = x;
+    for _ in 0..16 {
+        let error = bezier(x1, x2, s) - x;
+        if error.abs() < 1.0e-6 {
+            break;
+        }
+        if error > 0.0 {
+            hi = s;
+        } else {
+            lo = s;
+        }
+        let derivative = bezier_derivative(0.0, x1, x2, 1.0, s);
+        let next = s - error / derivative;
+        s = if derivative.abs() > f32::EPSILON && next > lo && next < hi {
+            next
+        } else {
+            (lo + hi) * 0.5
+        };
+    }
+    s
+}
+
+#[derive(Visit, Clone, Debug, PartialEq)]
+pub enum CurveKeyKind {
+    Constant,
+    Linear,
+    Cubic {
+        /// A `tan(angle)` of left tangent.
+        left_tangent: f32,
+        /// A `tan(angle)` of right tangent.
+        right_tangent: f32,
+    },
+    /// Cubic Bezier segment with weighted tangents. If next key is a Cubic key, its left tangent
+    /// is used with default weight, for other kinds of next key the tangent is flat.
+    Bezier {
+        /// A `tan(angle)` of left tangent.
+        left_tangent: f32,
+        /// A `tan(angle)` of right tangent.
+        right_tangent: f32,
+        /// Horizontal length of left tangent as a fraction of the span to previous key,
+        /// must be in `[0; 1]` range.
+        left_weight: f32,
+        /// Horizontal length of right tangent as a fraction of the span to next key, must be in
+        /// `[0; 1]` range.
+        right_weight: f32,
+    },
+}
+
+impl CurveKeyKind {
+    pub fn new_cubic(left_angle_radians: f32, right_angle_radians: f32) -> Self {
+        Self::Cubic {
+            left_tangent: left_angle_radians.tan(),
+            right_tangent: right_angle_radians.tan(),
+        }
+    }
+
+    pub fn new_bezier(
+        left_angle_radians: f32,
+        right_angle_radians: f32,
+        left_weight: f32,
+        right_weight: f32,
+    ) -> Self {
+        Self::Bezier {
+            left_tangent: left_angle_radians.tan(),
+            right_tangent: right_angle_radians.tan(),
+            left_weight,
+            right_weight,
+        }
+    }
+}
+
+impl Default for CurveKeyKind {
+    fn default() -> Self {
+        Self::Constant
+    }
+}
+
+#[derive(Visit, Clone, Default, Debug, PartialEq)]
+pub struct CurveKey {
+    pub id: Uuid,
+    location: f32,
+    pub value: f32,
+    pub kind: CurveKeyKind,
+}
+
+impl CurveKey {
+    pub fn new(location: f32, value: f32, kind: CurveKeyKind) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            location,
+            value,
+            kind,
+        }
+    }
+}
+
+impl CurveKey {
+    pub fn location(&self) -> f32 {
+        self.location
+    }
+
+    // Control points of Bezier segment between this key and other key in space where the segment
+    // spans [0; 1] range along x axis.
+    fn bezier_handles(&self, other: &Self) -> (f32, f32, f32, f32) {
+        let span = other.location - self.location;
+        let (x1, y1) = match self.kind {
+            CurveKeyKind::Bezier {
+                right_tangent,
+                right_weight,
+                ..
+            } => {
+                let weight = right_weight.clamp(0.0, 1.0);
+                (weight, self.value + right_tangent * weight * span)
+            }
+            _ => (DEFAULT_TANGENT_WEIGHT, self.value),
+        };
+        let (x2, y2) = match other.kind {
+            CurveKeyKind::Bezier {
+                left_tangent,
+                left_weight,
+                ..
+            } => {
+                let weight = left_weight.clamp(0.0, 1.0);
+                (1.0 - weight, other.value - left_tangent * weight * span)
+            }
+            // Tangents of cubic keys are scaled by the difference of values, the same as in
+            // cubic segments.
+            CurveKeyKind::Cubic { left_tangent, .. } => (
+                1.0 - DEFAULT_TANGENT_WEIGHT,
+                other.value
+                    - left_tangent * DEFAULT_TANGENT_WEIGHT * (other.value - self.value).abs(),
+            ),
+            _ => (1.0 - DEFAULT_TANGENT_WEIGHT, other.value),
+        };
+        (x1, y1, x2, y2)
+    }
+
+    pub fn interpolate(&self, other: &Self, t: f32) -> f32 {
+        match (&self.kind, &other.kind) {
+            // Constant-to-any
+            (CurveKeyKind::Constant, _) => stepf(self.value, other.value, t),
+
+            // Linear-to-any
+            (CurveKeyKind::Linear, _) => lerpf(self.value, other.value, t),
+
+            // Cubic-to-constant or cubic-to-linear
+            (
+                CurveKeyKind::Cubic {
+                    right_tangent: left_tangent,
+                    ..
+                },
+                CurveKeyKind::Constant,
+            )
+            | (
+                CurveKeyKind::Cubic {
+                    right_tangent: left_tangent,
+                    ..
+                },
+                CurveKeyKind::Linear,
+            ) => cubicf(self.value, other.value, t, *left_tangent, 0.0),
+
+            // Cubic-to-cubic or cubic-to-bezier
+            (
+                CurveKeyKind::Cubic {
+                    right_tangent: left_tangent,
+                    ..
+                },
+                CurveKeyKind::Cubic {
+                    left_tangent: right_tangent,
+                    ..
+                },
+            )
+            | (
+                CurveKeyKind::Cubic {
+                    right_tangent: left_tangent,
+                    ..
+                },
+                CurveKeyKind::Bezier {
+                    left_tangent: right_tangent,
+                    ..
+                },
+            ) => cubicf(self.value, other.value, t, *left_tangent, *right_tangent),
+
+            // Bezier-to-any
+            (CurveKeyKind::Bezier { .. }, _) => {
+                let (x1, y1, x2, y2) = self.bezier_handles(other);
+                let s = solve_bezier(x1, x2, t);
+                bezier_full(self.value, y1, y2, other.value, s)
+            }
+        }
+    }
+
+    // Derivative of the segment between this key and other key with respect to location.
+    fn derivative(&self, other: &Self, t: f32) -> f32 {
+        let span = other.location - self.location;
+        let derivative = match (&self.kind, &other.kind) {
+            (CurveKeyKind::Constant, _) => 0.0,
+            (CurveKeyKind::Linear, _) => other.value - self.value,
+            (
+                CurveKeyKind::Cubic {
+                    right_tangent: left_tangent,
+                    ..
+                },
+                CurveKeyKind::Constant,
+            )
+            | (
+                CurveKeyKind::Cubic {
+                    right_tangent: left_tangent,
+                    ..
+                },
+                CurveKeyKind::Linear,
+            ) => cubicf_derivative(self.value, other.value, t, *left_tangent, 0.0),
+            (
+                CurveKeyKind::Cubic {
+                    right_tangent: left_tangent,
+                    ..
+                },
+                CurveKeyKind::Cubic {
+                    left_tangent: right_tangent,
+                    ..
+                },
+            )
+            | (
+                CurveKeyKind::Cubic {
+                    right_tangent: left_tangent,
+                    ..
+                },
+                CurveKeyKind::Bezier {
+                    left_tangent: right_tangent,
+                    ..
+                },
+            ) => cubicf_derivative(self.value, other.value, t, *left_tangent, *right_tangent),
+            (CurveKeyKind::Bezier { .. }, _) => {
+                let (x1, y1, x2, y2) = self.bezier_handles(other);
+                let s = solve_bezier(x1, x2, t);
+                let dx = bezier_derivative(0.0, x1, x2, 1.0, s);
+                let dy = bezier_derivative(self.value, y1, y2, other.value, s);
+                if dx.abs() > f32::EPSILON {
+                    dy / dx
+                } else {
+                    0.0
+                }
+            }
+        };
+        derivative / span
+    }
+
+    // Integral of the segment between this key and other key from this key to given fraction
+    // of the segment.
+    fn integral(&self, other: &Self, t: f32) -> f32 {
+        let span = (other.location - self.location) * t;
+        match self.kind {
+            CurveKeyKind::Constant => self.value * span,
+            _ => {
+                GAUSS_LEGENDRE
+                    .iter()
+                    .map(|(x, weight)| weight * self.interpolate(other, x * t))
+                    .sum::<f32>()
+                    * span
+            }
+        }
+    }
+}
+
+/// Defines how a curve behaves outside of the range of its keys.
+#[derive(Visit, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CurveInfinity {
+    /// Value of the nearest key is used.
+    Clamp,
+    /// The curve is repeated.
+    Loop,
+    /// The curve is repeated, every other repetition is mirrored.
+    PingPong,
+    /// The curve is repeated, every repetition is offset by the difference between values of
+    /// last and first keys, so the curve continues smoothly.
+    LoopWithOffset,
+}
+
+impl Default for CurveInfinity {
+    fn default() -> Self {
+        Self::Clamp
+    }
+}
+
+// Maps given location into [begin; end] range according to given mode. Returns mapped location,
+// amount of periods by which the value must be offset and a flag that tells whether the curve
+// is mirrored at the location.
+fn wrap_location(location: f32, begin: f32, end: f32, mode: CurveInfinity) -> (f32, f32, bool) {
+    let period = end - begin;
+    if mode == CurveInfinity::Clamp || period <= f32::EPSILON {
+        return (location.clamp(begin, end), 0.0, false);
+    }
+    let cycle = ((location - begin) / period).floor();
+    let local = ((location - begin) - cycle * period).clamp(0.0, period);
+    match mode {
+        CurveInfinity::PingPong if cycle.rem_euclid(2.0) != 0.0 => (end - local, 0.0, true),
+        CurveInfinity::LoopWithOffset => (begin + local, cycle, false),
+        _ => (begin + local, 0.0, false),
+    }
+}
+
+// Helpers below are shared by curves of every kind of keys, `location` returns location of a
+// key. Keys must be sorted by location and there must be at least one key.
+
+fn keys_bounds<K>(keys: &[K], location: impl Fn(&K) -> f32) -> (f32, f32) {
+    (
+        location(keys.first().unwrap()),
+        location(keys.last().unwrap()),
+    )
+}
+
+// Maps given location into the range of keys, see `wrap_location`.
+fn wrap_keys<K>(
+    keys: &[K],
+    location: impl Fn(&K) -> f32,
+    pre_infinity: CurveInfinity,
+    post_infinity: CurveInfinity,
+    at: f32,
+) -> (f32, f32, bool) {
+    let (begin, end) = keys_bounds(keys, location);
+    if at < begin {
+        wrap_location(at, begin, end, pre_infinity)
+    } else if at > end {
+        wrap_location(at, begin, end, post_infinity)
+    } else {
+        (at, 0.0, false)
+    }
+}
+
+// Returns index of first key of the segment that contains given location (it must be in the
+// range of keys) along with the position of the location in the segment, `None` means the
+// location of last key.
+fn find_segment<K>(keys: &[K], location: impl Fn(&K) -> f32, at: f32) -> Option<(usize, f32)> {
+    let index = keys.partition_point(|key| location(key) <= at).max(1);
+    let begin = location(keys.get(index - 1)?);
+    let end = location(keys.get(index)?);
+    Some((index - 1, (at - begin) / (end - begin)))
+}
+
+// Whether given location is outside of the range of keys and the curve is clamped there, so
+// the derivative is zero.
+fn is_clamped_at<K>(
+    keys: &[K],
+    location: impl Fn(&K) -> f32,
+    pre_infinity: CurveInfinity,
+    post_infinity: CurveInfinity,
+    at: f32,
+) -> bool {
+    let (begin, end) = keys_bounds(keys, location);
+    (at < begin && pre_infinity == CurveInfinity::Clamp)
+        || (at > end && post_infinity == CurveInfinity::Clamp)
+}
+
+#[derive(Visit, Default, Clone, Debug, PartialEq)]
+pub struct Curve {
+    keys: Vec<CurveKey>,
+    #[visit(optional)] // Backward compatibility.
+    pre_infinity: CurveInfinity,
+    #[visit(optional)] // Backward compatibility.
+    post_infinity: CurveInfinity,
+}
+
+fn sort_keys(keys: &mut [CurveKey]) {
+    sort_keys_by_location(keys, |key| key.location);
+}
+
+fn sort_keys_by_location<K>(keys: &mut [K], location: impl Fn(&K) -> f32) {
+    keys.sort_by(|a, b| {
+        let (a, b) = (location(a), location(b));
+        if a > b {
+            Ordering::Greater
+        } else if a < b {
+            Ordering::Less
+        } else {
+            Ordering::Equal
+        }
+    });
+}
+
+impl From<Vec<CurveKey>> for Curve {
+    fn from(mut keys: Vec<CurveKey>) -> Self {
+        sort_keys(&mut keys);
+        Self {
+            keys,
+            ..Default::default()
+        }
+    }
+}
+
+impl Curve {
+    pub fn clear(&mut self) {
+        self.keys.clear()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn keys(&self) -> &[CurveKey] {
+        &self.keys
+    }
+
+    pub fn add_key(&mut self, new_key: CurveKey) {
+        self.keys.push(new_key);
+        sort_keys(&mut self.keys);
+    }
+
+    pub fn move_key(&mut self, key_id: usize, location: f32) {
+        if let Some(key) = self.keys.get_mut(key_id) {
+            key.location = location;
+            sort_keys(&mut self.keys);
+        }
+    }
+
+    /// Sets behaviour of the curve before its first key.
+    pub fn set_pre_infinity(&mut self, mode: CurveInfinity) {
+        self.pre_infinity = mode;
+    }
+
+    /// Returns behaviour of the curve before its first key.
+    pub fn pre_infinity(&self) -> CurveInfinity {
+        self.pre_infinity
+    }
+
+    /// Sets behaviour of the curve after its last key.
+    pub fn set_post_infinity(&mut self, mode: CurveInfinity) {
+        self.post_infinity = mode;
+    }
+
+    /// Returns behaviour of the curve after its last key.
+    pub fn post_infinity(&self) -> CurveInfinity {
+        self.post_infinity
+    }
+
+    fn bounds(&self) -> (f32, f32) {
+        keys_bounds(&self.keys, CurveKey::location)
+    }
+
+    fn wrap(&self, location: f32) -> (f32, f32, bool) {
+        wrap_keys(
+            &self.keys,
+            CurveKey::location,
+            self.pre_infinity,
+            self.post_infinity,
+            location,
+        )
+    }
+
+    fn segment(&self, location: f32) -> Option<(&CurveKey, &CurveKey, f32)> {
+        find_segment(&self.keys, CurveKey::location, location)
+            .map(|(index, t)| (&self.keys[index], &self.keys[index + 1], t))
+    }
+
+    fn offset(&self) -> f32 {
+        self.keys.last().unwrap().value - self.keys.first().unwrap().value
+    }
+
+    pub fn value_at(&self, location: f32) -> f32 {
+        if self.keys.is_empty() {
+            // Stub - zero
+            return Default::default();
+        } else if self.keys.len() == 1 {
+            // Single key - just return its value
+            return self.keys.first().unwrap().value;
+        }
+
+        let (location, cycles, _) = self.wrap(location);
+        let value = match self.segment(location) {
+            Some((pt_a, pt_b, t)) => pt_a.interpolate(pt_b, t),
+            None => self.keys.last().unwrap().value,
+        };
+        if cycles != 0.0 {
+            value + cycles * self.offset()
+        } else {
+            value
+        }
+    }
+
+    /// Returns derivative (slope) of the curve at given location.
+    pub fn derivative_at(&self, location: f32) -> f32 {
+        if self.keys.len() < 2 {
+            return 0.0;
+        }
+
+        if is_clamped_at(
+            &self.keys,
+            CurveKey::location,
+            self.pre_infinity,
+            self.post_infinity,
+            location,
+        ) {
+            return 0.0;
+        }
+
+        let (location, _, mirrored) = self.wrap(location);
+        let derivative = match self.segment(location) {
+            Some((pt_a, pt_b, t)) => pt_a.derivative(pt_b, t),
+            None => {
+                // Derivative at last key is the left-side derivative.
+                let pt_a = &self.keys[self.keys.len() - 2];
+                let pt_b = self.keys.last().unwrap();
+                pt_a.derivative(pt_b, 1.0)
+            }
+        };
+        if mirrored {
+            -derivative
+        } else {
+            derivative
+        }
+    }
+
+    // Integral from first key to given location in the range of keys.
+    fn partial_integral(&self, location: f32) -> f32 {
+        let mut integral = 0.0;
+        for pair in self.keys.windows(2) {
+            let (pt_a, pt_b) = (&pair[0], &pair[1]);
+            if location >= pt_b.location {
+                integral += pt_a.integral(pt_b, 1.0);
+            } else {
+                if location > pt_a.location {
+                    let t = (location - pt_a.location) / (pt_b.location - pt_a.location);
+                    integral += pt_a.integral(pt_b, t);
+                }
+                break;
+            }
+        }
+        integral
+    }
+
+    // Integral from first key to given location, negative for locations before first key.
+    fn antiderivative(&self, location: f32) -> f32 {
+        let (begin, end) = self.bounds();
+        if location >= begin && location <= end {
+            return self.partial_integral(location);
+        }
+
+        let first = self.keys.first().unwrap();
+        let last = self.keys.last().unwrap();
+        let full = self.partial_integral(end);
+        let period = end - begin;
+        let mode = if location < begin {
+            self.pre_infinity
+        } else {
+            self.post_infinity
+        };
+
+        if mode == CurveInfinity::Clamp || period <= f32::EPSILON {
+            return if location < begin {
+                (location - begin) * first.value
+            } else {
+                full + (location - end) * last.value
+            };
+        }
+
+        let cycle = ((location - begin) / period).floor();
+        let local = ((location - begin) - cycle * period).clamp(0.0, period);
+        match mode {
+            CurveInfinity::PingPong if cycle.rem_euclid(2.0) != 0.0 => {
+                cycle * full + full - self.partial_integral(end - local)
+            }
+            CurveInfinity::LoopWithOffset => {
+                let offset = self.offset();
+                cycle * full
+                    + offset * period * cycle * (cycle - 1.0) * 0.5
+                    + self.partial_integral(begin + local)
+                    + cycle * offset * local
+            }
+            _ => cycle * full + self.partial_integral(begin + local),
+        }
+    }
+
+    /// Returns integral (area under the curve) of the curve over given range of locations.
+    pub fn integral(&self, from: f32, to: f32) -> f32 {
+        match self.keys.len() {
+            0 => 0.0,
+            1 => self.keys[0].value * (to - from),
+            _ => self.antiderivative(to) - self.antiderivative(from),
+        }
+    }
+
+    /// Bakes the curve into a lookup table with given amount of evenly distributed samples
+    /// (at least two) over the range of keys. Baked curve is much faster to evaluate, but it is
+    /// only an approximation - every segment is linear and steps of constant keys are smoothed.
+    pub fn bake(&self, samples: usize) -> BakedCurve {
+        let (begin, end) = if self.keys.is_empty() {
+            (0.0, 0.0)
+        } else {
+            self.bounds()
+        };
+        let samples = samples.max(2);
+        let step = (end - begin) / (samples - 1) as f32;
+        let values = (0..samples)
+            .map(|i| self.value_at(begin + step * i as f32))
+            .collect();
+        BakedCurve {
+            begin,
+            end,
+            scale: if step > 0.0 { 1.0 / step } else { 0.0 },
+            values,
+            pre_infinity: self.pre_infinity,
+            post_infinity: self.post_infinity,
+        }
+    }
+}
+
+/// Curve baked into a lookup table, see [`Curve::bake`].
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct BakedCurve {
+    begin: f32,
+    end: f32,
+    scale: f32,
+    values: Vec<f32>,
+    pre_infinity: CurveInfinity,
+    post_infinity: CurveInfinity,
+}
+
+impl BakedCurve {
+    /// Returns samples of the curve.
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+
+    /// Returns approximate value of the curve at given location.
+    pub fn value_at(&self, location: f32) -> f32 {
+        let (location, cycles, _) = if location < self.begin {
+            wrap_location(location, self.begin, self.end, self.pre_infinity)
+        } else if location > self.end {
+            wrap_location(location, self.begin, self.end, self.post_infinity)
+        } else {
+            (location, 0.0, false)
+        };
+
+        let (first, last) = match (self.values.first(), self.values.last()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return 0.0,
+        };
+
+        let position = (location - self.begin) * self.scale;
+        let index = (position as usize).min(self.values.len() - 1);
+        let value = match self.values.get(index + 1) {
+            Some(next) => lerpf(self.values[index], *next, position - index as f32),
+            None => self.values[index],
+        };
+        value + cycles * (last - first)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::curve::{Curve, CurveInfinity, CurveKey, CurveKeyKind};
+
+    fn assert_near(a: f32, b: f32) {
+        assert!((a - b).abs() < 1.0e-3, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn curve_infinity() {
+        let mut curve = Curve::from(vec![
+            CurveKey::new(0.0, 0.0, CurveKeyKind::Linear),
+            CurveKey::new(1.0, 1.0, CurveKeyKind::Linear),
+        ]);
+
+        assert_near(curve.value_at(-1.0), 0.0);
+        assert_near(curve.value_at(2.0), 1.0);
+        assert_near(curve.derivative_at(0.5), 1.0);
+        assert_near(curve.derivative_at(2.0), 0.0);
+        assert_near(curve.integral(0.0, 1.0), 0.5);
+        assert_near(curve.integral(-1.0, 2.0), 1.5);
+
+        curve.set_post_infinity(CurveInfinity::Loop);
+        curve.set_pre_infinity(CurveInfinity::PingPong);
+        assert_near(curve.value_at(1.25), 0.25);
+        assert_near(curve.value_at(-0.25), 0.25);
+        assert_near(curve.value_at(-1.25), 0.75);
+        assert_near(curve.derivative_at(-0.25), -1.0);
+        assert_near(curve.integral(0.0, 3.0), 1.5);
+        assert_near(curve.integral(-2.0, 0.0), 1.0);
+        assert_near(curve.integral(-0.5, 0.0), 0.125);
+
+        curve.set_post_infinity(CurveInfinity::LoopWithOffset);
+        curve.set_pre_infinity(CurveInfinity::LoopWithOffset);
+        assert_near(curve.value_at(2.5), 2.5);
+        assert_near(curve.value_at(-1.5), -1.5);
+        assert_near(curve.integral(0.0, 3.0), 4.5);
+        assert_near(curve.integral(-2.0, 0.0), -2.0);
+    }
+
+    #[test]
+    fn curve_bezier() {
+        // Bezier with default weights is the same as straight line if tangents are along it.
+        let curve = Curve::from(vec![
+            CurveKey::new(
+                0.0,
+                0.0,
+                CurveKeyKind::new_bezier(0.0, std::f32::consts::FRAC_PI_4, 1.0 / 3.0, 1.0 / 3.0),
+            ),
+            CurveKey::new(
+                2.0,
+                2.0,
+                CurveKeyKind::new_bezier(std::f32::consts::FRAC_PI_4, 0.0, 1.0 / 3.0, 1.0 / 3.0),
+            ),
+        ]);
+        for i in 0..=10 {
+            let x = i as f32 * 0.2;
+            assert_near(curve.value_at(x), x);
+            assert_near(curve.derivative_at(x), 1.0);
+        }
+        assert_near(curve.integral(0.0, 2.0), 2.0);
+
+        // Heavy weights pull the curve towards tangents.
+        let curve = Curve::from(vec![
+            CurveKey::new(0.0, 0.0, CurveKeyKind::new_bezier(0.0, 0.0, 1.0, 1.0)),
+            CurveKey::new(1.0, 1.0, CurveKeyKind::new_bezier(0.0, 0.0, 1.0, 1.0)),
+        ]);
+        assert_near(curve.value_at(0.5), 0.5);
+        assert!(curve.value_at(0.1) < 0.01);
+        assert!(curve.value_at(0.9) > 0.99);
+    }
+
+    #[test]
+    fn curve_bezier_to_cubic() {
+        // Bezier with default weight followed by a cubic key is the same as cubic segment.
+        let curve = Curve::from(vec![
+            CurveKey::new(
+                0.0,
+                0.0,
+                CurveKeyKind::new_bezier(0.0, 0.0, 1.0 / 3.0, 1.0 / 3.0),
+            ),
+            CurveKey::new(2.0, 1.0, CurveKeyKind::new_cubic(1.0, 0.0)),
+        ]);
+        let reference = Curve::from(vec![
+            CurveKey::new(0.0, 0.0, CurveKeyKind::new_cubic(0.0, 0.0)),
+            CurveKey::new(2.0, 1.0, CurveKeyKind::new_cubic(1.0, 0.0)),
+        ]);
+        for i in 0..=10 {
+            let x = i as f32 * 0.2;
+            assert_near(curve.value_at(x), reference.value_at(x));
+            assert_near(curve.derivative_at(x), reference.derivative_at(x));
+        }
+        // Left tangent of the cubic key is not ignored, flat tangent would give 0.5 here.
+        assert!(curve.value_at(1.0) < 0.4);
+    }
+
+    #[test]
+    fn curve_bake() {
+        let mut curve = Curve::from(vec![
+            CurveKey::new(0.0, 0.0, CurveKeyKind::new_cubic(0.0, 0.0)),
+            CurveKey::new(1.0, 2.0, CurveKeyKind::new_cubic(0.0, 0.0)),
+            CurveKey::new(3.0, 1.0, CurveKeyKind::Linear),
+        ]);
+        curve.set_post_infinity(CurveInfinity::Loop);
+
+        let baked = curve.bake(301);
+        assert_eq!(baked.values().len(), 301);
+        for i in 0..=80 {
+            let x = i as f32 * 0.05 - 0.5;
+            assert!((baked.value_at(x) - curve.value_at(x)).abs() < 0.01);
+        }
+    }
+}
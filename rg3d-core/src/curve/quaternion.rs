@@ -0,0 +1,276 @@
+//! Rotation curves, keys of such curves hold rotations which are interpolated along the shortest
+//! arc on the unit sphere.
+
+use crate::{
+    algebra::UnitQuaternion,
+    curve::{find_segment, sort_keys_by_location, wrap_keys, CurveInfinity},
+    visitor::prelude::*,
+};
+use uuid::Uuid;
+
+/// Spherical linear interpolation along the shortest arc.
+pub fn slerp(a: &UnitQuaternion<f32>, b: &UnitQuaternion<f32>, t: f32) -> UnitQuaternion<f32> {
+    let b = same_hemisphere(a, b);
+    // Rotations are almost the same - use normalized linear interpolation instead.
+    a.try_slerp(&b, t, 1.0e-6).unwrap_or_else(|| a.nlerp(&b, t))
+}
+
+fn same_hemisphere(a: &UnitQuaternion<f32>, b: &UnitQuaternion<f32>) -> UnitQuaternion<f32> {
+    if a.coords.dot(&b.coords) < 0.0 {
+        UnitQuaternion::new_unchecked(-b.into_inner())
+    } else {
+        *b
+    }
+}
+
+// Inner control point of squad at `current` key.
+// See "Quaternions, Interpolation and Animation" by Erik B. Dam et al.
+fn squad_control(
+    prev: &UnitQuaternion<f32>,
+    current: &UnitQuaternion<f32>,
+    next: &UnitQuaternion<f32>,
+) -> UnitQuaternion<f32> {
+    let identity = UnitQuaternion::identity();
+    let inverse = current.inverse();
+    let to_next = same_hemisphere(&identity, &(inverse * next)).ln();
+    let to_prev = same_hemisphere(&identity, &(inverse * prev)).ln();
+    current * UnitQuaternion::new_normalize(((to_next + to_prev) * -0.25).exp())
+}
+
+/// Spherical cubic interpolation between `a` and `b` with inner control points `sa` and `sb`.
+pub fn squad(
+    a: &UnitQuaternion<f32>,
+    b: &UnitQuaternion<f32>,
+    sa: &UnitQuaternion<f32>,
+    sb: &UnitQuaternion<f32>,
+    t: f32,
+) -> UnitQuaternion<f32> {
+    slerp(&slerp(a, b, t), &slerp(sa, sb, t), 2.0 * t * (1.0 - t))
+}
+
+/// Defines how rotation of a key is interpolated to the rotation of next key.
+#[derive(Visit, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum QuaternionKeyKind {
+    /// Rotation of the key is held until next key.
+    Constant,
+    /// Spherical linear interpolation to next key.
+    Linear,
+    /// Spherical cubic interpolation to next key, angular velocity changes smoothly over keys.
+    Squad,
+}
+
+impl Default for QuaternionKeyKind {
+    fn default() -> Self {
+        Self::Constant
+    }
+}
+
+/// A key of [`QuaternionCurve`], holds a rotation at some location of the curve.
+#[derive(Visit, Clone, Default, Debug, PartialEq)]
+pub struct QuaternionCurveKey {
+    pub id: Uuid,
+    location: f32,
+    pub value: UnitQuaternion<f32>,
+    pub kind: QuaternionKeyKind,
+}
+
+impl QuaternionCurveKey {
+    pub fn new(location: f32, value: UnitQuaternion<f32>, kind: QuaternionKeyKind) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            location,
+            value,
+            kind,
+        }
+    }
+
+    pub fn location(&self) -> f32 {
+        self.location
+    }
+}
+
+/// Curve of rotations.
+#[derive(Visit, Default, Clone, Debug, PartialEq)]
+pub struct QuaternionCurve {
+    keys: Vec<QuaternionCurveKey>,
+    pre_infinity: CurveInfinity,
+    post_infinity: CurveInfinity,
+}
+
+impl From<Vec<QuaternionCurveKey>> for QuaternionCurve {
+    fn from(mut keys: Vec<QuaternionCurveKey>) -> Self {
+        sort_keys_by_location(&mut keys, |key| key.location);
+        Self {
+            keys,
+            pre_infinity: Default::default(),
+            post_infinity: Default::default(),
+        }
+    }
+}
+
+impl QuaternionCurve {
+    pub fn clear(&mut self) {
+        self.keys.clear()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn keys(&self) -> &[QuaternionCurveKey] {
+        &self.keys
+    }
+
+    pub fn add_key(&mut self, new_key: QuaternionCurveKey) {
+        self.keys.push(new_key);
+        sort_keys_by_location(&mut self.keys, |key| key.location);
+    }
+
+    pub fn move_key(&mut self, key_id: usize, location: f32) {
+        if let Some(key) = self.keys.get_mut(key_id) {
+            key.location = location;
+            sort_keys_by_location(&mut self.keys, |key| key.location);
+        }
+    }
+
+    /// Sets behaviour of the curve before its first key.
+    pub fn set_pre_infinity(&mut self, mode: CurveInfinity) {
+        self.pre_infinity = mode;
+    }
+
+    /// Returns behaviour of the curve before its first key.
+    pub fn pre_infinity(&self) -> CurveInfinity {
+        self.pre_infinity
+    }
+
+    /// Sets behaviour of the curve after its last key.
+    pub fn set_post_infinity(&mut self, mode: CurveInfinity) {
+        self.post_infinity = mode;
+    }
+
+    /// Returns behaviour of the curve after its last key.
+    pub fn post_infinity(&self) -> CurveInfinity {
+        self.post_infinity
+    }
+
+    fn interpolate(&self, index: usize, t: f32) -> UnitQuaternion<f32> {
+        let pt_a = &self.keys[index];
+        let pt_b = &self.keys[index + 1];
+        match pt_a.kind {
+            QuaternionKeyKind::Constant => {
+                if t.eq(&1.0) {
+                    pt_b.value
+                } else {
+                    pt_a.value
+                }
+            }
+            QuaternionKeyKind::Linear => slerp(&pt_a.value, &pt_b.value, t),
+            QuaternionKeyKind::Squad => {
+                // Outer keys are used as their own neighbours.
+                let prev = &self.keys[index.saturating_sub(1)].value;
+                let next = &self.keys[(index + 2).min(self.keys.len() - 1)].value;
+                let sa = squad_control(prev, &pt_a.value, &pt_b.value);
+                let sb = squad_control(&pt_a.value, &pt_b.value, next);
+                squad(&pt_a.value, &pt_b.value, &sa, &sb, t)
+            }
+        }
+    }
+
+    pub fn value_at(&self, location: f32) -> UnitQuaternion<f32> {
+        if self.keys.is_empty() {
+            return UnitQuaternion::identity();
+        } else if self.keys.len() == 1 {
+            return self.keys.first().unwrap().value;
+        }
+
+        let first = self.keys.first().unwrap();
+        let last = self.keys.last().unwrap();
+        let (location, cycles, _) = wrap_keys(
+            &self.keys,
+            QuaternionCurveKey::location,
+            self.pre_infinity,
+            self.post_infinity,
+            location,
+        );
+
+        let value = match find_segment(&self.keys, QuaternionCurveKey::location, location) {
+            Some((index, t)) => self.interpolate(index, t),
+            None => last.value,
+        };
+
+        if cycles != 0.0 {
+            // Every repetition continues from the rotation of last key.
+            let offset = last.value * first.value.inverse();
+            offset.powf(cycles) * value
+        } else {
+            value
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algebra::{UnitQuaternion, Vector3},
+        curve::{
+            quaternion::{QuaternionCurve, QuaternionCurveKey, QuaternionKeyKind},
+            CurveInfinity,
+        },
+    };
+
+    fn rotation(degrees: f32) -> UnitQuaternion<f32> {
+        UnitQuaternion::from_axis_angle(&Vector3::y_axis(), degrees.to_radians())
+    }
+
+    fn assert_rotation(a: UnitQuaternion<f32>, degrees: f32) {
+        assert!(a.angle_to(&rotation(degrees)) < 1.0e-3, "{:?}", a);
+    }
+
+    #[test]
+    fn quaternion_curve() {
+        let mut curve = QuaternionCurve::from(vec![
+            QuaternionCurveKey::new(0.0, rotation(0.0), QuaternionKeyKind::Linear),
+            QuaternionCurveKey::new(1.0, rotation(90.0), QuaternionKeyKind::Linear),
+        ]);
+        assert_rotation(curve.value_at(0.5), 45.0);
+        assert_rotation(curve.value_at(2.0), 90.0);
+
+        curve.set_post_infinity(CurveInfinity::LoopWithOffset);
+        assert_rotation(curve.value_at(1.5), 135.0);
+
+        let curve = QuaternionCurve::from(vec![
+            QuaternionCurveKey::new(0.0, rotation(0.0), QuaternionKeyKind::Constant),
+            QuaternionCurveKey::new(1.0, rotation(90.0), QuaternionKeyKind::Linear),
+        ]);
+        assert_rotation(curve.value_at(0.9), 0.0);
+    }
+
+    #[test]
+    fn quaternion_curve_squad() {
+        let curve = QuaternionCurve::from(vec![
+            QuaternionCurveKey::new(0.0, rotation(0.0), QuaternionKeyKind::Squad),
+            QuaternionCurveKey::new(1.0, rotation(90.0), QuaternionKeyKind::Squad),
+            QuaternionCurveKey::new(2.0, rotation(180.0), QuaternionKeyKind::Squad),
+            QuaternionCurveKey::new(3.0, rotation(270.0), QuaternionKeyKind::Squad),
+            QuaternionCurveKey::new(4.0, rotation(300.0), QuaternionKeyKind::Squad),
+        ]);
+
+        // Passes through keys.
+        assert_rotation(curve.value_at(0.0), 0.0);
+        assert_rotation(curve.value_at(1.0), 90.0);
+        assert_rotation(curve.value_at(2.0), 180.0);
+        assert_rotation(curve.value_at(3.0), 270.0);
+        assert_rotation(curve.value_at(4.0), 300.0);
+
+        // Uniform rotation stays uniform.
+        assert_rotation(curve.value_at(1.5), 135.0);
+
+        // Angular velocity changes smoothly, so the curve is ahead of linear interpolation
+        // while it slows down.
+        let angle = curve
+            .value_at(3.5)
+            .angle_to(&curve.value_at(3.0))
+            .to_degrees();
+        assert!(angle > 15.0 && angle < 30.0, "{}", angle);
+    }
+}
@@ -0,0 +1,345 @@
+//! Multi-channel curves, every key of such curve holds a vector and all channels of the vector
+//! share the same key locations. Each channel is evaluated exactly as [`Curve`] would do it.
+
+use crate::{
+    algebra::{Vector2, Vector3, Vector4},
+    curve::{
+        find_segment, is_clamped_at, sort_keys_by_location, wrap_keys, Curve, CurveInfinity,
+        CurveKey, CurveKeyKind,
+    },
+    visitor::prelude::*,
+};
+use std::fmt::Debug;
+use uuid::Uuid;
+
+/// A value with a fixed amount of independent channels that can be animated by [`VectorCurve`].
+pub trait CurveChannels: Copy + Default + Debug + PartialEq + Visit {
+    /// Amount of channels.
+    const COUNT: usize;
+
+    /// Returns value of a channel with given index.
+    fn channel(&self, index: usize) -> f32;
+
+    /// Sets value of a channel with given index.
+    fn set_channel(&mut self, index: usize, value: f32);
+}
+
+macro_rules! impl_curve_channels {
+    ($($ty:ty => $count:expr),*) => {
+        $(
+            impl CurveChannels for $ty {
+                const COUNT: usize = $count;
+
+                fn channel(&self, index: usize) -> f32 {
+                    self[index]
+                }
+
+                fn set_channel(&mut self, index: usize, value: f32) {
+                    self[index] = value;
+                }
+            }
+        )*
+    };
+}
+
+impl_curve_channels!(Vector2<f32> => 2, Vector3<f32> => 3, Vector4<f32> => 4);
+
+/// Same as [`CurveKeyKind`], but tangents are defined for every channel.
+#[derive(Visit, Clone, Debug, PartialEq)]
+pub enum VectorKeyKind<V: CurveChannels> {
+    /// Value of the key is held until next key, see [`CurveKeyKind::Constant`].
+    Constant,
+    /// Linear interpolation to next key, see [`CurveKeyKind::Linear`].
+    Linear,
+    /// Cubic interpolation to next key, see [`CurveKeyKind::Cubic`].
+    Cubic {
+        /// A `tan(angle)` of left tangent of every channel.
+        left_tangent: V,
+        /// A `tan(angle)` of right tangent of every channel.
+        right_tangent: V,
+    },
+    /// Bezier interpolation to next key, see [`CurveKeyKind::Bezier`].
+    Bezier {
+        /// A `tan(angle)` of left tangent of every channel.
+        left_tangent: V,
+        /// A `tan(angle)` of right tangent of every channel.
+        right_tangent: V,
+        /// See [`CurveKeyKind::Bezier`].
+        left_weight: f32,
+        /// See [`CurveKeyKind::Bezier`].
+        right_weight: f32,
+    },
+}
+
+impl<V: CurveChannels> Default for VectorKeyKind<V> {
+    fn default() -> Self {
+        Self::Constant
+    }
+}
+
+impl<V: CurveChannels> VectorKeyKind<V> {
+    /// Returns kind of a key of given channel.
+    pub fn channel(&self, index: usize) -> CurveKeyKind {
+        match self {
+            Self::Constant => CurveKeyKind::Constant,
+            Self::Linear => CurveKeyKind::Linear,
+            Self::Cubic {
+                left_tangent,
+                right_tangent,
+            } => CurveKeyKind::Cubic {
+                left_tangent: left_tangent.channel(index),
+                right_tangent: right_tangent.channel(index),
+            },
+            Self::Bezier {
+                left_tangent,
+                right_tangent,
+                left_weight,
+                right_weight,
+            } => CurveKeyKind::Bezier {
+                left_tangent: left_tangent.channel(index),
+                right_tangent: right_tangent.channel(index),
+                left_weight: *left_weight,
+                right_weight: *right_weight,
+            },
+        }
+    }
+}
+
+#[derive(Visit, Clone, Default, Debug, PartialEq)]
+pub struct VectorCurveKey<V: CurveChannels> {
+    pub id: Uuid,
+    location: f32,
+    pub value: V,
+    pub kind: VectorKeyKind<V>,
+}
+
+impl<V: CurveChannels> VectorCurveKey<V> {
+    pub fn new(location: f32, value: V, kind: VectorKeyKind<V>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            location,
+            value,
+            kind,
+        }
+    }
+
+    pub fn location(&self) -> f32 {
+        self.location
+    }
+
+    // Temporary scalar key, it is not stored anywhere so it does not need a real id.
+    fn channel_key(&self, index: usize) -> CurveKey {
+        CurveKey {
+            id: Uuid::nil(),
+            location: self.location,
+            value: self.value.channel(index),
+            kind: self.kind.channel(index),
+        }
+    }
+
+    pub fn interpolate(&self, other: &Self, t: f32) -> V {
+        let mut value = V::default();
+        for i in 0..V::COUNT {
+            value.set_channel(i, self.channel_key(i).interpolate(&other.channel_key(i), t));
+        }
+        value
+    }
+
+    fn derivative(&self, other: &Self, t: f32) -> V {
+        let mut derivative = V::default();
+        for i in 0..V::COUNT {
+            derivative.set_channel(i, self.channel_key(i).derivative(&other.channel_key(i), t));
+        }
+        derivative
+    }
+}
+
+/// Curve of vector values, see module docs.
+#[derive(Visit, Default, Clone, Debug, PartialEq)]
+pub struct VectorCurve<V: CurveChannels> {
+    keys: Vec<VectorCurveKey<V>>,
+    pre_infinity: CurveInfinity,
+    post_infinity: CurveInfinity,
+}
+
+/// Curve of 3D vectors, for example positions or scales.
+pub type Curve3 = VectorCurve<Vector3<f32>>;
+
+/// Curve of 4D vectors, for example colors.
+pub type Curve4 = VectorCurve<Vector4<f32>>;
+
+impl<V: CurveChannels> From<Vec<VectorCurveKey<V>>> for VectorCurve<V> {
+    fn from(mut keys: Vec<VectorCurveKey<V>>) -> Self {
+        sort_keys_by_location(&mut keys, |key| key.location);
+        Self {
+            keys,
+            pre_infinity: Default::default(),
+            post_infinity: Default::default(),
+        }
+    }
+}
+
+impl<V: CurveChannels> VectorCurve<V> {
+    pub fn clear(&mut self) {
+        self.keys.clear()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn keys(&self) -> &[VectorCurveKey<V>] {
+        &self.keys
+    }
+
+    pub fn add_key(&mut self, new_key: VectorCurveKey<V>) {
+        self.keys.push(new_key);
+        sort_keys_by_location(&mut self.keys, |key| key.location);
+    }
+
+    pub fn move_key(&mut self, key_id: usize, location: f32) {
+        if let Some(key) = self.keys.get_mut(key_id) {
+            key.location = location;
+            sort_keys_by_location(&mut self.keys, |key| key.location);
+        }
+    }
+
+    /// Sets behaviour of the curve before its first key.
+    pub fn set_pre_infinity(&mut self, mode: CurveInfinity) {
+        self.pre_infinity = mode;
+    }
+
+    /// Returns behaviour of the curve before its first key.
+    pub fn pre_infinity(&self) -> CurveInfinity {
+        self.pre_infinity
+    }
+
+    /// Sets behaviour of the curve after its last key.
+    pub fn set_post_infinity(&mut self, mode: CurveInfinity) {
+        self.post_infinity = mode;
+    }
+
+    /// Returns behaviour of the curve after its last key.
+    pub fn post_infinity(&self) -> CurveInfinity {
+        self.post_infinity
+    }
+
+    /// Returns scalar curve of given channel, it could be used to edit a channel in the curve
+    /// editor for example.
+    pub fn channel(&self, index: usize) -> Curve {
+        let mut curve = Curve::from(
+            self.keys
+                .iter()
+                .map(|key| CurveKey {
+                    id: key.id,
+                    ..key.channel_key(index)
+                })
+                .collect::<Vec<_>>(),
+        );
+        curve.set_pre_infinity(self.pre_infinity);
+        curve.set_post_infinity(self.post_infinity);
+        curve
+    }
+
+    fn wrap(&self, location: f32) -> (f32, f32, bool) {
+        wrap_keys(
+            &self.keys,
+            VectorCurveKey::location,
+            self.pre_infinity,
+            self.post_infinity,
+            location,
+        )
+    }
+
+    fn segment(&self, location: f32) -> Option<(&VectorCurveKey<V>, &VectorCurveKey<V>, f32)> {
+        find_segment(&self.keys, VectorCurveKey::location, location)
+            .map(|(index, t)| (&self.keys[index], &self.keys[index + 1], t))
+    }
+
+    pub fn value_at(&self, location: f32) -> V {
+        if self.keys.is_empty() {
+            return Default::default();
+        } else if self.keys.len() == 1 {
+            return self.keys.first().unwrap().value;
+        }
+
+        let (location, cycles, _) = self.wrap(location);
+        let mut value = match self.segment(location) {
+            Some((pt_a, pt_b, t)) => pt_a.interpolate(pt_b, t),
+            None => self.keys.last().unwrap().value,
+        };
+        if cycles != 0.0 {
+            let first = self.keys.first().unwrap().value;
+            let last = self.keys.last().unwrap().value;
+            for i in 0..V::COUNT {
+                let offset = last.channel(i) - first.channel(i);
+                value.set_channel(i, value.channel(i) + cycles * offset);
+            }
+        }
+        value
+    }
+
+    /// Returns derivative of every channel of the curve at given location.
+    pub fn derivative_at(&self, location: f32) -> V {
+        if self.keys.len() < 2 {
+            return Default::default();
+        }
+
+        if is_clamped_at(
+            &self.keys,
+            VectorCurveKey::location,
+            self.pre_infinity,
+            self.post_infinity,
+            location,
+        ) {
+            return Default::default();
+        }
+
+        let (location, _, mirrored) = self.wrap(location);
+        let mut derivative = match self.segment(location) {
+            Some((pt_a, pt_b, t)) => pt_a.derivative(pt_b, t),
+            None => {
+                let pt_a = &self.keys[self.keys.len() - 2];
+                let pt_b = self.keys.last().unwrap();
+                pt_a.derivative(pt_b, 1.0)
+            }
+        };
+        if mirrored {
+            for i in 0..V::COUNT {
+                derivative.set_channel(i, -derivative.channel(i));
+            }
+        }
+        derivative
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algebra::Vector3,
+        curve::{
+            vector::{Curve3, VectorCurveKey, VectorKeyKind},
+            CurveInfinity,
+        },
+    };
+
+    #[test]
+    fn vector_curve() {
+        let mut curve = Curve3::from(vec![
+            VectorCurveKey::new(1.0, Vector3::new(1.0, 0.0, 5.0), VectorKeyKind::Linear),
+            VectorCurveKey::new(0.0, Vector3::new(0.0, 2.0, 5.0), VectorKeyKind::Linear),
+        ]);
+
+        assert_eq!(curve.value_at(0.5), Vector3::new(0.5, 1.0, 5.0));
+        assert_eq!(curve.derivative_at(0.5), Vector3::new(1.0, -2.0, 0.0));
+        assert_eq!(curve.value_at(2.0), Vector3::new(1.0, 0.0, 5.0));
+
+        curve.set_post_infinity(CurveInfinity::LoopWithOffset);
+        assert_eq!(curve.value_at(1.5), Vector3::new(1.5, -1.0, 5.0));
+
+        let channel = curve.channel(1);
+        assert_eq!(channel.keys().len(), 2);
+        assert_eq!(channel.value_at(0.25), 1.5);
+        assert_eq!(channel.post_infinity(), CurveInfinity::LoopWithOffset);
+    }
+}
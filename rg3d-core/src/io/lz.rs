@@ -0,0 +1,209 @@
+//! Tiny LZ77 codec used by pak archives.
+//!
+//! Compressed block is a sequence of `token, [literal length], literals, offset, [match length]`
+//! records, where high four bits of the token is the amount of literals and low four bits is
+//! the length of a match minus [`MIN_MATCH`]. Value of 15 means that length continues in next
+//! bytes, each next byte is added to length until a byte is not 255. Offset is a little-endian
+//! u16 distance back to the beginning of a match. Last record has literals only. The layout
+//! is borrowed from LZ4, it decompresses very fast, which is what we need for loading assets.
+
+const MIN_MATCH: usize = 4;
+const HASH_LOG: u32 = 16;
+const MAX_OFFSET: usize = u16::MAX as usize;
+
+fn read_u32(data: &[u8], position: usize) -> u32 {
+    u32::from_le_bytes([
+        data[position],
+        data[position + 1],
+        data[position + 2],
+        data[position + 3],
+    ])
+}
+
+fn hash(sequence: u32) -> usize {
+    (sequence.wrapping_mul(2_654_435_761) >> (32 - HASH_LOG)) as usize
+}
+
+fn write_length(output: &mut Vec<u8>, mut length: usize) {
+    while length >= 255 {
+        output.push(255);
+        length -= 255;
+    }
+    output.push(length as u8);
+}
+
+fn write_record(output: &mut Vec<u8>, literals: &[u8], offset_and_length: Option<(u16, usize)>) {
+    let literal_nibble = literals.len().min(15) as u8;
+    let match_nibble =
+        offset_and_length.map_or(0, |(_, length)| (length - MIN_MATCH).min(15) as u8);
+    output.push((literal_nibble << 4) | match_nibble);
+    if literals.len() >= 15 {
+        write_length(output, literals.len() - 15);
+    }
+    output.extend_from_slice(literals);
+    if let Some((offset, length)) = offset_and_length {
+        output.extend_from_slice(&offset.to_le_bytes());
+        if length - MIN_MATCH >= 15 {
+            write_length(output, length - MIN_MATCH - 15);
+        }
+    }
+}
+
+/// Compresses given data.
+pub fn compress(input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len() / 2);
+    // Positions of sequences plus one, zero means no sequence.
+    let mut table = vec![0usize; 1 << HASH_LOG];
+    let mut anchor = 0;
+    let mut position = 0;
+
+    while position + MIN_MATCH <= input.len() {
+        let sequence = read_u32(input, position);
+        let slot = &mut table[hash(sequence)];
+        let candidate = slot.wrapping_sub(1);
+        *slot = position + 1;
+
+        if candidate != usize::MAX
+            && position - candidate <= MAX_OFFSET
+            && read_u32(input, candidate) == sequence
+        {
+            let mut length = MIN_MATCH;
+            while position + length < input.len()
+                && input[candidate + length] == input[position + length]
+            {
+                length += 1;
+            }
+
+            write_record(
+                &mut output,
+                &input[anchor..position],
+                Some(((position - candidate) as u16, length)),
+            );
+
+            position += length;
+            anchor = position;
+        } else {
+            position += 1;
+        }
+    }
+
+    write_record(&mut output, &input[anchor..], None);
+
+    output
+}
+
+fn read_length(input: &[u8], position: &mut usize, mut length: usize) -> Option<usize> {
+    loop {
+        let byte = *input.get(*position)?;
+        *position += 1;
+        length += byte as usize;
+        if byte != 255 {
+            return Some(length);
+        }
+    }
+}
+
+/// Decompresses given data, `size` is the size of decompressed data. Returns `None` if the data
+/// is malformed.
+pub fn decompress(input: &[u8], size: usize) -> Option<Vec<u8>> {
+    // Each byte of input adds at most 255 bytes to a length, so larger sizes are impossible and
+    // must not be used to allocate memory.
+    if size
+        > input
+            .len()
+            .saturating_mul(255)
+            .saturating_add(MIN_MATCH + 15)
+    {
+        return None;
+    }
+
+    let mut output = Vec::with_capacity(size);
+    let mut position = 0;
+
+    loop {
+        let token = *input.get(position)?;
+        position += 1;
+
+        let mut literal_length = (token >> 4) as usize;
+        if literal_length == 15 {
+            literal_length = read_length(input, &mut position, literal_length)?;
+        }
+        if output.len() + literal_length > size {
+            return None;
+        }
+        output.extend_from_slice(input.get(position..position + literal_length)?);
+        position += literal_length;
+
+        if position == input.len() {
+            break;
+        }
+
+        let offset =
+            u16::from_le_bytes([*input.get(position)?, *input.get(position + 1)?]) as usize;
+        position += 2;
+        if offset == 0 || offset > output.len() {
+            return None;
+        }
+
+        let mut match_length = (token & 0xF) as usize;
+        if match_length == 15 {
+            match_length = read_length(input, &mut position, match_length)?;
+        }
+        match_length += MIN_MATCH;
+        if output.len() + match_length > size {
+            return None;
+        }
+
+        // Match can overlap with itself, so copy byte by byte.
+        let start = output.len() - offset;
+        for i in 0..match_length {
+            let byte = output[start + i];
+            output.push(byte);
+        }
+    }
+
+    if output.len() == size {
+        Some(output)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::io::lz::{compress, decompress};
+
+    fn round_trip(data: &[u8]) -> usize {
+        let compressed = compress(data);
+        assert_eq!(decompress(&compressed, data.len()).unwrap(), data);
+        compressed.len()
+    }
+
+    #[test]
+    fn lz_round_trip() {
+        round_trip(b"");
+        round_trip(b"abc");
+        round_trip(b"abcdabcdabcdabcdabcd");
+
+        let repetitive = b"The quick brown fox jumps over the lazy dog. ".repeat(100);
+        assert!(round_trip(&repetitive) < repetitive.len() / 10);
+
+        let zeros = vec![0u8; 100_000];
+        assert!(round_trip(&zeros) < 1000);
+
+        let noise = (0..10_000u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect::<Vec<_>>();
+        round_trip(&noise);
+    }
+
+    #[test]
+    fn lz_malformed() {
+        let compressed = compress(&b"abcdabcdabcdabcdabcd".repeat(10));
+        assert!(decompress(&compressed, 10).is_none());
+        assert!(decompress(&compressed[..compressed.len() / 2], 200).is_none());
+        assert!(decompress(&[0x0F, 0xFF, 0xFF], 100).is_none());
+        assert!(decompress(&[0x10, b'a'], usize::MAX).is_none());
+        assert!(decompress(&compress(b"abc"), 2).is_none());
+    }
+}
@@ -1,6 +1,11 @@
-use std::io::Error;
+use crate::io::vfs::Vfs;
+use std::io::{Cursor, Error, Read, Seek};
 use std::path::Path;
 
+mod lz;
+pub mod pak;
+pub mod vfs;
+
 #[derive(Debug)]
 pub enum FileLoadError {
     Io(std::io::Error),
@@ -24,7 +29,13 @@ impl From<wasm_bindgen::JsValue> for FileLoadError {
     }
 }
 
+/// Loads whole file at given path. The file is looked up in mounted backends of [virtual file
+/// system](vfs) first, then in native file system (or fetched on WebAssembly).
 pub async fn load_file<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, FileLoadError> {
+    if let Some(result) = Vfs::global().read(path.as_ref()) {
+        return Ok(result?);
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     {
         use std::fs::File;
@@ -57,7 +68,40 @@ pub async fn load_file<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, FileLoadError
     }
 }
 
+/// Reader with random access, see [`open_file`].
+pub trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+/// Opens a file at given path for random access reading, see [`load_file`] for the order of
+/// lookup. Files from backends of [virtual file system](vfs) are read into memory at once, files
+/// from native file system are read on demand. Not supported on WebAssembly for native files.
+pub fn open_file<P: AsRef<Path>>(path: P) -> Result<Box<dyn ReadSeek>, FileLoadError> {
+    if let Some(result) = Vfs::global().read(path.as_ref()) {
+        return Ok(Box::new(Cursor::new(result?)));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let file = std::fs::File::open(path)?;
+        Ok(Box::new(std::io::BufReader::new(file)))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        Err(FileLoadError::Custom(format!(
+            "Unable to open {} for random access, it is not in virtual file system!",
+            path.as_ref().display()
+        )))
+    }
+}
+
+/// Checks if a file exists at given path, see [`load_file`] for the order of lookup.
 pub async fn exists<P: AsRef<Path>>(path: P) -> bool {
+    if Vfs::global().exists(path.as_ref()) {
+        return true;
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     {
         path.as_ref().exists()
@@ -0,0 +1,558 @@
+//! Pak archives - indexed archives of compressed files.
+//!
+//! Archive consists of a header, data of files and a table of contents:
+//!
+//! ```text
+//! magic: [u8; 8] = "RG3DPAK\0"
+//! version: u32
+//! entry_count: u32
+//! toc_offset: u64
+//! file data
+//! entry_count times (table of contents):
+//!     path_hash: u64 - FNV-1a hash of path
+//!     path_len: u32
+//!     path: [u8; path_len] - UTF-8 path with `/` separator
+//!     offset: u64
+//!     stored_size: u64 - size of (possibly compressed) data in archive
+//!     size: u64 - size of decompressed data
+//!     compression: u8
+//!     checksum: u32 - CRC32 of decompressed data
+//! ```
+//!
+//! All numbers are little-endian. Table of contents is sorted by path hash, so a file can be
+//! found by binary search. Files with same content share the same data in archive. Only the
+//! table of contents is read when an archive is opened, data of a file is read on request,
+//! decompressed and its checksum is verified.
+//!
+//! Use [`PakWriter`] to create an archive and [`PakArchive`] to read it, archive can be mounted
+//! into [virtual file system](crate::io::vfs) so the engine will load files from it transparently.
+
+use crate::io::{
+    lz,
+    vfs::{not_found, path_key, VfsBackend},
+    FileLoadError,
+};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{File, OpenOptions},
+    io::{self, BufReader, Cursor, ErrorKind, Read, Seek, SeekFrom, Write},
+    path::Path,
+    sync::Mutex,
+};
+
+/// Magic sequence at the beginning of every pak archive.
+pub const PAK_MAGIC: &[u8; 8] = b"RG3DPAK\0";
+
+/// Current version of pak archive format.
+pub const PAK_VERSION: u32 = 2;
+
+const HEADER_SIZE: u64 = 24;
+
+// Size of an entry of table of contents with empty path.
+const MIN_ENTRY_SIZE: u64 = 41;
+
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Compression method of a file in pak archive.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Compression {
+    /// Data is stored as is.
+    Stored = 0,
+    /// Data is compressed using LZ77-family algorithm, which is fast to decompress.
+    Lz = 1,
+}
+
+impl Compression {
+    fn from_u8(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Self::Stored),
+            1 => Some(Self::Lz),
+            _ => None,
+        }
+    }
+}
+
+/// An entry of table of contents of pak archive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PakEntry {
+    /// Path of the file, components are separated by `/`.
+    pub path: String,
+    /// Hash of the path.
+    pub path_hash: u64,
+    /// Offset of the file data from the beginning of the archive.
+    pub offset: u64,
+    /// Size of the file data in the archive.
+    pub stored_size: u64,
+    /// Size of the file.
+    pub size: u64,
+    /// Compression method of the file data.
+    pub compression: Compression,
+    /// CRC32 checksum of the file.
+    pub checksum: u32,
+}
+
+trait PakSource: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> PakSource for T {}
+
+/// Reader of pak archives, see module docs.
+pub struct PakArchive {
+    source: Mutex<Box<dyn PakSource>>,
+    entries: Vec<PakEntry>,
+}
+
+impl std::fmt::Debug for PakArchive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PakArchive")
+            .field("entries", &self.entries)
+            .finish()
+    }
+}
+
+impl PakArchive {
+    /// Opens an archive at given path of native file system.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, FileLoadError> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Creates an archive from data in memory, it could be useful on platforms without file
+    /// system (such as WebAssembly) - whole archive can be fetched at once.
+    pub fn from_memory(data: Vec<u8>) -> Result<Self, FileLoadError> {
+        Self::from_reader(Cursor::new(data))
+    }
+
+    /// Reads table of contents from given source. Sizes and offsets in the table are checked
+    /// against length of the source, so corrupted archive cannot cause huge allocations.
+    pub fn from_reader<R: Read + Seek + Send + 'static>(
+        mut source: R,
+    ) -> Result<Self, FileLoadError> {
+        let corrupted =
+            |what: &str| FileLoadError::Custom(format!("Corrupted pak archive: {}", what));
+
+        let archive_len = source.seek(SeekFrom::End(0))?;
+        source.seek(SeekFrom::Start(0))?;
+
+        let mut magic = [0; 8];
+        source.read_exact(&mut magic)?;
+        if &magic != PAK_MAGIC {
+            return Err(FileLoadError::Custom("Not a pak archive!".to_owned()));
+        }
+
+        let version = source.read_u32::<LittleEndian>()?;
+        if version != PAK_VERSION {
+            return Err(FileLoadError::Custom(format!(
+                "Unsupported pak archive version {}, expected {}",
+                version, PAK_VERSION
+            )));
+        }
+
+        let count = source.read_u32::<LittleEndian>()?;
+        let toc_offset = source.read_u64::<LittleEndian>()?;
+        if toc_offset > archive_len || count as u64 * MIN_ENTRY_SIZE > archive_len - toc_offset {
+            return Err(corrupted("table of contents is out of bounds"));
+        }
+        source.seek(SeekFrom::Start(toc_offset))?;
+
+        let mut entries = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let path_hash = source.read_u64::<LittleEndian>()?;
+            let path_len = source.read_u32::<LittleEndian>()?;
+            if path_len as u64 > archive_len - source.stream_position()? {
+                return Err(corrupted("path is out of bounds"));
+            }
+            let mut path = vec![0; path_len as usize];
+            source.read_exact(&mut path)?;
+            let path = String::from_utf8(path).map_err(|_| {
+                FileLoadError::Custom("Pak archive contains invalid path!".to_owned())
+            })?;
+            let offset = source.read_u64::<LittleEndian>()?;
+            let stored_size = source.read_u64::<LittleEndian>()?;
+            let size = source.read_u64::<LittleEndian>()?;
+            let compression = Compression::from_u8(source.read_u8()?).ok_or_else(|| {
+                FileLoadError::Custom(format!("Unknown compression method of {}", path))
+            })?;
+            let checksum = source.read_u32::<LittleEndian>()?;
+            match offset.checked_add(stored_size) {
+                Some(end) if end <= archive_len => (),
+                _ => return Err(corrupted(&format!("data of {} is out of bounds", path))),
+            }
+            if compression == Compression::Stored && size != stored_size {
+                return Err(corrupted(&format!("size of {} is invalid", path)));
+            }
+            entries.push(PakEntry {
+                path,
+                path_hash,
+                offset,
+                stored_size,
+                size,
+                compression,
+                checksum,
+            });
+        }
+
+        // Table should be sorted already, but do not rely on it - binary search depends on it.
+        entries.sort_by(|a, b| (a.path_hash, &a.path).cmp(&(b.path_hash, &b.path)));
+
+        Ok(Self {
+            source: Mutex::new(Box::new(source)),
+            entries,
+        })
+    }
+
+    /// Returns entry of table of contents for given path.
+    pub fn entry<P: AsRef<Path>>(&self, path: P) -> Option<&PakEntry> {
+        let path = path_key(path.as_ref());
+        let hash = fnv1a(path.as_bytes());
+        let first = self.entries.partition_point(|entry| entry.path_hash < hash);
+        self.entries[first..]
+            .iter()
+            .take_while(|entry| entry.path_hash == hash)
+            .find(|entry| entry.path == path)
+    }
+
+    /// Returns table of contents of the archive.
+    pub fn entries(&self) -> &[PakEntry] {
+        &self.entries
+    }
+}
+
+impl VfsBackend for PakArchive {
+    fn exists(&self, path: &Path) -> bool {
+        self.entry(path).is_some()
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let entry = self.entry(path).ok_or_else(|| not_found(path))?;
+
+        let mut stored = vec![0; entry.stored_size as usize];
+        {
+            let mut source = self.source.lock().unwrap();
+            source.seek(SeekFrom::Start(entry.offset))?;
+            source.read_exact(&mut stored)?;
+        }
+
+        let data = match entry.compression {
+            Compression::Stored => stored,
+            Compression::Lz => lz::decompress(&stored, entry.size as usize).ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Unable to decompress {} in pak archive", path.display()),
+                )
+            })?,
+        };
+
+        if crc32fast::hash(&data) != entry.checksum {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Checksum mismatch for {} in pak archive", path.display()),
+            ));
+        }
+
+        Ok(data)
+    }
+}
+
+/// Statistics of written archive.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PakStats {
+    /// Total amount of files in archive.
+    pub file_count: usize,
+    /// Amount of files that share data with other files.
+    pub duplicate_count: usize,
+    /// Total size of files.
+    pub total_size: u64,
+    /// Total size of data in archive.
+    pub stored_size: u64,
+}
+
+/// Creates pak archives, see module docs. Destination must be readable, because content of
+/// previously added files is read back to check if new file is a duplicate.
+///
+/// ```no_run
+/// use rg3d_core::io::pak::{Compression, PakWriter};
+///
+/// let mut writer = PakWriter::create("data.pak").unwrap();
+/// writer
+///     .add_file("data/config.txt", b"fullscreen = true", Compression::Lz)
+///     .unwrap();
+/// writer.finish().unwrap();
+/// ```
+pub struct PakWriter<W: Read + Write + Seek> {
+    writer: W,
+    position: u64,
+    entries: Vec<PakEntry>,
+    paths: HashSet<String>,
+    // Content hash, checksum and size of unique files mapped to indices of first entries with
+    // such content. There could be more than one entry in case of hash collision.
+    unique: HashMap<(u64, u32, u64), Vec<usize>>,
+    stats: PakStats,
+}
+
+fn content_key(data: &[u8]) -> (u64, u32, u64) {
+    (fnv1a(data), crc32fast::hash(data), data.len() as u64)
+}
+
+impl PakWriter<File> {
+    /// Creates new archive at given path of native file system.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::new(
+            OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(path)?,
+        )
+    }
+}
+
+impl<W: Read + Write + Seek> PakWriter<W> {
+    /// Creates new archive writer, that writes archive into given destination.
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        let mut header = Vec::with_capacity(HEADER_SIZE as usize);
+        header.extend_from_slice(PAK_MAGIC);
+        header.write_u32::<LittleEndian>(PAK_VERSION)?;
+        // Entry count and offset of the table will be written in `finish`.
+        header.write_u32::<LittleEndian>(0)?;
+        header.write_u64::<LittleEndian>(0)?;
+        writer.write_all(&header)?;
+        Ok(Self {
+            writer,
+            position: HEADER_SIZE,
+            entries: Default::default(),
+            paths: Default::default(),
+            unique: Default::default(),
+            stats: Default::default(),
+        })
+    }
+
+    // Checks if data of an entry is the same as given data.
+    fn has_same_content(&mut self, index: usize, data: &[u8]) -> io::Result<bool> {
+        let entry = &self.entries[index];
+        let mut stored = vec![0; entry.stored_size as usize];
+        self.writer.seek(SeekFrom::Start(entry.offset))?;
+        self.writer.read_exact(&mut stored)?;
+        self.writer.seek(SeekFrom::Start(self.position))?;
+        Ok(match entry.compression {
+            Compression::Stored => stored == data,
+            Compression::Lz => lz::decompress(&stored, data.len()).as_deref() == Some(data),
+        })
+    }
+
+    /// Adds new file to the archive. Data will be stored as is if it cannot be compressed with
+    /// given method. If the archive already has a file with the same content, only a reference to
+    /// the content will be written.
+    pub fn add_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        data: &[u8],
+        compression: Compression,
+    ) -> io::Result<()> {
+        let path = path_key(path.as_ref());
+        if self.paths.contains(&path) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("{} is already in pak archive", path),
+            ));
+        }
+
+        let content_key = content_key(data);
+        let (_, checksum, size) = content_key;
+
+        let mut duplicate = None;
+        if let Some(candidates) = self.unique.get(&content_key).cloned() {
+            for index in candidates {
+                if self.has_same_content(index, data)? {
+                    duplicate = Some(index);
+                    break;
+                }
+            }
+        }
+
+        let (offset, stored_size, compression) = match duplicate {
+            Some(index) => {
+                let original = &self.entries[index];
+                self.stats.duplicate_count += 1;
+                (original.offset, original.stored_size, original.compression)
+            }
+            None => {
+                let compressed = match compression {
+                    Compression::Stored => None,
+                    Compression::Lz => Some(lz::compress(data)).filter(|c| c.len() < data.len()),
+                };
+                let (stored, compression) = match compressed.as_ref() {
+                    Some(compressed) => (compressed.as_slice(), Compression::Lz),
+                    None => (data, Compression::Stored),
+                };
+                self.writer.write_all(stored)?;
+                let offset = self.position;
+                self.position += stored.len() as u64;
+                self.stats.stored_size += stored.len() as u64;
+                self.unique
+                    .entry(content_key)
+                    .or_default()
+                    .push(self.entries.len());
+                (offset, stored.len() as u64, compression)
+            }
+        };
+
+        self.stats.file_count += 1;
+        self.stats.total_size += size;
+        self.paths.insert(path.clone());
+        self.entries.push(PakEntry {
+            path_hash: fnv1a(path.as_bytes()),
+            path,
+            offset,
+            stored_size,
+            size,
+            compression,
+            checksum,
+        });
+
+        Ok(())
+    }
+
+    /// Returns statistics of files that were added so far.
+    pub fn stats(&self) -> PakStats {
+        self.stats
+    }
+
+    /// Writes table of contents and returns destination.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.entries
+            .sort_by(|a, b| (a.path_hash, &a.path).cmp(&(b.path_hash, &b.path)));
+
+        let toc_offset = self.position;
+        // Table is written at once, destination could be unbuffered.
+        let mut toc = Vec::new();
+        for entry in self.entries.iter() {
+            toc.write_u64::<LittleEndian>(entry.path_hash)?;
+            toc.write_u32::<LittleEndian>(entry.path.len() as u32)?;
+            toc.write_all(entry.path.as_bytes())?;
+            toc.write_u64::<LittleEndian>(entry.offset)?;
+            toc.write_u64::<LittleEndian>(entry.stored_size)?;
+            toc.write_u64::<LittleEndian>(entry.size)?;
+            toc.write_u8(entry.compression as u8)?;
+            toc.write_u32::<LittleEndian>(entry.checksum)?;
+        }
+        self.writer.write_all(&toc)?;
+
+        let mut counts = Vec::with_capacity(12);
+        counts.write_u32::<LittleEndian>(self.entries.len() as u32)?;
+        counts.write_u64::<LittleEndian>(toc_offset)?;
+        self.writer
+            .seek(SeekFrom::Start(PAK_MAGIC.len() as u64 + 4))?;
+        self.writer.write_all(&counts)?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::io::{
+        pak::{content_key, Compression, PakArchive, PakWriter},
+        vfs::{Vfs, VfsBackend},
+    };
+    use std::{convert::TryInto, io::Cursor, path::Path};
+
+    fn make_archive() -> Vec<u8> {
+        let mut writer = PakWriter::new(Cursor::new(Vec::new())).unwrap();
+        let text = b"Lorem ipsum dolor sit amet. ".repeat(50);
+        writer
+            .add_file("textures/a.png", b"aaaa", Compression::Lz)
+            .unwrap();
+        writer.add_file("b.txt", &text, Compression::Lz).unwrap();
+        writer
+            .add_file("./copy/b.txt", &text, Compression::Stored)
+            .unwrap();
+        assert!(writer.add_file("b.txt", b"", Compression::Lz).is_err());
+
+        let stats = writer.stats();
+        assert_eq!(stats.file_count, 3);
+        assert_eq!(stats.duplicate_count, 1);
+        assert!(stats.stored_size < stats.total_size / 2);
+
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn pak_round_trip() {
+        let archive = make_archive();
+        let pak = PakArchive::from_memory(archive).unwrap();
+        let text = b"Lorem ipsum dolor sit amet. ".repeat(50);
+
+        assert_eq!(pak.entries().len(), 3);
+        assert_eq!(pak.read(Path::new("textures/a.png")).unwrap(), b"aaaa");
+        assert_eq!(pak.read(Path::new("b.txt")).unwrap(), text);
+        assert_eq!(pak.read(Path::new("copy/b.txt")).unwrap(), text);
+        assert_eq!(
+            pak.entry("b.txt").unwrap().offset,
+            pak.entry("copy/b.txt").unwrap().offset
+        );
+        assert!(pak.read(Path::new("c.txt")).is_err());
+
+        let mut vfs = Vfs::default();
+        vfs.mount("", 0, pak);
+        assert_eq!(vfs.read("./textures/a.png").unwrap().unwrap(), b"aaaa");
+    }
+
+    #[test]
+    fn pak_corruption() {
+        let mut archive = make_archive();
+        let offset = PakArchive::from_memory(archive.clone())
+            .unwrap()
+            .entry("textures/a.png")
+            .unwrap()
+            .offset as usize;
+        archive[offset] = b'b';
+        let pak = PakArchive::from_memory(archive).unwrap();
+        assert!(pak.read(Path::new("textures/a.png")).is_err());
+        assert!(pak.read(Path::new("b.txt")).is_ok());
+    }
+
+    #[test]
+    fn pak_rejects_invalid_sizes() {
+        let archive = make_archive();
+        let toc_offset = u64::from_le_bytes(archive[16..24].try_into().unwrap()) as usize;
+        // First entry is "textures/a.png", its path is 14 bytes long.
+        let path_len = toc_offset + 8;
+        let stored_size = path_len + 4 + 14 + 8;
+
+        for (position, bytes) in [
+            (12, u32::MAX.to_le_bytes().to_vec()),
+            (16, u64::MAX.to_le_bytes().to_vec()),
+            (path_len, u32::MAX.to_le_bytes().to_vec()),
+            (stored_size, u64::MAX.to_le_bytes().to_vec()),
+        ]
+        .iter()
+        {
+            let mut corrupted = archive.clone();
+            corrupted[*position..*position + bytes.len()].copy_from_slice(bytes);
+            assert!(PakArchive::from_memory(corrupted).is_err());
+        }
+    }
+
+    #[test]
+    fn pak_compares_content_of_duplicates() {
+        let mut writer = PakWriter::new(Cursor::new(Vec::new())).unwrap();
+        writer.add_file("a.txt", b"aaaa", Compression::Lz).unwrap();
+        // Emulate hash collision of different files.
+        writer.unique.insert(content_key(b"bbbb"), vec![0]);
+        writer.add_file("b.txt", b"bbbb", Compression::Lz).unwrap();
+        writer.add_file("c.txt", b"aaaa", Compression::Lz).unwrap();
+        assert_eq!(writer.stats().duplicate_count, 1);
+
+        let pak = PakArchive::from_memory(writer.finish().unwrap().into_inner()).unwrap();
+        assert_eq!(pak.read(Path::new("a.txt")).unwrap(), b"aaaa");
+        assert_eq!(pak.read(Path::new("b.txt")).unwrap(), b"bbbb");
+        assert_eq!(pak.read(Path::new("c.txt")).unwrap(), b"aaaa");
+    }
+}
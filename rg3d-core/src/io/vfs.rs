@@ -0,0 +1,302 @@
+//! Virtual file system.
+//!
+//! Every file that is loaded via [`load_file`](super::load_file) (and so every resource loaded by
+//! the engine) is looked up in mounted backends first. Backend is a source of files - it could be
+//! a directory, a [pak archive](crate::io::pak::PakArchive) or a set of files in memory. Each backend is mounted
+//! at some mount point (a path prefix) with some priority, backends with higher priority are
+//! checked first, so it is possible to override files of packed game data by mods for example.
+//! If no mounted backend has requested file, it will be loaded from native file system as usual.
+//!
+//! ```no_run
+//! use rg3d_core::io::{
+//!     pak::PakArchive,
+//!     vfs::{DirectoryBackend, Vfs},
+//! };
+//!
+//! let mut vfs = Vfs::global_mut();
+//! vfs.mount("data", 0, PakArchive::open("data.pak").unwrap());
+//! // Files in `mods/data` will override files in the archive.
+//! vfs.mount("data", 1, DirectoryBackend::new("mods/data"));
+//! ```
+
+use std::{
+    collections::HashMap,
+    io::{self, ErrorKind},
+    path::{Component, Path, PathBuf},
+    sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+/// Source of files for the virtual file system. Paths passed to a backend are relative to its
+/// mount point and normalized (see [`normalize_path`]).
+pub trait VfsBackend: Send + Sync {
+    /// Returns true if the backend has a file at given path.
+    fn exists(&self, path: &Path) -> bool;
+
+    /// Reads whole file at given path. Must return error with [`ErrorKind::NotFound`] kind if
+    /// there is no such file.
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+}
+
+/// Removes `.` components and resolves `..` components of a path without accessing the file
+/// system, so `./data/../data/model.fbx` becomes `data/model.fbx`.
+pub fn normalize_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.as_ref().components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push(component);
+                }
+            }
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+// Paths in archives and in memory backend are stored as strings with `/` separator, so they are
+// the same on every platform.
+pub(crate) fn path_key(path: &Path) -> String {
+    normalize_path(path)
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+pub(crate) fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        ErrorKind::NotFound,
+        format!("{} does not exist in virtual file system", path.display()),
+    )
+}
+
+/// Backend that reads files from a directory of native file system.
+#[derive(Debug)]
+pub struct DirectoryBackend {
+    root: PathBuf,
+}
+
+impl DirectoryBackend {
+    /// Creates new backend that reads files from given directory.
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_owned(),
+        }
+    }
+
+    /// Returns root directory of the backend.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl VfsBackend for DirectoryBackend {
+    fn exists(&self, path: &Path) -> bool {
+        self.root.join(path).is_file()
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        std::fs::read(self.root.join(path))
+    }
+}
+
+/// Backend that holds files in memory, it could be useful for procedurally generated resources
+/// or for tests.
+#[derive(Debug, Default)]
+pub struct MemoryBackend {
+    files: HashMap<String, Vec<u8>>,
+}
+
+impl MemoryBackend {
+    /// Creates new empty backend.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds new file to the backend, returns previous content of the file if any.
+    pub fn insert<P: AsRef<Path>>(&mut self, path: P, data: Vec<u8>) -> Option<Vec<u8>> {
+        self.files.insert(path_key(path.as_ref()), data)
+    }
+
+    /// Removes a file from the backend and returns its content.
+    pub fn remove<P: AsRef<Path>>(&mut self, path: P) -> Option<Vec<u8>> {
+        self.files.remove(&path_key(path.as_ref()))
+    }
+
+    /// Builder-like version of [`Self::insert`].
+    pub fn with_file<P: AsRef<Path>>(mut self, path: P, data: Vec<u8>) -> Self {
+        self.insert(path, data);
+        self
+    }
+}
+
+impl VfsBackend for MemoryBackend {
+    fn exists(&self, path: &Path) -> bool {
+        self.files.contains_key(&path_key(path))
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.files
+            .get(&path_key(path))
+            .cloned()
+            .ok_or_else(|| not_found(path))
+    }
+}
+
+struct Mount {
+    point: PathBuf,
+    priority: i32,
+    backend: Box<dyn VfsBackend>,
+}
+
+/// Set of mounted backends, see module docs.
+#[derive(Default)]
+pub struct Vfs {
+    mounts: Vec<Mount>,
+}
+
+impl std::fmt::Debug for Vfs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(
+                self.mounts
+                    .iter()
+                    .map(|mount| (&mount.point, mount.priority)),
+            )
+            .finish()
+    }
+}
+
+lazy_static! {
+    static ref VFS: RwLock<Vfs> = RwLock::new(Vfs::default());
+}
+
+impl Vfs {
+    /// Returns virtual file system that is used by [`load_file`](super::load_file) and
+    /// [`exists`](super::exists).
+    pub fn global() -> RwLockReadGuard<'static, Vfs> {
+        VFS.read().unwrap()
+    }
+
+    /// Returns virtual file system that is used by [`load_file`](super::load_file) and
+    /// [`exists`](super::exists) for modification.
+    pub fn global_mut() -> RwLockWriteGuard<'static, Vfs> {
+        VFS.write().unwrap()
+    }
+
+    /// Mounts a backend at given mount point, for example backend mounted at `data` will be
+    /// used for paths like `data/textures/wall.png` and it will receive `textures/wall.png`
+    /// path. Empty mount point means that the backend will be used for every path. Backends
+    /// with higher priority are checked first, if priorities are equal then backend that was
+    /// mounted last is checked first.
+    pub fn mount<P, B>(&mut self, point: P, priority: i32, backend: B)
+    where
+        P: AsRef<Path>,
+        B: VfsBackend + 'static,
+    {
+        let index = self
+            .mounts
+            .iter()
+            .position(|mount| mount.priority <= priority)
+            .unwrap_or(self.mounts.len());
+        self.mounts.insert(
+            index,
+            Mount {
+                point: normalize_path(point),
+                priority,
+                backend: Box::new(backend),
+            },
+        );
+    }
+
+    /// Removes every backend mounted at given mount point, returns amount of removed backends.
+    pub fn unmount<P: AsRef<Path>>(&mut self, point: P) -> usize {
+        let point = normalize_path(point);
+        let count = self.mounts.len();
+        self.mounts.retain(|mount| mount.point != point);
+        count - self.mounts.len()
+    }
+
+    /// Removes every mounted backend.
+    pub fn unmount_all(&mut self) {
+        self.mounts.clear();
+    }
+
+    /// Returns true if there are no mounted backends.
+    pub fn is_empty(&self) -> bool {
+        self.mounts.is_empty()
+    }
+
+    // Backends that can contain given path in order of priority, along with path relative to
+    // their mount points.
+    fn candidates<'a>(
+        &'a self,
+        path: &Path,
+    ) -> impl Iterator<Item = (&'a dyn VfsBackend, PathBuf)> + 'a {
+        let path = normalize_path(path);
+        self.mounts.iter().filter_map(move |mount| {
+            path.strip_prefix(&mount.point)
+                .ok()
+                .map(|relative| (&*mount.backend, relative.to_owned()))
+        })
+    }
+
+    /// Returns true if any of mounted backends has a file at given path.
+    pub fn exists<P: AsRef<Path>>(&self, path: P) -> bool {
+        self.candidates(path.as_ref())
+            .any(|(backend, relative)| backend.exists(&relative))
+    }
+
+    /// Reads a file from first mounted backend that has it. Returns `None` if no mounted backend
+    /// has the file.
+    pub fn read<P: AsRef<Path>>(&self, path: P) -> Option<io::Result<Vec<u8>>> {
+        self.candidates(path.as_ref())
+            .find(|(backend, relative)| backend.exists(relative))
+            .map(|(backend, relative)| backend.read(&relative))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::io::vfs::{normalize_path, MemoryBackend, Vfs};
+    use std::path::Path;
+
+    #[test]
+    fn vfs_normalize_path() {
+        assert_eq!(
+            normalize_path("./data/../data/model.fbx"),
+            Path::new("data/model.fbx")
+        );
+    }
+
+    #[test]
+    fn vfs_priority() {
+        let mut vfs = Vfs::default();
+        vfs.mount(
+            "data",
+            0,
+            MemoryBackend::new()
+                .with_file("a.txt", b"base".to_vec())
+                .with_file("b.txt", b"base".to_vec()),
+        );
+        vfs.mount(
+            "data",
+            1,
+            MemoryBackend::new().with_file("a.txt", b"mod".to_vec()),
+        );
+
+        assert_eq!(vfs.read("data/a.txt").unwrap().unwrap(), b"mod");
+        assert_eq!(vfs.read("./data/b.txt").unwrap().unwrap(), b"base");
+        assert!(vfs.read("data/c.txt").is_none());
+        assert!(vfs.read("a.txt").is_none());
+        assert!(vfs.exists("data/b.txt"));
+
+        assert_eq!(vfs.unmount("data"), 2);
+        assert!(vfs.is_empty());
+    }
+}
@@ -5,6 +5,8 @@
 extern crate memoffset;
 #[macro_use]
 extern crate lazy_static;
+#[cfg(feature = "serde")]
+extern crate serde_crate as serde;
 
 pub use arrayvec;
 pub use byteorder;
@@ -17,6 +19,7 @@ use std::collections::HashMap;
 use std::hash::Hash;
 use std::path::{Path, PathBuf};
 
+pub mod bvh;
 pub mod color;
 pub mod color_gradient;
 pub mod curve;
@@ -28,6 +31,7 @@ pub mod pool;
 pub mod profiler;
 pub mod quadtree;
 pub mod rectpack;
+pub mod reflect;
 pub mod visitor;
 pub use futures;
 pub use instant;
@@ -0,0 +1,413 @@
+//! Convex hull of a set of points in 3D space built by quickhull algorithm.
+//!
+//! See "The quickhull algorithm for convex hulls" by C. Bradford Barber et al.
+
+use crate::{algebra::Vector3, math::TriangleDefinition};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::{Display, Formatter},
+};
+
+/// An error that may occur during convex hull generation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConvexHullError {
+    /// Input has less than four points.
+    NotEnoughPoints,
+    /// A point with given index has NaN or infinite coordinates.
+    InvalidPoint(usize),
+    /// Every point lies on the same plane, so the hull has no volume.
+    Degenerate,
+    /// Faces of the hull do not form closed surface, it could happen because of precision
+    /// issues with nearly degenerate input.
+    BrokenTopology,
+}
+
+impl Display for ConvexHullError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotEnoughPoints => write!(f, "there must be at least four points"),
+            Self::InvalidPoint(i) => write!(f, "point {} has non-finite coordinates", i),
+            Self::Degenerate => write!(f, "every point lies on the same plane"),
+            Self::BrokenTopology => write!(f, "faces of the hull do not form closed surface"),
+        }
+    }
+}
+
+impl std::error::Error for ConvexHullError {}
+
+struct Face {
+    vertices: [usize; 3],
+    normal: Vector3<f64>,
+    offset: f64,
+    // Points that are above the face and not assigned to any other face.
+    outside: Vec<usize>,
+    alive: bool,
+}
+
+impl Face {
+    fn distance(&self, point: &Vector3<f64>) -> f64 {
+        self.normal.dot(point) - self.offset
+    }
+}
+
+struct Hull<'a> {
+    points: &'a [Vector3<f64>],
+    faces: Vec<Face>,
+    // Directed edge to a face that contains it.
+    edges: HashMap<(usize, usize), usize>,
+    epsilon: f64,
+}
+
+impl<'a> Hull<'a> {
+    fn add_face(&mut self, a: usize, b: usize, c: usize) -> usize {
+        let (pa, pb, pc) = (self.points[a], self.points[b], self.points[c]);
+        let normal = (pb - pa)
+            .cross(&(pc - pa))
+            .try_normalize(f64::EPSILON)
+            .unwrap_or_default();
+        let index = self.faces.len();
+        self.faces.push(Face {
+            vertices: [a, b, c],
+            normal,
+            offset: normal.dot(&pa),
+            outside: Vec::new(),
+            alive: true,
+        });
+        for &(from, to) in [(a, b), (b, c), (c, a)].iter() {
+            self.edges.insert((from, to), index);
+        }
+        index
+    }
+
+    fn remove_face(&mut self, index: usize) {
+        let face = &mut self.faces[index];
+        face.alive = false;
+        let [a, b, c] = face.vertices;
+        for edge in [(a, b), (b, c), (c, a)].iter() {
+            if self.edges.get(edge) == Some(&index) {
+                self.edges.remove(edge);
+            }
+        }
+    }
+
+    // Assigns every point to the first face it is above of, points that are inside of every
+    // face are dropped.
+    fn assign(&mut self, points: impl Iterator<Item = usize>, faces: &[usize]) {
+        for point in points {
+            let p = self.points[point];
+            if let Some(&face) = faces
+                .iter()
+                .find(|&&face| self.faces[face].distance(&p) > self.epsilon)
+            {
+                self.faces[face].outside.push(point);
+            }
+        }
+    }
+
+    fn add_point(&mut self, face: usize) -> Result<(), ConvexHullError> {
+        let outside = &self.faces[face].outside;
+        let eye = *outside
+            .iter()
+            .max_by(|&&a, &&b| {
+                let distance = |i: usize| self.faces[face].distance(&self.points[i]);
+                distance(a)
+                    .partial_cmp(&distance(b))
+                    .unwrap()
+                    .then(b.cmp(&a))
+            })
+            .unwrap();
+        let eye_point = self.points[eye];
+
+        // Flood fill faces that are visible from the eye point.
+        let mut visible = vec![face];
+        let mut visited = HashMap::new();
+        visited.insert(face, true);
+        let mut queue = VecDeque::from(vec![face]);
+        // Edges between visible and invisible faces, new faces will be built on them.
+        let mut horizon = Vec::new();
+        while let Some(current) = queue.pop_front() {
+            let [a, b, c] = self.faces[current].vertices;
+            for &(from, to) in [(a, b), (b, c), (c, a)].iter() {
+                let neighbour = *self
+                    .edges
+                    .get(&(to, from))
+                    .ok_or(ConvexHullError::BrokenTopology)?;
+                let is_visible = match visited.get(&neighbour) {
+                    Some(&is_visible) => is_visible,
+                    None => {
+                        let is_visible = self.faces[neighbour].distance(&eye_point) > self.epsilon;
+                        visited.insert(neighbour, is_visible);
+                        if is_visible {
+                            visible.push(neighbour);
+                            queue.push_back(neighbour);
+                        }
+                        is_visible
+                    }
+                };
+                if !is_visible {
+                    horizon.push((from, to));
+                }
+            }
+        }
+
+        let mut orphans = Vec::new();
+        for &index in visible.iter() {
+            orphans.extend(
+                std::mem::take(&mut self.faces[index].outside)
+                    .into_iter()
+                    .filter(|&p| p != eye),
+            );
+            self.remove_face(index);
+        }
+
+        let new_faces = horizon
+            .into_iter()
+            .map(|(from, to)| self.add_face(from, to, eye))
+            .collect::<Vec<_>>();
+        self.assign(orphans.into_iter(), &new_faces);
+        Ok(())
+    }
+}
+
+/// Builds convex hull of given points. Triangles reference the points by their indices, they are
+/// counter-clockwise when looking from outside of the hull. Points that lie inside of the hull
+/// or on its faces are not used.
+///
+/// # Example
+///
+/// ```
+/// use rg3d_core::{algebra::Vector3, math::convex_hull::quickhull};
+///
+/// let points = [
+///     Vector3::new(0.0, 0.0, 0.0),
+///     Vector3::new(1.0, 0.0, 0.0),
+///     Vector3::new(0.0, 1.0, 0.0),
+///     Vector3::new(0.0, 0.0, 1.0),
+///     // Inside of the tetrahedron.
+///     Vector3::new(0.1, 0.1, 0.1),
+/// ];
+/// let triangles = quickhull(&points).unwrap();
+/// assert_eq!(triangles.len(), 4);
+/// ```
+pub fn quickhull(points: &[Vector3<f32>]) -> Result<Vec<TriangleDefinition>, ConvexHullError> {
+    if points.len() < 4 {
+        return Err(ConvexHullError::NotEnoughPoints);
+    }
+    if let Some(i) = points.iter().position(|p| p.iter().any(|c| !c.is_finite())) {
+        return Err(ConvexHullError::InvalidPoint(i));
+    }
+
+    let points = points.iter().map(|p| p.cast::<f64>()).collect::<Vec<_>>();
+    let max_coordinate = points
+        .iter()
+        .flat_map(|p| p.iter().map(|c| c.abs()))
+        .fold(0.0f64, f64::max);
+    // Points closer than this to a plane are considered lying on it.
+    let epsilon = 3.0 * max_coordinate * f32::EPSILON as f64;
+
+    // Initial tetrahedron, it starts from the most distant pair of extreme points.
+    let mut extremes = Vec::new();
+    for axis in 0..3 {
+        let by_axis = |a: &&Vector3<f64>, b: &&Vector3<f64>| a[axis].partial_cmp(&b[axis]).unwrap();
+        let min = points.iter().enumerate().min_by(|a, b| by_axis(&a.1, &b.1));
+        let max = points.iter().enumerate().max_by(|a, b| by_axis(&a.1, &b.1));
+        extremes.push(min.unwrap().0);
+        extremes.push(max.unwrap().0);
+    }
+    let mut best = (0.0, 0, 0);
+    for &a in extremes.iter() {
+        for &b in extremes.iter() {
+            let distance = (points[a] - points[b]).norm_squared();
+            if distance > best.0 {
+                best = (distance, a, b);
+            }
+        }
+    }
+    let (_, v0, v1) = best;
+    if best.0.sqrt() <= epsilon {
+        return Err(ConvexHullError::Degenerate);
+    }
+
+    let farthest = |distance: &dyn Fn(&Vector3<f64>) -> f64| {
+        points
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (distance(p), i))
+            .fold(
+                (0.0, 0),
+                |best, current| {
+                    if current.0 > best.0 {
+                        current
+                    } else {
+                        best
+                    }
+                },
+            )
+    };
+
+    let axis = (points[v1] - points[v0]).normalize();
+    let (line_distance, v2) = farthest(&|p: &Vector3<f64>| (p - points[v0]).cross(&axis).norm());
+    if line_distance <= epsilon {
+        return Err(ConvexHullError::Degenerate);
+    }
+
+    let normal = (points[v1] - points[v0])
+        .cross(&(points[v2] - points[v0]))
+        .normalize();
+    let (plane_distance, v3) = farthest(&|p: &Vector3<f64>| normal.dot(&(p - points[v0])).abs());
+    if plane_distance <= epsilon {
+        return Err(ConvexHullError::Degenerate);
+    }
+
+    let mut hull = Hull {
+        points: &points,
+        faces: Vec::new(),
+        edges: HashMap::new(),
+        epsilon,
+    };
+    // Orient faces outwards.
+    let (v1, v2) = if normal.dot(&(points[v3] - points[v0])) > 0.0 {
+        (v2, v1)
+    } else {
+        (v1, v2)
+    };
+    let initial = vec![
+        hull.add_face(v0, v1, v2),
+        hull.add_face(v0, v3, v1),
+        hull.add_face(v1, v3, v2),
+        hull.add_face(v2, v3, v0),
+    ];
+    hull.assign(
+        (0..points.len()).filter(|i| ![v0, v1, v2, v3].contains(i)),
+        &initial,
+    );
+
+    // Faces are processed in order of creation, which makes the result deterministic.
+    let mut current = 0;
+    while current < hull.faces.len() {
+        if hull.faces[current].alive && !hull.faces[current].outside.is_empty() {
+            hull.add_point(current)?;
+        }
+        current += 1;
+    }
+
+    Ok(hull
+        .faces
+        .iter()
+        .filter(|f| f.alive)
+        .map(|f| {
+            let [a, b, c] = f.vertices;
+            TriangleDefinition([a as u32, b as u32, c as u32])
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algebra::Vector3,
+        math::convex_hull::{quickhull, ConvexHullError, Hull},
+    };
+    use std::collections::HashMap;
+
+    fn random_points(count: usize) -> Vec<Vector3<f32>> {
+        // Simple LCG to keep the test deterministic.
+        let mut state = 777u32;
+        let mut random = move || {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (state >> 8) as f32 / (1 << 24) as f32 * 2.0 - 1.0
+        };
+        (0..count)
+            .map(|_| Vector3::new(random(), random(), random()))
+            .collect()
+    }
+
+    fn assert_convex(points: &[Vector3<f32>], triangles: &[crate::math::TriangleDefinition]) {
+        for triangle in triangles {
+            let [a, b, c] = triangle.0;
+            let (a, b, c) = (points[a as usize], points[b as usize], points[c as usize]);
+            let normal = (b - a).cross(&(c - a)).normalize();
+            for p in points {
+                assert!(normal.dot(&(p - a)) <= 1.0e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_cube() {
+        let mut points = random_points(100)
+            .into_iter()
+            .map(|p| p.scale(0.9))
+            .collect::<Vec<_>>();
+        for i in 0..8 {
+            points.push(Vector3::new(
+                if i & 1 == 0 { -1.0 } else { 1.0 },
+                if i & 2 == 0 { -1.0 } else { 1.0 },
+                if i & 4 == 0 { -1.0 } else { 1.0 },
+            ));
+        }
+        let triangles = quickhull(&points).unwrap();
+        assert_eq!(triangles.len(), 12);
+        for triangle in triangles.iter() {
+            assert!(triangle.0.iter().all(|&i| i >= 100));
+        }
+        assert_convex(&points, &triangles);
+    }
+
+    #[test]
+    fn test_sphere() {
+        let points = random_points(500)
+            .into_iter()
+            .filter_map(|p| p.try_normalize(0.1))
+            .collect::<Vec<_>>();
+        let triangles = quickhull(&points).unwrap();
+        assert_convex(&points, &triangles);
+
+        // Every point is on the hull, Euler's formula gives 2V - 4 faces.
+        let mut used = triangles.iter().flat_map(|t| t.0).collect::<Vec<_>>();
+        used.sort_unstable();
+        used.dedup();
+        assert_eq!(triangles.len(), 2 * used.len() - 4);
+
+        // Deterministic.
+        assert_eq!(quickhull(&points).unwrap(), triangles);
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let square = [
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(1.0, 1.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        ];
+        assert_eq!(quickhull(&square), Err(ConvexHullError::Degenerate));
+        assert_eq!(
+            quickhull(&square[..3]),
+            Err(ConvexHullError::NotEnoughPoints)
+        );
+        let mut invalid = square.to_vec();
+        invalid.push(Vector3::new(0.0, f32::INFINITY, 0.0));
+        assert_eq!(quickhull(&invalid), Err(ConvexHullError::InvalidPoint(4)));
+    }
+
+    #[test]
+    fn test_broken_topology() {
+        let points = [
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0),
+        ];
+        let mut hull = Hull {
+            points: &points,
+            faces: Vec::new(),
+            edges: HashMap::new(),
+            epsilon: 1.0e-6,
+        };
+        // Single face has no neighbours, so its edges cannot be crossed.
+        let face = hull.add_face(0, 1, 2);
+        hull.faces[face].outside.push(3);
+        assert_eq!(hull.add_point(face), Err(ConvexHullError::BrokenTopology));
+    }
+}
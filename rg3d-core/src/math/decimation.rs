@@ -0,0 +1,427 @@
+//! Mesh decimation using quadric error metrics.
+//!
+//! Edges of a mesh are collapsed one by one in order of increasing error, where error of a vertex
+//! is a sum of squared distances to planes of original triangles around it. See "Surface
+//! Simplification Using Quadric Error Metrics" by M. Garland and P. Heckbert.
+//!
+//! Vertices are never moved, an edge is collapsed into one of its ends. This way remaining
+//! vertices keep all their attributes (normals, texture coordinates, etc.) and decimated mesh
+//! can reference the same vertex buffer. Collapses that would flip triangles or make the mesh
+//! non-manifold are rejected, boundary edges (including seams of texture coordinates) are
+//! preserved as much as possible.
+
+use crate::{algebra::Vector3, math::TriangleDefinition};
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+    fmt::{Display, Formatter},
+};
+
+/// An error that may occur during decimation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecimationError {
+    /// A triangle with given index references a vertex that does not exist.
+    InvalidIndex(usize),
+}
+
+impl Display for DecimationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidIndex(i) => write!(f, "triangle {} references non-existent vertex", i),
+        }
+    }
+}
+
+impl std::error::Error for DecimationError {}
+
+// Boundaries are kept much stronger than surface itself.
+const BOUNDARY_WEIGHT: f64 = 100.0;
+
+// Symmetric 4x4 matrix, only upper triangle is stored.
+#[derive(Copy, Clone, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    fn from_plane(normal: Vector3<f64>, point: Vector3<f64>, weight: f64) -> Self {
+        let (a, b, c) = (normal.x, normal.y, normal.z);
+        let d = -normal.dot(&point);
+        Self([
+            a * a * weight,
+            a * b * weight,
+            a * c * weight,
+            a * d * weight,
+            b * b * weight,
+            b * c * weight,
+            b * d * weight,
+            c * c * weight,
+            c * d * weight,
+            d * d * weight,
+        ])
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        let mut result = *self;
+        for (a, b) in result.0.iter_mut().zip(other.0.iter()) {
+            *a += b;
+        }
+        result
+    }
+
+    fn error(&self, p: &Vector3<f64>) -> f64 {
+        let q = &self.0;
+        let (x, y, z) = (p.x, p.y, p.z);
+        q[0] * x * x
+            + 2.0 * q[1] * x * y
+            + 2.0 * q[2] * x * z
+            + 2.0 * q[3] * x
+            + q[4] * y * y
+            + 2.0 * q[5] * y * z
+            + 2.0 * q[6] * y
+            + q[7] * z * z
+            + 2.0 * q[8] * z
+            + q[9]
+    }
+}
+
+// Collapse of vertex `from` into vertex `to`.
+struct Collapse {
+    cost: f64,
+    from: usize,
+    to: usize,
+    from_version: u32,
+    to_version: u32,
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    // Reversed, so the cheapest collapse is on top of the heap. Ties are resolved by vertex
+    // indices to keep the result deterministic.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .cost
+            .partial_cmp(&self.cost)
+            .unwrap_or(Ordering::Equal)
+            .then(other.from.cmp(&self.from))
+            .then(other.to.cmp(&self.to))
+    }
+}
+
+struct Decimator {
+    positions: Vec<Vector3<f64>>,
+    triangles: Vec<[usize; 3]>,
+    alive: Vec<bool>,
+    alive_count: usize,
+    vertex_triangles: Vec<Vec<usize>>,
+    quadrics: Vec<Quadric>,
+    versions: Vec<u32>,
+    removed: Vec<bool>,
+    heap: BinaryHeap<Collapse>,
+}
+
+impl Decimator {
+    fn normal(&self, triangle: [usize; 3]) -> Vector3<f64> {
+        let [a, b, c] = triangle.map(|i| self.positions[i]);
+        (b - a).cross(&(c - a))
+    }
+
+    fn neighbours(&self, vertex: usize) -> Vec<usize> {
+        let mut neighbours = self.vertex_triangles[vertex]
+            .iter()
+            .flat_map(|&t| self.triangles[t])
+            .filter(|&v| v != vertex)
+            .collect::<Vec<_>>();
+        neighbours.sort_unstable();
+        neighbours.dedup();
+        neighbours
+    }
+
+    fn push(&mut self, from: usize, to: usize) {
+        let cost = self.quadrics[from]
+            .add(&self.quadrics[to])
+            .error(&self.positions[to]);
+        self.heap.push(Collapse {
+            // Error could be slightly negative because of precision issues.
+            cost: cost.max(0.0),
+            from,
+            to,
+            from_version: self.versions[from],
+            to_version: self.versions[to],
+        });
+    }
+
+    fn is_valid(&self, from: usize, to: usize) -> bool {
+        // Link condition: vertices that are adjacent to both ends of the edge must be exactly
+        // the opposite vertices of triangles sharing the edge, otherwise the mesh will become
+        // non-manifold.
+        let mut opposite = Vec::<usize>::new();
+        for &t in self.vertex_triangles[from].iter() {
+            let triangle = self.triangles[t];
+            if triangle.contains(&to) {
+                opposite.extend(triangle.iter().filter(|&&v| v != from && v != to).copied());
+            }
+        }
+        if opposite.is_empty() {
+            return false;
+        }
+        let to_neighbours = self.neighbours(to);
+        let common = self
+            .neighbours(from)
+            .into_iter()
+            .filter(|v| to_neighbours.binary_search(v).is_ok())
+            .count();
+        if common != opposite.len() {
+            return false;
+        }
+
+        // Remaining triangles must not flip or degenerate.
+        for &t in self.vertex_triangles[from].iter() {
+            let triangle = self.triangles[t];
+            if triangle.contains(&to) {
+                continue;
+            }
+            let old_normal = self.normal(triangle);
+            let new_normal = self.normal(triangle.map(|v| if v == from { to } else { v }));
+            if new_normal.norm_squared() <= f64::EPSILON * old_normal.norm_squared()
+                || old_normal.dot(&new_normal) <= 0.0
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn collapse(&mut self, from: usize, to: usize) {
+        for t in std::mem::take(&mut self.vertex_triangles[from]) {
+            let triangle = &mut self.triangles[t];
+            if triangle.contains(&to) {
+                self.alive[t] = false;
+                self.alive_count -= 1;
+                for v in *triangle {
+                    if v != from {
+                        self.vertex_triangles[v].retain(|&other| other != t);
+                    }
+                }
+            } else {
+                for v in triangle.iter_mut() {
+                    if *v == from {
+                        *v = to;
+                    }
+                }
+                self.vertex_triangles[to].push(t);
+            }
+        }
+        self.removed[from] = true;
+        self.quadrics[to] = self.quadrics[to].add(&self.quadrics[from]);
+        self.versions[to] += 1;
+
+        for neighbour in self.neighbours(to) {
+            self.push(to, neighbour);
+            self.push(neighbour, to);
+        }
+    }
+}
+
+/// Decimates a mesh down to `target_triangle_count` triangles. Decimation stops earlier if the
+/// next collapse would exceed `max_error`, which is a sum of squared distances from a vertex to
+/// planes of original triangles around it. Degenerate triangles are removed.
+///
+/// Returned triangles reference the same positions, some of them become unused. The result is
+/// deterministic.
+pub fn decimate(
+    positions: &[Vector3<f32>],
+    triangles: &[TriangleDefinition],
+    target_triangle_count: usize,
+    max_error: f32,
+) -> Result<Vec<TriangleDefinition>, DecimationError> {
+    if let Some(i) = triangles
+        .iter()
+        .position(|t| t.0.iter().any(|&i| i as usize >= positions.len()))
+    {
+        return Err(DecimationError::InvalidIndex(i));
+    }
+
+    let mut decimator = Decimator {
+        positions: positions.iter().map(|p| p.cast::<f64>()).collect(),
+        triangles: Vec::with_capacity(triangles.len()),
+        alive: Vec::with_capacity(triangles.len()),
+        alive_count: 0,
+        vertex_triangles: vec![Vec::new(); positions.len()],
+        quadrics: vec![Quadric::default(); positions.len()],
+        versions: vec![0; positions.len()],
+        removed: vec![false; positions.len()],
+        heap: BinaryHeap::new(),
+    };
+
+    let mut edge_usage = HashMap::new();
+    for triangle in triangles {
+        let triangle = triangle.0.map(|i| i as usize);
+        let normal = match decimator.normal(triangle).try_normalize(f64::EPSILON) {
+            Some(normal) => normal,
+            None => continue,
+        };
+        let index = decimator.triangles.len();
+        decimator.triangles.push(triangle);
+        decimator.alive.push(true);
+        let plane = Quadric::from_plane(normal, decimator.positions[triangle[0]], 1.0);
+        for (i, &v) in triangle.iter().enumerate() {
+            decimator.vertex_triangles[v].push(index);
+            decimator.quadrics[v] = decimator.quadrics[v].add(&plane);
+            let next = triangle[(i + 1) % 3];
+            *edge_usage.entry((v.min(next), v.max(next))).or_insert(0) += 1;
+        }
+    }
+    decimator.alive_count = decimator.triangles.len();
+
+    // Constrain boundary edges with planes perpendicular to their triangles.
+    for index in 0..decimator.triangles.len() {
+        let triangle = decimator.triangles[index];
+        let normal = decimator.normal(triangle).normalize();
+        for i in 0..3 {
+            let (a, b) = (triangle[i], triangle[(i + 1) % 3]);
+            if edge_usage[&(a.min(b), a.max(b))] == 1 {
+                let (pa, pb) = (decimator.positions[a], decimator.positions[b]);
+                if let Some(side) = (pb - pa).cross(&normal).try_normalize(f64::EPSILON) {
+                    let plane = Quadric::from_plane(side, pa, BOUNDARY_WEIGHT);
+                    decimator.quadrics[a] = decimator.quadrics[a].add(&plane);
+                    decimator.quadrics[b] = decimator.quadrics[b].add(&plane);
+                }
+            }
+        }
+    }
+
+    for vertex in 0..positions.len() {
+        for neighbour in decimator.neighbours(vertex) {
+            decimator.push(vertex, neighbour);
+        }
+    }
+
+    while decimator.alive_count > target_triangle_count {
+        let collapse = match decimator.heap.pop() {
+            Some(collapse) => collapse,
+            None => break,
+        };
+        let (from, to) = (collapse.from, collapse.to);
+        if decimator.removed[from]
+            || decimator.removed[to]
+            || decimator.versions[from] != collapse.from_version
+            || decimator.versions[to] != collapse.to_version
+        {
+            // Outdated.
+            continue;
+        }
+        if collapse.cost > max_error as f64 {
+            break;
+        }
+        if decimator.is_valid(from, to) {
+            decimator.collapse(from, to);
+        }
+    }
+
+    Ok(decimator
+        .triangles
+        .iter()
+        .zip(decimator.alive.iter())
+        .filter(|(_, &alive)| alive)
+        .map(|(t, _)| TriangleDefinition(t.map(|i| i as u32)))
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algebra::Vector3,
+        math::{
+            decimation::{decimate, DecimationError},
+            TriangleDefinition,
+        },
+    };
+
+    // Grid of `size` x `size` quads on XZ plane with heights from given function.
+    fn grid(
+        size: usize,
+        height: impl Fn(f32, f32) -> f32,
+    ) -> (Vec<Vector3<f32>>, Vec<TriangleDefinition>) {
+        let mut positions = Vec::new();
+        for z in 0..=size {
+            for x in 0..=size {
+                let (x, z) = (x as f32, z as f32);
+                positions.push(Vector3::new(x, height(x, z), z));
+            }
+        }
+        let mut triangles = Vec::new();
+        let row = size as u32 + 1;
+        for z in 0..size as u32 {
+            for x in 0..size as u32 {
+                let i = z * row + x;
+                triangles.push(TriangleDefinition([i, i + row, i + 1]));
+                triangles.push(TriangleDefinition([i + 1, i + row, i + row + 1]));
+            }
+        }
+        (positions, triangles)
+    }
+
+    fn area(positions: &[Vector3<f32>], triangles: &[TriangleDefinition]) -> f32 {
+        triangles
+            .iter()
+            .map(|t| {
+                let [a, b, c] = t.0.map(|i| positions[i as usize]);
+                let normal = (b - a).cross(&(c - a));
+                // No flipped triangles.
+                assert!(normal.y > 0.0);
+                normal.norm() * 0.5
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_flat_grid() {
+        let (positions, triangles) = grid(10, |_, _| 0.0);
+        let decimated = decimate(&positions, &triangles, 0, 1.0e-6).unwrap();
+        // Only corners are left.
+        assert_eq!(decimated.len(), 2);
+        assert!((area(&positions, &decimated) - 100.0).abs() < 1.0e-3);
+    }
+
+    #[test]
+    fn test_target_count() {
+        let (positions, triangles) = grid(16, |x, z| (x * 0.7).sin() + (z * 0.4).cos());
+        let decimated = decimate(&positions, &triangles, 100, f32::MAX).unwrap();
+        assert!(decimated.len() <= 100 && decimated.len() >= 98);
+        area(&positions, &decimated);
+
+        // Deterministic.
+        assert_eq!(
+            decimate(&positions, &triangles, 100, f32::MAX).unwrap(),
+            decimated
+        );
+
+        // Nothing can be removed without an error.
+        assert_eq!(
+            decimate(&positions, &triangles, 100, 0.0).unwrap().len(),
+            triangles.len()
+        );
+    }
+
+    #[test]
+    fn test_invalid_index() {
+        let (positions, mut triangles) = grid(2, |_, _| 0.0);
+        triangles[3].0[1] = positions.len() as u32;
+        assert_eq!(
+            decimate(&positions, &triangles, 0, 1.0),
+            Err(DecimationError::InvalidIndex(3))
+        );
+    }
+}
@@ -2,19 +2,23 @@
 #![allow(clippy::many_single_char_names)]
 
 pub mod aabb;
+pub mod convex_hull;
+pub mod decimation;
 pub mod frustum;
 pub mod plane;
 pub mod ray;
+pub mod triangulation;
 pub mod triangulator;
 
 use crate::math::ray::IntersectionResult;
 use crate::{
     algebra::{Matrix3, Matrix4, Scalar, UnitQuaternion, Vector2, Vector3},
+    reflect::Reflect,
     visitor::{Visit, VisitResult, Visitor},
 };
 use std::ops::{Add, AddAssign, Index, IndexMut, Mul, Sub};
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Reflect)]
 pub struct Rect<T: Scalar> {
     pub position: Vector2<T>,
     pub size: Vector2<T>,
@@ -0,0 +1,1120 @@
+//! Constrained Delaunay triangulation of point sets and polygons with holes.
+//!
+//! Unlike [ear clipping](super::triangulator), which only handles a single simple polygon and
+//! produces a lot of thin triangles, this module builds Delaunay triangulation (the one that
+//! maximizes minimal angle of triangles) with forced constraint edges. It is suitable for
+//! navigational meshes, procedural terrain, etc.
+//!
+//! Input is validated, degenerate input (duplicated points, self-intersecting or zero-area
+//! contours, holes outside of outline and so on) is reported as [`TriangulationError`].
+//!
+//! Geometric predicates use plain `f64` arithmetic (no adaptive exact evaluation), so nearly
+//! degenerate configurations of points (almost collinear or almost cocircular) may be resolved
+//! either way.
+
+use crate::{algebra::Vector2, math::TriangleDefinition};
+use std::{
+    collections::VecDeque,
+    fmt::{Display, Formatter},
+};
+
+/// An error that may occur during triangulation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TriangulationError {
+    /// Input has less than three points, or every point lies on the same line.
+    NotEnoughPoints,
+    /// A contour of polygon has zero area.
+    ZeroArea,
+    /// A point with given index has NaN or infinite coordinates.
+    InvalidPoint(usize),
+    /// Two points with given indices have same position.
+    DuplicatePoint(usize, usize),
+    /// Edges of polygon (each edge is defined by index of its first point) intersect each other.
+    SelfIntersection(usize, usize),
+    /// A hole with given index is outside of outline or inside of another hole.
+    InvalidHole(usize),
+    /// A constraint with given index references a point that does not exist.
+    InvalidConstraint(usize),
+    /// A constraint with given index intersects one of previous constraints.
+    IntersectingConstraints(usize),
+    /// Triangulation failed because of numerical issues, it may happen on nearly degenerate
+    /// input.
+    NumericalFailure,
+}
+
+impl Display for TriangulationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotEnoughPoints => write!(f, "there must be at least three non-collinear points"),
+            Self::ZeroArea => write!(f, "contour has zero area"),
+            Self::InvalidPoint(i) => write!(f, "point {} has non-finite coordinates", i),
+            Self::DuplicatePoint(a, b) => write!(f, "points {} and {} are the same", a, b),
+            Self::SelfIntersection(a, b) => {
+                write!(f, "edges starting at points {} and {} intersect", a, b)
+            }
+            Self::InvalidHole(i) => write!(f, "hole {} is not inside of outline", i),
+            Self::InvalidConstraint(i) => write!(f, "constraint {} has invalid point index", i),
+            Self::IntersectingConstraints(i) => {
+                write!(f, "constraint {} intersects another constraint", i)
+            }
+            Self::NumericalFailure => write!(f, "triangulation failed due to numerical issues"),
+        }
+    }
+}
+
+impl std::error::Error for TriangulationError {}
+
+type Point = [f64; 2];
+
+// Twice the signed area of triangle abc, positive if the triangle is counter-clockwise.
+fn orient(a: Point, b: Point, c: Point) -> f64 {
+    (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+}
+
+// Positive if d lies inside of circumcircle of counter-clockwise triangle abc.
+fn incircle(a: Point, b: Point, c: Point, d: Point) -> f64 {
+    let (adx, ady) = (a[0] - d[0], a[1] - d[1]);
+    let (bdx, bdy) = (b[0] - d[0], b[1] - d[1]);
+    let (cdx, cdy) = (c[0] - d[0], c[1] - d[1]);
+    (adx * adx + ady * ady) * (bdx * cdy - cdx * bdy)
+        + (bdx * bdx + bdy * bdy) * (cdx * ady - adx * cdy)
+        + (cdx * cdx + cdy * cdy) * (adx * bdy - bdx * ady)
+}
+
+// Whether c lies strictly between a and b on segment ab.
+fn on_segment(a: Point, b: Point, c: Point) -> bool {
+    orient(a, b, c) == 0.0
+        && (c[0] - a[0]) * (b[0] - a[0]) + (c[1] - a[1]) * (b[1] - a[1]) > 0.0
+        && (c[0] - b[0]) * (a[0] - b[0]) + (c[1] - b[1]) * (a[1] - b[1]) > 0.0
+}
+
+// Whether segments ab and cd intersect in a single point which is not an end of any of them.
+fn properly_intersect(a: Point, b: Point, c: Point, d: Point) -> bool {
+    orient(a, b, c) * orient(a, b, d) < 0.0 && orient(c, d, a) * orient(c, d, b) < 0.0
+}
+
+// Whether closed segments ab and cd have at least one common point.
+fn segments_touch(a: Point, b: Point, c: Point, d: Point) -> bool {
+    let (o1, o2) = (orient(a, b, c), orient(a, b, d));
+    let (o3, o4) = (orient(c, d, a), orient(c, d, b));
+    (o1 * o2 < 0.0 && o3 * o4 < 0.0)
+        || (o1 == 0.0 && within_box(a, b, c))
+        || (o2 == 0.0 && within_box(a, b, d))
+        || (o3 == 0.0 && within_box(c, d, a))
+        || (o4 == 0.0 && within_box(c, d, b))
+}
+
+// Whether c lies in bounding box of segment ab.
+fn within_box(a: Point, b: Point, c: Point) -> bool {
+    c[0] >= a[0].min(b[0])
+        && c[0] <= a[0].max(b[0])
+        && c[1] >= a[1].min(b[1])
+        && c[1] <= a[1].max(b[1])
+}
+
+fn next(i: usize) -> usize {
+    (i + 1) % 3
+}
+
+fn prev(i: usize) -> usize {
+    (i + 2) % 3
+}
+
+const NONE: usize = usize::MAX;
+
+#[derive(Copy, Clone, Debug)]
+struct Triangle {
+    // Counter-clockwise.
+    vertices: [usize; 3],
+    // Neighbour across edge `i` which goes from `vertices[i]` to `vertices[next(i)]`.
+    neighbours: [usize; 3],
+    constrained: [bool; 3],
+}
+
+enum Location {
+    Inside(usize),
+    OnEdge(usize, usize),
+    Vertex(usize),
+}
+
+// Triangulation of input points inside of a "super triangle" which encloses every point,
+// three last points are vertices of the super triangle.
+struct Mesh {
+    points: Vec<Point>,
+    triangles: Vec<Triangle>,
+    // Any triangle that contains a vertex.
+    vertex_triangle: Vec<usize>,
+    last_located: usize,
+}
+
+impl Mesh {
+    fn new(points: &[Vector2<f32>]) -> Self {
+        let mut min = [f64::MAX; 2];
+        let mut max = [f64::MIN; 2];
+        for p in points {
+            min = [min[0].min(p.x as f64), min[1].min(p.y as f64)];
+            max = [max[0].max(p.x as f64), max[1].max(p.y as f64)];
+        }
+        let size = (max[0] - min[0]).max(max[1] - min[1]).max(1.0);
+        let center = [(min[0] + max[0]) * 0.5, (min[1] + max[1]) * 0.5];
+
+        let mut all_points = points
+            .iter()
+            .map(|p| [p.x as f64, p.y as f64])
+            .collect::<Vec<_>>();
+        let first_super = all_points.len();
+        all_points.push([center[0] - 20.0 * size, center[1] - 10.0 * size]);
+        all_points.push([center[0] + 20.0 * size, center[1] - 10.0 * size]);
+        all_points.push([center[0], center[1] + 20.0 * size]);
+
+        let mut mesh = Self {
+            vertex_triangle: vec![NONE; all_points.len()],
+            points: all_points,
+            triangles: Vec::new(),
+            last_located: 0,
+        };
+        mesh.push(Triangle {
+            vertices: [first_super, first_super + 1, first_super + 2],
+            neighbours: [NONE; 3],
+            constrained: [false; 3],
+        });
+        mesh
+    }
+
+    fn is_super(&self, vertex: usize) -> bool {
+        vertex >= self.points.len() - 3
+    }
+
+    fn point(&self, vertex: usize) -> Point {
+        self.points[vertex]
+    }
+
+    fn set(&mut self, index: usize, triangle: Triangle) {
+        for &v in triangle.vertices.iter() {
+            self.vertex_triangle[v] = index;
+        }
+        self.triangles[index] = triangle;
+    }
+
+    fn push(&mut self, triangle: Triangle) -> usize {
+        let index = self.triangles.len();
+        self.triangles.push(triangle);
+        self.set(index, triangle);
+        index
+    }
+
+    fn replace_neighbour(&mut self, triangle: usize, old: usize, new: usize) {
+        if triangle != NONE {
+            for n in self.triangles[triangle].neighbours.iter_mut() {
+                if *n == old {
+                    *n = new;
+                }
+            }
+        }
+    }
+
+    // Index of edge `a -> b` in given triangle.
+    fn edge_index(&self, triangle: usize, a: usize, b: usize) -> Option<usize> {
+        let vertices = &self.triangles[triangle].vertices;
+        (0..3).find(|&i| vertices[i] == a && vertices[next(i)] == b)
+    }
+
+    fn triangles_around(&self, vertex: usize) -> Vec<usize> {
+        let start = self.vertex_triangle[vertex];
+        let vertex_index = |t: usize| {
+            self.triangles[t]
+                .vertices
+                .iter()
+                .position(|&v| v == vertex)
+                .unwrap()
+        };
+        let mut result = vec![start];
+        // Counter-clockwise first, then clockwise if the vertex is on the boundary.
+        let mut t = start;
+        loop {
+            t = self.triangles[t].neighbours[prev(vertex_index(t))];
+            if t == NONE {
+                break;
+            } else if t == start {
+                return result;
+            }
+            result.push(t);
+        }
+        let mut t = start;
+        loop {
+            t = self.triangles[t].neighbours[vertex_index(t)];
+            if t == NONE {
+                break;
+            }
+            result.push(t);
+        }
+        result
+    }
+
+    // Returns triangle with edge `a -> b` and index of the edge in it.
+    fn find_edge(&self, a: usize, b: usize) -> Option<(usize, usize)> {
+        self.triangles_around(a)
+            .into_iter()
+            .find_map(|t| self.edge_index(t, a, b).map(|e| (t, e)))
+    }
+
+    fn locate(&mut self, p: Point) -> Location {
+        let mut t = self.last_located.min(self.triangles.len() - 1);
+        let mut steps = 0;
+        'walk: loop {
+            steps += 1;
+            if steps > self.triangles.len() {
+                // Walk should never cycle in Delaunay triangulation, but stay on the safe side.
+                return self.locate_brute_force(p);
+            }
+            let triangle = self.triangles[t];
+            for i in 0..3 {
+                let a = self.point(triangle.vertices[i]);
+                let b = self.point(triangle.vertices[next(i)]);
+                if orient(a, b, p) < 0.0 && triangle.neighbours[i] != NONE {
+                    t = triangle.neighbours[i];
+                    continue 'walk;
+                }
+            }
+            self.last_located = t;
+            return self.classify_location(t, p);
+        }
+    }
+
+    fn locate_brute_force(&mut self, p: Point) -> Location {
+        let t = (0..self.triangles.len())
+            .find(|&t| {
+                let v = self.triangles[t].vertices;
+                (0..3).all(|i| orient(self.point(v[i]), self.point(v[next(i)]), p) >= 0.0)
+            })
+            .unwrap_or(0);
+        self.last_located = t;
+        self.classify_location(t, p)
+    }
+
+    fn classify_location(&self, t: usize, p: Point) -> Location {
+        let triangle = &self.triangles[t];
+        for i in 0..3 {
+            if self.point(triangle.vertices[i]) == p {
+                return Location::Vertex(triangle.vertices[i]);
+            }
+        }
+        for i in 0..3 {
+            let a = self.point(triangle.vertices[i]);
+            let b = self.point(triangle.vertices[next(i)]);
+            if orient(a, b, p) == 0.0 {
+                return Location::OnEdge(t, i);
+            }
+        }
+        Location::Inside(t)
+    }
+
+    fn insert_point(&mut self, vertex: usize) -> Result<(), TriangulationError> {
+        match self.locate(self.point(vertex)) {
+            Location::Vertex(existing) => {
+                return Err(TriangulationError::DuplicatePoint(existing, vertex))
+            }
+            Location::Inside(t) => self.split_triangle(t, vertex),
+            Location::OnEdge(t, e) => self.split_edge(t, e, vertex),
+        }
+        Ok(())
+    }
+
+    fn split_triangle(&mut self, t: usize, p: usize) {
+        let Triangle {
+            vertices: [a, b, c],
+            neighbours: [nab, nbc, nca],
+            constrained: [cab, cbc, cca],
+        } = self.triangles[t];
+        let t1 = self.triangles.len();
+        let t2 = t1 + 1;
+        self.push(Triangle {
+            vertices: [b, c, p],
+            neighbours: [nbc, t2, t],
+            constrained: [cbc, false, false],
+        });
+        self.push(Triangle {
+            vertices: [c, a, p],
+            neighbours: [nca, t, t1],
+            constrained: [cca, false, false],
+        });
+        self.set(
+            t,
+            Triangle {
+                vertices: [a, b, p],
+                neighbours: [nab, t1, t2],
+                constrained: [cab, false, false],
+            },
+        );
+        self.replace_neighbour(nbc, t, t1);
+        self.replace_neighbour(nca, t, t2);
+        self.legalize(vec![(t, 0), (t1, 0), (t2, 0)]);
+    }
+
+    fn split_edge(&mut self, t: usize, e: usize, p: usize) {
+        let triangle = self.triangles[t];
+        let (a, b, c) = (
+            triangle.vertices[e],
+            triangle.vertices[next(e)],
+            triangle.vertices[prev(e)],
+        );
+        let (nbc, nca) = (triangle.neighbours[next(e)], triangle.neighbours[prev(e)]);
+        let (cab, cbc, cca) = (
+            triangle.constrained[e],
+            triangle.constrained[next(e)],
+            triangle.constrained[prev(e)],
+        );
+        let u = triangle.neighbours[e];
+
+        let t2 = self.triangles.len();
+        let u2 = if u != NONE { t2 + 1 } else { NONE };
+        self.push(Triangle {
+            vertices: [p, b, c],
+            neighbours: [u, nbc, t],
+            constrained: [cab, cbc, false],
+        });
+        self.set(
+            t,
+            Triangle {
+                vertices: [a, p, c],
+                neighbours: [u2, t2, nca],
+                constrained: [cab, false, cca],
+            },
+        );
+        self.replace_neighbour(nbc, t, t2);
+        let mut stack = vec![(t, 2), (t2, 1)];
+
+        if u != NONE {
+            let other = self.triangles[u];
+            let f = self.edge_index(u, b, a).unwrap();
+            let d = other.vertices[prev(f)];
+            let (nad, ndb) = (other.neighbours[next(f)], other.neighbours[prev(f)]);
+            let (cad, cdb) = (other.constrained[next(f)], other.constrained[prev(f)]);
+            self.push(Triangle {
+                vertices: [p, a, d],
+                neighbours: [t, nad, u],
+                constrained: [cab, cad, false],
+            });
+            self.set(
+                u,
+                Triangle {
+                    vertices: [b, p, d],
+                    neighbours: [t2, u2, ndb],
+                    constrained: [cab, false, cdb],
+                },
+            );
+            self.replace_neighbour(nad, u, u2);
+            stack.push((u, 2));
+            stack.push((u2, 1));
+        }
+
+        self.legalize(stack);
+    }
+
+    // Flips edge `e` of triangle `t`. Triangles `abc` and `bad` become `adc` and `bcd`, their
+    // indices are returned in that order.
+    fn flip(&mut self, t: usize, e: usize) -> (usize, usize) {
+        let triangle = self.triangles[t];
+        let (a, b, c) = (
+            triangle.vertices[e],
+            triangle.vertices[next(e)],
+            triangle.vertices[prev(e)],
+        );
+        let (nbc, nca) = (triangle.neighbours[next(e)], triangle.neighbours[prev(e)]);
+        let (cbc, cca) = (triangle.constrained[next(e)], triangle.constrained[prev(e)]);
+
+        let u = triangle.neighbours[e];
+        let other = self.triangles[u];
+        let f = self.edge_index(u, b, a).unwrap();
+        let d = other.vertices[prev(f)];
+        let (nad, ndb) = (other.neighbours[next(f)], other.neighbours[prev(f)]);
+        let (cad, cdb) = (other.constrained[next(f)], other.constrained[prev(f)]);
+
+        self.set(
+            t,
+            Triangle {
+                vertices: [a, d, c],
+                neighbours: [nad, u, nca],
+                constrained: [cad, false, cca],
+            },
+        );
+        self.set(
+            u,
+            Triangle {
+                vertices: [b, c, d],
+                neighbours: [nbc, t, ndb],
+                constrained: [cbc, false, cdb],
+            },
+        );
+        self.replace_neighbour(nad, u, t);
+        self.replace_neighbour(nbc, t, u);
+        (t, u)
+    }
+
+    // Returns opposite vertex of the neighbour across edge `e` of triangle `t`.
+    fn opposite(&self, t: usize, e: usize) -> Option<usize> {
+        let triangle = &self.triangles[t];
+        let u = triangle.neighbours[e];
+        if u == NONE {
+            return None;
+        }
+        let a = triangle.vertices[e];
+        let b = triangle.vertices[next(e)];
+        let f = self.edge_index(u, b, a)?;
+        Some(self.triangles[u].vertices[prev(f)])
+    }
+
+    fn is_delaunay(&self, t: usize, e: usize) -> bool {
+        let triangle = &self.triangles[t];
+        if triangle.constrained[e] {
+            return true;
+        }
+        match self.opposite(t, e) {
+            Some(d) => {
+                let [a, b, c] = triangle.vertices;
+                incircle(self.point(a), self.point(b), self.point(c), self.point(d)) <= 0.0
+            }
+            None => true,
+        }
+    }
+
+    // Restores Delaunay property after insertion of a point. Every edge in the stack is opposite
+    // to the new point.
+    fn legalize(&mut self, mut stack: Vec<(usize, usize)>) {
+        while let Some((t, e)) = stack.pop() {
+            if !self.is_delaunay(t, e) {
+                let (t, u) = self.flip(t, e);
+                stack.push((t, 0));
+                stack.push((u, 2));
+            }
+        }
+    }
+
+    fn mark_constrained(&mut self, a: usize, b: usize) -> bool {
+        let mut found = false;
+        for &(from, to) in [(a, b), (b, a)].iter() {
+            if let Some((t, e)) = self.find_edge(from, to) {
+                self.triangles[t].constrained[e] = true;
+                found = true;
+            }
+        }
+        found
+    }
+
+    // Collects edges crossed by segment from `a` towards `b`, walk stops at `b` or at the first
+    // vertex that lies exactly on the segment. Returns the edges and the vertex.
+    fn crossed_edges(
+        &self,
+        a: usize,
+        b: usize,
+    ) -> Result<(Vec<(usize, usize)>, usize), TriangulationError> {
+        let (pa, pb) = (self.point(a), self.point(b));
+
+        let mut start = None;
+        for t in self.triangles_around(a) {
+            let triangle = &self.triangles[t];
+            let i = triangle.vertices.iter().position(|&v| v == a).unwrap();
+            let (u, w) = (triangle.vertices[next(i)], triangle.vertices[prev(i)]);
+            for &v in [u, w].iter() {
+                if v == b || on_segment(pa, pb, self.point(v)) {
+                    return Ok((Vec::new(), v));
+                }
+            }
+            if orient(pa, pb, self.point(u)) < 0.0 && orient(pa, pb, self.point(w)) > 0.0 {
+                start = Some((t, u, w));
+                break;
+            }
+        }
+        let (mut t, mut u, mut w) = start.ok_or(TriangulationError::NumericalFailure)?;
+
+        let mut edges = Vec::new();
+        loop {
+            let e = self
+                .edge_index(t, u, w)
+                .ok_or(TriangulationError::NumericalFailure)?;
+            if self.triangles[t].constrained[e] {
+                return Err(TriangulationError::IntersectingConstraints(0));
+            }
+            edges.push((u, w));
+            let x = self
+                .opposite(t, e)
+                .ok_or(TriangulationError::NumericalFailure)?;
+            t = self.triangles[t].neighbours[e];
+            if x == b {
+                return Ok((edges, b));
+            }
+            let side = orient(pa, pb, self.point(x));
+            if side == 0.0 {
+                return if on_segment(pa, pb, self.point(x)) {
+                    Ok((edges, x))
+                } else {
+                    Err(TriangulationError::NumericalFailure)
+                };
+            } else if side < 0.0 {
+                u = x;
+            } else {
+                w = x;
+            }
+        }
+    }
+
+    // Forces edge `a -> b` into triangulation by flipping every edge that crosses it.
+    // See "A fast algorithm for generating constrained Delaunay triangulations" by S. W. Sloan.
+    fn insert_constraint(&mut self, a: usize, b: usize) -> Result<(), TriangulationError> {
+        let mut from = a;
+        while from != b {
+            let (crossed, to) = self.crossed_edges(from, b)?;
+            if !crossed.is_empty() {
+                self.remove_crossing_edges(from, to, crossed)?;
+            }
+            if !self.mark_constrained(from, to) {
+                return Err(TriangulationError::NumericalFailure);
+            }
+            from = to;
+        }
+        Ok(())
+    }
+
+    fn remove_crossing_edges(
+        &mut self,
+        a: usize,
+        b: usize,
+        crossed: Vec<(usize, usize)>,
+    ) -> Result<(), TriangulationError> {
+        let (pa, pb) = (self.point(a), self.point(b));
+        let limit = 16 * (crossed.len() + 1) * (crossed.len() + 1);
+        let mut queue = VecDeque::from(crossed);
+        let mut new_edges = Vec::new();
+        let mut iterations = 0;
+        while let Some((u, w)) = queue.pop_front() {
+            iterations += 1;
+            if iterations > limit {
+                return Err(TriangulationError::NumericalFailure);
+            }
+            let (t, e) = self
+                .find_edge(u, w)
+                .ok_or(TriangulationError::NumericalFailure)?;
+            let c = self.triangles[t].vertices[prev(e)];
+            let d = self
+                .opposite(t, e)
+                .ok_or(TriangulationError::NumericalFailure)?;
+            let (pu, pw, pc, pd) = (self.point(u), self.point(w), self.point(c), self.point(d));
+            if properly_intersect(pu, pw, pc, pd) {
+                // Quad is strictly convex, the diagonal can be flipped.
+                self.flip(t, e);
+                if properly_intersect(pa, pb, pc, pd) {
+                    queue.push_back((c, d));
+                } else {
+                    new_edges.push((c, d));
+                }
+            } else {
+                queue.push_back((u, w));
+            }
+        }
+
+        // Restore Delaunay property of new edges, except the constraint itself.
+        let limit = 16 * (new_edges.len() + 1) * (new_edges.len() + 1);
+        let mut iterations = 0;
+        let mut swapped = true;
+        while swapped {
+            swapped = false;
+            for edge in new_edges.iter_mut() {
+                let (u, w) = *edge;
+                if (u == a && w == b) || (u == b && w == a) {
+                    continue;
+                }
+                let (t, e) = self
+                    .find_edge(u, w)
+                    .ok_or(TriangulationError::NumericalFailure)?;
+                if !self.is_delaunay(t, e) {
+                    iterations += 1;
+                    if iterations > limit {
+                        return Err(TriangulationError::NumericalFailure);
+                    }
+                    let c = self.triangles[t].vertices[prev(e)];
+                    let d = self.opposite(t, e).unwrap();
+                    self.flip(t, e);
+                    *edge = (c, d);
+                    swapped = true;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn insert_points(&mut self) -> Result<(), TriangulationError> {
+        for vertex in 0..self.points.len() - 3 {
+            self.insert_point(vertex)?;
+        }
+        Ok(())
+    }
+
+    // Triangles that do not touch the super triangle.
+    fn inner_triangles(&self) -> impl Iterator<Item = &Triangle> {
+        self.triangles
+            .iter()
+            .filter(move |t| !t.vertices.iter().any(|&v| self.is_super(v)))
+    }
+
+    // Amount of constrained edges between every triangle and the super triangle.
+    fn depths(&self) -> Vec<u32> {
+        let mut depths = vec![u32::MAX; self.triangles.len()];
+        let start = self.vertex_triangle[self.points.len() - 1];
+        depths[start] = 0;
+        let mut queue = VecDeque::from(vec![start]);
+        while let Some(t) = queue.pop_front() {
+            let triangle = &self.triangles[t];
+            for i in 0..3 {
+                let n = triangle.neighbours[i];
+                if n == NONE {
+                    continue;
+                }
+                let constrained = triangle.constrained[i];
+                let depth = depths[t] + constrained as u32;
+                if depth < depths[n] {
+                    depths[n] = depth;
+                    if constrained {
+                        queue.push_back(n);
+                    } else {
+                        queue.push_front(n);
+                    }
+                }
+            }
+        }
+        depths
+    }
+}
+
+fn to_definition(triangle: &Triangle) -> TriangleDefinition {
+    let [a, b, c] = triangle.vertices;
+    TriangleDefinition([a as u32, b as u32, c as u32])
+}
+
+fn check_points(points: &[Vector2<f32>]) -> Result<(), TriangulationError> {
+    if let Some(i) = points
+        .iter()
+        .position(|p| !p.x.is_finite() || !p.y.is_finite())
+    {
+        return Err(TriangulationError::InvalidPoint(i));
+    }
+    let mut sorted = (0..points.len()).collect::<Vec<_>>();
+    sorted.sort_by(|&a, &b| {
+        points[a]
+            .x
+            .partial_cmp(&points[b].x)
+            .unwrap()
+            .then(points[a].y.partial_cmp(&points[b].y).unwrap())
+    });
+    for pair in sorted.windows(2) {
+        if points[pair[0]] == points[pair[1]] {
+            let (a, b) = (pair[0].min(pair[1]), pair[0].max(pair[1]));
+            return Err(TriangulationError::DuplicatePoint(a, b));
+        }
+    }
+    Ok(())
+}
+
+// Monotone chain, collinear points are excluded.
+fn convex_hull(points: &[Vector2<f32>]) -> Vec<usize> {
+    let point = |i: usize| [points[i].x as f64, points[i].y as f64];
+    let mut sorted = (0..points.len()).collect::<Vec<_>>();
+    sorted.sort_by(|&a, &b| {
+        points[a]
+            .x
+            .partial_cmp(&points[b].x)
+            .unwrap()
+            .then(points[a].y.partial_cmp(&points[b].y).unwrap())
+    });
+    let mut hull: Vec<usize> = Vec::new();
+    for pass in 0..2 {
+        let lower_len = hull.len();
+        for &i in sorted.iter() {
+            while hull.len() >= lower_len + 2
+                && orient(
+                    point(hull[hull.len() - 2]),
+                    point(hull[hull.len() - 1]),
+                    point(i),
+                ) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(i);
+        }
+        hull.pop();
+        if pass == 0 {
+            sorted.reverse();
+        }
+    }
+    hull
+}
+
+/// Builds Delaunay triangulation of convex hull of given points. Triangles are counter-clockwise
+/// and reference the points by their indices.
+pub fn delaunay(points: &[Vector2<f32>]) -> Result<Vec<TriangleDefinition>, TriangulationError> {
+    constrained_delaunay(points, &[])
+}
+
+/// Builds Delaunay triangulation of convex hull of given points in which every constraint edge
+/// (a pair of point indices) is present. Constraints must not intersect each other, but they
+/// may pass through points. Triangles are counter-clockwise and reference the points by their
+/// indices.
+pub fn constrained_delaunay(
+    points: &[Vector2<f32>],
+    constraints: &[[u32; 2]],
+) -> Result<Vec<TriangleDefinition>, TriangulationError> {
+    check_points(points)?;
+    let hull = convex_hull(points);
+    if hull.len() < 3 {
+        return Err(TriangulationError::NotEnoughPoints);
+    }
+    if let Some(i) = constraints
+        .iter()
+        .position(|c| c.iter().any(|&v| v as usize >= points.len()))
+    {
+        return Err(TriangulationError::InvalidConstraint(i));
+    }
+
+    let mut mesh = Mesh::new(points);
+    mesh.insert_points()?;
+    // Edges of the hull are always Delaunay, forcing them guarantees that the triangulation
+    // covers the whole hull regardless of size of the super triangle.
+    for (i, &a) in hull.iter().enumerate() {
+        mesh.insert_constraint(a, hull[(i + 1) % hull.len()])?;
+    }
+    for (i, constraint) in constraints.iter().enumerate() {
+        mesh.insert_constraint(constraint[0] as usize, constraint[1] as usize)
+            .map_err(|e| match e {
+                TriangulationError::IntersectingConstraints(_) => {
+                    TriangulationError::IntersectingConstraints(i)
+                }
+                e => e,
+            })?;
+    }
+
+    Ok(mesh.inner_triangles().map(to_definition).collect())
+}
+
+fn signed_area(contour: &[Vector2<f32>]) -> f64 {
+    let mut area = 0.0;
+    for (i, a) in contour.iter().enumerate() {
+        let b = contour[(i + 1) % contour.len()];
+        area += a.x as f64 * b.y as f64 - b.x as f64 * a.y as f64;
+    }
+    area * 0.5
+}
+
+fn contains_point(contour: &[Vector2<f32>], p: &Vector2<f32>) -> bool {
+    let mut inside = false;
+    for (i, a) in contour.iter().enumerate() {
+        let b = contour[(i + 1) % contour.len()];
+        if (a.y > p.y) != (b.y > p.y) && p.x < (b.x - a.x) * (p.y - a.y) / (b.y - a.y) + a.x {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+// Checks every pair of edges whose bounding boxes overlap on X axis.
+fn find_self_intersection(points: &[Point], edges: &[[usize; 2]]) -> Option<(usize, usize)> {
+    let min_x = |e: &[usize; 2]| points[e[0]][0].min(points[e[1]][0]);
+    let max_x = |e: &[usize; 2]| points[e[0]][0].max(points[e[1]][0]);
+    let mut sorted = edges.to_vec();
+    sorted.sort_by(|a, b| min_x(a).partial_cmp(&min_x(b)).unwrap());
+    for (i, e1) in sorted.iter().enumerate() {
+        for e2 in sorted[i + 1..].iter() {
+            if min_x(e2) > max_x(e1) {
+                break;
+            }
+            let (a, b, c, d) = (points[e1[0]], points[e1[1]], points[e2[0]], points[e2[1]]);
+            let intersect = if e1[1] == e2[0] {
+                // Adjacent edges only may overlap.
+                orient(a, b, d) == 0.0 && within_box(a, b, d)
+                    || within_box(c, d, a) && orient(c, d, a) == 0.0
+            } else if e1[0] == e2[1] {
+                orient(a, b, c) == 0.0 && within_box(a, b, c)
+                    || within_box(c, d, b) && orient(c, d, b) == 0.0
+            } else {
+                segments_touch(a, b, c, d)
+            };
+            if intersect {
+                return Some((e1[0].min(e2[0]), e1[0].max(e2[0])));
+            }
+        }
+    }
+    None
+}
+
+/// Triangulates a polygon with holes. The outline and the holes are closed contours of any
+/// winding, they must not intersect each other. Triangles are counter-clockwise and reference
+/// points of the outline followed by points of every hole in order.
+///
+/// # Example
+///
+/// ```
+/// use rg3d_core::{algebra::Vector2, math::triangulation::triangulate_with_holes};
+///
+/// let outline = [
+///     Vector2::new(0.0, 0.0),
+///     Vector2::new(3.0, 0.0),
+///     Vector2::new(3.0, 3.0),
+///     Vector2::new(0.0, 3.0),
+/// ];
+/// let hole = vec![
+///     Vector2::new(1.0, 1.0),
+///     Vector2::new(2.0, 1.0),
+///     Vector2::new(2.0, 2.0),
+///     Vector2::new(1.0, 2.0),
+/// ];
+/// let triangles = triangulate_with_holes(&outline, &[hole]).unwrap();
+/// assert_eq!(triangles.len(), 8);
+/// ```
+pub fn triangulate_with_holes(
+    outline: &[Vector2<f32>],
+    holes: &[Vec<Vector2<f32>>],
+) -> Result<Vec<TriangleDefinition>, TriangulationError> {
+    let contours = std::iter::once(outline)
+        .chain(holes.iter().map(|h| h.as_slice()))
+        .collect::<Vec<_>>();
+    if contours.iter().any(|c| c.len() < 3) {
+        return Err(TriangulationError::NotEnoughPoints);
+    }
+
+    let points = contours
+        .iter()
+        .flat_map(|c| c.iter().cloned())
+        .collect::<Vec<_>>();
+    check_points(&points)?;
+    if contours.iter().any(|c| signed_area(c) == 0.0) {
+        return Err(TriangulationError::ZeroArea);
+    }
+
+    let mut edges = Vec::new();
+    let mut offset = 0;
+    for contour in contours.iter() {
+        for i in 0..contour.len() {
+            edges.push([offset + i, offset + (i + 1) % contour.len()]);
+        }
+        offset += contour.len();
+    }
+    let exact_points = points
+        .iter()
+        .map(|p| [p.x as f64, p.y as f64])
+        .collect::<Vec<_>>();
+    if let Some((a, b)) = find_self_intersection(&exact_points, &edges) {
+        return Err(TriangulationError::SelfIntersection(a, b));
+    }
+
+    for (i, hole) in holes.iter().enumerate() {
+        // Contours do not intersect, so it is enough to check a single point.
+        let inside_other_hole = holes
+            .iter()
+            .enumerate()
+            .any(|(j, other)| i != j && contains_point(other, &hole[0]));
+        if !contains_point(outline, &hole[0]) || inside_other_hole {
+            return Err(TriangulationError::InvalidHole(i));
+        }
+    }
+
+    let mut mesh = Mesh::new(&points);
+    mesh.insert_points()?;
+    for edge in edges {
+        mesh.insert_constraint(edge[0], edge[1])?;
+    }
+
+    // Every crossing of a contour switches between inside and outside.
+    let depths = mesh.depths();
+    Ok(mesh
+        .triangles
+        .iter()
+        .zip(depths)
+        .filter(|(t, depth)| depth % 2 == 1 && !t.vertices.iter().any(|&v| mesh.is_super(v)))
+        .map(|(t, _)| to_definition(t))
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algebra::Vector2,
+        math::{
+            triangulation::{
+                constrained_delaunay, delaunay, triangulate_with_holes, TriangulationError,
+            },
+            TriangleDefinition,
+        },
+    };
+
+    fn area(points: &[Vector2<f32>], triangles: &[TriangleDefinition]) -> f32 {
+        triangles
+            .iter()
+            .map(|t| {
+                let [a, b, c] = t.0;
+                let (a, b, c) = (points[a as usize], points[b as usize], points[c as usize]);
+                let area = (b - a).perp(&(c - a)) * 0.5;
+                assert!(area > 0.0);
+                area
+            })
+            .sum()
+    }
+
+    fn square(x: f32, y: f32, size: f32) -> Vec<Vector2<f32>> {
+        vec![
+            Vector2::new(x, y),
+            Vector2::new(x + size, y),
+            Vector2::new(x + size, y + size),
+            Vector2::new(x, y + size),
+        ]
+    }
+
+    fn random_points(count: usize) -> Vec<Vector2<f32>> {
+        // Simple LCG to keep the test deterministic.
+        let mut state = 12345u32;
+        let mut random = move || {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (state >> 8) as f32 / (1 << 24) as f32
+        };
+        (0..count)
+            .map(|_| Vector2::new(random() * 100.0, random() * 100.0))
+            .collect()
+    }
+
+    #[test]
+    fn test_delaunay() {
+        let points = random_points(200);
+        let triangles = delaunay(&points).unwrap();
+
+        // Empty circumcircle of every triangle.
+        for triangle in triangles.iter() {
+            let [a, b, c] = triangle.0;
+            let p = |i: u32| {
+                let p = points[i as usize];
+                [p.x as f64, p.y as f64]
+            };
+            for (i, point) in points.iter().enumerate() {
+                let d = [point.x as f64, point.y as f64];
+                assert!(super::incircle(p(a), p(b), p(c), d) <= 1.0e-6, "{}", i);
+            }
+        }
+
+        // Euler's formula: 2n - 2 - h triangles.
+        let hull = super::convex_hull(&points).len();
+        assert_eq!(triangles.len(), 2 * points.len() - 2 - hull);
+    }
+
+    #[test]
+    fn test_delaunay_grid() {
+        // Lots of collinear and cocircular points.
+        let mut points = Vec::new();
+        for y in 0..5 {
+            for x in 0..5 {
+                points.push(Vector2::new(x as f32, y as f32));
+            }
+        }
+        let triangles = delaunay(&points).unwrap();
+        assert_eq!(triangles.len(), 32);
+        assert!((area(&points, &triangles) - 16.0).abs() < 1.0e-4);
+    }
+
+    #[test]
+    fn test_constrained_delaunay() {
+        // Long diagonal is not Delaunay, but it must be present in triangulation.
+        let points = vec![
+            Vector2::new(0.0, 0.0),
+            Vector2::new(10.0, 1.0),
+            Vector2::new(5.0, -1.0),
+            Vector2::new(5.0, 2.0),
+        ];
+        let triangles = constrained_delaunay(&points, &[[0, 1]]).unwrap();
+        assert_eq!(triangles.len(), 2);
+        for triangle in triangles {
+            assert!(triangle.0.contains(&0) && triangle.0.contains(&1));
+        }
+
+        assert_eq!(
+            constrained_delaunay(&points, &[[0, 1], [2, 3]]),
+            Err(TriangulationError::IntersectingConstraints(1))
+        );
+        assert_eq!(
+            constrained_delaunay(&points, &[[0, 4]]),
+            Err(TriangulationError::InvalidConstraint(0))
+        );
+    }
+
+    #[test]
+    fn test_polygon_with_holes() {
+        let outline = square(0.0, 0.0, 10.0);
+        let holes = vec![square(1.0, 1.0, 2.0), square(5.0, 5.0, 3.0)];
+        let triangles = triangulate_with_holes(&outline, &holes).unwrap();
+        let points = outline
+            .iter()
+            .chain(holes.iter().flatten())
+            .cloned()
+            .collect::<Vec<_>>();
+        assert!((area(&points, &triangles) - (100.0 - 4.0 - 9.0)).abs() < 1.0e-3);
+
+        // Concave polygon with collinear edges (comb).
+        let comb = vec![
+            Vector2::new(0.0, 0.0),
+            Vector2::new(5.0, 0.0),
+            Vector2::new(5.0, 3.0),
+            Vector2::new(4.0, 3.0),
+            Vector2::new(4.0, 1.0),
+            Vector2::new(3.0, 1.0),
+            Vector2::new(3.0, 3.0),
+            Vector2::new(2.0, 3.0),
+            Vector2::new(2.0, 1.0),
+            Vector2::new(1.0, 1.0),
+            Vector2::new(1.0, 3.0),
+            Vector2::new(0.0, 3.0),
+        ];
+        let triangles = triangulate_with_holes(&comb, &[]).unwrap();
+        assert_eq!(triangles.len(), comb.len() - 2);
+        assert!((area(&comb, &triangles) - 11.0).abs() < 1.0e-4);
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let bow_tie = vec![
+            Vector2::new(0.0, 0.0),
+            Vector2::new(2.0, 2.0),
+            Vector2::new(2.0, 0.0),
+            Vector2::new(0.0, 1.0),
+        ];
+        assert_eq!(
+            triangulate_with_holes(&bow_tie, &[]),
+            Err(TriangulationError::SelfIntersection(0, 2))
+        );
+
+        let line = vec![
+            Vector2::new(0.0, 0.0),
+            Vector2::new(1.0, 0.0),
+            Vector2::new(2.0, 0.0),
+        ];
+        assert_eq!(
+            triangulate_with_holes(&line, &[]),
+            Err(TriangulationError::ZeroArea)
+        );
+        assert_eq!(delaunay(&line), Err(TriangulationError::NotEnoughPoints));
+
+        let mut duplicate = square(0.0, 0.0, 1.0);
+        duplicate.push(Vector2::new(1.0, 0.0));
+        assert_eq!(
+            delaunay(&duplicate),
+            Err(TriangulationError::DuplicatePoint(1, 4))
+        );
+
+        let outline = square(0.0, 0.0, 1.0);
+        assert_eq!(
+            triangulate_with_holes(&outline, &[square(2.0, 2.0, 1.0)]),
+            Err(TriangulationError::InvalidHole(0))
+        );
+        assert_eq!(
+            triangulate_with_holes(&outline, &[square(0.5, 0.5, 1.0)]),
+            Err(TriangulationError::SelfIntersection(2, 7))
+        );
+        assert_eq!(
+            triangulate_with_holes(&[Vector2::new(f32::NAN, 0.0); 3], &[]),
+            Err(TriangulationError::InvalidPoint(0))
+        );
+    }
+}
@@ -18,8 +18,13 @@
 
 #![allow(clippy::unneeded_field_pattern)]
 
+mod sync;
+
+pub use sync::{SyncPool, SyncPoolRef, SyncPoolRefMut};
+
 use crate::visitor::{Visit, VisitResult, Visitor};
 use std::{
+    collections::HashMap,
     fmt::{Debug, Formatter},
     hash::{Hash, Hasher},
     iter::FromIterator,
@@ -40,6 +45,8 @@ const INVALID_GENERATION: u32 = 0;
 pub struct Pool<T: Sized> {
     records: Vec<PoolRecord<T>>,
     free_stack: Vec<u32>,
+    // Max generation of records removed by compaction.
+    generation_floor: u32,
 }
 
 impl<T: PartialEq> PartialEq for Pool<T> {
@@ -307,6 +314,7 @@ impl<T: Clone> Clone for Pool<T> {
         Self {
             records: self.records.clone(),
             free_stack: self.free_stack.clone(),
+            generation_floor: self.generation_floor,
         }
     }
 }
@@ -317,6 +325,7 @@ impl<T> Pool<T> {
         Pool {
             records: Vec::new(),
             free_stack: Vec::new(),
+            generation_floor: 0,
         }
     }
 
@@ -325,6 +334,7 @@ impl<T> Pool<T> {
         Pool {
             records: Vec::with_capacity(capacity),
             free_stack: Vec::new(),
+            generation_floor: 0,
         }
     }
 
@@ -365,8 +375,9 @@ impl<T> Pool<T> {
             record.payload.replace(payload);
             handle
         } else {
-            // No free records, create new one
-            let generation = 1;
+            // No free records, create new one. Its generation must be greater than generation of
+            // any record that was removed by compaction at the same index.
+            let generation = self.generation_floor + 1;
 
             let handle = Handle {
                 index: self.records.len() as u32,
@@ -421,8 +432,9 @@ impl<T> Pool<T> {
             record.payload.replace(payload);
             handle
         } else {
-            // No free records, create new one
-            let generation = 1;
+            // No free records, create new one. Its generation must be greater than generation of
+            // any record that was removed by compaction at the same index.
+            let generation = self.generation_floor + 1;
 
             let handle = Handle {
                 index: self.records.len() as u32,
@@ -824,6 +836,78 @@ impl<T> Pool<T> {
         self.free_stack.clear();
     }
 
+    /// Moves all objects to the beginning of the pool and removes vacant records, so iteration
+    /// does not have to skip holes left by freed objects. Returns mapping from old handles to
+    /// new ones for every moved object, handles of objects that weren't moved stay valid.
+    ///
+    /// # Remarks
+    ///
+    /// Objects in pool that store handles to each other must be remapped using returned
+    /// mapping. Moved objects get new generation, so old handles to them become invalid. Handles
+    /// to already freed objects stay invalid too, records created later at removed indices get
+    /// generation greater than any generation that was used before compaction.
+    ///
+    /// # Panics
+    ///
+    /// Panics if some record is reserved by [`take_reserve`](Self::take_reserve).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rg3d_core::pool::Pool;
+    /// let mut pool = Pool::<u32>::new();
+    /// let a = pool.spawn(1);
+    /// let b = pool.spawn(2);
+    /// let c = pool.spawn(3);
+    /// pool.free(b);
+    /// let mapping = pool.compact();
+    /// assert_eq!(pool.get_capacity(), 2);
+    /// assert!(!mapping.contains_key(&a));
+    /// assert_eq!(pool[mapping[&c]], 3);
+    /// ```
+    pub fn compact(&mut self) -> HashMap<Handle<T>, Handle<T>> {
+        let alive_count = self.alive_count();
+        assert_eq!(
+            alive_count + self.free_stack.len(),
+            self.records.len(),
+            "Attempt to compact pool with reserved records!"
+        );
+
+        let mut mapping = HashMap::new();
+        let mut vacant = 0;
+        for index in 0..self.records.len() {
+            if self.records[index].payload.is_none() {
+                continue;
+            }
+
+            if index != vacant {
+                // Every record in `vacant..index` is vacant.
+                let (head, tail) = self.records.split_at_mut(index);
+                let source = &mut tail[0];
+                let target = &mut head[vacant];
+
+                // New generation must differ from any generation ever used in both records.
+                let generation = target.generation.max(source.generation) + 1;
+                target.generation = generation;
+                target.payload = source.payload.take();
+
+                mapping.insert(
+                    Handle::new(index as u32, source.generation),
+                    Handle::new(vacant as u32, generation),
+                );
+            }
+
+            vacant += 1;
+        }
+
+        for record in self.records.drain(alive_count..) {
+            self.generation_floor = self.generation_floor.max(record.generation);
+        }
+        self.free_stack.clear();
+
+        mapping
+    }
+
     #[inline]
     #[must_use]
     pub fn at_mut(&mut self, n: usize) -> Option<&mut T> {
@@ -1212,4 +1296,67 @@ mod test {
         assert_eq!(pool.handle_of(pool.borrow(bar)), bar);
         assert_eq!(pool.handle_of(pool.borrow(baz)), baz);
     }
+
+    #[test]
+    fn pool_compact() {
+        let mut pool = Pool::new();
+        let handles = (0..10).map(|i| pool.spawn(i)).collect::<Vec<_>>();
+        for handle in handles.iter().step_by(3) {
+            pool.free(*handle);
+        }
+        // Reuse one of the records to bump its generation.
+        let reused = pool.spawn(100);
+        assert_eq!(reused.index, 9);
+
+        let mapping = pool.compact();
+        assert_eq!(pool.get_capacity(), pool.alive_count());
+        assert_eq!(pool.alive_count(), 7);
+        assert_eq!(
+            pool.iter().cloned().collect::<Vec<_>>(),
+            [1, 2, 4, 5, 7, 8, 100]
+        );
+
+        for (i, handle) in handles.iter().enumerate().filter(|(i, _)| i % 3 != 0) {
+            let new_handle = mapping.get(handle).cloned().unwrap_or(*handle);
+            assert_eq!(pool[new_handle], i);
+            if new_handle != *handle {
+                assert!(!pool.is_valid_handle(*handle));
+            }
+        }
+        assert_eq!(pool[mapping[&reused]], 100);
+        // Handles to freed objects are out of bounds or point to moved objects with new generation.
+        for handle in handles.iter().step_by(3) {
+            assert!(!pool.is_valid_handle(*handle));
+        }
+
+        // Pool is usable after compaction.
+        let new = pool.spawn(200);
+        assert_eq!(new.index, 7);
+        assert!(pool.compact().is_empty());
+    }
+
+    #[test]
+    fn pool_compact_keeps_stale_handles_invalid() {
+        let mut pool = Pool::new();
+        let a = pool.spawn(1);
+        let b = pool.spawn(2);
+        let c = pool.spawn(3);
+        pool.free(b);
+        pool.free(c);
+        let mapping = pool.compact();
+        assert!(mapping.is_empty());
+        assert_eq!(pool.get_capacity(), 1);
+
+        // New records are created at indices of removed ones.
+        let d = pool.spawn(4);
+        let e = pool.spawn(5);
+        assert_eq!(d.index, b.index);
+        assert_eq!(e.index, c.index);
+        assert!(!pool.is_valid_handle(b));
+        assert!(!pool.is_valid_handle(c));
+        assert!(pool.try_borrow(b).is_none());
+        assert_eq!(pool[a], 1);
+        assert_eq!(pool[d], 4);
+        assert_eq!(pool[e], 5);
+    }
 }
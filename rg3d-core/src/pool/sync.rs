@@ -0,0 +1,523 @@
+//! Thread-safe variant of [`Pool`](super::Pool).
+//!
+//! # Overview
+//!
+//! [`SyncPool`] can be shared between threads and allows to spawn, free and borrow objects
+//! through shared reference. It uses the same [`Handle`] and generation semantics as ordinary
+//! pool: a handle is valid until its object is freed, a record reused by another object gets new
+//! generation.
+//!
+//! Records are stored in segments of growing size which are never moved or deallocated while
+//! pool is alive, so growing the pool does not block readers. Each record has atomic generation
+//! which allows to validate handles without any locks, payload of a record is protected by its
+//! own lock, so threads working with different objects do not block each other.
+//!
+//! # Example
+//!
+//! ```
+//! use rg3d_core::pool::SyncPool;
+//! use rayon::prelude::*;
+//!
+//! let pool = SyncPool::new();
+//! let handles = (0..100u32)
+//!     .into_par_iter()
+//!     .map(|i| pool.spawn(i))
+//!     .collect::<Vec<_>>();
+//! assert_eq!(pool.alive_count(), 100);
+//!
+//! pool.par_for_each_mut(|_, value| *value *= 2);
+//! assert_eq!(*pool.borrow(handles[10]), 20);
+//!
+//! handles.par_iter().for_each(|handle| {
+//!     pool.free(*handle);
+//! });
+//! assert!(!pool.is_valid_handle(handles[10]));
+//! ```
+
+use crate::pool::{Handle, INVALID_GENERATION};
+use rayon::prelude::*;
+use std::{
+    fmt::{Debug, Formatter},
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    ptr,
+    sync::{
+        atomic::{AtomicPtr, AtomicU32, Ordering},
+        Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard,
+    },
+};
+
+/// First segment has `2^FIRST_SEGMENT_BITS` records, every next one is twice as large.
+const FIRST_SEGMENT_BITS: u32 = 5;
+/// Enough segments to address any `u32` index.
+const SEGMENT_COUNT: usize = (u32::BITS - FIRST_SEGMENT_BITS + 1) as usize;
+
+fn segment_len(segment: usize) -> usize {
+    1 << (segment as u32 + FIRST_SEGMENT_BITS)
+}
+
+/// Returns segment and offset in the segment of a record with given index.
+fn location(index: u32) -> (usize, usize) {
+    let i = index as u64 + (1 << FIRST_SEGMENT_BITS);
+    let bit = u64::BITS - 1 - i.leading_zeros();
+    (
+        (bit - FIRST_SEGMENT_BITS) as usize,
+        (i - (1 << bit)) as usize,
+    )
+}
+
+struct Slot<T> {
+    /// Generation of last object that was put in the record.
+    generation: u32,
+    payload: Option<T>,
+}
+
+struct SyncPoolRecord<T> {
+    /// Generation of current object or [`INVALID_GENERATION`] if the record is vacant. Changed
+    /// only while `slot` is locked for writing.
+    generation: AtomicU32,
+    slot: RwLock<Slot<T>>,
+}
+
+impl<T> Default for SyncPoolRecord<T> {
+    fn default() -> Self {
+        Self {
+            generation: AtomicU32::new(INVALID_GENERATION),
+            slot: RwLock::new(Slot {
+                generation: INVALID_GENERATION,
+                payload: None,
+            }),
+        }
+    }
+}
+
+/// Pool that allows concurrent access from multiple threads, see module docs for more info.
+pub struct SyncPool<T> {
+    segments: [AtomicPtr<SyncPoolRecord<T>>; SEGMENT_COUNT],
+    /// Amount of records that were ever used.
+    record_count: AtomicU32,
+    free_stack: Mutex<Vec<u32>>,
+    alive_count: AtomicU32,
+    type_marker: PhantomData<T>,
+}
+
+// Objects can be moved to another thread by `free` and accessed by many threads at once by
+// `borrow`, same requirements as for `RwLock<T>`.
+unsafe impl<T: Send> Send for SyncPool<T> {}
+unsafe impl<T: Send + Sync> Sync for SyncPool<T> {}
+
+impl<T> Default for SyncPool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Debug for SyncPool<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SyncPool")
+            .field("record_count", &self.record_count.load(Ordering::Acquire))
+            .field("alive_count", &self.alive_count())
+            .finish()
+    }
+}
+
+impl<T> Drop for SyncPool<T> {
+    fn drop(&mut self) {
+        for (segment, ptr) in self.segments.iter_mut().enumerate() {
+            let ptr = *ptr.get_mut();
+            if !ptr.is_null() {
+                // SAFETY: Segment was created by `Box::into_raw` of a slice of the same length.
+                unsafe {
+                    drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+                        ptr,
+                        segment_len(segment),
+                    )));
+                }
+            }
+        }
+    }
+}
+
+impl<T> SyncPool<T> {
+    /// Creates new empty pool.
+    pub fn new() -> Self {
+        Self {
+            segments: Default::default(),
+            record_count: AtomicU32::new(0),
+            free_stack: Mutex::new(Vec::new()),
+            alive_count: AtomicU32::new(0),
+            type_marker: PhantomData,
+        }
+    }
+
+    fn record(&self, index: u32) -> Option<&SyncPoolRecord<T>> {
+        if index >= self.record_count.load(Ordering::Acquire) {
+            return None;
+        }
+        let (segment, offset) = location(index);
+        let ptr = self.segments[segment].load(Ordering::Acquire);
+        if ptr.is_null() {
+            // Index was reserved, but its segment is still being created.
+            None
+        } else {
+            // SAFETY: Segments are never deallocated while pool is alive and offset is always
+            // less than segment length.
+            Some(unsafe { &*ptr.add(offset) })
+        }
+    }
+
+    /// Returns record with given index, creates its segment if needed.
+    fn record_or_create(&self, index: u32) -> &SyncPoolRecord<T> {
+        let (segment, offset) = location(index);
+        let mut ptr = self.segments[segment].load(Ordering::Acquire);
+        if ptr.is_null() {
+            let new_segment = (0..segment_len(segment))
+                .map(|_| SyncPoolRecord::<T>::default())
+                .collect::<Box<[_]>>();
+            let new_ptr = Box::into_raw(new_segment) as *mut SyncPoolRecord<T>;
+            match self.segments[segment].compare_exchange(
+                ptr::null_mut(),
+                new_ptr,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => ptr = new_ptr,
+                Err(existing) => {
+                    // Other thread was faster.
+                    // SAFETY: Segment was just created by `Box::into_raw` and was never shared.
+                    unsafe {
+                        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+                            new_ptr,
+                            segment_len(segment),
+                        )));
+                    }
+                    ptr = existing;
+                }
+            }
+        }
+        // SAFETY: See `record`.
+        unsafe { &*ptr.add(offset) }
+    }
+
+    /// Puts an object in the pool and returns its handle. Can be called from multiple threads
+    /// at once.
+    pub fn spawn(&self, payload: T) -> Handle<T> {
+        let free_index = self.free_stack.lock().unwrap().pop();
+        let index = match free_index {
+            Some(index) => index,
+            None => {
+                let index = self.record_count.fetch_add(1, Ordering::AcqRel);
+                assert_ne!(index, u32::MAX, "SyncPool is out of handles!");
+                index
+            }
+        };
+
+        let record = self.record_or_create(index);
+        let mut slot = record.slot.write().unwrap();
+        assert!(
+            slot.payload.is_none(),
+            "Attempt to spawn an object at pool record with payload! Record index is {}",
+            index
+        );
+        slot.generation = slot.generation.wrapping_add(1);
+        if slot.generation == INVALID_GENERATION {
+            slot.generation += 1;
+        }
+        slot.payload = Some(payload);
+        record.generation.store(slot.generation, Ordering::Release);
+        self.alive_count.fetch_add(1, Ordering::AcqRel);
+
+        Handle::new(index, slot.generation)
+    }
+
+    /// Checks if given handle points to an object in the pool. Does not take any locks, but the
+    /// object may be freed by other thread right after the check.
+    pub fn is_valid_handle(&self, handle: Handle<T>) -> bool {
+        self.valid_record(handle).is_some()
+    }
+
+    fn valid_record(&self, handle: Handle<T>) -> Option<&SyncPoolRecord<T>> {
+        if handle.generation() == INVALID_GENERATION {
+            return None;
+        }
+        // Atomic check first, so handles to freed objects never wait for locks.
+        self.record(handle.index())
+            .filter(|record| record.generation.load(Ordering::Acquire) == handle.generation())
+    }
+
+    /// Borrows an object by its handle, returns `None` if handle is invalid. Blocks while the
+    /// object is mutably borrowed by other thread.
+    pub fn try_borrow(&self, handle: Handle<T>) -> Option<SyncPoolRef<'_, T>> {
+        let slot = self.valid_record(handle)?.slot.read().unwrap();
+        // Object could be freed while we were waiting for the lock.
+        if slot.generation == handle.generation() && slot.payload.is_some() {
+            Some(SyncPoolRef { slot })
+        } else {
+            None
+        }
+    }
+
+    /// Mutably borrows an object by its handle, returns `None` if handle is invalid. Blocks
+    /// while the object is borrowed by other threads.
+    pub fn try_borrow_mut(&self, handle: Handle<T>) -> Option<SyncPoolRefMut<'_, T>> {
+        let slot = self.valid_record(handle)?.slot.write().unwrap();
+        if slot.generation == handle.generation() && slot.payload.is_some() {
+            Some(SyncPoolRefMut { slot })
+        } else {
+            None
+        }
+    }
+
+    /// Borrows an object by its handle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if handle is invalid.
+    pub fn borrow(&self, handle: Handle<T>) -> SyncPoolRef<'_, T> {
+        self.try_borrow(handle).unwrap_or_else(|| {
+            panic!(
+                "Attempt to borrow object using invalid handle {:?}!",
+                handle
+            )
+        })
+    }
+
+    /// Mutably borrows an object by its handle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if handle is invalid.
+    pub fn borrow_mut(&self, handle: Handle<T>) -> SyncPoolRefMut<'_, T> {
+        self.try_borrow_mut(handle).unwrap_or_else(|| {
+            panic!(
+                "Attempt to borrow object using invalid handle {:?}!",
+                handle
+            )
+        })
+    }
+
+    /// Moves an object out of the pool, returns `None` if handle is invalid (for example if the
+    /// object was already freed by other thread).
+    pub fn try_free(&self, handle: Handle<T>) -> Option<T> {
+        let record = self.valid_record(handle)?;
+        let payload = {
+            let mut slot = record.slot.write().unwrap();
+            if slot.generation != handle.generation() {
+                return None;
+            }
+            let payload = slot.payload.take()?;
+            record
+                .generation
+                .store(INVALID_GENERATION, Ordering::Release);
+            payload
+        };
+        self.free_stack.lock().unwrap().push(handle.index());
+        self.alive_count.fetch_sub(1, Ordering::AcqRel);
+        Some(payload)
+    }
+
+    /// Moves an object out of the pool. All handles to the object will become invalid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if handle is invalid.
+    pub fn free(&self, handle: Handle<T>) -> T {
+        self.try_free(handle)
+            .unwrap_or_else(|| panic!("Attempt to free object using invalid handle {:?}!", handle))
+    }
+
+    /// Returns amount of objects in the pool.
+    pub fn alive_count(&self) -> usize {
+        self.alive_count.load(Ordering::Acquire) as usize
+    }
+
+    /// Returns amount of records in the pool, both vacant and occupied.
+    pub fn get_capacity(&self) -> usize {
+        self.record_count.load(Ordering::Acquire) as usize
+    }
+
+    /// Removes all objects from the pool. All handles will become invalid.
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Calls given function for every object in the pool in the current thread.
+    pub fn for_each<F>(&self, mut func: F)
+    where
+        F: FnMut(Handle<T>, &T),
+    {
+        for index in 0..self.record_count.load(Ordering::Acquire) {
+            if let Some(record) = self.record(index) {
+                let slot = record.slot.read().unwrap();
+                if let Some(payload) = slot.payload.as_ref() {
+                    func(Handle::new(index, slot.generation), payload);
+                }
+            }
+        }
+    }
+
+    /// Calls given function for every object in the pool, objects are processed in parallel by
+    /// rayon thread pool. Objects spawned during iteration may or may not be visited.
+    pub fn par_for_each<F>(&self, func: F)
+    where
+        F: Fn(Handle<T>, &T) + Send + Sync,
+        T: Send + Sync,
+    {
+        (0..self.record_count.load(Ordering::Acquire))
+            .into_par_iter()
+            .for_each(|index| {
+                if let Some(record) = self.record(index) {
+                    let slot = record.slot.read().unwrap();
+                    if let Some(payload) = slot.payload.as_ref() {
+                        func(Handle::new(index, slot.generation), payload);
+                    }
+                }
+            });
+    }
+
+    /// Same as [`Self::par_for_each`], but gives mutable access to objects.
+    pub fn par_for_each_mut<F>(&self, func: F)
+    where
+        F: Fn(Handle<T>, &mut T) + Send + Sync,
+        T: Send + Sync,
+    {
+        (0..self.record_count.load(Ordering::Acquire))
+            .into_par_iter()
+            .for_each(|index| {
+                if let Some(record) = self.record(index) {
+                    let mut slot = record.slot.write().unwrap();
+                    let generation = slot.generation;
+                    if let Some(payload) = slot.payload.as_mut() {
+                        func(Handle::new(index, generation), payload);
+                    }
+                }
+            });
+    }
+}
+
+/// Shared borrow of an object in [`SyncPool`], the object can't be freed or mutably borrowed
+/// while the borrow is alive.
+pub struct SyncPoolRef<'a, T> {
+    slot: RwLockReadGuard<'a, Slot<T>>,
+}
+
+impl<'a, T> Deref for SyncPoolRef<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // Payload is checked when borrow is created and can't be taken while it is alive.
+        self.slot.payload.as_ref().unwrap()
+    }
+}
+
+/// Mutable borrow of an object in [`SyncPool`], the object can't be freed or borrowed by
+/// other threads while the borrow is alive.
+pub struct SyncPoolRefMut<'a, T> {
+    slot: RwLockWriteGuard<'a, Slot<T>>,
+}
+
+impl<'a, T> Deref for SyncPoolRefMut<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.slot.payload.as_ref().unwrap()
+    }
+}
+
+impl<'a, T> DerefMut for SyncPoolRefMut<'a, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.slot.payload.as_mut().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::pool::{
+        sync::{location, segment_len, SEGMENT_COUNT},
+        SyncPool,
+    };
+    use rayon::prelude::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn segment_locations() {
+        assert_eq!(location(0), (0, 0));
+        assert_eq!(location(31), (0, 31));
+        assert_eq!(location(32), (1, 0));
+        assert_eq!(location(95), (1, 63));
+        assert_eq!(location(96), (2, 0));
+        let (segment, offset) = location(u32::MAX);
+        assert_eq!(segment, SEGMENT_COUNT - 1);
+        assert!(offset < segment_len(segment));
+    }
+
+    #[test]
+    fn sync_pool_sanity() {
+        let pool = SyncPool::new();
+        let foo = pool.spawn("Foo".to_owned());
+        let bar = pool.spawn("Bar".to_owned());
+        assert_eq!(&*pool.borrow(foo), "Foo");
+        pool.borrow_mut(bar).push('!');
+        assert_eq!(&*pool.borrow(bar), "Bar!");
+
+        assert_eq!(pool.free(foo), "Foo");
+        assert!(!pool.is_valid_handle(foo));
+        assert!(pool.try_borrow(foo).is_none());
+        assert!(pool.try_free(foo).is_none());
+
+        // Record is reused with new generation, old handle stays invalid.
+        let baz = pool.spawn("Baz".to_owned());
+        assert_eq!(baz.index(), foo.index());
+        assert_ne!(baz.generation(), foo.generation());
+        assert!(pool.try_borrow(foo).is_none());
+        assert_eq!(&*pool.borrow(baz), "Baz");
+        assert_eq!(pool.alive_count(), 2);
+    }
+
+    #[test]
+    fn concurrent_spawn_and_free() {
+        let pool = SyncPool::new();
+
+        let handles = (0..10_000usize)
+            .into_par_iter()
+            .map(|i| pool.spawn(i))
+            .collect::<Vec<_>>();
+        assert_eq!(pool.alive_count(), 10_000);
+        for (i, handle) in handles.iter().enumerate() {
+            assert_eq!(*pool.borrow(*handle), i);
+        }
+
+        // Free every odd object while spawning new ones at the same time.
+        let respawned = handles
+            .par_iter()
+            .enumerate()
+            .filter_map(|(i, handle)| {
+                if i % 2 == 1 {
+                    assert_eq!(pool.free(*handle), i);
+                    Some(pool.spawn(i + 10_000))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(pool.alive_count(), 10_000);
+        assert!(pool.get_capacity() <= 10_000 + rayon::current_num_threads());
+
+        let sum = AtomicUsize::new(0);
+        pool.par_for_each(|handle, value| {
+            assert!(pool.is_valid_handle(handle));
+            sum.fetch_add(*value, Ordering::Relaxed);
+        });
+        let expected = (0..10_000).step_by(2).sum::<usize>()
+            + (1..10_000).step_by(2).map(|i| i + 10_000).sum::<usize>();
+        assert_eq!(sum.into_inner(), expected);
+
+        pool.par_for_each_mut(|_, value| *value = 0);
+        for handle in handles.iter().step_by(2).chain(respawned.iter()) {
+            assert_eq!(*pool.borrow(*handle), 0);
+        }
+        for handle in handles.iter().skip(1).step_by(2) {
+            assert!(!pool.is_valid_handle(*handle));
+        }
+    }
+}
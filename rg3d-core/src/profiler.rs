@@ -1,17 +1,40 @@
 //! Built-in scoped profiler. You must compile with feature "enable_profiler" to
 //! force profiler gather info! It is disabled by default because it is not cheap
 //! and takes 3-5% of performance for internal needs.
+//!
+//! Besides accumulated samples, profiler captures a timeline of scopes of every thread for last
+//! few frames (see [`set_capture_capacity`]). Frames are separated by [`end_frame`] calls (the
+//! engine does this at the end of `Engine::render`). The timeline can be dumped at any time in
+//! Chrome `trace_event` format by [`save_chrome_trace`] and then inspected in `chrome://tracing`,
+//! [Perfetto](https://ui.perfetto.dev) or in Tracy (via its `import-chrome` tool).
+//!
+//! Profiling can be paused and resumed at runtime by [`stop`] and [`start`]. Along with scopes,
+//! profiler records named per-frame counters (see [`add_counter`] and [`record_counters`]), the
+//! engine feeds renderer and physics statistics into them, so CPU timings of a frame can be
+//! correlated with amount of draw calls, physics step time, etc.
 
 #![allow(dead_code)]
 
 use std::{
-    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    collections::{hash_map::DefaultHasher, HashMap, HashSet, VecDeque},
     fmt,
     fmt::Write,
+    fs::File,
     hash::{Hash, Hasher},
+    io::{self, BufWriter},
+    path::Path,
     sync::{Arc, Mutex},
+    thread::ThreadId,
+    time::Instant,
 };
 
+/// Default amount of frames in timeline capture.
+pub const DEFAULT_CAPTURE_CAPACITY: usize = 120;
+
+/// Maximum amount of scope events in a single frame of timeline capture. Events above the limit
+/// are dropped, this keeps memory usage bounded if [`end_frame`] is never called.
+pub const MAX_FRAME_EVENTS: usize = 65536;
+
 pub fn print() -> Result<String, fmt::Error> {
     #[cfg(feature = "enable_profiler")]
     {
@@ -40,6 +63,172 @@ pub fn print_hot_path() -> Result<String, fmt::Error> {
     }
 }
 
+/// Resumes profiling, does nothing if profiler is already running.
+pub fn start() {
+    PROFILER.lock().unwrap().start();
+}
+
+/// Pauses profiling, scopes that are entered while profiler is stopped are ignored.
+pub fn stop() {
+    PROFILER.lock().unwrap().running = false;
+}
+
+/// Returns true if profiler gathers info, it is always false if the engine is compiled without
+/// "enable_profiler" feature.
+pub fn is_running() -> bool {
+    cfg!(feature = "enable_profiler") && PROFILER.lock().unwrap().running
+}
+
+/// Discards every gathered sample, captured frames and counters.
+pub fn reset() {
+    PROFILER.lock().unwrap().reset();
+}
+
+/// Adds given value to a counter of current frame, counter is created if it does not exist.
+pub fn add_counter(name: &'static str, value: f64) {
+    #[cfg(feature = "enable_profiler")]
+    {
+        PROFILER.lock().unwrap().add_counter(name, value);
+    }
+    #[cfg(not(feature = "enable_profiler"))]
+    {
+        let _ = (name, value);
+    }
+}
+
+/// Sets value of a counter of current frame, counter is created if it does not exist.
+pub fn set_counter(name: &'static str, value: f64) {
+    #[cfg(feature = "enable_profiler")]
+    {
+        PROFILER.lock().unwrap().set_counter(name, value);
+    }
+    #[cfg(not(feature = "enable_profiler"))]
+    {
+        let _ = (name, value);
+    }
+}
+
+/// Adds every counter of given source to counters of current frame.
+pub fn record_counters<S: CounterSource + ?Sized>(source: &S) {
+    #[cfg(feature = "enable_profiler")]
+    {
+        let mut profiler = PROFILER.lock().unwrap();
+        source.for_each_counter(&mut |name, value| profiler.add_counter(name, value));
+    }
+    #[cfg(not(feature = "enable_profiler"))]
+    {
+        let _ = source;
+    }
+}
+
+/// Something that has numbers worth to be shown next to scopes in profiling results, for
+/// example renderer statistics.
+pub trait CounterSource {
+    /// Calls given function for every counter with its name and value.
+    fn for_each_counter(&self, func: &mut dyn FnMut(&'static str, f64));
+}
+
+/// Finishes current frame of timeline capture and begins new one. If there are more frames in
+/// the capture than its capacity, the oldest frame is discarded.
+pub fn end_frame() {
+    #[cfg(feature = "enable_profiler")]
+    {
+        PROFILER.lock().unwrap().end_frame();
+    }
+}
+
+/// Sets maximum amount of frames in timeline capture, zero disables the capture.
+pub fn set_capture_capacity(frames: usize) {
+    PROFILER.lock().unwrap().set_capture_capacity(frames);
+}
+
+/// Returns maximum amount of frames in timeline capture.
+pub fn capture_capacity() -> usize {
+    PROFILER.lock().unwrap().capture_capacity
+}
+
+/// Returns copy of every finished frame in timeline capture, from oldest to newest.
+pub fn captured_frames() -> Vec<FrameCapture> {
+    PROFILER.lock().unwrap().frames.iter().cloned().collect()
+}
+
+/// Writes every finished frame in timeline capture in Chrome `trace_event` JSON format.
+pub fn write_chrome_trace<W: io::Write>(writer: &mut W) -> io::Result<()> {
+    PROFILER.lock().unwrap().write_chrome_trace(writer)
+}
+
+/// Saves every finished frame in timeline capture in Chrome `trace_event` JSON format to given
+/// file.
+pub fn save_chrome_trace<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_chrome_trace(&mut writer)?;
+    io::Write::flush(&mut writer)
+}
+
+/// Single execution of a profiled scope.
+#[derive(Clone, Debug)]
+pub struct ScopeEvent {
+    /// Name of the function that contains the scope.
+    pub function_name: &'static str,
+    /// Line of the scope.
+    pub line: u32,
+    /// Index of the thread that executed the scope, threads are numbered in order of appearance.
+    pub thread: u32,
+    /// Time when the scope was entered, in seconds since profiler start.
+    pub begin: f64,
+    /// Time when the scope was left, in seconds since profiler start.
+    pub end: f64,
+}
+
+/// Timeline of a single frame.
+#[derive(Clone, Debug, Default)]
+pub struct FrameCapture {
+    /// Sequential number of the frame.
+    pub index: u64,
+    /// Time when the frame has begun, in seconds since profiler start.
+    pub begin: f64,
+    /// Time when the frame has ended, in seconds since profiler start.
+    pub end: f64,
+    /// Every scope that was left during the frame, in order of leaving.
+    pub events: Vec<ScopeEvent>,
+    /// Amount of scope events that were dropped, because there were more than
+    /// [`MAX_FRAME_EVENTS`] events in the frame.
+    pub dropped_events: usize,
+    /// Values of counters of the frame, in order of first appearance.
+    pub counters: Vec<(&'static str, f64)>,
+}
+
+impl FrameCapture {
+    /// Returns value of a counter with given name.
+    pub fn counter(&self, name: &str) -> Option<f64> {
+        self.counters
+            .iter()
+            .find(|(counter, _)| *counter == name)
+            .map(|(_, value)| *value)
+    }
+
+    fn counter_mut(&mut self, name: &'static str) -> &mut f64 {
+        let index = match self
+            .counters
+            .iter()
+            .position(|(counter, _)| *counter == name)
+        {
+            Some(index) => index,
+            None => {
+                self.counters.push((name, 0.0));
+                self.counters.len() - 1
+            }
+        };
+        &mut self.counters[index].1
+    }
+}
+
+struct ThreadState {
+    index: u32,
+    name: String,
+    scope_stack: Vec<ScopeMark>,
+}
+
 struct Sample {
     count: u64,
     time: f64,
@@ -71,9 +260,13 @@ struct ScopeMark {
 }
 
 struct Profiler {
-    start_time: std::time::Instant,
+    running: bool,
+    start_time: Instant,
     samples: HashMap<ScopeMark, Sample>,
-    scope_stack: Vec<ScopeMark>,
+    threads: HashMap<ThreadId, ThreadState>,
+    capture_capacity: usize,
+    frames: VecDeque<FrameCapture>,
+    current_frame: FrameCapture,
 }
 
 const ENTRY_SCOPE_MARK: ScopeMark = ScopeMark {
@@ -93,9 +286,13 @@ impl Default for Profiler {
         let mut samples = HashMap::new();
         samples.insert(ENTRY_SCOPE_MARK, entry_sample);
         Self {
-            start_time: std::time::Instant::now(),
+            running: true,
+            start_time: Instant::now(),
             samples,
-            scope_stack: vec![ENTRY_SCOPE_MARK],
+            threads: Default::default(),
+            capture_capacity: DEFAULT_CAPTURE_CAPACITY,
+            frames: Default::default(),
+            current_frame: Default::default(),
         }
     }
 }
@@ -106,27 +303,228 @@ fn calculate_hash<T: Hash>(t: &T) -> u64 {
     s.finish()
 }
 
+fn escape_json(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len());
+    for c in string.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 impl Profiler {
+    fn current_thread(&mut self) -> &mut ThreadState {
+        let thread = std::thread::current();
+        let index = self.threads.len() as u32;
+        self.threads
+            .entry(thread.id())
+            .or_insert_with(|| ThreadState {
+                index,
+                name: thread
+                    .name()
+                    .map(|name| name.to_owned())
+                    .unwrap_or_else(|| format!("Thread {}", index)),
+                scope_stack: vec![ENTRY_SCOPE_MARK],
+            })
+    }
+
+    fn time(&self, instant: Instant) -> f64 {
+        instant
+            .saturating_duration_since(self.start_time)
+            .as_secs_f64()
+    }
+
     fn enter_scope(&mut self, scope: &mut ScopeMark) {
-        let parent_scope_mark = *self.scope_stack.last().unwrap();
+        let scope_stack = &mut self.current_thread().scope_stack;
+        let parent_scope_mark = *scope_stack.last().unwrap();
         scope.parent_scope_hash = calculate_hash(&parent_scope_mark);
-        self.scope_stack.push(*scope);
+        scope_stack.push(*scope);
         self.samples.entry(*scope).or_default();
+        // Parent sample could be discarded by reset while its scope is still active.
         self.samples
-            .get_mut(&parent_scope_mark)
-            .unwrap()
+            .entry(parent_scope_mark)
+            .or_default()
             .children
             .insert(*scope);
     }
 
-    fn leave_scope(&mut self, scope: ScopeMark, elapsed: f64) {
-        self.scope_stack.pop();
-        self.samples.get_mut(&scope).unwrap().collect(elapsed);
+    fn leave_scope(&mut self, scope: ScopeMark, begin: Instant, end: Instant) {
+        let thread = self.current_thread();
+        thread.scope_stack.pop();
+        let parent_scope_mark = *thread.scope_stack.last().unwrap();
+        let thread = thread.index;
+
+        self.samples
+            .entry(scope)
+            .or_default()
+            .collect((end - begin).as_secs_f64());
+        self.samples
+            .entry(parent_scope_mark)
+            .or_default()
+            .children
+            .insert(scope);
+
+        if self.current_frame.events.len() >= MAX_FRAME_EVENTS {
+            self.current_frame.dropped_events += 1;
+        } else if self.capture_capacity > 0 {
+            let event = ScopeEvent {
+                function_name: scope.function_name,
+                line: scope.line,
+                thread,
+                begin: self.time(begin),
+                end: self.time(end),
+            };
+            self.current_frame.events.push(event);
+        }
+    }
+
+    fn start(&mut self) {
+        if !self.running {
+            self.running = true;
+            // Do not count the pause as a part of current frame.
+            self.current_frame.begin = self.time(Instant::now());
+        }
+    }
+
+    fn reset(&mut self) {
+        self.start_time = Instant::now();
+        self.samples.clear();
+        self.samples.insert(ENTRY_SCOPE_MARK, Sample::default());
+        self.frames.clear();
+        self.current_frame = FrameCapture {
+            index: self.current_frame.index,
+            ..Default::default()
+        };
+    }
+
+    fn add_counter(&mut self, name: &'static str, value: f64) {
+        if self.running {
+            *self.current_frame.counter_mut(name) += value;
+        }
+    }
+
+    fn set_counter(&mut self, name: &'static str, value: f64) {
+        if self.running {
+            *self.current_frame.counter_mut(name) = value;
+        }
+    }
+
+    fn end_frame(&mut self) {
+        if !self.running {
+            return;
+        }
+
+        let now = self.time(Instant::now());
+        let next_frame = FrameCapture {
+            index: self.current_frame.index + 1,
+            begin: now,
+            end: now,
+            ..Default::default()
+        };
+        let mut frame = std::mem::replace(&mut self.current_frame, next_frame);
+        frame.end = now;
+
+        if self.capture_capacity > 0 {
+            while self.frames.len() >= self.capture_capacity {
+                self.frames.pop_front();
+            }
+            self.frames.push_back(frame);
+        }
+    }
+
+    fn set_capture_capacity(&mut self, frames: usize) {
+        self.capture_capacity = frames;
+        while self.frames.len() > frames {
+            self.frames.pop_front();
+        }
+        if frames == 0 {
+            self.current_frame.events.clear();
+        }
+    }
+
+    // See https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+    // for format description. Timestamps are in microseconds, frames are shown as a separate
+    // "thread" with zero id, so spikes could be spotted immediately.
+    fn write_chrome_trace<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        write!(writer, "{{\"displayTimeUnit\":\"ms\",\"traceEvents\":[")?;
+        write!(
+            writer,
+            "{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":1,\"tid\":0,\"args\":{{\"name\":\"Frames\"}}}}"
+        )?;
+
+        let mut threads = self.threads.values().collect::<Vec<_>>();
+        threads.sort_by_key(|thread| thread.index);
+        for thread in threads {
+            write!(
+                writer,
+                ",{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":1,\"tid\":{},\"args\":{{\"name\":\"{}\"}}}}",
+                thread.index + 1,
+                escape_json(&thread.name)
+            )?;
+        }
+
+        for frame in self.frames.iter() {
+            write!(
+                writer,
+                ",{{\"name\":\"Frame {}\",\"cat\":\"frame\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\"pid\":1,\"tid\":0}}",
+                frame.index,
+                frame.begin * 1_000_000.0,
+                (frame.end - frame.begin) * 1_000_000.0
+            )?;
+
+            for (name, value) in frame.counters.iter() {
+                // JSON has no representation of NaN and infinities.
+                let value = if value.is_finite() {
+                    value.to_string()
+                } else {
+                    "null".to_owned()
+                };
+                write!(
+                    writer,
+                    ",{{\"name\":\"{}\",\"cat\":\"counter\",\"ph\":\"C\",\"ts\":{:.3},\"pid\":1,\"args\":{{\"value\":{}}}}}",
+                    escape_json(name),
+                    frame.begin * 1_000_000.0,
+                    value
+                )?;
+            }
+
+            for event in frame.events.iter() {
+                let name = event
+                    .function_name
+                    .strip_suffix("::scope")
+                    .unwrap_or(event.function_name);
+                write!(
+                    writer,
+                    ",{{\"name\":\"{}\",\"cat\":\"scope\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\"pid\":1,\"tid\":{},\"args\":{{\"line\":{}}}}}",
+                    escape_json(name),
+                    event.begin * 1_000_000.0,
+                    (event.end - event.begin) * 1_000_000.0,
+                    event.thread + 1,
+                    event.line
+                )?;
+            }
+        }
+
+        write!(writer, "]}}")
     }
 
     fn print(&self, buffer: &mut String) -> fmt::Result {
-        let full_time = (std::time::Instant::now() - self.start_time).as_secs_f64();
+        let full_time = (Instant::now() - self.start_time).as_secs_f64();
         self.recursive_print(buffer, &ENTRY_SCOPE_MARK, 0, full_time)?;
+        if let Some(frame) = self.frames.back() {
+            if !frame.counters.is_empty() {
+                writeln!(buffer, "Counters of frame {}:", frame.index)?;
+                for (name, value) in frame.counters.iter() {
+                    writeln!(buffer, "\t{} - {}", name, value)?;
+                }
+            }
+        }
         writeln!(buffer,"=========================================================================================================")?;
         Ok(())
     }
@@ -172,7 +570,7 @@ impl Profiler {
     }
 
     fn print_hot_path(&self, buffer: &mut String) -> fmt::Result {
-        let full_time = (std::time::Instant::now() - self.start_time).as_secs_f64();
+        let full_time = (Instant::now() - self.start_time).as_secs_f64();
         self.print_hot_path_recursive(buffer, &ENTRY_SCOPE_MARK, 0, full_time)?;
         writeln!(buffer, "=========================================================================================================")?;
         Ok(())
@@ -235,7 +633,8 @@ lazy_static! {
 
 pub struct ScopeDefinition {
     scope: ScopeMark,
-    start_time: std::time::Instant,
+    start_time: Instant,
+    active: bool,
 }
 
 impl ScopeDefinition {
@@ -248,24 +647,34 @@ impl ScopeDefinition {
             line,
         };
 
-        PROFILER.lock().unwrap().enter_scope(&mut scope);
+        let active = {
+            let mut profiler = PROFILER.lock().unwrap();
+            if profiler.running {
+                profiler.enter_scope(&mut scope);
+            }
+            profiler.running
+        };
 
         Self {
             scope,
-            start_time: std::time::Instant::now(),
+            start_time: Instant::now(),
+            active,
         }
     }
-
-    #[inline]
-    fn elapsed(&self) -> f64 {
-        (std::time::Instant::now() - self.start_time).as_secs_f64()
-    }
 }
 
 impl Drop for ScopeDefinition {
     fn drop(&mut self) {
-        let elapsed = self.elapsed();
-        PROFILER.lock().unwrap().leave_scope(self.scope, elapsed);
+        // Scope must be left even if profiler was stopped after entering it, otherwise scope
+        // stack will be corrupted.
+        if !self.active {
+            return;
+        }
+        let end_time = Instant::now();
+        PROFILER
+            .lock()
+            .unwrap()
+            .leave_scope(self.scope, self.start_time, end_time);
     }
 }
 
@@ -294,8 +703,8 @@ macro_rules! scope_profile {
 
 #[cfg(test)]
 mod test {
-    use crate::profiler;
-    use std::time::Duration;
+    use crate::profiler::{self, Profiler, ScopeMark, MAX_FRAME_EVENTS};
+    use std::time::{Duration, Instant};
 
     fn nested_func() {
         scope_profile!();
@@ -323,4 +732,125 @@ mod test {
 
         println!("{:?}", profiler::print());
     }
+
+    #[test]
+    fn test_chrome_trace() {
+        let mut profiler = Profiler::default();
+        profiler.set_capture_capacity(2);
+
+        for _ in 0..3 {
+            let mut scope = ScopeMark {
+                parent_scope_hash: 0,
+                function_name: "test::\"quoted\"::scope",
+                line: 42,
+            };
+            profiler.enter_scope(&mut scope);
+            let begin = Instant::now();
+            std::thread::sleep(Duration::from_millis(1));
+            profiler.leave_scope(scope, begin, Instant::now());
+            profiler.end_frame();
+        }
+
+        assert_eq!(profiler.frames.len(), 2);
+        assert_eq!(profiler.frames[0].index, 1);
+        assert_eq!(profiler.frames[1].index, 2);
+        let event = &profiler.frames[1].events[0];
+        assert!(event.end - event.begin >= 0.001);
+
+        let mut trace = Vec::new();
+        profiler.write_chrome_trace(&mut trace).unwrap();
+        let trace: serde_json::Value = serde_json::from_slice(&trace).unwrap();
+        let events = trace["traceEvents"].as_array().unwrap();
+        // Two thread names, two frames and two scopes.
+        assert_eq!(events.len(), 6);
+        assert_eq!(events[3]["name"], "test::\"quoted\"");
+        assert_eq!(events[3]["tid"], 1);
+        assert_eq!(events[3]["args"]["line"], 42);
+    }
+
+    #[test]
+    fn test_counters_and_control() {
+        let mut profiler = Profiler::default();
+
+        profiler.add_counter("Draw Calls", 2.0);
+        profiler.add_counter("Draw Calls", 3.0);
+        profiler.set_counter("Triangles", 100.0);
+        profiler.end_frame();
+
+        profiler.running = false;
+        profiler.add_counter("Draw Calls", 10.0);
+        profiler.end_frame();
+        profiler.start();
+        profiler.end_frame();
+
+        assert_eq!(profiler.frames.len(), 2);
+        assert_eq!(profiler.frames[0].counter("Draw Calls"), Some(5.0));
+        assert_eq!(profiler.frames[0].counter("Triangles"), Some(100.0));
+        assert_eq!(profiler.frames[1].counter("Draw Calls"), None);
+
+        let mut trace = Vec::new();
+        profiler.write_chrome_trace(&mut trace).unwrap();
+        let trace: serde_json::Value = serde_json::from_slice(&trace).unwrap();
+        let counters = trace["traceEvents"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|event| event["ph"] == "C")
+            .count();
+        assert_eq!(counters, 2);
+
+        // Reset while a scope is active must not break leaving of the scope.
+        let mut scope = ScopeMark {
+            parent_scope_hash: 0,
+            function_name: "test",
+            line: 1,
+        };
+        profiler.enter_scope(&mut scope);
+        profiler.reset();
+        let now = Instant::now();
+        profiler.leave_scope(scope, now, now);
+        assert!(profiler.frames.is_empty());
+        assert_eq!(profiler.current_frame.events.len(), 1);
+        assert_eq!(profiler.samples[&scope].count, 1);
+    }
+
+    #[test]
+    fn test_frame_limits() {
+        let mut profiler = Profiler::default();
+
+        // Frame is never ended, amount of events must not grow without limit.
+        let now = Instant::now();
+        for _ in 0..MAX_FRAME_EVENTS + 10 {
+            let mut scope = ScopeMark {
+                parent_scope_hash: 0,
+                function_name: "test",
+                line: 1,
+            };
+            profiler.enter_scope(&mut scope);
+            profiler.leave_scope(scope, now, now);
+        }
+        assert_eq!(profiler.current_frame.events.len(), MAX_FRAME_EVENTS);
+        assert_eq!(profiler.current_frame.dropped_events, 10);
+
+        profiler.set_counter("NaN", f64::NAN);
+        profiler.set_counter("Infinity", f64::INFINITY);
+        profiler.set_counter("Finite", 1.5);
+        profiler.end_frame();
+        assert!(profiler.current_frame.events.is_empty());
+
+        let mut trace = Vec::new();
+        profiler.write_chrome_trace(&mut trace).unwrap();
+        let trace: serde_json::Value = serde_json::from_slice(&trace).unwrap();
+        let counters = trace["traceEvents"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|event| event["ph"] == "C")
+            .map(|event| event["args"]["value"].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            counters,
+            [serde_json::Value::Null, serde_json::Value::Null, 1.5.into()]
+        );
+    }
 }
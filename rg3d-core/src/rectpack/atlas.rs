@@ -0,0 +1,352 @@
+//! Texture atlases built on top of [`MaxRectsPacker`]. [`Atlas`] is a runtime allocator of
+//! regions that spills into additional pages when existing pages are full, [`AtlasBuilder`]
+//! packs a set of images into page images at once.
+
+use crate::{
+    math::Rect,
+    pool::Handle,
+    rectpack::maxrects::{MaxRectsPacker, PackedRect},
+};
+use std::fmt::{Display, Formatter};
+
+/// A region of an atlas page.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AtlasRegion {
+    /// Index of a page.
+    pub page: usize,
+    /// Bounds of the region in the page without padding. Width and height are swapped if the
+    /// region is rotated.
+    pub bounds: Rect<u32>,
+    /// Whether the region is rotated by 90 degrees clockwise.
+    pub rotated: bool,
+    handle: Handle<PackedRect>,
+}
+
+/// Multi-page atlas, see module docs.
+#[derive(Clone, Debug)]
+pub struct Atlas {
+    page_width: u32,
+    page_height: u32,
+    allow_rotation: bool,
+    padding: u32,
+    pages: Vec<MaxRectsPacker>,
+}
+
+impl Atlas {
+    /// Creates new atlas without pages, pages with given size are added when needed.
+    pub fn new(page_width: u32, page_height: u32) -> Self {
+        Self {
+            page_width,
+            page_height,
+            allow_rotation: false,
+            padding: 0,
+            pages: Vec::new(),
+        }
+    }
+
+    /// Allows or forbids rotation of regions by 90 degrees.
+    pub fn with_rotation(mut self, allow_rotation: bool) -> Self {
+        self.allow_rotation = allow_rotation;
+        self
+    }
+
+    /// Sets amount of empty pixels between regions, it prevents bleeding of neighbouring regions
+    /// when a texture is filtered.
+    pub fn with_padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Returns width of every page.
+    pub fn page_width(&self) -> u32 {
+        self.page_width
+    }
+
+    /// Returns height of every page.
+    pub fn page_height(&self) -> u32 {
+        self.page_height
+    }
+
+    /// Returns packers of every page.
+    pub fn pages(&self) -> &[MaxRectsPacker] {
+        &self.pages
+    }
+
+    /// Allocates a region with given size in the first page that has enough space, adds new
+    /// page if there is no such page. Returns None if the region does not fit in an empty page.
+    pub fn allocate(&mut self, width: u32, height: u32) -> Option<AtlasRegion> {
+        let w = width.checked_add(self.padding)?;
+        let h = height.checked_add(self.padding)?;
+        for (page, packer) in self.pages.iter_mut().enumerate() {
+            if let Some(handle) = packer.allocate(w, h) {
+                return Some(Self::region(page, packer, handle, self.padding));
+            }
+        }
+
+        let mut packer = MaxRectsPacker::new(self.page_width, self.page_height)
+            .with_rotation(self.allow_rotation);
+        let handle = packer.allocate(w, h)?;
+        let region = Self::region(self.pages.len(), &packer, handle, self.padding);
+        self.pages.push(packer);
+        Some(region)
+    }
+
+    fn region(
+        page: usize,
+        packer: &MaxRectsPacker,
+        handle: Handle<PackedRect>,
+        padding: u32,
+    ) -> AtlasRegion {
+        let packed = packer.get(handle).unwrap();
+        let mut bounds = packed.bounds;
+        bounds.size.x -= padding;
+        bounds.size.y -= padding;
+        AtlasRegion {
+            page,
+            bounds,
+            rotated: packed.rotated,
+            handle,
+        }
+    }
+
+    /// Frees given region, returns false if the region was already freed. Pages are never
+    /// removed, so indices of pages stay valid.
+    pub fn deallocate(&mut self, region: &AtlasRegion) -> bool {
+        match self.pages.get_mut(region.page) {
+            Some(packer) => packer.deallocate(region.handle).is_some(),
+            None => false,
+        }
+    }
+
+    /// Returns average occupancy of pages, see [`MaxRectsPacker::occupancy`].
+    pub fn occupancy(&self) -> f32 {
+        if self.pages.is_empty() {
+            0.0
+        } else {
+            self.pages.iter().map(|p| p.occupancy()).sum::<f32>() / self.pages.len() as f32
+        }
+    }
+
+    /// Removes every page.
+    pub fn clear(&mut self) {
+        self.pages.clear();
+    }
+}
+
+/// An image in row-major order without gaps between rows.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AtlasImage {
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+    /// Pixels, every pixel has the same amount of bytes.
+    pub pixels: Vec<u8>,
+}
+
+/// An error that may occur during atlas building.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AtlasError {
+    /// An image with given index does not fit in an empty page.
+    ImageTooLarge(usize),
+    /// Amount of pixel data of an image with given index does not match its size.
+    InvalidPixelData(usize),
+}
+
+impl Display for AtlasError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ImageTooLarge(i) => write!(f, "image {} is larger than atlas page", i),
+            Self::InvalidPixelData(i) => write!(f, "image {} has invalid amount of pixels", i),
+        }
+    }
+}
+
+impl std::error::Error for AtlasError {}
+
+/// Result of [`AtlasBuilder::build`].
+#[derive(Clone, Debug)]
+pub struct PackedAtlas {
+    /// Images of pages.
+    pub pages: Vec<AtlasImage>,
+    /// Regions of images in order of their addition.
+    pub regions: Vec<AtlasRegion>,
+}
+
+/// Packs a set of images into pages of an atlas.
+///
+/// # Example
+///
+/// ```
+/// use rg3d_core::rectpack::atlas::AtlasBuilder;
+///
+/// // Single channel images.
+/// let mut builder = AtlasBuilder::new(64, 64, 1).with_padding(1);
+/// let a = builder.add_image(16, 8, vec![255; 16 * 8]);
+/// let b = builder.add_image(60, 60, vec![128; 60 * 60]);
+/// let atlas = builder.build().unwrap();
+/// assert_eq!(atlas.pages.len(), 2);
+/// assert_ne!(atlas.regions[a].page, atlas.regions[b].page);
+/// ```
+pub struct AtlasBuilder {
+    atlas: Atlas,
+    bytes_per_pixel: usize,
+    images: Vec<AtlasImage>,
+}
+
+impl AtlasBuilder {
+    /// Creates new builder of pages with given size and format.
+    pub fn new(page_width: u32, page_height: u32, bytes_per_pixel: usize) -> Self {
+        Self {
+            atlas: Atlas::new(page_width, page_height),
+            bytes_per_pixel,
+            images: Vec::new(),
+        }
+    }
+
+    /// See [`Atlas::with_rotation`].
+    pub fn with_rotation(mut self, allow_rotation: bool) -> Self {
+        self.atlas = self.atlas.with_rotation(allow_rotation);
+        self
+    }
+
+    /// See [`Atlas::with_padding`].
+    pub fn with_padding(mut self, padding: u32) -> Self {
+        self.atlas = self.atlas.with_padding(padding);
+        self
+    }
+
+    /// Adds new image and returns its index in [`PackedAtlas::regions`].
+    pub fn add_image(&mut self, width: u32, height: u32, pixels: Vec<u8>) -> usize {
+        self.images.push(AtlasImage {
+            width,
+            height,
+            pixels,
+        });
+        self.images.len() - 1
+    }
+
+    /// Packs every image and copies their pixels into pages. Large images are packed first,
+    /// which gives denser packing.
+    pub fn build(mut self) -> Result<PackedAtlas, AtlasError> {
+        let bpp = self.bytes_per_pixel;
+        if let Some(i) = self.images.iter().position(|image| {
+            image.pixels.len() != image.width as usize * image.height as usize * bpp
+        }) {
+            return Err(AtlasError::InvalidPixelData(i));
+        }
+
+        let mut order = (0..self.images.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&i| {
+            let image = &self.images[i];
+            std::cmp::Reverse((
+                image.width.max(image.height),
+                image.width as u64 * image.height as u64,
+            ))
+        });
+
+        let mut regions = vec![None; self.images.len()];
+        for i in order {
+            let image = &self.images[i];
+            regions[i] = Some(
+                self.atlas
+                    .allocate(image.width, image.height)
+                    .ok_or(AtlasError::ImageTooLarge(i))?,
+            );
+        }
+        let regions = regions.into_iter().map(Option::unwrap).collect::<Vec<_>>();
+
+        let (page_width, page_height) = (self.atlas.page_width, self.atlas.page_height);
+        let mut pages = self
+            .atlas
+            .pages
+            .iter()
+            .map(|_| AtlasImage {
+                width: page_width,
+                height: page_height,
+                pixels: vec![0; page_width as usize * page_height as usize * bpp],
+            })
+            .collect::<Vec<_>>();
+
+        for (image, region) in self.images.iter().zip(regions.iter()) {
+            let page = &mut pages[region.page];
+            for y in 0..image.height {
+                for x in 0..image.width {
+                    // Clockwise rotation: top row of the image becomes right column.
+                    let (dx, dy) = if region.rotated {
+                        (image.height - 1 - y, x)
+                    } else {
+                        (x, y)
+                    };
+                    let src = (y as usize * image.width as usize + x as usize) * bpp;
+                    let dst = ((region.bounds.position.y + dy) as usize * page_width as usize
+                        + (region.bounds.position.x + dx) as usize)
+                        * bpp;
+                    page.pixels[dst..dst + bpp].copy_from_slice(&image.pixels[src..src + bpp]);
+                }
+            }
+        }
+
+        Ok(PackedAtlas { pages, regions })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::rectpack::atlas::{Atlas, AtlasBuilder, AtlasError};
+
+    #[test]
+    fn test_atlas_pages() {
+        let mut atlas = Atlas::new(32, 32).with_padding(2);
+        let regions = (0..5)
+            .map(|_| atlas.allocate(14, 14).unwrap())
+            .collect::<Vec<_>>();
+        // Four regions with padding fill the first page.
+        assert_eq!(atlas.pages().len(), 2);
+        assert_eq!(regions[4].page, 1);
+        assert_eq!(regions[0].bounds.size.x, 14);
+        assert!(atlas.allocate(31, 31).is_none());
+        // Size with padding overflows.
+        assert!(atlas.allocate(u32::MAX, 1).is_none());
+        assert_eq!(atlas.pages().len(), 2);
+
+        assert!(atlas.deallocate(&regions[1]));
+        assert!(!atlas.deallocate(&regions[1]));
+        let region = atlas.allocate(14, 14).unwrap();
+        assert_eq!(region.page, 0);
+        assert_eq!(region.bounds, regions[1].bounds);
+    }
+
+    #[test]
+    fn test_builder() {
+        let mut builder = AtlasBuilder::new(4, 2, 1).with_rotation(true);
+        // 2x3 image could only fit rotated.
+        let tall = builder.add_image(2, 3, vec![1, 2, 3, 4, 5, 6]);
+        let small = builder.add_image(1, 1, vec![7]);
+        let atlas = builder.build().unwrap();
+        assert_eq!(atlas.pages.len(), 1);
+        let region = atlas.regions[tall];
+        assert!(region.rotated);
+        assert_eq!((region.bounds.size.x, region.bounds.size.y), (3, 2));
+
+        let page = &atlas.pages[0];
+        let pixel = |x: u32, y: u32| page.pixels[(y * page.width + x) as usize];
+        let (ox, oy) = (region.bounds.position.x, region.bounds.position.y);
+        // Rows of the image become columns from right to left.
+        assert_eq!([pixel(ox + 2, oy), pixel(ox + 2, oy + 1)], [1, 2]);
+        assert_eq!([pixel(ox, oy), pixel(ox, oy + 1)], [5, 6]);
+        let small = atlas.regions[small].bounds.position;
+        assert_eq!(pixel(small.x, small.y), 7);
+
+        let mut builder = AtlasBuilder::new(4, 4, 2);
+        builder.add_image(1, 1, vec![0]);
+        assert_eq!(
+            builder.build().unwrap_err(),
+            AtlasError::InvalidPixelData(0)
+        );
+
+        let mut builder = AtlasBuilder::new(4, 4, 1);
+        builder.add_image(5, 1, vec![0; 5]);
+        assert_eq!(builder.build().unwrap_err(), AtlasError::ImageTooLarge(0));
+    }
+}
@@ -0,0 +1,352 @@
+//! Rectangle packer based on "maximal rectangles" algorithm. Unlike [`RectPacker`](super::RectPacker)
+//! it supports removal of rectangles, rotation by 90 degrees and growth of the packing area.
+//!
+//! The packer keeps a list of maximal free rectangles (free rectangles that are not contained in
+//! any other free rectangle, they may overlap) and places every new rectangle into a free one
+//! using "best short side fit" heuristic. See "A Thousand Ways to Pack the Bin" by J. Jylänki.
+
+use crate::{
+    math::Rect,
+    pool::{Handle, Pool},
+};
+
+/// A rectangle that was placed by [`MaxRectsPacker`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PackedRect {
+    /// Bounds of the rectangle in the packing area. If the rectangle is rotated, its width and
+    /// height are swapped compared to requested size.
+    pub bounds: Rect<u32>,
+    /// Whether the rectangle was rotated by 90 degrees to fit.
+    pub rotated: bool,
+}
+
+/// See module docs.
+#[derive(Clone, Debug)]
+pub struct MaxRectsPacker {
+    width: u32,
+    height: u32,
+    allow_rotation: bool,
+    free: Vec<Rect<u32>>,
+    used: Pool<PackedRect>,
+    used_area: u64,
+}
+
+fn right(rect: &Rect<u32>) -> u32 {
+    rect.position.x + rect.size.x
+}
+
+fn bottom(rect: &Rect<u32>) -> u32 {
+    rect.position.y + rect.size.y
+}
+
+fn overlaps(a: &Rect<u32>, b: &Rect<u32>) -> bool {
+    a.position.x < right(b)
+        && b.position.x < right(a)
+        && a.position.y < bottom(b)
+        && b.position.y < bottom(a)
+}
+
+fn contains(outer: &Rect<u32>, inner: &Rect<u32>) -> bool {
+    inner.position.x >= outer.position.x
+        && inner.position.y >= outer.position.y
+        && right(inner) <= right(outer)
+        && bottom(inner) <= bottom(outer)
+}
+
+impl MaxRectsPacker {
+    /// Creates new packer with given size of the packing area.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            allow_rotation: false,
+            free: vec![Rect::new(0, 0, width, height)],
+            used: Default::default(),
+            used_area: 0,
+        }
+    }
+
+    /// Allows or forbids rotation of rectangles by 90 degrees, rotation improves packing density
+    /// but the user of packed rectangles must handle it (see [`PackedRect::rotated`]).
+    pub fn with_rotation(mut self, allow_rotation: bool) -> Self {
+        self.allow_rotation = allow_rotation;
+        self
+    }
+
+    /// Returns true if rectangles can be rotated by 90 degrees.
+    pub fn is_rotation_allowed(&self) -> bool {
+        self.allow_rotation
+    }
+
+    /// Returns width of the packing area.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns height of the packing area.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    // Score of placing a rectangle of given size into a free rectangle, lower is better.
+    fn score(free: &Rect<u32>, w: u32, h: u32) -> Option<(u32, u32)> {
+        if free.size.x >= w && free.size.y >= h {
+            let dw = free.size.x - w;
+            let dh = free.size.y - h;
+            Some((dw.min(dh), dw.max(dh)))
+        } else {
+            None
+        }
+    }
+
+    /// Tries to place a rectangle with given size, returns None if there is no free space for
+    /// it. Rectangles with zero size are never placed.
+    pub fn allocate(&mut self, w: u32, h: u32) -> Option<Handle<PackedRect>> {
+        if w == 0 || h == 0 {
+            return None;
+        }
+
+        let mut best: Option<((u32, u32), PackedRect)> = None;
+        for free in self.free.iter() {
+            let mut candidates = vec![(w, h, false)];
+            if self.allow_rotation && w != h {
+                candidates.push((h, w, true));
+            }
+            for (w, h, rotated) in candidates {
+                if let Some(score) = Self::score(free, w, h) {
+                    let is_better = match best {
+                        Some((best_score, _)) => score < best_score,
+                        None => true,
+                    };
+                    if is_better {
+                        best = Some((
+                            score,
+                            PackedRect {
+                                bounds: Rect::new(free.position.x, free.position.y, w, h),
+                                rotated,
+                            },
+                        ));
+                    }
+                }
+            }
+        }
+
+        let (_, packed) = best?;
+        self.place(&packed.bounds);
+        self.used_area += packed.bounds.size.x as u64 * packed.bounds.size.y as u64;
+        Some(self.used.spawn(packed))
+    }
+
+    // Splits every free rectangle that overlaps with given one.
+    fn place(&mut self, rect: &Rect<u32>) {
+        let mut new_free = Vec::new();
+        self.free.retain(|free| {
+            if !overlaps(free, rect) {
+                return true;
+            }
+            if rect.position.x > free.position.x {
+                new_free.push(Rect::new(
+                    free.position.x,
+                    free.position.y,
+                    rect.position.x - free.position.x,
+                    free.size.y,
+                ));
+            }
+            if right(rect) < right(free) {
+                new_free.push(Rect::new(
+                    right(rect),
+                    free.position.y,
+                    right(free) - right(rect),
+                    free.size.y,
+                ));
+            }
+            if rect.position.y > free.position.y {
+                new_free.push(Rect::new(
+                    free.position.x,
+                    free.position.y,
+                    free.size.x,
+                    rect.position.y - free.position.y,
+                ));
+            }
+            if bottom(rect) < bottom(free) {
+                new_free.push(Rect::new(
+                    free.position.x,
+                    bottom(rect),
+                    free.size.x,
+                    bottom(free) - bottom(rect),
+                ));
+            }
+            false
+        });
+
+        // Keep only maximal rectangles. Remaining old rectangles cannot be contained in new ones,
+        // because new ones are parts of removed rectangles which were maximal.
+        let old_count = self.free.len();
+        for (i, rect) in new_free.iter().enumerate() {
+            let is_contained = self.free[..old_count].iter().any(|r| contains(r, rect))
+                || new_free.iter().enumerate().any(|(j, other)| {
+                    // Keep only the first of equal rectangles.
+                    j != i && contains(other, rect) && (other != rect || j < i)
+                });
+            if !is_contained {
+                self.free.push(*rect);
+            }
+        }
+    }
+
+    // Free space is rebuilt from scratch, it is the only way to get maximal free rectangles
+    // back without tracking history of splits.
+    fn rebuild(&mut self) {
+        self.free = vec![Rect::new(0, 0, self.width, self.height)];
+        let used = self.used.iter().map(|r| r.bounds).collect::<Vec<_>>();
+        for bounds in used {
+            self.place(&bounds);
+        }
+    }
+
+    /// Removes previously placed rectangle and returns it, freed space can be used by next
+    /// allocations. Removal is more expensive than allocation, because free space is rebuilt.
+    pub fn deallocate(&mut self, handle: Handle<PackedRect>) -> Option<PackedRect> {
+        if !self.used.is_valid_handle(handle) {
+            return None;
+        }
+        let packed = self.used.free(handle);
+        self.used_area -= packed.bounds.size.x as u64 * packed.bounds.size.y as u64;
+        self.rebuild();
+        Some(packed)
+    }
+
+    /// Returns a placed rectangle by its handle.
+    pub fn get(&self, handle: Handle<PackedRect>) -> Option<&PackedRect> {
+        self.used.try_borrow(handle)
+    }
+
+    /// Returns an iterator over every placed rectangle.
+    pub fn iter(&self) -> impl Iterator<Item = (Handle<PackedRect>, &PackedRect)> {
+        self.used.pair_iter()
+    }
+
+    /// Returns amount of placed rectangles.
+    pub fn len(&self) -> usize {
+        self.used.alive_count()
+    }
+
+    /// Returns true if there is no placed rectangles.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Enlarges the packing area, placed rectangles keep their positions. Size cannot be
+    /// reduced, smaller values are ignored.
+    pub fn grow(&mut self, width: u32, height: u32) {
+        self.width = self.width.max(width);
+        self.height = self.height.max(height);
+        self.rebuild();
+    }
+
+    /// Returns ratio of occupied area to the total area of the packer, in `[0; 1]` range.
+    pub fn occupancy(&self) -> f32 {
+        let total = self.width as u64 * self.height as u64;
+        if total == 0 {
+            0.0
+        } else {
+            (self.used_area as f64 / total as f64) as f32
+        }
+    }
+
+    /// Removes every placed rectangle.
+    pub fn clear(&mut self) {
+        self.used.clear();
+        self.used_area = 0;
+        self.free = vec![Rect::new(0, 0, self.width, self.height)];
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::rectpack::maxrects::{overlaps, MaxRectsPacker};
+
+    fn assert_disjoint(packer: &MaxRectsPacker) {
+        let rects = packer.iter().map(|(_, r)| r.bounds).collect::<Vec<_>>();
+        for (i, a) in rects.iter().enumerate() {
+            assert!(a.position.x + a.size.x <= packer.width());
+            assert!(a.position.y + a.size.y <= packer.height());
+            for b in rects[i + 1..].iter() {
+                assert!(!overlaps(a, b), "{:?} {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_allocate_deallocate() {
+        let mut packer = MaxRectsPacker::new(64, 64);
+        let handles = (0..16)
+            .map(|_| packer.allocate(16, 16).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(packer.occupancy(), 1.0);
+        assert!(packer.allocate(1, 1).is_none());
+        assert_disjoint(&packer);
+
+        // Freed space is reused.
+        let freed = packer.deallocate(handles[5]).unwrap();
+        assert!(packer.deallocate(handles[5]).is_none());
+        let handle = packer.allocate(16, 16).unwrap();
+        assert_eq!(packer.get(handle).unwrap().bounds, freed.bounds);
+
+        // Two neighbouring rectangles form space for a bigger one.
+        let a = packer.get(handles[0]).unwrap().bounds;
+        let b = packer.get(handles[1]).unwrap().bounds;
+        assert!(packer.allocate(32, 16).is_none() && packer.allocate(16, 32).is_none());
+        packer.deallocate(handles[0]);
+        packer.deallocate(handles[1]);
+        let (w, h) = if a.position.y == b.position.y {
+            (32, 16)
+        } else {
+            (16, 32)
+        };
+        assert!(packer.allocate(w, h).is_some());
+        assert_disjoint(&packer);
+    }
+
+    #[test]
+    fn test_rotation_and_growth() {
+        let mut packer = MaxRectsPacker::new(10, 30);
+        assert!(packer.allocate(30, 10).is_none());
+
+        let mut packer = packer.with_rotation(true);
+        let handle = packer.allocate(30, 10).unwrap();
+        let packed = *packer.get(handle).unwrap();
+        assert!(packed.rotated);
+        assert_eq!((packed.bounds.size.x, packed.bounds.size.y), (10, 30));
+        assert!(packer.allocate(5, 5).is_none());
+
+        packer.grow(20, 30);
+        assert_eq!(packer.occupancy(), 0.5);
+        assert_eq!(packer.get(handle).unwrap().bounds, packed.bounds);
+        assert!(packer.allocate(10, 30).is_some());
+        assert!(packer.allocate(1, 1).is_none());
+        assert_disjoint(&packer);
+    }
+
+    #[test]
+    fn test_random_sizes() {
+        let mut state = 42u32;
+        let mut random = move |max: u32| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (state >> 16) % max + 1
+        };
+        let mut packer = MaxRectsPacker::new(128, 128).with_rotation(true);
+        let mut handles = Vec::new();
+        while let Some(handle) = packer.allocate(random(16), random(16)) {
+            handles.push(handle);
+        }
+        assert_disjoint(&packer);
+        assert!(packer.occupancy() > 0.75, "{}", packer.occupancy());
+
+        for handle in handles.iter().step_by(2) {
+            packer.deallocate(*handle);
+        }
+        while packer.allocate(random(16), random(16)).is_some() {}
+        assert_disjoint(&packer);
+    }
+}
@@ -1,5 +1,12 @@
 //! Rectangle packer is used to pack set of smaller rectangles into one big, it
 //! used in texture atlas packer.
+//!
+//! [`RectPacker`] is a simple packer that only can place rectangles. See [`maxrects`] for
+//! a packer that supports removal and rotation of rectangles and [`atlas`] for multi-page
+//! texture atlases.
+
+pub mod atlas;
+pub mod maxrects;
 
 use crate::{
     math::Rect,
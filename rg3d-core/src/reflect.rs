@@ -0,0 +1,505 @@
+//! Runtime reflection.
+//!
+//! # Overview
+//!
+//! [`Reflect`] gives access to fields of a value by their names at runtime, which is useful for
+//! editors and debug consoles which don't know concrete types of objects they're working with.
+//! The trait can be derived by `#[derive(Reflect)]`, see [`Reflect`] for the list of attributes.
+//!
+//! Nested fields are accessed by paths where names are separated by dots, items of a `Vec` are
+//! accessed by their indices:
+//!
+//! ```
+//! use rg3d_core::reflect::{Reflect, ResolvePath};
+//!
+//! #[derive(Reflect, Default)]
+//! struct Cone {
+//!     angle: f32,
+//! }
+//!
+//! #[derive(Reflect, Default)]
+//! struct Light {
+//!     name: String,
+//!     cones: Vec<Cone>,
+//! }
+//!
+//! let mut light = Light {
+//!     cones: vec![Cone::default()],
+//!     ..Default::default()
+//! };
+//! light.set_field("cones.0.angle", 0.5f32).unwrap();
+//! assert_eq!(*light.get_field::<f32>("cones.0.angle").unwrap(), 0.5);
+//! assert!(light.get_field::<u32>("cones.0.angle").is_err());
+//! ```
+
+use crate::{
+    algebra::{
+        Matrix2, Matrix3, Matrix4, Scalar, UnitComplex, UnitQuaternion, Vector2, Vector3, Vector4,
+    },
+    pool::Handle,
+};
+use std::{
+    any::Any,
+    fmt::{Display, Formatter},
+};
+use uuid::Uuid;
+
+pub use rg3d_core_derive::Reflect;
+
+/// Runtime access to the fields of a value.
+///
+/// # Derive
+///
+/// `#[derive(Reflect)]` implements the trait for structs and enums, user has to import `Reflect`.
+/// Every field must implement `Reflect` too, unless it is marked with `#[reflect(skip)]`. Field
+/// names are the same as in Rust code, tuple fields are named by their indices, a field can be
+/// renamed by `#[reflect(rename = "name")]`. Enums expose fields of their active variant.
+pub trait Reflect: Any {
+    /// Returns the name of the type of the value.
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Casts the value to `Any`, can be used to downcast to concrete type.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Casts the value to mutable `Any`, can be used to downcast to concrete type.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    /// Casts the value to `dyn Reflect`.
+    fn as_reflect(&self) -> &dyn Reflect;
+
+    /// Casts the value to mutable `dyn Reflect`.
+    fn as_reflect_mut(&mut self) -> &mut dyn Reflect;
+
+    /// Returns names and values of all reflected fields, in order of declaration.
+    fn fields(&self) -> Vec<(&'static str, &dyn Reflect)> {
+        Vec::new()
+    }
+
+    /// Returns names and mutable values of all reflected fields, in order of declaration.
+    fn fields_mut(&mut self) -> Vec<(&'static str, &mut dyn Reflect)> {
+        Vec::new()
+    }
+
+    /// Returns a field with given name.
+    fn field(&self, name: &str) -> Option<&dyn Reflect> {
+        self.fields()
+            .into_iter()
+            .find(|(field_name, _)| *field_name == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns mutable field with given name.
+    fn field_mut(&mut self, name: &str) -> Option<&mut dyn Reflect> {
+        self.fields_mut()
+            .into_iter()
+            .find(|(field_name, _)| *field_name == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns items of a list (`Vec`, arrays), empty for other types.
+    fn items(&self) -> Vec<&dyn Reflect> {
+        Vec::new()
+    }
+
+    /// Returns mutable items of a list (`Vec`, arrays), empty for other types.
+    fn items_mut(&mut self) -> Vec<&mut dyn Reflect> {
+        Vec::new()
+    }
+
+    /// Returns the name of active variant for enums, `None` for other types.
+    fn variant_name(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Returns names of all variants for enums, empty for other types.
+    fn variant_names(&self) -> &'static [&'static str] {
+        &[]
+    }
+}
+
+/// An error that may occur when accessing a value by path.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReflectPathError {
+    /// There is no field at given path.
+    UnknownField {
+        /// Path to the field.
+        path: String,
+    },
+    /// Field exists, but has different type.
+    InvalidType {
+        /// Path to the field.
+        path: String,
+        /// Requested type.
+        expected: &'static str,
+        /// Actual type of the field.
+        actual: &'static str,
+    },
+}
+
+impl Display for ReflectPathError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownField { path } => write!(f, "there is no field `{}`", path),
+            Self::InvalidType {
+                path,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "field `{}` has type `{}`, but `{}` was requested",
+                path, actual, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReflectPathError {}
+
+fn child<'a>(value: &'a dyn Reflect, name: &str) -> Option<&'a dyn Reflect> {
+    match name.parse::<usize>() {
+        Ok(index) if value.field(name).is_none() => value.items().get(index).copied(),
+        _ => value.field(name),
+    }
+}
+
+fn child_mut<'a>(value: &'a mut dyn Reflect, name: &str) -> Option<&'a mut dyn Reflect> {
+    match name.parse::<usize>() {
+        Ok(index) if value.field(name).is_none() => value.items_mut().into_iter().nth(index),
+        _ => value.field_mut(name),
+    }
+}
+
+/// Access to nested fields by dot-separated paths, implemented for every [`Reflect`] type.
+pub trait ResolvePath {
+    /// Returns a field at given path, empty path means the value itself.
+    fn resolve_path(&self, path: &str) -> Result<&dyn Reflect, ReflectPathError>;
+
+    /// Returns mutable field at given path, empty path means the value itself.
+    fn resolve_path_mut(&mut self, path: &str) -> Result<&mut dyn Reflect, ReflectPathError>;
+
+    /// Returns a field at given path with its concrete type.
+    fn get_field<T: Reflect>(&self, path: &str) -> Result<&T, ReflectPathError> {
+        let value = self.resolve_path(path)?;
+        let actual = value.type_name();
+        value
+            .as_any()
+            .downcast_ref()
+            .ok_or_else(|| ReflectPathError::InvalidType {
+                path: path.to_owned(),
+                expected: std::any::type_name::<T>(),
+                actual,
+            })
+    }
+
+    /// Returns mutable field at given path with its concrete type.
+    fn get_field_mut<T: Reflect>(&mut self, path: &str) -> Result<&mut T, ReflectPathError> {
+        let value = self.resolve_path_mut(path)?;
+        let actual = value.type_name();
+        value
+            .as_any_mut()
+            .downcast_mut()
+            .ok_or_else(|| ReflectPathError::InvalidType {
+                path: path.to_owned(),
+                expected: std::any::type_name::<T>(),
+                actual,
+            })
+    }
+
+    /// Replaces a field at given path with new value, returns previous value.
+    fn set_field<T: Reflect>(&mut self, path: &str, value: T) -> Result<T, ReflectPathError> {
+        self.get_field_mut(path)
+            .map(|field| std::mem::replace(field, value))
+    }
+}
+
+impl<R: Reflect + ?Sized> ResolvePath for R {
+    fn resolve_path(&self, path: &str) -> Result<&dyn Reflect, ReflectPathError> {
+        let mut value = self.as_reflect();
+        if !path.is_empty() {
+            for name in path.split('.') {
+                value = child(value, name).ok_or_else(|| ReflectPathError::UnknownField {
+                    path: path.to_owned(),
+                })?;
+            }
+        }
+        Ok(value)
+    }
+
+    fn resolve_path_mut(&mut self, path: &str) -> Result<&mut dyn Reflect, ReflectPathError> {
+        let mut value = self.as_reflect_mut();
+        if !path.is_empty() {
+            for name in path.split('.') {
+                value = child_mut(value, name).ok_or_else(|| ReflectPathError::UnknownField {
+                    path: path.to_owned(),
+                })?;
+            }
+        }
+        Ok(value)
+    }
+}
+
+/// Implements `Reflect` for types without reflected fields.
+#[macro_export]
+macro_rules! impl_reflect_leaf {
+    ($($ty:ty),*) => {
+        $(
+            impl $crate::reflect::Reflect for $ty {
+                fn as_any(&self) -> &dyn std::any::Any {
+                    self
+                }
+
+                fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+                    self
+                }
+
+                fn as_reflect(&self) -> &dyn $crate::reflect::Reflect {
+                    self
+                }
+
+                fn as_reflect_mut(&mut self) -> &mut dyn $crate::reflect::Reflect {
+                    self
+                }
+            }
+        )*
+    };
+}
+
+impl_reflect_leaf!(
+    bool,
+    u8,
+    i8,
+    u16,
+    i16,
+    u32,
+    i32,
+    u64,
+    i64,
+    usize,
+    isize,
+    f32,
+    f64,
+    String,
+    Uuid,
+    UnitQuaternion<f32>,
+    UnitComplex<f32>,
+    Matrix2<f32>,
+    Matrix3<f32>,
+    Matrix4<f32>
+);
+
+impl<T: 'static> Reflect for Handle<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_reflect(&self) -> &dyn Reflect {
+        self
+    }
+
+    fn as_reflect_mut(&mut self) -> &mut dyn Reflect {
+        self
+    }
+}
+
+macro_rules! impl_reflect_vector {
+    ($ty:ident, $($field:ident),*) => {
+        impl<T: Reflect + Scalar> Reflect for $ty<T> {
+            fn as_any(&self) -> &dyn Any {
+                self
+            }
+
+            fn as_any_mut(&mut self) -> &mut dyn Any {
+                self
+            }
+
+            fn as_reflect(&self) -> &dyn Reflect {
+                self
+            }
+
+            fn as_reflect_mut(&mut self) -> &mut dyn Reflect {
+                self
+            }
+
+            fn fields(&self) -> Vec<(&'static str, &dyn Reflect)> {
+                vec![$((stringify!($field), &self.$field as &dyn Reflect)),*]
+            }
+
+            fn fields_mut(&mut self) -> Vec<(&'static str, &mut dyn Reflect)> {
+                let coords = &mut **self;
+                vec![$((stringify!($field), &mut coords.$field as &mut dyn Reflect)),*]
+            }
+        }
+    };
+}
+
+impl_reflect_vector!(Vector2, x, y);
+impl_reflect_vector!(Vector3, x, y, z);
+impl_reflect_vector!(Vector4, x, y, z, w);
+
+impl<T: Reflect> Reflect for Vec<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_reflect(&self) -> &dyn Reflect {
+        self
+    }
+
+    fn as_reflect_mut(&mut self) -> &mut dyn Reflect {
+        self
+    }
+
+    fn items(&self) -> Vec<&dyn Reflect> {
+        self.iter().map(|item| item as &dyn Reflect).collect()
+    }
+
+    fn items_mut(&mut self) -> Vec<&mut dyn Reflect> {
+        self.iter_mut()
+            .map(|item| item as &mut dyn Reflect)
+            .collect()
+    }
+}
+
+impl<T: Reflect> Reflect for Option<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_reflect(&self) -> &dyn Reflect {
+        self
+    }
+
+    fn as_reflect_mut(&mut self) -> &mut dyn Reflect {
+        self
+    }
+
+    fn fields(&self) -> Vec<(&'static str, &dyn Reflect)> {
+        match self {
+            Some(value) => vec![("0", value as &dyn Reflect)],
+            None => Vec::new(),
+        }
+    }
+
+    fn fields_mut(&mut self) -> Vec<(&'static str, &mut dyn Reflect)> {
+        match self {
+            Some(value) => vec![("0", value as &mut dyn Reflect)],
+            None => Vec::new(),
+        }
+    }
+
+    fn variant_name(&self) -> Option<&'static str> {
+        Some(if self.is_some() { "Some" } else { "None" })
+    }
+
+    fn variant_names(&self) -> &'static [&'static str] {
+        &["None", "Some"]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algebra::Vector3,
+        reflect::{Reflect, ReflectPathError, ResolvePath},
+    };
+
+    #[derive(Reflect, Default)]
+    struct Transform {
+        position: Vector3<f32>,
+        #[reflect(skip)]
+        #[allow(dead_code)]
+        dirty: bool,
+    }
+
+    #[derive(Reflect, Default)]
+    struct Node {
+        name: String,
+        #[reflect(rename = "transform")]
+        local_transform: Transform,
+        children: Vec<Node>,
+        lifetime: Option<f32>,
+    }
+
+    #[derive(Reflect)]
+    enum Shape {
+        Sphere { radius: f32 },
+        Box(Vector3<f32>),
+        Point,
+    }
+
+    #[test]
+    fn fields_and_paths() {
+        let mut node = Node {
+            children: vec![Node::default()],
+            ..Default::default()
+        };
+
+        let names = node
+            .fields()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["name", "transform", "children", "lifetime"]);
+        assert!(node.field("dirty").is_none());
+        assert!(node.local_transform.field("dirty").is_none());
+
+        node.set_field("children.0.transform.position.y", 2.0f32)
+            .unwrap();
+        assert_eq!(node.children[0].local_transform.position.y, 2.0);
+
+        node.set_field("name", "Root".to_owned()).unwrap();
+        assert_eq!(node.name, "Root");
+
+        let dynamic: &mut dyn Reflect = &mut node;
+        *dynamic
+            .get_field_mut::<f32>("transform.position.x")
+            .unwrap() = 3.0;
+        assert_eq!(node.local_transform.position.x, 3.0);
+
+        assert_eq!(
+            node.set_field("children.1", Node::default()).err(),
+            Some(ReflectPathError::UnknownField {
+                path: "children.1".to_owned()
+            })
+        );
+        assert!(matches!(
+            node.get_field::<u32>("name"),
+            Err(ReflectPathError::InvalidType { .. })
+        ));
+
+        assert!(node.resolve_path("lifetime.0").is_err());
+        node.lifetime = Some(1.0);
+        assert_eq!(*node.get_field::<f32>("lifetime.0").unwrap(), 1.0);
+    }
+
+    #[test]
+    fn enum_variants() {
+        let mut shape = Shape::Sphere { radius: 1.0 };
+        assert_eq!(shape.variant_name(), Some("Sphere"));
+        assert_eq!(shape.variant_names(), ["Sphere", "Box", "Point"]);
+        shape.set_field("radius", 2.0f32).unwrap();
+        assert!(matches!(shape, Shape::Sphere { radius } if radius == 2.0));
+
+        shape = Shape::Box(Vector3::default());
+        assert_eq!(shape.variant_name(), Some("Box"));
+        shape.set_field("0.z", 2.0f32).unwrap();
+        assert!(shape.field("radius").is_none());
+
+        assert!(Shape::Point.fields().is_empty());
+        assert_eq!(Shape::Point.variant_name(), Some("Point"));
+    }
+}
@@ -0,0 +1,195 @@
+//! Indexed binary representation of visitor tree.
+//!
+//! # Overview
+//!
+//! Plain binary format has to be decoded at once, because position of a region in a file is known
+//! only after all previous regions were decoded. Indexed format stores fields of each region in a
+//! separate block and puts a table with names, parents and offsets of all regions at the end of
+//! the file. This allows to open a file, read only the table and decode fields of a region only
+//! when it is entered - regions that were never entered are never decoded.
+//!
+//! # Layout
+//!
+//! ```text
+//! "RG3I" | version: u32 | table offset: u64 | blocks | table
+//!
+//! block: field count: u32 | fields
+//! table: region count: u32 | entries
+//! entry: name length: u32 | name | parent index: u32 | block offset: u64
+//! ```
+//!
+//! Entries are stored in depth-first order, so parent entry always precedes its children. Fields
+//! are stored exactly as in plain binary format.
+
+use crate::{
+    io::ReadSeek,
+    pool::{Handle, Pool},
+    visitor::{Field, Node, VisitError, VisitResult},
+};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Seek, SeekFrom, Write};
+
+pub(in crate::visitor) const INDEXED_MAGIC: &[u8; 4] = b"RG3I";
+const INDEXED_VERSION: u32 = 1;
+const NO_PARENT: u32 = u32::MAX;
+
+pub(in crate::visitor) fn write<W: Write + Seek>(
+    nodes: &Pool<Node>,
+    root: Handle<Node>,
+    writer: &mut W,
+) -> VisitResult {
+    writer.write_all(INDEXED_MAGIC)?;
+    writer.write_u32::<LittleEndian>(INDEXED_VERSION)?;
+    let table_offset_position = writer.stream_position()?;
+    // Will be overwritten when table offset is known.
+    writer.write_u64::<LittleEndian>(0)?;
+
+    // Handle, parent index and block offset of each region in depth-first order.
+    let mut entries = Vec::new();
+    let mut stack = vec![(root, NO_PARENT)];
+    while let Some((node_handle, parent)) = stack.pop() {
+        let node = &nodes[node_handle];
+        let index = entries.len() as u32;
+        entries.push((node_handle, parent, writer.stream_position()?));
+
+        writer.write_u32::<LittleEndian>(node.fields.len() as u32)?;
+        for field in node.fields.iter() {
+            Field::save(field, writer)?;
+        }
+
+        // Reversed to keep original order of children when popping from the stack.
+        stack.extend(node.children.iter().rev().map(|child| (*child, index)));
+    }
+
+    let table_offset = writer.stream_position()?;
+    writer.write_u32::<LittleEndian>(entries.len() as u32)?;
+    for (node_handle, parent, block_offset) in entries {
+        let name = nodes[node_handle].name.as_bytes();
+        writer.write_u32::<LittleEndian>(name.len() as u32)?;
+        writer.write_all(name)?;
+        writer.write_u32::<LittleEndian>(parent)?;
+        writer.write_u64::<LittleEndian>(block_offset)?;
+    }
+
+    writer.seek(SeekFrom::Start(table_offset_position))?;
+    writer.write_u64::<LittleEndian>(table_offset)?;
+    writer.seek(SeekFrom::End(0))?;
+
+    Ok(())
+}
+
+fn invalid_size(what: &str) -> VisitError {
+    VisitError::Io(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("{} exceeds length of the data", what),
+    ))
+}
+
+/// Seekable source of indexed data together with its length. Length is obtained once, because
+/// seeking discards buffers of buffered readers. It is used to validate sizes read from the data
+/// before allocating memory for them.
+pub(in crate::visitor) struct IndexedSource {
+    reader: Box<dyn ReadSeek>,
+    len: u64,
+}
+
+impl IndexedSource {
+    pub(in crate::visitor) fn new(mut reader: Box<dyn ReadSeek>) -> Result<Self, VisitError> {
+        let len = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(0))?;
+        Ok(Self { reader, len })
+    }
+
+    /// Reads region table and creates tree of regions without fields, fields of each region must
+    /// be loaded by [`Self::read_block`] before use.
+    pub(in crate::visitor) fn read_table(
+        &mut self,
+    ) -> Result<(Pool<Node>, Handle<Node>), VisitError> {
+        read_table(&mut *self.reader, self.len)
+    }
+
+    /// Decodes fields block located at given offset.
+    pub(in crate::visitor) fn read_block(&mut self, offset: u64) -> Result<Vec<Field>, VisitError> {
+        read_block(&mut *self.reader, self.len, offset)
+    }
+}
+
+fn read_table(
+    reader: &mut dyn ReadSeek,
+    len: u64,
+) -> Result<(Pool<Node>, Handle<Node>), VisitError> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != INDEXED_MAGIC {
+        return Err(VisitError::NotSupportedFormat);
+    }
+    let version = reader.read_u32::<LittleEndian>()?;
+    if version > INDEXED_VERSION {
+        return Err(VisitError::NotSupportedFormat);
+    }
+
+    let table_offset = reader.read_u64::<LittleEndian>()?;
+    reader.seek(SeekFrom::Start(table_offset))?;
+
+    let count = reader.read_u32::<LittleEndian>()? as usize;
+    let mut position = table_offset.saturating_add(4);
+    // Each entry takes at least 16 bytes: name length, parent index and block offset.
+    if count as u64 * 16 > len.saturating_sub(position) {
+        return Err(invalid_size("region count"));
+    }
+    let mut nodes = Pool::new();
+    let mut handles: Vec<Handle<Node>> = Vec::with_capacity(count);
+    for _ in 0..count {
+        let name_len = reader.read_u32::<LittleEndian>()? as usize;
+        position += 4;
+        if name_len as u64 > len.saturating_sub(position) {
+            return Err(invalid_size("region name length"));
+        }
+        position += name_len as u64 + 12;
+        let mut name = vec![0; name_len];
+        reader.read_exact(&mut name)?;
+        let parent = reader.read_u32::<LittleEndian>()?;
+        let block_offset = reader.read_u64::<LittleEndian>()?;
+
+        let parent = if parent == NO_PARENT {
+            Handle::NONE
+        } else {
+            *handles
+                .get(parent as usize)
+                .ok_or(VisitError::InvalidCurrentNode)?
+        };
+
+        let handle = nodes.spawn(Node {
+            name: String::from_utf8(name)?,
+            parent,
+            block: Some(block_offset),
+            loaded: false,
+            ..Node::default()
+        });
+        if parent.is_some() {
+            nodes[parent].children.push(handle);
+        }
+        handles.push(handle);
+    }
+
+    let root = handles.first().cloned().ok_or(VisitError::NoActiveNode)?;
+
+    Ok((nodes, root))
+}
+
+fn read_block(
+    mut reader: &mut dyn ReadSeek,
+    len: u64,
+    offset: u64,
+) -> Result<Vec<Field>, VisitError> {
+    reader.seek(SeekFrom::Start(offset))?;
+    let count = reader.read_u32::<LittleEndian>()? as usize;
+    if count as u64 > len.saturating_sub(offset.saturating_add(4)) {
+        return Err(invalid_size("field count"));
+    }
+    let mut fields = Vec::with_capacity(count);
+    for _ in 0..count {
+        fields.push(Field::load(&mut reader)?);
+    }
+    Ok(fields)
+}
@@ -7,9 +7,26 @@
 //! container for data fields. Data field is tuple of name and value, value can be any of simple Rust
 //! types and some of basic structures of the crate. Main criteria of what could be the field and what
 //! not is the ability to be represented as set of bytes without any aliasing issues.
+//!
+//! # Formats
+//!
+//! Visitor tree can be stored either in compact binary format (see [`Visitor::save_binary`]) or in
+//! human-readable text format (see [`Visitor::save_text`]), which is suitable for version control
+//! systems. Both formats can be loaded by [`Visitor::load_from_file`] or
+//! [`Visitor::load_from_memory`], actual format is detected automatically.
+//!
+//! Large trees can be stored in indexed binary format (see [`Visitor::save_indexed`]), which can
+//! be opened by [`Visitor::open_indexed`] without decoding the whole file - fields of a region are
+//! decoded only when the region is entered.
 
+pub use crate::io::ReadSeek;
 pub use rg3d_core_derive::Visit;
 
+mod indexed;
+#[cfg(feature = "serde")]
+pub mod serde_bridge;
+mod text;
+
 pub mod prelude {
     //! Types to use `#[derive(Visit)]`
     pub use super::{Visit, VisitResult, Visitor};
@@ -23,6 +40,7 @@ use crate::{
     io::{self, FileLoadError},
     pool::{Handle, Pool},
     replace_slashes,
+    visitor::indexed::IndexedSource,
 };
 
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
@@ -33,7 +51,7 @@ use std::{
     fmt::{Display, Formatter},
     fs::File,
     hash::Hash,
-    io::{BufWriter, Cursor, Read, Write},
+    io::{BufWriter, Cursor, Read, Seek, Write},
     ops::DerefMut,
     path::{Path, PathBuf},
     rc::Rc,
@@ -42,6 +60,7 @@ use std::{
 };
 use uuid::Uuid;
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum FieldKind {
     Bool(bool),
     U8(u8),
@@ -202,75 +221,6 @@ impl<'a, T: Pod> Visit for PodVecView<'a, T> {
     }
 }
 
-impl FieldKind {
-    fn as_string(&self) -> String {
-        match self {
-            Self::Bool(data) => format!("<bool = {}>, ", data),
-            Self::U8(data) => format!("<u8 = {}>, ", data),
-            Self::I8(data) => format!("<i8 = {}>, ", data),
-            Self::U16(data) => format!("<u16 = {}>, ", data),
-            Self::I16(data) => format!("<i16 = {}>, ", data),
-            Self::U32(data) => format!("<u32 = {}>, ", data),
-            Self::I32(data) => format!("<i32 = {}>, ", data),
-            Self::U64(data) => format!("<u64 = {}>, ", data),
-            Self::I64(data) => format!("<i64 = {}>, ", data),
-            Self::F32(data) => format!("<f32 = {}>, ", data),
-            Self::F64(data) => format!("<f64 = {}>, ", data),
-            Self::Vector3(data) => format!("<vec3 = {}; {}; {}>, ", data.x, data.y, data.z),
-            Self::UnitQuaternion(data) => {
-                format!("<quat = {}; {}; {}; {}>, ", data.i, data.j, data.k, data.w)
-            }
-            Self::Matrix4(data) => {
-                let mut out = String::from("<mat4 = ");
-                for f in data.iter() {
-                    out += format!("{}; ", f).as_str();
-                }
-                out
-            }
-            Self::Data(data) => {
-                let out = match String::from_utf8(data.clone()) {
-                    Ok(s) => s,
-                    Err(_) => base64::encode(data),
-                };
-                format!("<data = {}>, ", out)
-            }
-            Self::Matrix3(data) => {
-                let mut out = String::from("<mat3 = ");
-                for f in data.iter() {
-                    out += format!("{}; ", f).as_str();
-                }
-                out
-            }
-            Self::Vector2(data) => format!("<vec2 = {}; {}>, ", data.x, data.y),
-            Self::Vector4(data) => {
-                format!("<vec4 = {}; {}; {}; {}>, ", data.x, data.y, data.z, data.w)
-            }
-            Self::Uuid(uuid) => uuid.to_string(),
-            Self::UnitComplex(data) => {
-                format!("<complex = {}; {}>, ", data.re, data.im)
-            }
-            FieldKind::PodArray {
-                type_id,
-                element_size,
-                bytes,
-            } => {
-                let base64_encoded = base64::encode(bytes);
-                format!(
-                    "<podarray = {}; {}; [{}]>",
-                    type_id, element_size, base64_encoded
-                )
-            }
-            Self::Matrix2(data) => {
-                let mut out = String::from("<mat2 = ");
-                for f in data.iter() {
-                    out += format!("{}; ", f).as_str();
-                }
-                out
-            }
-        }
-    }
-}
-
 macro_rules! impl_field_data {
     ($type_name:ty, $($kind:tt)*) => {
         impl Visit for $type_name {
@@ -351,6 +301,7 @@ impl<'a> Visit for Data<'a> {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct Field {
     name: String,
     kind: FieldKind,
@@ -375,6 +326,23 @@ pub enum VisitError {
     UnexpectedRcNullIndex,
     PoisonedMutex,
     FileLoadError(FileLoadError),
+    InvalidTextFormat(String),
+    UnsupportedVersion {
+        region: String,
+        version: u32,
+        supported: u32,
+    },
+    ChecksumMismatch {
+        expected: u32,
+        actual: u32,
+    },
+    /// Data could not be decoded, `path` is a path of the region (and possibly a field) that failed,
+    /// `offset` is a position in bytes of the region or field in the source data.
+    Corrupted {
+        path: String,
+        offset: u64,
+        reason: Box<VisitError>,
+    },
 }
 
 impl Display for VisitError {
@@ -397,6 +365,30 @@ impl Display for VisitError {
             Self::UnexpectedRcNullIndex => write!(f, "unexpected rc null index"),
             Self::PoisonedMutex => write!(f, "attempt to lock poisoned mutex"),
             Self::FileLoadError(e) => write!(f, "file load error: {:?}", e),
+            Self::InvalidTextFormat(msg) => write!(f, "invalid text format: {}", msg),
+            Self::UnsupportedVersion {
+                region,
+                version,
+                supported,
+            } => write!(
+                f,
+                "region {} has version {}, but only versions up to {} are supported",
+                region, version, supported
+            ),
+            Self::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "checksum mismatch, expected {:08x}, got {:08x}",
+                expected, actual
+            ),
+            Self::Corrupted {
+                path,
+                offset,
+                reason,
+            } => write!(
+                f,
+                "corrupted data at {} (byte offset {}): {}",
+                path, offset, reason
+            ),
         }
     }
 }
@@ -575,107 +567,131 @@ impl Field {
     }
 
     fn load(file: &mut dyn Read) -> Result<Field, VisitError> {
-        let name_len = file.read_u32::<LittleEndian>()? as usize;
-        let mut raw_name = Vec::with_capacity(name_len);
-        unsafe { raw_name.set_len(name_len) };
-        file.read_exact(raw_name.as_mut_slice())?;
+        let name = read_string(file)?;
+        let kind = Self::load_kind(file)?;
+        Ok(Field { name, kind })
+    }
+
+    fn load_kind(file: &mut dyn Read) -> Result<FieldKind, VisitError> {
         let id = file.read_u8()?;
-        Ok(Field::new(
-            String::from_utf8(raw_name)?.as_str(),
-            match id {
-                1 => FieldKind::U8(file.read_u8()?),
-                2 => FieldKind::I8(file.read_i8()?),
-                3 => FieldKind::U16(file.read_u16::<LittleEndian>()?),
-                4 => FieldKind::I16(file.read_i16::<LittleEndian>()?),
-                5 => FieldKind::U32(file.read_u32::<LittleEndian>()?),
-                6 => FieldKind::I32(file.read_i32::<LittleEndian>()?),
-                7 => FieldKind::U64(file.read_u64::<LittleEndian>()?),
-                8 => FieldKind::I64(file.read_i64::<LittleEndian>()?),
-                9 => FieldKind::F32(file.read_f32::<LittleEndian>()?),
-                10 => FieldKind::F64(file.read_f64::<LittleEndian>()?),
-                11 => FieldKind::Vector3({
-                    let x = file.read_f32::<LittleEndian>()?;
-                    let y = file.read_f32::<LittleEndian>()?;
-                    let z = file.read_f32::<LittleEndian>()?;
-                    Vector3::new(x, y, z)
-                }),
-                12 => FieldKind::UnitQuaternion({
-                    let x = file.read_f32::<LittleEndian>()?;
-                    let y = file.read_f32::<LittleEndian>()?;
-                    let z = file.read_f32::<LittleEndian>()?;
-                    let w = file.read_f32::<LittleEndian>()?;
-                    UnitQuaternion::new_normalize(Quaternion::new(w, x, y, z))
-                }),
-                13 => FieldKind::Matrix4({
-                    let mut f = [0.0f32; 16];
-                    for n in &mut f {
-                        *n = file.read_f32::<LittleEndian>()?;
-                    }
-                    Matrix4::from_row_slice(&f)
-                }),
-                14 => FieldKind::Data({
-                    let len = file.read_u32::<LittleEndian>()? as usize;
-                    let mut vec = Vec::with_capacity(len);
-                    unsafe { vec.set_len(len) };
-                    file.read_exact(vec.as_mut_slice())?;
-                    vec
-                }),
-                15 => FieldKind::Bool(file.read_u8()? != 0),
-                16 => FieldKind::Matrix3({
-                    let mut f = [0.0f32; 9];
-                    for n in &mut f {
-                        *n = file.read_f32::<LittleEndian>()?;
-                    }
-                    Matrix3::from_row_slice(&f)
-                }),
-                17 => FieldKind::Vector2({
-                    let x = file.read_f32::<LittleEndian>()?;
-                    let y = file.read_f32::<LittleEndian>()?;
-                    Vector2::new(x, y)
-                }),
-                18 => FieldKind::Vector4({
-                    let x = file.read_f32::<LittleEndian>()?;
-                    let y = file.read_f32::<LittleEndian>()?;
-                    let z = file.read_f32::<LittleEndian>()?;
-                    let w = file.read_f32::<LittleEndian>()?;
-                    Vector4::new(x, y, z, w)
-                }),
-                19 => FieldKind::Uuid({
-                    let mut bytes = uuid::Bytes::default();
-                    file.read_exact(&mut bytes)?;
-                    Uuid::from_bytes(bytes)
-                }),
-                20 => FieldKind::UnitComplex({
-                    let re = file.read_f32::<LittleEndian>()?;
-                    let im = file.read_f32::<LittleEndian>()?;
-                    UnitComplex::from_complex(Complex::new(re, im))
-                }),
-                21 => {
-                    let type_id = file.read_u8()?;
-                    let element_size = file.read_u32::<LittleEndian>()?;
-                    let data_size = file.read_u64::<LittleEndian>()?;
-                    let mut bytes = vec![0; data_size as usize];
-                    file.read_exact(&mut bytes)?;
-                    FieldKind::PodArray {
-                        type_id,
-                        element_size,
-                        bytes,
-                    }
+        Ok(match id {
+            1 => FieldKind::U8(file.read_u8()?),
+            2 => FieldKind::I8(file.read_i8()?),
+            3 => FieldKind::U16(file.read_u16::<LittleEndian>()?),
+            4 => FieldKind::I16(file.read_i16::<LittleEndian>()?),
+            5 => FieldKind::U32(file.read_u32::<LittleEndian>()?),
+            6 => FieldKind::I32(file.read_i32::<LittleEndian>()?),
+            7 => FieldKind::U64(file.read_u64::<LittleEndian>()?),
+            8 => FieldKind::I64(file.read_i64::<LittleEndian>()?),
+            9 => FieldKind::F32(file.read_f32::<LittleEndian>()?),
+            10 => FieldKind::F64(file.read_f64::<LittleEndian>()?),
+            11 => FieldKind::Vector3({
+                let x = file.read_f32::<LittleEndian>()?;
+                let y = file.read_f32::<LittleEndian>()?;
+                let z = file.read_f32::<LittleEndian>()?;
+                Vector3::new(x, y, z)
+            }),
+            12 => FieldKind::UnitQuaternion({
+                let x = file.read_f32::<LittleEndian>()?;
+                let y = file.read_f32::<LittleEndian>()?;
+                let z = file.read_f32::<LittleEndian>()?;
+                let w = file.read_f32::<LittleEndian>()?;
+                UnitQuaternion::new_normalize(Quaternion::new(w, x, y, z))
+            }),
+            13 => FieldKind::Matrix4({
+                let mut f = [0.0f32; 16];
+                for n in &mut f {
+                    *n = file.read_f32::<LittleEndian>()?;
                 }
-                22 => FieldKind::Matrix2({
-                    let mut f = [0.0f32; 3];
-                    for n in &mut f {
-                        *n = file.read_f32::<LittleEndian>()?;
-                    }
-                    Matrix2::from_row_slice(&f)
-                }),
-                _ => return Err(VisitError::UnknownFieldType(id)),
-            },
-        ))
+                Matrix4::from_column_slice(&f)
+            }),
+            14 => FieldKind::Data({
+                let len = file.read_u32::<LittleEndian>()? as usize;
+                read_bytes(file, len)?
+            }),
+            15 => FieldKind::Bool(file.read_u8()? != 0),
+            16 => FieldKind::Matrix3({
+                let mut f = [0.0f32; 9];
+                for n in &mut f {
+                    *n = file.read_f32::<LittleEndian>()?;
+                }
+                Matrix3::from_column_slice(&f)
+            }),
+            17 => FieldKind::Vector2({
+                let x = file.read_f32::<LittleEndian>()?;
+                let y = file.read_f32::<LittleEndian>()?;
+                Vector2::new(x, y)
+            }),
+            18 => FieldKind::Vector4({
+                let x = file.read_f32::<LittleEndian>()?;
+                let y = file.read_f32::<LittleEndian>()?;
+                let z = file.read_f32::<LittleEndian>()?;
+                let w = file.read_f32::<LittleEndian>()?;
+                Vector4::new(x, y, z, w)
+            }),
+            19 => FieldKind::Uuid({
+                let mut bytes = uuid::Bytes::default();
+                file.read_exact(&mut bytes)?;
+                Uuid::from_bytes(bytes)
+            }),
+            20 => FieldKind::UnitComplex({
+                let re = file.read_f32::<LittleEndian>()?;
+                let im = file.read_f32::<LittleEndian>()?;
+                UnitComplex::from_complex(Complex::new(re, im))
+            }),
+            21 => {
+                let type_id = file.read_u8()?;
+                let element_size = file.read_u32::<LittleEndian>()?;
+                let data_size = file.read_u64::<LittleEndian>()?;
+                let bytes = read_bytes(file, data_size as usize)?;
+                FieldKind::PodArray {
+                    type_id,
+                    element_size,
+                    bytes,
+                }
+            }
+            22 => FieldKind::Matrix2({
+                let mut f = [0.0f32; 4];
+                for n in &mut f {
+                    *n = file.read_f32::<LittleEndian>()?;
+                }
+                Matrix2::from_column_slice(&f)
+            }),
+            _ => return Err(VisitError::UnknownFieldType(id)),
+        })
+    }
+}
+
+/// Reads exactly `len` bytes. Unlike pre-allocating a buffer, doesn't try to allocate huge amount
+/// of memory if `len` is garbage read from corrupted data.
+fn read_bytes(file: &mut dyn Read, len: usize) -> Result<Vec<u8>, VisitError> {
+    let mut bytes = Vec::new();
+    Read::take(file, len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() == len {
+        Ok(bytes)
+    } else {
+        Err(VisitError::Io(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            format!("expected {} bytes, got {}", len, bytes.len()),
+        )))
     }
+}
 
-    fn as_string(&self) -> String {
-        format!("{}{}", self.name, self.kind.as_string())
+fn read_string(file: &mut dyn Read) -> Result<String, VisitError> {
+    let len = file.read_u32::<LittleEndian>()? as usize;
+    Ok(String::from_utf8(read_bytes(file, len)?)?)
+}
+
+/// Wraps an error with its location, `path` starts from root region which is omitted.
+fn corrupted(path: &[String], offset: u64, reason: VisitError) -> VisitError {
+    match reason {
+        // Keep the innermost location.
+        VisitError::Corrupted { .. } => reason,
+        _ => VisitError::Corrupted {
+            path: path.get(1..).unwrap_or_default().join("/"),
+            offset,
+            reason: Box::new(reason),
+        },
     }
 }
 
@@ -684,15 +700,18 @@ pub struct Node {
     fields: Vec<Field>,
     parent: Handle<Node>,
     children: Vec<Handle<Node>>,
+    /// Offset of fields block in indexed source, if the node was read from it.
+    block: Option<u64>,
+    /// Whether fields were decoded or not, always true for nodes that have no indexed source.
+    loaded: bool,
 }
 
 impl Node {
     fn new(name: &str, parent: Handle<Node>) -> Self {
         Self {
             name: name.to_owned(),
-            fields: Vec::new(),
             parent,
-            children: Vec::new(),
+            ..Default::default()
         }
     }
 }
@@ -704,6 +723,8 @@ impl Default for Node {
             fields: Vec::new(),
             parent: Handle::NONE,
             children: Vec::new(),
+            block: None,
+            loaded: true,
         }
     }
 }
@@ -715,6 +736,8 @@ pub struct Visitor {
     reading: bool,
     current_node: Handle<Node>,
     root: Handle<Node>,
+    /// Source of lazily loaded fields, see [`Visitor::open_indexed`].
+    source: Option<IndexedSource>,
 }
 
 pub trait Visit {
@@ -729,6 +752,8 @@ impl Default for Visitor {
 
 impl Visitor {
     const MAGIC: &'static str = "RG3D";
+    const CHECKED_MAGIC: &'static str = "RG3C";
+    const VERSION_FIELD: &'static str = "__Version";
 
     pub fn new() -> Self {
         let mut nodes = Pool::new();
@@ -740,6 +765,7 @@ impl Visitor {
             reading: false,
             current_node: root,
             root,
+            source: None,
         }
     }
 
@@ -773,6 +799,7 @@ impl Visitor {
                 }
             }
             if region.is_some() {
+                self.load_fields(region)?;
                 self.current_node = region;
                 Ok(())
             } else {
@@ -798,7 +825,100 @@ impl Visitor {
         }
     }
 
+    /// Enters a region that carries a version number. On write, the version is stored in the
+    /// region and returned as is. On read, returns the version the region was saved with (regions
+    /// saved without version have version 0), or [`VisitError::UnsupportedVersion`] if the
+    /// region was saved with a version newer than `version`. The region is left on error, so
+    /// reading could continue with sibling fields.
+    ///
+    /// Returned version can be compared with current one to upgrade old data explicitly.
+    pub fn enter_region_with_version(
+        &mut self,
+        name: &str,
+        version: u32,
+    ) -> Result<u32, VisitError> {
+        self.enter_region(name)?;
+
+        if self.reading {
+            let stored = self.region_version();
+            if stored > version {
+                // Leave the region, so the caller could handle the error and continue reading
+                // sibling fields.
+                self.leave_region()?;
+                Err(VisitError::UnsupportedVersion {
+                    region: name.to_owned(),
+                    version: stored,
+                    supported: version,
+                })
+            } else {
+                Ok(stored)
+            }
+        } else {
+            let mut version = version;
+            version.visit(Self::VERSION_FIELD, self)?;
+            Ok(version)
+        }
+    }
+
+    /// Returns version of current region, regions without explicit version have version 0.
+    pub fn region_version(&self) -> u32 {
+        self.nodes[self.current_node]
+            .fields
+            .iter()
+            .find_map(|field| match field.kind {
+                FieldKind::U32(version) if field.name == Self::VERSION_FIELD => Some(version),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    fn node_path(&self, mut node_handle: Handle<Node>) -> Vec<String> {
+        let mut path = Vec::new();
+        while node_handle.is_some() {
+            let node = &self.nodes[node_handle];
+            path.push(node.name.clone());
+            node_handle = node.parent;
+        }
+        path.reverse();
+        path
+    }
+
+    /// Returns path of current region, names of regions are separated by `/`. Useful to make
+    /// error messages more precise.
+    pub fn current_path(&self) -> String {
+        self.node_path(self.current_node)
+            .get(1..)
+            .unwrap_or_default()
+            .join("/")
+    }
+
+    /// Decodes fields of a node from indexed source, if they weren't decoded yet.
+    fn load_fields(&mut self, node_handle: Handle<Node>) -> VisitResult {
+        let node = &mut self.nodes[node_handle];
+        if !node.loaded {
+            if let (Some(source), Some(block)) = (self.source.as_mut(), node.block) {
+                match source.read_block(block) {
+                    Ok(fields) => {
+                        node.fields = fields;
+                        node.loaded = true;
+                    }
+                    Err(e) => return Err(corrupted(&self.node_path(node_handle), block, e)),
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn leave_region(&mut self) -> VisitResult {
+        if self.source.is_some() {
+            // Fields of lazily loaded regions are released when leaving the region to keep memory
+            // usage low, they'll be decoded again if the region is entered one more time.
+            let node = &mut self.nodes[self.current_node];
+            if node.block.is_some() && node.parent.is_some() {
+                node.fields = Vec::new();
+                node.loaded = false;
+            }
+        }
         self.current_node = self.nodes.borrow(self.current_node).parent;
         if self.current_node.is_none() {
             Err(VisitError::NoActiveNode)
@@ -807,81 +927,111 @@ impl Visitor {
         }
     }
 
-    fn print_node(&self, node_handle: Handle<Node>, nesting: usize, out_string: &mut String) {
-        let offset = (0..nesting).map(|_| "\t").collect::<String>();
-        let node = self.nodes.borrow(node_handle);
-        *out_string += format!(
-            "{}{}[Fields={}, Children={}]: ",
-            offset,
-            node.name,
-            node.fields.len(),
-            node.children.len()
-        )
-        .as_str();
-        for field in node.fields.iter() {
-            *out_string += field.as_string().as_str();
-        }
-
-        *out_string += "\n";
-
-        for child_handle in node.children.iter() {
-            self.print_node(*child_handle, nesting + 1, out_string);
-        }
+    pub fn save_binary<P: AsRef<Path>>(&self, path: P) -> VisitResult {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_binary(&mut writer, false)?;
+        writer.flush()?;
+        Ok(())
     }
 
-    pub fn save_text(&self) -> String {
-        let mut out_string = String::new();
-        self.print_node(self.root, 0, &mut out_string);
-        out_string
+    /// Same as [`Self::save_binary`], but also stores CRC32 checksum of every region. Corrupted
+    /// regions will be reported with [`VisitError::ChecksumMismatch`] on load. The file is loaded
+    /// by the same methods as plain binary file.
+    pub fn save_binary_with_checksums<P: AsRef<Path>>(&self, path: P) -> VisitResult {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_binary(&mut writer, true)?;
+        writer.flush()?;
+        Ok(())
     }
 
-    pub fn save_binary<P: AsRef<Path>>(&self, path: P) -> VisitResult {
-        let mut writer = BufWriter::new(File::create(path)?);
-        writer.write_all(Self::MAGIC.as_bytes())?;
+    fn write_binary(&self, writer: &mut dyn Write, checksums: bool) -> VisitResult {
+        writer.write_all(if checksums {
+            Self::CHECKED_MAGIC.as_bytes()
+        } else {
+            Self::MAGIC.as_bytes()
+        })?;
+        let mut record = Vec::new();
         let mut stack = vec![self.root];
         while let Some(node_handle) = stack.pop() {
             let node = self.nodes.borrow(node_handle);
+
+            record.clear();
             let name = node.name.as_bytes();
-            writer.write_u32::<LittleEndian>(name.len() as u32)?;
-            writer.write_all(name)?;
+            record.write_u32::<LittleEndian>(name.len() as u32)?;
+            record.write_all(name)?;
 
-            writer.write_u32::<LittleEndian>(node.fields.len() as u32)?;
+            record.write_u32::<LittleEndian>(node.fields.len() as u32)?;
             for field in node.fields.iter() {
-                Field::save(field, &mut writer)?
+                Field::save(field, &mut record)?
+            }
+
+            record.write_u32::<LittleEndian>(node.children.len() as u32)?;
+            writer.write_all(&record)?;
+            if checksums {
+                writer.write_u32::<LittleEndian>(crc32fast::hash(&record))?;
             }
 
-            writer.write_u32::<LittleEndian>(node.children.len() as u32)?;
             stack.extend_from_slice(&node.children);
         }
         Ok(())
     }
 
-    fn load_node_binary(&mut self, file: &mut dyn Read) -> Result<Handle<Node>, VisitError> {
-        let name_len = file.read_u32::<LittleEndian>()? as usize;
-        let mut raw_name = Vec::with_capacity(name_len);
-        unsafe { raw_name.set_len(name_len) };
-        file.read_exact(raw_name.as_mut_slice())?;
+    fn load_node_binary(
+        &mut self,
+        reader: &mut Cursor<&[u8]>,
+        checksums: bool,
+        path: &mut Vec<String>,
+    ) -> Result<Handle<Node>, VisitError> {
+        let node_offset = reader.position();
+        let name = read_string(reader).map_err(|e| corrupted(path, node_offset, e))?;
 
-        let mut node = Node {
-            name: String::from_utf8(raw_name)?,
-            ..Node::default()
-        };
+        path.push(name);
 
-        let field_count = file.read_u32::<LittleEndian>()? as usize;
+        let mut fields = Vec::new();
+        let field_count = reader
+            .read_u32::<LittleEndian>()
+            .map_err(|e| corrupted(path, node_offset, e.into()))?;
         for _ in 0..field_count {
-            let field = Field::load(file)?;
-            node.fields.push(field);
+            let field_offset = reader.position();
+            let name = read_string(reader).map_err(|e| corrupted(path, field_offset, e))?;
+            path.push(name);
+            let kind = Field::load_kind(reader).map_err(|e| corrupted(path, field_offset, e))?;
+            fields.push(Field {
+                name: path.pop().unwrap(),
+                kind,
+            });
+        }
+
+        let child_count = reader
+            .read_u32::<LittleEndian>()
+            .map_err(|e| corrupted(path, node_offset, e.into()))?;
+
+        if checksums {
+            let record = &reader.get_ref()[node_offset as usize..reader.position() as usize];
+            let actual = crc32fast::hash(record);
+            let expected = reader
+                .read_u32::<LittleEndian>()
+                .map_err(|e| corrupted(path, node_offset, e.into()))?;
+            if actual != expected {
+                return Err(corrupted(
+                    path,
+                    node_offset,
+                    VisitError::ChecksumMismatch { expected, actual },
+                ));
+            }
         }
 
         let mut children = Vec::new();
-        let child_count = file.read_u32::<LittleEndian>()? as usize;
         for _ in 0..child_count {
-            children.push(self.load_node_binary(file)?);
+            children.push(self.load_node_binary(reader, checksums, path)?);
         }
 
-        node.children = children.clone();
-
-        let handle = self.nodes.spawn(node);
+        let handle = self.nodes.spawn(Node {
+            name: path.pop().unwrap(),
+            fields,
+            children: children.clone(),
+            ..Node::default()
+        });
         for child_handle in children.iter() {
             let child = self.nodes.borrow_mut(*child_handle);
             child.parent = handle;
@@ -890,25 +1040,139 @@ impl Visitor {
         Ok(handle)
     }
 
-    pub async fn load_binary<P: AsRef<Path>>(path: P) -> Result<Self, VisitError> {
-        let mut reader = Cursor::new(io::load_file(path).await?);
-        let mut magic: [u8; 4] = Default::default();
-        reader.read_exact(&mut magic)?;
-        if !magic.eq(Self::MAGIC.as_bytes()) {
-            return Err(VisitError::NotSupportedFormat);
-        }
-        let mut visitor = Self {
+    /// Creates textual representation of the visitor tree. Text format is lossless and can be
+    /// loaded back by [`Self::load_from_memory`] or [`Self::load_from_file`].
+    pub fn save_text(&self) -> String {
+        let mut out_string = format!("{} {}\n", text::TEXT_MAGIC, text::TEXT_VERSION);
+        text::write_node(&self.nodes, self.root, 0, &mut out_string);
+        out_string
+    }
+
+    /// Writes textual representation of the visitor tree to a file at given path.
+    pub fn save_text_to_file<P: AsRef<Path>>(&self, path: P) -> VisitResult {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(self.save_text().as_bytes())?;
+        Ok(())
+    }
+
+    fn empty_for_reading() -> Self {
+        Self {
             nodes: Pool::new(),
             rc_map: Default::default(),
             arc_map: Default::default(),
             reading: true,
             current_node: Handle::NONE,
             root: Handle::NONE,
+            source: None,
+        }
+    }
+
+    fn load_binary_from_memory(data: &[u8]) -> Result<Self, VisitError> {
+        let mut reader = Cursor::new(data);
+        let mut magic: [u8; 4] = Default::default();
+        reader.read_exact(&mut magic)?;
+        let checksums = if magic.eq(Self::MAGIC.as_bytes()) {
+            false
+        } else if magic.eq(Self::CHECKED_MAGIC.as_bytes()) {
+            true
+        } else {
+            return Err(VisitError::NotSupportedFormat);
         };
-        visitor.root = visitor.load_node_binary(&mut reader)?;
+        let mut visitor = Self::empty_for_reading();
+        visitor.root = visitor.load_node_binary(&mut reader, checksums, &mut Vec::new())?;
         visitor.current_node = visitor.root;
         Ok(visitor)
     }
+
+    fn load_text_from_memory(data: &[u8]) -> Result<Self, VisitError> {
+        let text = std::str::from_utf8(data).map_err(|_| VisitError::NotSupportedFormat)?;
+        let (nodes, root) = text::parse(text)?;
+        Ok(Self {
+            nodes,
+            current_node: root,
+            root,
+            ..Self::empty_for_reading()
+        })
+    }
+
+    /// Writes the visitor tree to a file at given path in indexed binary format, which can be
+    /// loaded partially by [`Self::open_indexed`].
+    pub fn save_indexed<P: AsRef<Path>>(&self, path: P) -> VisitResult {
+        let mut writer = BufWriter::new(File::create(path)?);
+        indexed::write(&self.nodes, self.root, &mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Opens a file in indexed binary format for reading. Only the table of regions is read at
+    /// once, fields of a region are decoded when the region is entered and released when it is
+    /// left. Regions that were never entered are never decoded, so it is possible to read only
+    /// some parts of a large file:
+    ///
+    /// ```no_run
+    /// # use rg3d_core::visitor::{Visit, Visitor};
+    /// let mut visitor = Visitor::open_indexed("scene.rgs").unwrap();
+    /// visitor.enter_region("Scene").unwrap();
+    /// let mut enabled = false;
+    /// // Other regions of the scene won't be decoded.
+    /// enabled.visit("Enabled", &mut visitor).unwrap();
+    /// ```
+    pub fn open_indexed<P: AsRef<Path>>(path: P) -> Result<Self, VisitError> {
+        Self::open_indexed_reader(io::open_file(path)?)
+    }
+
+    /// Same as [`Self::open_indexed`], but reads data from arbitrary seekable source.
+    pub fn open_indexed_reader<R: Read + Seek + 'static>(reader: R) -> Result<Self, VisitError> {
+        let mut source = IndexedSource::new(Box::new(reader))?;
+        let (nodes, root) = source.read_table()?;
+        let mut visitor = Self {
+            nodes,
+            current_node: root,
+            root,
+            source: Some(source),
+            ..Self::empty_for_reading()
+        };
+        visitor.load_fields(root)?;
+        Ok(visitor)
+    }
+
+    fn load_indexed_from_memory(data: &[u8]) -> Result<Self, VisitError> {
+        let mut visitor = Self::open_indexed_reader(Cursor::new(data.to_vec()))?;
+        // Decode everything at once, the data is in memory anyway.
+        let handles = visitor
+            .nodes
+            .pair_iter()
+            .map(|(handle, _)| handle)
+            .collect::<Vec<_>>();
+        for handle in handles {
+            visitor.load_fields(handle)?;
+        }
+        visitor.source = None;
+        Ok(visitor)
+    }
+
+    pub async fn load_binary<P: AsRef<Path>>(path: P) -> Result<Self, VisitError> {
+        Self::load_binary_from_memory(&io::load_file(path).await?)
+    }
+
+    /// Tries to create visitor for reading from given data. Data could be in any of binary, indexed
+    /// binary or text formats, format is detected automatically.
+    pub fn load_from_memory(data: &[u8]) -> Result<Self, VisitError> {
+        if data.starts_with(text::TEXT_MAGIC.as_bytes()) {
+            Self::load_text_from_memory(data)
+        } else if data.starts_with(indexed::INDEXED_MAGIC) {
+            Self::load_indexed_from_memory(data)
+        } else {
+            Self::load_binary_from_memory(data)
+        }
+    }
+
+    /// Tries to create visitor for reading from a file at given path. File could be in any of
+    /// binary, indexed binary or text formats, format is detected automatically. Whole file is
+    /// read into memory, use [`Self::open_indexed`] to read large files partially.
+    pub async fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, VisitError> {
+        Self::load_from_memory(&io::load_file(path).await?)
+    }
 }
 
 impl<T> Visit for RefCell<T>
@@ -1367,8 +1631,21 @@ impl<T: Default + Visit, const SIZE: usize> Visit for [T; SIZE] {
 
 #[cfg(test)]
 mod test {
-    use crate::visitor::{Data, Visit, VisitError, VisitResult, Visitor};
-    use std::{fs::File, io::Write, path::Path, rc::Rc};
+    use crate::{
+        algebra::{
+            Matrix2, Matrix3, Matrix4, UnitComplex, UnitQuaternion, Vector2, Vector3, Vector4,
+        },
+        io::vfs::{MemoryBackend, Vfs},
+        pool::Handle,
+        visitor::{Data, Node, PodVecView, Visit, VisitError, VisitResult, Visitor},
+    };
+    use std::{
+        fs::File,
+        io::{Cursor, Write},
+        path::Path,
+        rc::Rc,
+    };
+    use uuid::Uuid;
 
     pub struct Model {
         data: u64,
@@ -1516,4 +1793,271 @@ mod test {
             objects.visit("Objects", &mut visitor).unwrap();
         }
     }
+
+    fn same_tree(a: &Visitor, a_node: Handle<Node>, b: &Visitor, b_node: Handle<Node>) -> bool {
+        let a_node = &a.nodes[a_node];
+        let b_node = &b.nodes[b_node];
+        a_node.name == b_node.name
+            && a_node.fields == b_node.fields
+            && a_node.children.len() == b_node.children.len()
+            && a_node.children.iter().all(|a_child| {
+                // Binary format does not preserve order of children, so match them by name.
+                b_node.children.iter().any(|b_child| {
+                    a.nodes[*a_child].name == b.nodes[*b_child].name
+                        && same_tree(a, *a_child, b, *b_child)
+                })
+            })
+    }
+
+    #[test]
+    fn text_format_round_trip() {
+        let mut visitor = Visitor::new();
+        {
+            visitor.enter_region("Fields").unwrap();
+            true.visit("Bool", &mut visitor).unwrap();
+            1u8.visit("U8", &mut visitor).unwrap();
+            (-2i8).visit("I8", &mut visitor).unwrap();
+            3u16.visit("U16", &mut visitor).unwrap();
+            (-4i16).visit("I16", &mut visitor).unwrap();
+            5u32.visit("U32", &mut visitor).unwrap();
+            (-6i32).visit("I32", &mut visitor).unwrap();
+            (u64::MAX - 1).visit("U64", &mut visitor).unwrap();
+            (i64::MIN + 1).visit("I64", &mut visitor).unwrap();
+            0.1f32.visit("F32", &mut visitor).unwrap();
+            (std::f64::consts::PI * 2.0)
+                .visit("F64", &mut visitor)
+                .unwrap();
+            (-f32::INFINITY).visit("Inf", &mut visitor).unwrap();
+            Vector2::new(1.0f32, -1.0e-20)
+                .visit("Vector2", &mut visitor)
+                .unwrap();
+            Vector3::new(1.0f32, 2.5, 3.25)
+                .visit("Vector3", &mut visitor)
+                .unwrap();
+            Vector4::new(1.0f32, 2.0, 3.0, 4.0)
+                .visit("Vector4", &mut visitor)
+                .unwrap();
+            UnitQuaternion::from_euler_angles(0.1f32, 0.2, 0.3)
+                .visit("Quaternion", &mut visitor)
+                .unwrap();
+            UnitComplex::new(0.7f32)
+                .visit("Complex", &mut visitor)
+                .unwrap();
+            Matrix2::new(1.0f32, 2.0, 3.0, 4.0)
+                .visit("Matrix2", &mut visitor)
+                .unwrap();
+            Matrix3::new(1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0)
+                .visit("Matrix3", &mut visitor)
+                .unwrap();
+            Matrix4::new_translation(&Vector3::new(1.0f32, 2.0, 3.0))
+                .visit("Matrix4", &mut visitor)
+                .unwrap();
+            Uuid::new_v4().visit("Uuid", &mut visitor).unwrap();
+            "Quoted \"text\"\nwith\ttabs \\ and unicode \u{1F600}"
+                .to_owned()
+                .visit("String", &mut visitor)
+                .unwrap();
+            Data {
+                vec: &mut vec![0, 159, 146, 150, 255],
+            }
+            .visit("Binary", &mut visitor)
+            .unwrap();
+            PodVecView::from_pod_vec(&mut vec![1.0f32, -0.5, 1.0e10])
+                .visit("PodArray", &mut visitor)
+                .unwrap();
+            visitor.enter_region("Nested").unwrap();
+            visitor.enter_region("Empty").unwrap();
+            visitor.leave_region().unwrap();
+            visitor.leave_region().unwrap();
+            visitor.leave_region().unwrap();
+        }
+
+        let text = visitor.save_text();
+        let from_text = Visitor::load_from_memory(text.as_bytes()).unwrap();
+        assert_eq!(from_text.save_text(), text);
+
+        let path = Path::new("test_text_round_trip.bin");
+        visitor.save_binary(path).unwrap();
+        let from_binary = futures::executor::block_on(Visitor::load_from_file(path)).unwrap();
+
+        assert!(same_tree(
+            &from_text,
+            from_text.root,
+            &from_binary,
+            from_binary.root
+        ));
+    }
+
+    #[test]
+    fn unsupported_version_leaves_region() {
+        let mut visitor = Visitor::new();
+        visitor.enter_region("Parent").unwrap();
+        visitor.enter_region_with_version("Data", 2).unwrap();
+        1u32.visit("Field", &mut visitor).unwrap();
+        visitor.leave_region().unwrap();
+        2u32.visit("Sibling", &mut visitor).unwrap();
+        visitor.leave_region().unwrap();
+
+        let mut visitor = Visitor::load_from_memory(visitor.save_text().as_bytes()).unwrap();
+        visitor.enter_region("Parent").unwrap();
+        assert!(matches!(
+            visitor.enter_region_with_version("Data", 1),
+            Err(VisitError::UnsupportedVersion {
+                version: 2,
+                supported: 1,
+                ..
+            })
+        ));
+        let mut sibling = 0u32;
+        sibling.visit("Sibling", &mut visitor).unwrap();
+        assert_eq!(sibling, 2);
+        visitor.leave_region().unwrap();
+        assert_eq!(visitor.current_node, visitor.root);
+    }
+
+    #[test]
+    fn text_format_errors() {
+        assert!(matches!(
+            Visitor::load_from_memory(b"RG3D_TEXT 1\n\"__ROOT__\" {\n\"A\": u8(256)\n}"),
+            Err(VisitError::InvalidTextFormat(_))
+        ));
+        assert!(matches!(
+            Visitor::load_from_memory(b"RG3D_TEXT 1\n\"__ROOT__\" {\n"),
+            Err(VisitError::InvalidTextFormat(_))
+        ));
+        assert!(matches!(
+            Visitor::load_from_memory(b"RG3D_TEXT 999\n\"__ROOT__\" {}"),
+            Err(VisitError::InvalidTextFormat(_))
+        ));
+    }
+
+    #[test]
+    fn indexed_format_partial_loading() {
+        let mut visitor = Visitor::new();
+        for i in 0..10u32 {
+            let mut items = (0..100).map(|n| n * i).collect::<Vec<u32>>();
+            items.visit(&format!("Chunk{}", i), &mut visitor).unwrap();
+        }
+        let path = Path::new("test_indexed.bin");
+        visitor.save_indexed(path).unwrap();
+
+        // Eagerly loaded indexed file must be the same as the original tree.
+        let data = std::fs::read(path).unwrap();
+        let loaded = Visitor::load_from_memory(&data).unwrap();
+        assert!(same_tree(&loaded, loaded.root, &visitor, visitor.root));
+
+        let mut visitor = Visitor::open_indexed(path).unwrap();
+        let loaded_count = |visitor: &Visitor| visitor.nodes.iter().filter(|n| n.loaded).count();
+        assert_eq!(loaded_count(&visitor), 1);
+
+        let mut items = Vec::<u32>::new();
+        items.visit("Chunk7", &mut visitor).unwrap();
+        assert_eq!(items, (0..100).map(|n| n * 7).collect::<Vec<u32>>());
+
+        // Only root is left loaded, other chunks were never decoded.
+        assert_eq!(loaded_count(&visitor), 1);
+
+        // Entering the same region again decodes it once more.
+        let mut items = Vec::<u32>::new();
+        items.visit("Chunk7", &mut visitor).unwrap();
+        assert_eq!(items.len(), 100);
+    }
+
+    #[test]
+    fn indexed_format_rejects_invalid_sizes() {
+        let mut visitor = Visitor::new();
+        1u32.visit("Field", &mut visitor).unwrap();
+        let path = Path::new("test_indexed_sizes.bin");
+        visitor.save_indexed(path).unwrap();
+        let data = std::fs::read(path).unwrap();
+
+        let mut table_offset = [0; 8];
+        table_offset.copy_from_slice(&data[8..16]);
+        let table_offset = u64::from_le_bytes(table_offset) as usize;
+
+        // Region count.
+        let mut corrupted = data.clone();
+        corrupted[table_offset..table_offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(Visitor::open_indexed_reader(Cursor::new(corrupted)).is_err());
+
+        // Length of root region name.
+        let mut corrupted = data;
+        corrupted[table_offset + 4..table_offset + 8].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(Visitor::open_indexed_reader(Cursor::new(corrupted)).is_err());
+    }
+
+    #[test]
+    fn indexed_format_is_opened_from_vfs() {
+        let mut visitor = Visitor::new();
+        42u32.visit("Answer", &mut visitor).unwrap();
+        let path = Path::new("test_indexed_vfs.bin");
+        visitor.save_indexed(path).unwrap();
+
+        Vfs::global_mut().mount(
+            "indexed_vfs_test",
+            0,
+            MemoryBackend::new().with_file("scene.bin", std::fs::read(path).unwrap()),
+        );
+        let result = Visitor::open_indexed("indexed_vfs_test/scene.bin");
+        Vfs::global_mut().unmount("indexed_vfs_test");
+
+        let mut visitor = result.unwrap();
+        let mut answer = 0u32;
+        answer.visit("Answer", &mut visitor).unwrap();
+        assert_eq!(answer, 42);
+    }
+
+    fn save_corruption_test_data(path: &Path, checksums: bool) -> Vec<u8> {
+        let mut visitor = Visitor::new();
+        visitor.enter_region("Scene").unwrap();
+        visitor.enter_region("Graph").unwrap();
+        visitor.enter_region("Item12").unwrap();
+        Vector3::new(1.0f32, 2.0, 3.0)
+            .visit("LocalTransform", &mut visitor)
+            .unwrap();
+        visitor.leave_region().unwrap();
+        visitor.leave_region().unwrap();
+        visitor.leave_region().unwrap();
+        if checksums {
+            visitor.save_binary_with_checksums(path).unwrap();
+        } else {
+            visitor.save_binary(path).unwrap();
+        }
+        std::fs::read(path).unwrap()
+    }
+
+    #[test]
+    fn checksum_mismatch_is_reported_with_path() {
+        let mut data = save_corruption_test_data(Path::new("test_checksums.bin"), true);
+        assert!(Visitor::load_from_memory(&data).is_ok());
+
+        // Damage last component of the vector, it is followed by children count and checksum.
+        let len = data.len();
+        data[len - 9] ^= 0xFF;
+
+        match Visitor::load_from_memory(&data) {
+            Err(VisitError::Corrupted { path, reason, .. }) => {
+                assert_eq!(path, "Scene/Graph/Item12");
+                assert!(matches!(*reason, VisitError::ChecksumMismatch { .. }));
+            }
+            _ => panic!("corruption must be detected"),
+        }
+    }
+
+    #[test]
+    fn truncation_is_reported_with_path_and_offset() {
+        let data = save_corruption_test_data(Path::new("test_truncated.bin"), false);
+
+        // Cut the last float of the vector, it is followed by children count.
+        let truncated = &data[..data.len() - 6];
+        let field_offset = data.len() - (4 + "LocalTransform".len() + 1 + 3 * 4 + 4);
+
+        match Visitor::load_from_memory(truncated) {
+            Err(VisitError::Corrupted { path, offset, .. }) => {
+                assert_eq!(path, "Scene/Graph/Item12/LocalTransform");
+                assert_eq!(offset, field_offset as u64);
+            }
+            _ => panic!("truncation must be detected"),
+        }
+    }
 }
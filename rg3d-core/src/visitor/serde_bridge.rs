@@ -0,0 +1,535 @@
+//! Bridge between [`Visit`] and serde.
+//!
+//! # Overview
+//!
+//! Any type that implements [`Visit`] can be written to or read from any serde format without
+//! writing mirror structures. Regions of visitor tree are mapped to maps, fields are mapped to
+//! pairs of type name and value, so type information is preserved and data can be read back
+//! exactly:
+//!
+//! ```json
+//! {
+//!     "Scene": {
+//!         "Enabled": ["bool", true],
+//!         "Position": ["vec3", [1.0, 2.0, 3.0]],
+//!         "Name": {
+//!             "Length": ["u32", 4],
+//!             "Data": ["str", "Root"]
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Self-describing formats (JSON, CBOR, MessagePack, RON, etc.) are supported for reading.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let json = serde_json::to_string(&SerdeVisitor::new(&mut scene)?)?;
+//! let mut scene = Scene::default();
+//! serde_bridge::deserialize(&mut scene, &mut serde_json::Deserializer::from_str(&json))?;
+//! ```
+
+use crate::{
+    algebra::{
+        Complex, Matrix2, Matrix3, Matrix4, Quaternion, UnitComplex, UnitQuaternion, Vector2,
+        Vector3, Vector4,
+    },
+    pool::Handle,
+    visitor::{Field, FieldKind, Node, Visit, VisitError, Visitor},
+};
+use serde::{
+    de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess},
+    ser::{self, SerializeMap, SerializeSeq, SerializeTuple, Serializer},
+    Deserialize, Serialize,
+};
+use std::fmt::Formatter;
+use uuid::Uuid;
+
+/// Name of the region that holds visited value.
+const DATA_REGION: &str = "Data";
+
+/// Visits given value and serializes its data through given serializer.
+pub fn serialize<T, S>(value: &mut T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Visit,
+    S: Serializer,
+{
+    SerdeVisitor::new(value)
+        .map_err(ser::Error::custom)?
+        .serialize(serializer)
+}
+
+/// Reads data through given deserializer and visits given value with it.
+pub fn deserialize<'de, T, D>(value: &mut T, deserializer: D) -> Result<(), D::Error>
+where
+    T: Visit,
+    D: Deserializer<'de>,
+{
+    let mut visitor = SerdeVisitor::deserialize(deserializer)?;
+    visitor.read(value).map_err(de::Error::custom)
+}
+
+/// Visitor tree that implements both [`Serialize`] and [`Deserialize`], see module docs for
+/// more info.
+pub struct SerdeVisitor {
+    visitor: Visitor,
+}
+
+impl SerdeVisitor {
+    /// Creates new tree filled with the data of given value.
+    pub fn new<T: Visit>(value: &mut T) -> Result<Self, VisitError> {
+        let mut visitor = Visitor::new();
+        value.visit(DATA_REGION, &mut visitor)?;
+        Ok(Self { visitor })
+    }
+
+    /// Visits given value with the data of the tree.
+    pub fn read<T: Visit>(&mut self, value: &mut T) -> Result<(), VisitError> {
+        value.visit(DATA_REGION, &mut self.visitor)
+    }
+
+    /// Returns inner visitor.
+    pub fn into_inner(self) -> Visitor {
+        self.visitor
+    }
+}
+
+impl Serialize for SerdeVisitor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RegionRef {
+            visitor: &self.visitor,
+            node: self.visitor.root,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SerdeVisitor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut visitor = Visitor {
+            reading: true,
+            ..Visitor::new()
+        };
+        let root = visitor.root;
+        deserializer.deserialize_map(RegionSeed {
+            visitor: &mut visitor,
+            node: root,
+        })?;
+        Ok(Self { visitor })
+    }
+}
+
+struct RegionRef<'a> {
+    visitor: &'a Visitor,
+    node: Handle<Node>,
+}
+
+impl<'a> Serialize for RegionRef<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let node = &self.visitor.nodes[self.node];
+        let mut map = serializer.serialize_map(Some(node.fields.len() + node.children.len()))?;
+        for field in node.fields.iter() {
+            map.serialize_entry(&field.name, &FieldRef(&field.kind))?;
+        }
+        for child in node.children.iter() {
+            map.serialize_entry(
+                &self.visitor.nodes[*child].name,
+                &RegionRef {
+                    visitor: self.visitor,
+                    node: *child,
+                },
+            )?;
+        }
+        map.end()
+    }
+}
+
+struct Floats<'a>(&'a [f32]);
+
+impl<'a> Serialize for Floats<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(self.0.len())?;
+        for f in self.0 {
+            tuple.serialize_element(f)?;
+        }
+        tuple.end()
+    }
+}
+
+struct Bytes(Vec<u8>);
+
+impl Serialize for Bytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Bytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BytesVisitor;
+
+        impl<'de> de::Visitor<'de> for BytesVisitor {
+            type Value = Bytes;
+
+            fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+                formatter.write_str("bytes")
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                Ok(Bytes(v.to_vec()))
+            }
+
+            fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+                Ok(Bytes(v))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+                while let Some(byte) = seq.next_element()? {
+                    bytes.push(byte);
+                }
+                Ok(Bytes(bytes))
+            }
+        }
+
+        deserializer.deserialize_bytes(BytesVisitor)
+    }
+}
+
+struct FieldRef<'a>(&'a FieldKind);
+
+impl<'a> Serialize for FieldRef<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(2))?;
+        match self.0 {
+            FieldKind::Bool(v) => {
+                seq.serialize_element("bool")?;
+                seq.serialize_element(v)?;
+            }
+            FieldKind::U8(v) => {
+                seq.serialize_element("u8")?;
+                seq.serialize_element(v)?;
+            }
+            FieldKind::I8(v) => {
+                seq.serialize_element("i8")?;
+                seq.serialize_element(v)?;
+            }
+            FieldKind::U16(v) => {
+                seq.serialize_element("u16")?;
+                seq.serialize_element(v)?;
+            }
+            FieldKind::I16(v) => {
+                seq.serialize_element("i16")?;
+                seq.serialize_element(v)?;
+            }
+            FieldKind::U32(v) => {
+                seq.serialize_element("u32")?;
+                seq.serialize_element(v)?;
+            }
+            FieldKind::I32(v) => {
+                seq.serialize_element("i32")?;
+                seq.serialize_element(v)?;
+            }
+            FieldKind::U64(v) => {
+                seq.serialize_element("u64")?;
+                seq.serialize_element(v)?;
+            }
+            FieldKind::I64(v) => {
+                seq.serialize_element("i64")?;
+                seq.serialize_element(v)?;
+            }
+            FieldKind::F32(v) => {
+                seq.serialize_element("f32")?;
+                seq.serialize_element(v)?;
+            }
+            FieldKind::F64(v) => {
+                seq.serialize_element("f64")?;
+                seq.serialize_element(v)?;
+            }
+            FieldKind::Vector2(v) => {
+                seq.serialize_element("vec2")?;
+                seq.serialize_element(&Floats(v.as_slice()))?;
+            }
+            FieldKind::Vector3(v) => {
+                seq.serialize_element("vec3")?;
+                seq.serialize_element(&Floats(v.as_slice()))?;
+            }
+            FieldKind::Vector4(v) => {
+                seq.serialize_element("vec4")?;
+                seq.serialize_element(&Floats(v.as_slice()))?;
+            }
+            FieldKind::UnitQuaternion(v) => {
+                seq.serialize_element("quat")?;
+                seq.serialize_element(&Floats(&[v.i, v.j, v.k, v.w]))?;
+            }
+            FieldKind::UnitComplex(v) => {
+                seq.serialize_element("complex")?;
+                seq.serialize_element(&Floats(&[v.re, v.im]))?;
+            }
+            FieldKind::Matrix2(m) => {
+                seq.serialize_element("mat2")?;
+                seq.serialize_element(&Floats(m.as_slice()))?;
+            }
+            FieldKind::Matrix3(m) => {
+                seq.serialize_element("mat3")?;
+                seq.serialize_element(&Floats(m.as_slice()))?;
+            }
+            FieldKind::Matrix4(m) => {
+                seq.serialize_element("mat4")?;
+                seq.serialize_element(&Floats(m.as_slice()))?;
+            }
+            FieldKind::Uuid(uuid) => {
+                seq.serialize_element("uuid")?;
+                seq.serialize_element(&uuid.to_string())?;
+            }
+            FieldKind::Data(data) => match std::str::from_utf8(data) {
+                Ok(str) => {
+                    seq.serialize_element("str")?;
+                    seq.serialize_element(str)?;
+                }
+                Err(_) => {
+                    seq.serialize_element("data")?;
+                    seq.serialize_element(&Bytes(data.clone()))?;
+                }
+            },
+            FieldKind::PodArray {
+                type_id,
+                element_size,
+                bytes,
+            } => {
+                seq.serialize_element("podarray")?;
+                seq.serialize_element(&(type_id, element_size, Bytes(bytes.clone())))?;
+            }
+        }
+        seq.end()
+    }
+}
+
+struct RegionSeed<'a> {
+    visitor: &'a mut Visitor,
+    node: Handle<Node>,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for RegionSeed<'a> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'a, 'de> de::Visitor<'de> for RegionSeed<'a> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("a map of fields and regions")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(name) = map.next_key::<String>()? {
+            map.next_value_seed(EntrySeed {
+                visitor: self.visitor,
+                parent: self.node,
+                name,
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// Either a field (sequence of type and value) or a child region (map).
+struct EntrySeed<'a> {
+    visitor: &'a mut Visitor,
+    parent: Handle<Node>,
+    name: String,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for EntrySeed<'a> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'a, 'de> de::Visitor<'de> for EntrySeed<'a> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("a region map or a [type, value] field")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<(), A::Error> {
+        let child = self.visitor.nodes.spawn(Node::new(&self.name, self.parent));
+        self.visitor.nodes[self.parent].children.push(child);
+        RegionSeed {
+            visitor: self.visitor,
+            node: child,
+        }
+        .visit_map(map)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let type_name = seq
+            .next_element::<String>()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let kind = seq
+            .next_element_seed(KindSeed(&type_name))?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        self.visitor.nodes[self.parent]
+            .fields
+            .push(Field::new(&self.name, kind));
+        Ok(())
+    }
+}
+
+struct KindSeed<'a>(&'a str);
+
+fn floats<'de, D: Deserializer<'de>, const N: usize>(
+    deserializer: D,
+) -> Result<[f32; N], D::Error> {
+    let values = Vec::<f32>::deserialize(deserializer)?;
+    let len = values.len();
+    let mut array = [0.0; N];
+    if len != N {
+        return Err(de::Error::invalid_length(
+            len,
+            &format!("{} numbers", N).as_str(),
+        ));
+    }
+    array.copy_from_slice(&values);
+    Ok(array)
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for KindSeed<'a> {
+    type Value = FieldKind;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<FieldKind, D::Error> {
+        Ok(match self.0 {
+            "bool" => FieldKind::Bool(bool::deserialize(deserializer)?),
+            "u8" => FieldKind::U8(u8::deserialize(deserializer)?),
+            "i8" => FieldKind::I8(i8::deserialize(deserializer)?),
+            "u16" => FieldKind::U16(u16::deserialize(deserializer)?),
+            "i16" => FieldKind::I16(i16::deserialize(deserializer)?),
+            "u32" => FieldKind::U32(u32::deserialize(deserializer)?),
+            "i32" => FieldKind::I32(i32::deserialize(deserializer)?),
+            "u64" => FieldKind::U64(u64::deserialize(deserializer)?),
+            "i64" => FieldKind::I64(i64::deserialize(deserializer)?),
+            "f32" => FieldKind::F32(f32::deserialize(deserializer)?),
+            "f64" => FieldKind::F64(f64::deserialize(deserializer)?),
+            "vec2" => FieldKind::Vector2(Vector2::from(floats::<_, 2>(deserializer)?)),
+            "vec3" => FieldKind::Vector3(Vector3::from(floats::<_, 3>(deserializer)?)),
+            "vec4" => FieldKind::Vector4(Vector4::from(floats::<_, 4>(deserializer)?)),
+            "quat" => {
+                let [i, j, k, w] = floats::<_, 4>(deserializer)?;
+                FieldKind::UnitQuaternion(UnitQuaternion::new_normalize(Quaternion::new(
+                    w, i, j, k,
+                )))
+            }
+            "complex" => {
+                let [re, im] = floats::<_, 2>(deserializer)?;
+                FieldKind::UnitComplex(UnitComplex::from_complex(Complex::new(re, im)))
+            }
+            "mat2" => {
+                FieldKind::Matrix2(Matrix2::from_column_slice(&floats::<_, 4>(deserializer)?))
+            }
+            "mat3" => {
+                FieldKind::Matrix3(Matrix3::from_column_slice(&floats::<_, 9>(deserializer)?))
+            }
+            "mat4" => {
+                FieldKind::Matrix4(Matrix4::from_column_slice(&floats::<_, 16>(deserializer)?))
+            }
+            "uuid" => FieldKind::Uuid(
+                Uuid::parse_str(&String::deserialize(deserializer)?).map_err(de::Error::custom)?,
+            ),
+            "str" => FieldKind::Data(String::deserialize(deserializer)?.into_bytes()),
+            "data" => FieldKind::Data(Bytes::deserialize(deserializer)?.0),
+            "podarray" => {
+                let (type_id, element_size, bytes) = <(u8, u32, Bytes)>::deserialize(deserializer)?;
+                FieldKind::PodArray {
+                    type_id,
+                    element_size,
+                    bytes: bytes.0,
+                }
+            }
+            _ => {
+                return Err(de::Error::unknown_variant(
+                    self.0,
+                    &[
+                        "bool", "u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64", "f32", "f64",
+                        "vec2", "vec3", "vec4", "quat", "complex", "mat2", "mat3", "mat4", "uuid",
+                        "str", "data", "podarray",
+                    ],
+                ))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algebra::{Matrix4, UnitQuaternion, Vector3},
+        visitor::{
+            serde_bridge::{self, SerdeVisitor},
+            Data, PodVecView, Visit, VisitResult, Visitor,
+        },
+    };
+    use uuid::Uuid;
+
+    #[derive(Default, Debug, PartialEq)]
+    struct Transform {
+        name: String,
+        position: Vector3<f32>,
+        rotation: UnitQuaternion<f32>,
+        matrix: Matrix4<f32>,
+        id: Uuid,
+        blob: Vec<u8>,
+        weights: Vec<f32>,
+        children: Vec<Transform>,
+    }
+
+    impl Visit for Transform {
+        fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+            visitor.enter_region(name)?;
+            self.name.visit("Name", visitor)?;
+            self.position.visit("Position", visitor)?;
+            self.rotation.visit("Rotation", visitor)?;
+            self.matrix.visit("Matrix", visitor)?;
+            self.id.visit("Id", visitor)?;
+            Data {
+                vec: &mut self.blob,
+            }
+            .visit("Blob", visitor)?;
+            PodVecView::from_pod_vec(&mut self.weights).visit("Weights", visitor)?;
+            self.children.visit("Children", visitor)?;
+            visitor.leave_region()
+        }
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let mut transform = Transform {
+            name: "Root".to_owned(),
+            position: Vector3::new(1.0, 2.0, 3.0),
+            rotation: UnitQuaternion::from_euler_angles(0.1, 0.2, 0.3),
+            matrix: Matrix4::new_translation(&Vector3::new(4.0, 5.0, 6.0)),
+            id: Uuid::new_v4(),
+            blob: vec![0, 159, 146, 150],
+            weights: vec![0.25, 0.75],
+            children: vec![Transform {
+                name: "Child".to_owned(),
+                ..Default::default()
+            }],
+        };
+
+        let json = serde_json::to_string(&SerdeVisitor::new(&mut transform).unwrap()).unwrap();
+        assert!(json.contains(r#""Position":["vec3",[1.0,2.0,3.0]]"#));
+
+        let mut loaded = Transform::default();
+        serde_bridge::deserialize(&mut loaded, &mut serde_json::Deserializer::from_str(&json))
+            .unwrap();
+        assert_eq!(loaded, transform);
+    }
+}
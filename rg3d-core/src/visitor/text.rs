@@ -0,0 +1,599 @@
+//! Human-readable text representation of visitor tree.
+//!
+//! # Format
+//!
+//! Text format is designed to be diffable and mergeable by usual version control systems. Each
+//! field and each region is written on its own line, fields go first, then child regions. Names
+//! are always quoted, values are written as `type(arguments)`:
+//!
+//! ```text
+//! RG3D_TEXT 1
+//! "__ROOT__" {
+//!     "Scene" {
+//!         "Enabled": bool(true)
+//!         "AmbientColor": u32(4284769380)
+//!         "Name" {
+//!             "Length": u32(4)
+//!             "Data": str("Root")
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Floating point numbers are written in shortest form that guarantees exact round trip, so
+//! loading text form produces exactly the same tree as loading the binary form.
+
+use crate::{
+    algebra::{
+        Complex, Matrix2, Matrix3, Matrix4, Quaternion, UnitComplex, UnitQuaternion, Vector2,
+        Vector3, Vector4,
+    },
+    pool::{Handle, Pool},
+    visitor::{Field, FieldKind, Node, VisitError},
+};
+use std::{fmt::Write, iter::Peekable, str::FromStr, vec::IntoIter};
+
+pub(in crate::visitor) const TEXT_MAGIC: &str = "RG3D_TEXT";
+pub(in crate::visitor) const TEXT_VERSION: u32 = 1;
+
+fn write_string(string: &str, out: &mut String) {
+    out.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{{{:x}}}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn write_list<T: std::fmt::Debug>(items: impl Iterator<Item = T>, out: &mut String) {
+    for (i, item) in items.enumerate() {
+        if i != 0 {
+            out.push_str(", ");
+        }
+        let _ = write!(out, "{:?}", item);
+    }
+}
+
+fn write_pod_array(type_id: u8, element_size: u32, bytes: &[u8], out: &mut String) {
+    macro_rules! write_elements {
+        ($ty:ty) => {
+            if element_size as usize == std::mem::size_of::<$ty>()
+                && bytes.len() % std::mem::size_of::<$ty>() == 0
+            {
+                out.push('[');
+                write_list(
+                    bytes
+                        .chunks_exact(std::mem::size_of::<$ty>())
+                        .map(|c| <$ty>::from_le_bytes(c.try_into().unwrap())),
+                    out,
+                );
+                out.push(']');
+                return;
+            }
+        };
+    }
+
+    use std::convert::TryInto;
+
+    let _ = write!(out, "{}, {}, ", type_id, element_size);
+    match type_id {
+        0 => write_elements!(u8),
+        1 => write_elements!(i8),
+        2 => write_elements!(u16),
+        3 => write_elements!(i16),
+        4 => write_elements!(u32),
+        5 => write_elements!(i32),
+        6 => write_elements!(u64),
+        7 => write_elements!(i64),
+        8 => write_elements!(f32),
+        9 => write_elements!(f64),
+        _ => (),
+    }
+    // Unknown layout, fallback to opaque bytes.
+    write_string(&base64::encode(bytes), out);
+}
+
+fn write_field_kind(kind: &FieldKind, out: &mut String) {
+    let _ = match kind {
+        FieldKind::Bool(v) => write!(out, "bool({})", v),
+        FieldKind::U8(v) => write!(out, "u8({})", v),
+        FieldKind::I8(v) => write!(out, "i8({})", v),
+        FieldKind::U16(v) => write!(out, "u16({})", v),
+        FieldKind::I16(v) => write!(out, "i16({})", v),
+        FieldKind::U32(v) => write!(out, "u32({})", v),
+        FieldKind::I32(v) => write!(out, "i32({})", v),
+        FieldKind::U64(v) => write!(out, "u64({})", v),
+        FieldKind::I64(v) => write!(out, "i64({})", v),
+        FieldKind::F32(v) => write!(out, "f32({:?})", v),
+        FieldKind::F64(v) => write!(out, "f64({:?})", v),
+        FieldKind::Vector2(v) => write!(out, "vec2({:?}, {:?})", v.x, v.y),
+        FieldKind::Vector3(v) => write!(out, "vec3({:?}, {:?}, {:?})", v.x, v.y, v.z),
+        FieldKind::Vector4(v) => {
+            write!(out, "vec4({:?}, {:?}, {:?}, {:?})", v.x, v.y, v.z, v.w)
+        }
+        FieldKind::UnitQuaternion(v) => {
+            write!(out, "quat({:?}, {:?}, {:?}, {:?})", v.i, v.j, v.k, v.w)
+        }
+        FieldKind::UnitComplex(v) => write!(out, "complex({:?}, {:?})", v.re, v.im),
+        FieldKind::Matrix2(m) => {
+            out.push_str("mat2(");
+            write_list(m.iter(), out);
+            write!(out, ")")
+        }
+        FieldKind::Matrix3(m) => {
+            out.push_str("mat3(");
+            write_list(m.iter(), out);
+            write!(out, ")")
+        }
+        FieldKind::Matrix4(m) => {
+            out.push_str("mat4(");
+            write_list(m.iter(), out);
+            write!(out, ")")
+        }
+        FieldKind::Uuid(uuid) => write!(out, "uuid({})", uuid),
+        FieldKind::Data(data) => {
+            match std::str::from_utf8(data) {
+                Ok(str) => {
+                    out.push_str("str(");
+                    write_string(str, out);
+                }
+                Err(_) => {
+                    out.push_str("data(");
+                    write_string(&base64::encode(data), out);
+                }
+            }
+            write!(out, ")")
+        }
+        FieldKind::PodArray {
+            type_id,
+            element_size,
+            bytes,
+        } => {
+            out.push_str("podarray(");
+            write_pod_array(*type_id, *element_size, bytes, out);
+            write!(out, ")")
+        }
+    };
+}
+
+pub(in crate::visitor) fn write_node(
+    nodes: &Pool<Node>,
+    node_handle: Handle<Node>,
+    nesting: usize,
+    out: &mut String,
+) {
+    let node = &nodes[node_handle];
+
+    let indent = "    ".repeat(nesting);
+    out.push_str(&indent);
+    write_string(&node.name, out);
+    out.push_str(" {\n");
+
+    for field in node.fields.iter() {
+        out.push_str(&indent);
+        out.push_str("    ");
+        write_string(&field.name, out);
+        out.push_str(": ");
+        write_field_kind(&field.kind, out);
+        out.push('\n');
+    }
+
+    for child in node.children.iter() {
+        write_node(nodes, *child, nesting + 1, out);
+    }
+
+    out.push_str(&indent);
+    out.push_str("}\n");
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Str(String),
+    OpenBrace,
+    CloseBrace,
+    OpenParen,
+    CloseParen,
+    OpenBracket,
+    CloseBracket,
+    Colon,
+    Comma,
+}
+
+fn error(line: usize, message: impl AsRef<str>) -> VisitError {
+    VisitError::InvalidTextFormat(format!("line {}: {}", line, message.as_ref()))
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-' || c == '+' || c == '.'
+}
+
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, VisitError> {
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            '\n' => {
+                line += 1;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            '{' => Token::OpenBrace,
+            '}' => Token::CloseBrace,
+            '(' => Token::OpenParen,
+            ')' => Token::CloseParen,
+            '[' => Token::OpenBracket,
+            ']' => Token::CloseBracket,
+            ':' => Token::Colon,
+            ',' => Token::Comma,
+            '"' => {
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('"') => string.push('"'),
+                            Some('\\') => string.push('\\'),
+                            Some('n') => string.push('\n'),
+                            Some('r') => string.push('\r'),
+                            Some('t') => string.push('\t'),
+                            Some('u') => {
+                                if chars.next() != Some('{') {
+                                    return Err(error(line, "malformed unicode escape"));
+                                }
+                                let mut code = String::new();
+                                for c in chars.by_ref() {
+                                    if c == '}' {
+                                        break;
+                                    }
+                                    code.push(c);
+                                }
+                                match u32::from_str_radix(&code, 16)
+                                    .ok()
+                                    .and_then(std::char::from_u32)
+                                {
+                                    Some(c) => string.push(c),
+                                    None => return Err(error(line, "malformed unicode escape")),
+                                }
+                            }
+                            _ => return Err(error(line, "unknown escape sequence")),
+                        },
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            string.push(c)
+                        }
+                        None => return Err(error(line, "unterminated string")),
+                    }
+                }
+                Token::Str(string)
+            }
+            c if is_word_char(c) => {
+                let mut word = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if !is_word_char(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                Token::Word(word)
+            }
+            c => return Err(error(line, format!("unexpected character {:?}", c))),
+        };
+        tokens.push((token, line));
+    }
+    Ok(tokens)
+}
+
+enum Value {
+    Word(String),
+    Str(String),
+    List(Vec<String>),
+}
+
+struct Parser {
+    tokens: Peekable<IntoIter<(Token, usize)>>,
+    line: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Result<Token, VisitError> {
+        match self.tokens.next() {
+            Some((token, line)) => {
+                self.line = line;
+                Ok(token)
+            }
+            None => Err(error(self.line, "unexpected end of file")),
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), VisitError> {
+        let token = self.next()?;
+        if token == expected {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected {:?}, got {:?}", expected, token)))
+        }
+    }
+
+    fn error(&self, message: impl AsRef<str>) -> VisitError {
+        error(self.line, message)
+    }
+
+    fn parse_node(&mut self, nodes: &mut Pool<Node>) -> Result<Handle<Node>, VisitError> {
+        let name = match self.next()? {
+            Token::Str(name) => name,
+            token => return Err(self.error(format!("expected region name, got {:?}", token))),
+        };
+        self.expect(Token::OpenBrace)?;
+        self.parse_node_body(name, nodes)
+    }
+
+    fn parse_node_body(
+        &mut self,
+        name: String,
+        nodes: &mut Pool<Node>,
+    ) -> Result<Handle<Node>, VisitError> {
+        let mut node = Node {
+            name,
+            ..Default::default()
+        };
+        let mut children = Vec::new();
+
+        loop {
+            match self.next()? {
+                Token::CloseBrace => break,
+                Token::Str(name) => match self.next()? {
+                    Token::Colon => {
+                        let kind = self.parse_field_kind()?;
+                        node.fields.push(Field::new(&name, kind));
+                    }
+                    Token::OpenBrace => children.push(self.parse_node_body(name, nodes)?),
+                    token => {
+                        return Err(self.error(format!("expected : or {{, got {:?}", token)));
+                    }
+                },
+                token => {
+                    return Err(self.error(format!("expected field or region, got {:?}", token)));
+                }
+            }
+        }
+
+        node.children = children.clone();
+        let handle = nodes.spawn(node);
+        for child in children {
+            nodes[child].parent = handle;
+        }
+        Ok(handle)
+    }
+
+    fn parse_value(&mut self) -> Result<Value, VisitError> {
+        match self.next()? {
+            Token::Word(word) => Ok(Value::Word(word)),
+            Token::Str(str) => Ok(Value::Str(str)),
+            Token::OpenBracket => {
+                let mut items = Vec::new();
+                loop {
+                    match self.next()? {
+                        Token::CloseBracket => break,
+                        Token::Comma => (),
+                        Token::Word(word) => items.push(word),
+                        token => {
+                            return Err(self.error(format!("expected number, got {:?}", token)))
+                        }
+                    }
+                }
+                Ok(Value::List(items))
+            }
+            token => Err(self.error(format!("expected value, got {:?}", token))),
+        }
+    }
+
+    fn parse_args(&mut self) -> Result<Vec<Value>, VisitError> {
+        self.expect(Token::OpenParen)?;
+        let mut args = Vec::new();
+        loop {
+            match self.tokens.peek() {
+                Some((Token::CloseParen, _)) => {
+                    self.next()?;
+                    break;
+                }
+                Some((Token::Comma, _)) => {
+                    self.next()?;
+                }
+                _ => args.push(self.parse_value()?),
+            }
+        }
+        Ok(args)
+    }
+
+    fn parse_field_kind(&mut self) -> Result<FieldKind, VisitError> {
+        let type_name = match self.next()? {
+            Token::Word(word) => word,
+            token => return Err(self.error(format!("expected field type, got {:?}", token))),
+        };
+        let args = self.parse_args()?;
+        let args = Args {
+            values: args,
+            line: self.line,
+        };
+
+        Ok(match type_name.as_str() {
+            "bool" => FieldKind::Bool(args.number(0)?),
+            "u8" => FieldKind::U8(args.number(0)?),
+            "i8" => FieldKind::I8(args.number(0)?),
+            "u16" => FieldKind::U16(args.number(0)?),
+            "i16" => FieldKind::I16(args.number(0)?),
+            "u32" => FieldKind::U32(args.number(0)?),
+            "i32" => FieldKind::I32(args.number(0)?),
+            "u64" => FieldKind::U64(args.number(0)?),
+            "i64" => FieldKind::I64(args.number(0)?),
+            "f32" => FieldKind::F32(args.number(0)?),
+            "f64" => FieldKind::F64(args.number(0)?),
+            "vec2" => FieldKind::Vector2(Vector2::new(args.number(0)?, args.number(1)?)),
+            "vec3" => FieldKind::Vector3(Vector3::new(
+                args.number(0)?,
+                args.number(1)?,
+                args.number(2)?,
+            )),
+            "vec4" => FieldKind::Vector4(Vector4::new(
+                args.number(0)?,
+                args.number(1)?,
+                args.number(2)?,
+                args.number(3)?,
+            )),
+            "quat" => FieldKind::UnitQuaternion(UnitQuaternion::new_normalize(Quaternion::new(
+                args.number(3)?,
+                args.number(0)?,
+                args.number(1)?,
+                args.number(2)?,
+            ))),
+            "complex" => FieldKind::UnitComplex(UnitComplex::from_complex(Complex::new(
+                args.number(0)?,
+                args.number(1)?,
+            ))),
+            "mat2" => FieldKind::Matrix2(Matrix2::from_column_slice(&args.numbers::<f32>(4)?)),
+            "mat3" => FieldKind::Matrix3(Matrix3::from_column_slice(&args.numbers::<f32>(9)?)),
+            "mat4" => FieldKind::Matrix4(Matrix4::from_column_slice(&args.numbers::<f32>(16)?)),
+            "uuid" => FieldKind::Uuid(args.number(0)?),
+            "str" => FieldKind::Data(args.string(0)?.as_bytes().to_vec()),
+            "data" => FieldKind::Data(
+                base64::decode(args.string(0)?).map_err(|e| args.error(e.to_string()))?,
+            ),
+            "podarray" => {
+                let type_id = args.number(0)?;
+                let element_size = args.number(1)?;
+                let bytes = match args.values.get(2) {
+                    Some(Value::Str(str)) => {
+                        base64::decode(str).map_err(|e| args.error(e.to_string()))?
+                    }
+                    Some(Value::List(items)) => args.pod_bytes(type_id, items)?,
+                    _ => return Err(args.error("expected pod array contents")),
+                };
+                FieldKind::PodArray {
+                    type_id,
+                    element_size,
+                    bytes,
+                }
+            }
+            _ => return Err(self.error(format!("unknown field type {}", type_name))),
+        })
+    }
+}
+
+struct Args {
+    values: Vec<Value>,
+    line: usize,
+}
+
+impl Args {
+    fn error(&self, message: impl AsRef<str>) -> VisitError {
+        error(self.line, message)
+    }
+
+    fn word(&self, index: usize) -> Result<&str, VisitError> {
+        match self.values.get(index) {
+            Some(Value::Word(word)) => Ok(word),
+            _ => Err(self.error(format!("expected value at argument {}", index))),
+        }
+    }
+
+    fn string(&self, index: usize) -> Result<&str, VisitError> {
+        match self.values.get(index) {
+            Some(Value::Str(str)) => Ok(str),
+            _ => Err(self.error(format!("expected string at argument {}", index))),
+        }
+    }
+
+    fn number<T: FromStr>(&self, index: usize) -> Result<T, VisitError> {
+        let word = self.word(index)?;
+        word.parse()
+            .map_err(|_| self.error(format!("invalid value {}", word)))
+    }
+
+    fn numbers<T: FromStr>(&self, count: usize) -> Result<Vec<T>, VisitError> {
+        if self.values.len() != count {
+            return Err(self.error(format!(
+                "expected {} values, got {}",
+                count,
+                self.values.len()
+            )));
+        }
+        (0..count).map(|i| self.number(i)).collect()
+    }
+
+    fn pod_bytes(&self, type_id: u8, items: &[String]) -> Result<Vec<u8>, VisitError> {
+        macro_rules! parse_elements {
+            ($ty:ty) => {{
+                let mut bytes = Vec::with_capacity(items.len() * std::mem::size_of::<$ty>());
+                for item in items {
+                    let value = item
+                        .parse::<$ty>()
+                        .map_err(|_| self.error(format!("invalid value {}", item)))?;
+                    bytes.extend_from_slice(&value.to_le_bytes());
+                }
+                bytes
+            }};
+        }
+
+        Ok(match type_id {
+            0 => parse_elements!(u8),
+            1 => parse_elements!(i8),
+            2 => parse_elements!(u16),
+            3 => parse_elements!(i16),
+            4 => parse_elements!(u32),
+            5 => parse_elements!(i32),
+            6 => parse_elements!(u64),
+            7 => parse_elements!(i64),
+            8 => parse_elements!(f32),
+            9 => parse_elements!(f64),
+            _ => return Err(self.error(format!("unknown pod type {}", type_id))),
+        })
+    }
+}
+
+/// Parses text representation of visitor tree, returns pool of nodes and handle of root node.
+pub(in crate::visitor) fn parse(text: &str) -> Result<(Pool<Node>, Handle<Node>), VisitError> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?.into_iter().peekable(),
+        line: 1,
+    };
+
+    match parser.next()? {
+        Token::Word(magic) if magic == TEXT_MAGIC => (),
+        _ => return Err(VisitError::NotSupportedFormat),
+    }
+    let version: u32 = match parser.next()? {
+        Token::Word(version) => version
+            .parse()
+            .map_err(|_| parser.error("invalid format version"))?,
+        token => return Err(parser.error(format!("expected version, got {:?}", token))),
+    };
+    if version > TEXT_VERSION {
+        return Err(parser.error(format!("unsupported format version {}", version)));
+    }
+
+    let mut nodes = Pool::new();
+    let root = parser.parse_node(&mut nodes)?;
+
+    if let Some((token, line)) = parser.tokens.next() {
+        return Err(error(
+            line,
+            format!("unexpected {:?} after root region", token),
+        ));
+    }
+
+    Ok((nodes, root))
+}
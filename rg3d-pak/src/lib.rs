@@ -0,0 +1,179 @@
+#![warn(missing_docs)]
+
+//! Asset packing tool for rg3d engine.
+//!
+//! Packs assets of a project (textures, models, scenes, shaders, sounds, etc.) into a single
+//! [pak archive](rg3d_core::io::pak). Paths in the archive are relative to the project directory,
+//! so the archive can be mounted at empty mount point of virtual file system and the engine will
+//! load every asset from it without any changes in game code:
+//!
+//! ```no_run
+//! use rg3d_core::io::{pak::PakArchive, vfs::Vfs};
+//!
+//! Vfs::global_mut().mount("", 0, PakArchive::open("data.pak").unwrap());
+//! ```
+
+use rg3d_core::io::pak::{Compression, PakStats, PakWriter};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+/// Extensions of files that are packed by default: textures and their import options, models,
+/// scenes, shaders, sounds and fonts.
+pub const DEFAULT_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "tga", "bmp", "gif", "tif", "tiff", "dds", "options", "fbx", "gltf",
+    "glb", "obj", "mtl", "rgs", "shader", "wav", "ogg", "ttf",
+];
+
+/// Options of packing.
+#[derive(Clone, Debug)]
+pub struct PackOptions {
+    /// Extensions of files (without dot, case-insensitive) that will be packed, every file will
+    /// be packed if the list is empty.
+    pub extensions: Vec<String>,
+    /// Compression method of files.
+    pub compression: Compression,
+}
+
+impl Default for PackOptions {
+    fn default() -> Self {
+        Self {
+            extensions: DEFAULT_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+            compression: Compression::Lz,
+        }
+    }
+}
+
+/// Extension of archives, such files are never packed, because they're usually archives made by
+/// previous runs.
+const ARCHIVE_EXTENSION: &str = "pak";
+
+impl PackOptions {
+    fn accepts(&self, path: &Path) -> bool {
+        if matches!(path.extension(), Some(e) if e.eq_ignore_ascii_case(ARCHIVE_EXTENSION)) {
+            return false;
+        }
+        if self.extensions.is_empty() {
+            return true;
+        }
+        match path.extension() {
+            Some(extension) => {
+                let extension = extension.to_string_lossy();
+                self.extensions
+                    .iter()
+                    .any(|e| e.eq_ignore_ascii_case(&extension))
+            }
+            None => false,
+        }
+    }
+}
+
+/// An error that may occur during packing.
+#[derive(Debug)]
+pub enum PackError {
+    /// An i/o error has occurred.
+    Io(io::Error),
+    /// Unable to walk project directory.
+    Walk(walkdir::Error),
+}
+
+impl From<io::Error> for PackError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<walkdir::Error> for PackError {
+    fn from(e: walkdir::Error) -> Self {
+        Self::Walk(e)
+    }
+}
+
+impl std::fmt::Display for PackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "i/o error: {}", e),
+            Self::Walk(e) => write!(f, "unable to walk directory: {}", e),
+        }
+    }
+}
+
+/// Collects paths of every file in given project directory that should be packed. Paths are
+/// relative to the project directory and sorted, so archives are reproducible. Archives (`.pak`
+/// files) are never collected.
+pub fn collect_files<P: AsRef<Path>>(
+    root: P,
+    options: &PackOptions,
+) -> Result<Vec<PathBuf>, PackError> {
+    let root = root.as_ref();
+    let mut files = Vec::new();
+    for entry in WalkDir::new(root) {
+        let entry = entry?;
+        if entry.file_type().is_file() && options.accepts(entry.path()) {
+            if let Ok(relative) = entry.path().strip_prefix(root) {
+                files.push(relative.to_owned());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Packs every suitable file (see [`PackOptions`]) in given project directory into given
+/// archive writer.
+pub fn pack_directory_into<P, W>(
+    root: P,
+    writer: &mut PakWriter<W>,
+    options: &PackOptions,
+) -> Result<(), PackError>
+where
+    P: AsRef<Path>,
+    W: io::Read + io::Write + io::Seek,
+{
+    let files = collect_files(root.as_ref(), options)?;
+    pack_files(root.as_ref(), &files, writer, options)
+}
+
+fn pack_files<W: io::Read + io::Write + io::Seek>(
+    root: &Path,
+    files: &[PathBuf],
+    writer: &mut PakWriter<W>,
+    options: &PackOptions,
+) -> Result<(), PackError> {
+    for relative in files {
+        let data = std::fs::read(root.join(relative))?;
+        writer.add_file(relative, &data, options.compression)?;
+    }
+    Ok(())
+}
+
+/// Packs every suitable file (see [`PackOptions`]) in given project directory into new archive
+/// at `output` path.
+pub fn pack_directory<P, Q>(
+    root: P,
+    output: Q,
+    options: &PackOptions,
+) -> Result<PakStats, PackError>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    // Collect files before creating the archive, so the archive won't be packed into itself.
+    let mut files = collect_files(root.as_ref(), options)?;
+    // Output could have other extension than `.pak` and already exist.
+    if let Ok(output) = output.as_ref().canonicalize() {
+        files.retain(|relative| {
+            root.as_ref()
+                .join(relative)
+                .canonicalize()
+                .map_or(true, |path| path != output)
+        });
+    }
+    let mut writer = PakWriter::create(output)?;
+    pack_files(root.as_ref(), &files, &mut writer, options)?;
+    let stats = writer.stats();
+    writer.finish()?;
+    Ok(stats)
+}
@@ -0,0 +1,54 @@
+//! Command line interface of the packing tool.
+//!
+//! Usage: `rg3d-pak [--store] [--all] <project-dir> <output.pak>`
+//!
+//! `--store` disables compression, `--all` packs every file instead of known asset types.
+
+use rg3d_core::io::pak::Compression;
+use rg3d_pak::PackOptions;
+use std::process::exit;
+
+fn print_usage() {
+    eprintln!("Usage: rg3d-pak [--store] [--all] <project-dir> <output.pak>");
+    eprintln!();
+    eprintln!("    --store    Store files without compression.");
+    eprintln!("    --all      Pack every file, not only known asset types.");
+}
+
+fn main() {
+    let mut options = PackOptions::default();
+    let mut paths = Vec::new();
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--store" => options.compression = Compression::Stored,
+            "--all" => options.extensions.clear(),
+            "-h" | "--help" => {
+                print_usage();
+                return;
+            }
+            _ => paths.push(arg),
+        }
+    }
+
+    if paths.len() != 2 {
+        print_usage();
+        exit(1);
+    }
+
+    match rg3d_pak::pack_directory(&paths[0], &paths[1], &options) {
+        Ok(stats) => {
+            println!(
+                "Packed {} files ({} duplicates) into {}: {} bytes -> {} bytes",
+                stats.file_count,
+                stats.duplicate_count,
+                paths[1],
+                stats.total_size,
+                stats.stored_size
+            );
+        }
+        Err(e) => {
+            eprintln!("Unable to pack {}: {}", paths[0], e);
+            exit(1);
+        }
+    }
+}
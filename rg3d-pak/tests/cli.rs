@@ -0,0 +1,130 @@
+use rg3d_core::io::{
+    pak::{Compression, PakArchive},
+    vfs::VfsBackend,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+// Creates a project directory with a few assets and returns path to it.
+fn make_project(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("rg3d-pak-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("data/models")).unwrap();
+    fs::write(root.join("data/grass.png"), b"png data".repeat(100)).unwrap();
+    fs::write(root.join("data/models/house.fbx"), b"fbx data").unwrap();
+    fs::write(root.join("data/models/house.gltf"), b"gltf data").unwrap();
+    fs::write(root.join("data/models/copy.glb"), b"fbx data").unwrap();
+    fs::write(root.join("data/models/tree.obj"), b"obj data").unwrap();
+    fs::write(root.join("data/models/tree.mtl"), b"mtl data").unwrap();
+    fs::write(root.join("notes.txt"), b"some notes").unwrap();
+    root
+}
+
+fn run(args: &[&Path]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rg3d-pak"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn entry_paths(archive: &PakArchive) -> Vec<&str> {
+    archive
+        .entries()
+        .iter()
+        .map(|entry| entry.path.as_str())
+        .collect()
+}
+
+#[test]
+fn cli_packs_known_assets() {
+    let root = make_project("default");
+    let output = root.join("data.pak");
+
+    let result = run(&[&root, &output]);
+    assert!(result.status.success());
+    let message = String::from_utf8_lossy(&result.stdout);
+    assert!(message.starts_with("Packed 6 files (1 duplicates)"));
+
+    let archive = PakArchive::open(&output).unwrap();
+    let mut paths = entry_paths(&archive);
+    paths.sort_unstable();
+    assert_eq!(
+        paths,
+        [
+            "data/grass.png",
+            "data/models/copy.glb",
+            "data/models/house.fbx",
+            "data/models/house.gltf",
+            "data/models/tree.mtl",
+            "data/models/tree.obj",
+        ]
+    );
+    assert_eq!(
+        archive.read(Path::new("data/models/copy.glb")).unwrap(),
+        b"fbx data"
+    );
+    assert_eq!(
+        archive.entry("data/grass.png").unwrap().compression,
+        Compression::Lz
+    );
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn cli_options() {
+    let root = make_project("options");
+    let output = root.join("data.pak");
+
+    let result = run(&[Path::new("--store"), Path::new("--all"), &root, &output]);
+    assert!(result.status.success());
+
+    let archive = PakArchive::open(&output).unwrap();
+    assert_eq!(archive.entries().len(), 7);
+    assert_eq!(archive.read(Path::new("notes.txt")).unwrap(), b"some notes");
+    assert!(archive
+        .entries()
+        .iter()
+        .all(|entry| entry.compression == Compression::Stored));
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn cli_errors() {
+    let root = make_project("errors");
+
+    let result = run(&[&root]);
+    assert_eq!(result.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&result.stderr).starts_with("Usage: rg3d-pak"));
+
+    let result = run(&[&root.join("missing"), &root.join("data.pak")]);
+    assert_eq!(result.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&result.stderr).starts_with("Unable to pack"));
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn cli_skips_archives() {
+    let root = make_project("archives");
+    fs::write(root.join("old.pak"), b"old archive").unwrap();
+    let output = root.join("data.bin");
+
+    // Pack twice, so the output of the first run exists in the project directory.
+    for _ in 0..2 {
+        let result = run(&[Path::new("--all"), &root, &output]);
+        assert!(result.status.success());
+    }
+
+    let archive = PakArchive::open(&output).unwrap();
+    let paths = entry_paths(&archive);
+    assert_eq!(paths.len(), 7);
+    assert!(!paths.contains(&"old.pak"));
+    assert!(!paths.contains(&"data.bin"));
+
+    fs::remove_dir_all(root).unwrap();
+}
@@ -64,10 +64,17 @@ pub enum DataSource {
 
 impl DataSource {
     /// Tries to create new `File` data source from given path. May fail if file does not exists.
+    /// If the file is in one of mounted backends of virtual file system, it will be loaded into
+    /// memory entirely and `Memory` data source will be returned.
     pub async fn from_file<P>(path: P) -> Result<Self, FileLoadError>
     where
         P: AsRef<Path>,
     {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(data) = rg3d_core::io::vfs::Vfs::global().read(path.as_ref()) {
+            return Ok(DataSource::Memory(Cursor::new(data?)));
+        }
+
         Ok(DataSource::File {
             path: path.as_ref().to_path_buf(),
 
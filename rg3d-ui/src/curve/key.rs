@@ -1,6 +1,6 @@
 use crate::core::{
     algebra::Vector2,
-    curve::{Curve, CurveKey, CurveKeyKind},
+    curve::{Curve, CurveInfinity, CurveKey, CurveKeyKind},
     uuid::Uuid,
 };
 use std::cmp::Ordering;
@@ -25,6 +25,8 @@ impl From<&CurveKey> for CurveKeyView {
 #[derive(Clone)]
 pub struct KeyContainer {
     keys: Vec<CurveKeyView>,
+    pre_infinity: CurveInfinity,
+    post_infinity: CurveInfinity,
 }
 
 impl From<&Curve> for KeyContainer {
@@ -35,6 +37,8 @@ impl From<&Curve> for KeyContainer {
                 .iter()
                 .map(CurveKeyView::from)
                 .collect::<Vec<_>>(),
+            pre_infinity: curve.pre_infinity(),
+            post_infinity: curve.post_infinity(),
         }
     }
 }
@@ -89,11 +93,14 @@ impl KeyContainer {
     }
 
     pub fn curve(&self) -> Curve {
-        Curve::from(
+        let mut curve = Curve::from(
             self.keys
                 .iter()
                 .map(|k| CurveKey::new(k.position.x, k.position.y, k.kind.clone()))
                 .collect::<Vec<_>>(),
-        )
+        );
+        curve.set_pre_infinity(self.pre_infinity);
+        curve.set_post_infinity(self.post_infinity);
+        curve
     }
 }
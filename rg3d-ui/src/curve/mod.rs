@@ -3,7 +3,7 @@ use crate::{
     core::{
         algebra::{Matrix3, Point2, Vector2, Vector3},
         color::Color,
-        curve::{Curve, CurveKeyKind},
+        curve::{Curve, CurveKey, CurveKeyKind},
         math::{cubicf, inf_sup_cubicf, lerpf, wrap_angle, Rect},
         pool::Handle,
         uuid::Uuid,
@@ -176,6 +176,11 @@ impl<M: MessageData, C: Control<M, C>> Control<M, C> for CurveEditor<M, C> {
                                     if let CurveKeyKind::Cubic {
                                         left_tangent,
                                         right_tangent,
+                                    }
+                                    | CurveKeyKind::Bezier {
+                                        left_tangent,
+                                        right_tangent,
+                                        ..
                                     } = &mut key.kind
                                     {
                                         let mut local_delta = pos - screen_key_pos;
@@ -680,6 +685,11 @@ impl<M: MessageData, C: Control<M, C>> CurveEditor<M, C> {
             if let CurveKeyKind::Cubic {
                 left_tangent,
                 right_tangent,
+            }
+            | CurveKeyKind::Bezier {
+                left_tangent,
+                right_tangent,
+                ..
             } = key.kind
             {
                 let left_handle_pos = self.tangent_screen_position(
@@ -831,19 +841,13 @@ impl<M: MessageData, C: Control<M, C>> CurveEditor<M, C> {
 
             match (&left.kind, &right.kind) {
                 // Constant-to-any is depicted as two straight lines.
-                (CurveKeyKind::Constant, CurveKeyKind::Constant)
-                | (CurveKeyKind::Constant, CurveKeyKind::Linear)
-                | (CurveKeyKind::Constant, CurveKeyKind::Cubic { .. }) => {
+                (CurveKeyKind::Constant, _) => {
                     ctx.push_line(left_pos, Vector2::new(right_pos.x, left_pos.y), 1.0);
                     ctx.push_line(Vector2::new(right_pos.x, left_pos.y), right_pos, 1.0);
                 }
 
                 // Linear-to-any is depicted as a straight line.
-                (CurveKeyKind::Linear, CurveKeyKind::Constant)
-                | (CurveKeyKind::Linear, CurveKeyKind::Linear)
-                | (CurveKeyKind::Linear, CurveKeyKind::Cubic { .. }) => {
-                    ctx.push_line(left_pos, right_pos, 1.0)
-                }
+                (CurveKeyKind::Linear, _) => ctx.push_line(left_pos, right_pos, 1.0),
 
                 // Cubic-to-constant and cubic-to-linear is depicted as Hermite spline with right tangent == 0.0.
                 (
@@ -861,7 +865,7 @@ impl<M: MessageData, C: Control<M, C>> CurveEditor<M, C> {
                     CurveKeyKind::Linear,
                 ) => draw_cubic(left_pos, *left_tangent, right_pos, 0.0, steps, ctx),
 
-                // Cubic-to-cubic is depicted as Hermite spline.
+                // Cubic-to-cubic and cubic-to-bezier is depicted as Hermite spline.
                 (
                     CurveKeyKind::Cubic {
                         right_tangent: left_tangent,
@@ -871,6 +875,16 @@ impl<M: MessageData, C: Control<M, C>> CurveEditor<M, C> {
                         left_tangent: right_tangent,
                         ..
                     },
+                )
+                | (
+                    CurveKeyKind::Cubic {
+                        right_tangent: left_tangent,
+                        ..
+                    },
+                    CurveKeyKind::Bezier {
+                        left_tangent: right_tangent,
+                        ..
+                    },
                 ) => draw_cubic(
                     left_pos,
                     *left_tangent,
@@ -879,6 +893,24 @@ impl<M: MessageData, C: Control<M, C>> CurveEditor<M, C> {
                     steps,
                     ctx,
                 ),
+
+                // Bezier-to-any is sampled in local space, because its shape depends on the span
+                // between keys.
+                (CurveKeyKind::Bezier { .. }, _) => {
+                    let left_key = CurveKey::new(left.position.x, left.position.y, left.kind.clone());
+                    let right_key =
+                        CurveKey::new(right.position.x, right.position.y, right.kind.clone());
+                    let mut prev = left_pos;
+                    for i in 0..steps {
+                        let t = i as f32 / (steps - 1) as f32;
+                        let pt = self.point_to_screen_space(Vector2::new(
+                            lerpf(left.position.x, right.position.x, t),
+                            left_key.interpolate(&right_key, t),
+                        ));
+                        ctx.push_line(prev, pt, 1.0);
+                        prev = pt;
+                    }
+                }
             }
         }
         ctx.commit(screen_bounds, self.foreground(), CommandTexture::None, None);
@@ -919,13 +951,18 @@ impl<M: MessageData, C: Control<M, C>> CurveEditor<M, C> {
             if selected {
                 let (show_left, show_right) = match keys_to_draw.get(i.wrapping_sub(1)) {
                     Some(left) => match (&left.kind, &key.kind) {
-                        (CurveKeyKind::Cubic { .. }, CurveKeyKind::Cubic { .. }) => (true, true),
-                        (CurveKeyKind::Linear, CurveKeyKind::Cubic { .. })
-                        | (CurveKeyKind::Constant, CurveKeyKind::Cubic { .. }) => (false, true),
+                        (
+                            CurveKeyKind::Cubic { .. } | CurveKeyKind::Bezier { .. },
+                            CurveKeyKind::Cubic { .. } | CurveKeyKind::Bezier { .. },
+                        ) => (true, true),
+                        (
+                            CurveKeyKind::Linear | CurveKeyKind::Constant,
+                            CurveKeyKind::Cubic { .. } | CurveKeyKind::Bezier { .. },
+                        ) => (false, true),
                         _ => (false, false),
                     },
                     None => match key.kind {
-                        CurveKeyKind::Cubic { .. } => (false, true),
+                        CurveKeyKind::Cubic { .. } | CurveKeyKind::Bezier { .. } => (false, true),
                         _ => (false, false),
                     },
                 };
@@ -933,6 +970,11 @@ impl<M: MessageData, C: Control<M, C>> CurveEditor<M, C> {
                 if let CurveKeyKind::Cubic {
                     left_tangent,
                     right_tangent,
+                }
+                | CurveKeyKind::Bezier {
+                    left_tangent,
+                    right_tangent,
+                    ..
                 } = key.kind
                 {
                     if show_left {
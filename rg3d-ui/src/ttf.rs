@@ -1,6 +1,6 @@
 use crate::core::algebra::Vector2;
 use crate::core::io;
-use crate::{core::rectpack::RectPacker, draw::SharedTexture};
+use crate::{core::rectpack::maxrects::MaxRectsPacker, draw::SharedTexture};
 use std::{
     collections::HashMap,
     fmt::{Debug, Formatter},
@@ -177,40 +177,55 @@ impl Font {
     fn pack(&mut self) {
         let border = 2;
         self.atlas_size = self.compute_atlas_size(border);
+
+        // Estimated size could be insufficient, grow the atlas until every glyph fits.
+        let placements = loop {
+            let size = self.atlas_size as u32;
+            let mut rect_packer = MaxRectsPacker::new(size, size);
+            let placements = self
+                .glyphs
+                .iter()
+                .map(|glyph| {
+                    let handle = rect_packer.allocate(
+                        (glyph.bitmap_width + border) as u32,
+                        (glyph.bitmap_height + border) as u32,
+                    )?;
+                    rect_packer.get(handle).map(|packed| packed.bounds)
+                })
+                .collect::<Option<Vec<_>>>();
+            match placements {
+                Some(placements) => break placements,
+                None => self.atlas_size += self.atlas_size / 4 + 1,
+            }
+        };
+
         self.atlas = vec![0; (self.atlas_size * self.atlas_size) as usize];
         let k = 1.0 / self.atlas_size as f32;
-        let mut rect_packer = RectPacker::new(self.atlas_size, self.atlas_size);
-        for glyph in self.glyphs.iter_mut() {
-            if let Some(bounds) =
-                rect_packer.find_free(glyph.bitmap_width + border, glyph.bitmap_height + border)
-            {
-                let bw = (bounds.w() - border) as usize;
-                let bh = (bounds.h() - border) as usize;
-                let bx = (bounds.x() + border / 2) as usize;
-                let by = (bounds.y() + border / 2) as usize;
-
-                let tw = bw as f32 * k;
-                let th = bh as f32 * k;
-                let tx = bx as f32 * k;
-                let ty = by as f32 * k;
-
-                glyph.tex_coords[0] = Vector2::new(tx, ty);
-                glyph.tex_coords[1] = Vector2::new(tx + tw, ty);
-                glyph.tex_coords[2] = Vector2::new(tx + tw, ty + th);
-                glyph.tex_coords[3] = Vector2::new(tx, ty + th);
-
-                let row_end = by + bh;
-                let col_end = bx + bw;
-
-                // Copy glyph pixels to atlas pixels
-                for (src_row, row) in (by..row_end).enumerate() {
-                    for (src_col, col) in (bx..col_end).enumerate() {
-                        self.atlas[row * self.atlas_size + col] =
-                            glyph.pixels[src_row * bw + src_col];
-                    }
+        for (glyph, bounds) in self.glyphs.iter_mut().zip(placements) {
+            let bw = bounds.size.x as usize - border;
+            let bh = bounds.size.y as usize - border;
+            let bx = bounds.position.x as usize + border / 2;
+            let by = bounds.position.y as usize + border / 2;
+
+            let tw = bw as f32 * k;
+            let th = bh as f32 * k;
+            let tx = bx as f32 * k;
+            let ty = by as f32 * k;
+
+            glyph.tex_coords[0] = Vector2::new(tx, ty);
+            glyph.tex_coords[1] = Vector2::new(tx + tw, ty);
+            glyph.tex_coords[2] = Vector2::new(tx + tw, ty + th);
+            glyph.tex_coords[3] = Vector2::new(tx, ty + th);
+
+            let row_end = by + bh;
+            let col_end = bx + bw;
+
+            // Copy glyph pixels to atlas pixels
+            for (src_row, row) in (by..row_end).enumerate() {
+                for (src_col, col) in (bx..col_end).enumerate() {
+                    self.atlas[row * self.atlas_size + col] =
+                        glyph.pixels[src_row * bw + src_col];
                 }
-            } else {
-                println!("Insufficient atlas size!");
             }
         }
     }
@@ -68,6 +68,20 @@ impl Visit for KeyFrame {
     }
 }
 
+/// Key frame of weights of morph targets of a mesh, see
+/// [`crate::scene::mesh::Mesh::set_morph_weights`].
+#[derive(Clone, Debug, Default)]
+pub struct WeightKeyFrame {
+    pub time: f32,
+    pub weights: Vec<f32>,
+}
+
+impl WeightKeyFrame {
+    pub fn new(time: f32, weights: Vec<f32>) -> Self {
+        Self { time, weights }
+    }
+}
+
 #[derive(Default, Copy, Clone, Debug)]
 pub struct PoseEvaluationFlags {
     pub ignore_position: bool,
@@ -92,6 +106,7 @@ pub struct Track {
     // Frames are not serialized, because it makes no sense to store them in save file,
     // they will be taken from resource on Resolve stage.
     frames: Vec<KeyFrame>,
+    weight_frames: Vec<WeightKeyFrame>,
     enabled: bool,
     max_time: f32,
     node: Handle<Node>,
@@ -102,6 +117,7 @@ impl Clone for Track {
     fn clone(&self) -> Self {
         Self {
             frames: self.frames.clone(),
+            weight_frames: self.weight_frames.clone(),
             enabled: self.enabled,
             max_time: self.max_time,
             node: self.node,
@@ -114,6 +130,7 @@ impl Default for Track {
     fn default() -> Self {
         Self {
             frames: Vec::new(),
+            weight_frames: Vec::new(),
             enabled: true,
             max_time: 0.0,
             node: Default::default(),
@@ -179,9 +196,14 @@ impl Track {
         self.frames = key_frames.to_vec();
         self.max_time = 0.0;
 
-        for key_frame in self.frames.iter() {
-            if key_frame.time > self.max_time {
-                self.max_time = key_frame.time;
+        for time in self
+            .frames
+            .iter()
+            .map(|k| k.time)
+            .chain(self.weight_frames.iter().map(|k| k.time))
+        {
+            if time > self.max_time {
+                self.max_time = time;
             }
         }
     }
@@ -190,17 +212,67 @@ impl Track {
         &self.frames
     }
 
+    /// Adds key frame of weights of morph targets, weights are interpolated separately from
+    /// transform key frames.
+    pub fn add_weight_key_frame(&mut self, key_frame: WeightKeyFrame) {
+        let index = self
+            .weight_frames
+            .iter()
+            .position(|other| key_frame.time < other.time)
+            .unwrap_or(self.weight_frames.len());
+
+        if key_frame.time > self.max_time {
+            self.max_time = key_frame.time;
+        }
+
+        self.weight_frames.insert(index, key_frame);
+    }
+
+    pub fn set_weight_key_frames(&mut self, key_frames: &[WeightKeyFrame]) {
+        self.weight_frames = key_frames.to_vec();
+
+        for key_frame in self.weight_frames.iter() {
+            if key_frame.time > self.max_time {
+                self.max_time = key_frame.time;
+            }
+        }
+    }
+
+    pub fn get_weight_key_frames(&self) -> &[WeightKeyFrame] {
+        &self.weight_frames
+    }
+
+    fn get_weights(&self, time: f32) -> Vec<f32> {
+        match self.weight_frames.iter().position(|k| k.time >= time) {
+            None => self
+                .weight_frames
+                .last()
+                .map(|k| k.weights.clone())
+                .unwrap_or_default(),
+            Some(0) => self.weight_frames[0].weights.clone(),
+            Some(right_index) => {
+                let left = &self.weight_frames[right_index - 1];
+                let right = &self.weight_frames[right_index];
+                let interpolator = (time - left.time) / (right.time - left.time);
+                lerp_weights(&left.weights, &right.weights, interpolator)
+            }
+        }
+    }
+
     pub fn get_local_pose(&self, mut time: f32) -> Option<LocalPose> {
         if self.frames.is_empty() {
             return None;
         }
 
+        let weights = self.get_weights(time);
+
         if time >= self.max_time {
             return self.frames.last().map(|k| LocalPose {
                 node: self.node,
                 position: k.position,
                 scale: k.scale,
                 rotation: k.rotation,
+                weights,
             });
         }
 
@@ -220,6 +292,7 @@ impl Track {
                 position: k.position,
                 scale: k.scale,
                 rotation: k.rotation,
+                weights,
             })
         } else {
             let left = &self.frames[right_index - 1];
@@ -243,6 +316,7 @@ impl Track {
                 } else {
                     left.rotation.nlerp(&right.rotation, interpolator)
                 },
+                weights,
             })
         }
     }
@@ -256,6 +330,17 @@ impl Track {
     }
 }
 
+/// Interpolates weights of morph targets, missing weights are treated as zero.
+fn lerp_weights(left: &[f32], right: &[f32], t: f32) -> Vec<f32> {
+    (0..left.len().max(right.len()))
+        .map(|i| {
+            let a = left.get(i).cloned().unwrap_or_default();
+            let b = right.get(i).cloned().unwrap_or_default();
+            a + (b - a) * t
+        })
+        .collect()
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct AnimationEvent {
     pub signal_id: u64,
@@ -339,6 +424,7 @@ pub struct LocalPose {
     position: Vector3<f32>,
     scale: Vector3<f32>,
     rotation: UnitQuaternion<f32>,
+    weights: Vec<f32>,
 }
 
 impl Default for LocalPose {
@@ -348,6 +434,7 @@ impl Default for LocalPose {
             position: Vector3::default(),
             scale: Vector3::new(1.0, 1.0, 1.0),
             rotation: UnitQuaternion::identity(),
+            weights: Default::default(),
         }
     }
 }
@@ -359,6 +446,7 @@ impl LocalPose {
             position: self.position.scale(weight),
             rotation: self.rotation.nlerp(&self.rotation, weight),
             scale: self.scale.scale(weight),
+            weights: self.weights.iter().map(|w| w * weight).collect(),
         }
     }
 
@@ -366,6 +454,12 @@ impl LocalPose {
         self.position += other.position.scale(weight);
         self.rotation = self.rotation.nlerp(&other.rotation, weight);
         self.scale += other.scale.scale(weight);
+        if self.weights.len() < other.weights.len() {
+            self.weights.resize(other.weights.len(), 0.0);
+        }
+        for (w, other_w) in self.weights.iter_mut().zip(other.weights.iter()) {
+            *w += other_w * weight;
+        }
     }
 
     pub fn position(&self) -> Vector3<f32> {
@@ -379,6 +473,11 @@ impl LocalPose {
     pub fn rotation(&self) -> UnitQuaternion<f32> {
         self.rotation
     }
+
+    /// Returns weights of morph targets, empty if the pose does not affect them.
+    pub fn weights(&self) -> &[f32] {
+        &self.weights
+    }
 }
 
 #[derive(Default, Debug)]
@@ -419,11 +518,16 @@ impl AnimationPose {
             if node.is_none() {
                 Log::writeln(MessageKind::Error, "Invalid node handle found for animation pose, most likely it means that animation retargeting failed!".to_owned());
             } else {
-                graph[*node]
-                    .local_transform_mut()
+                let node = &mut graph[*node];
+                node.local_transform_mut()
                     .set_position(local_pose.position)
                     .set_rotation(local_pose.rotation)
                     .set_scale(local_pose.scale);
+                if !local_pose.weights.is_empty() {
+                    if let Node::Mesh(mesh) = node {
+                        mesh.set_morph_weights(&local_pose.weights);
+                    }
+                }
             }
         }
     }
@@ -575,6 +679,17 @@ impl Animation {
         self.tracks.retain(filter)
     }
 
+    /// Replaces handles of animated nodes using given old-to-new mapping, handles that are not
+    /// in the mapping are left as is. Current pose is reset, because it refers to old handles.
+    pub fn remap_handles(&mut self, mapping: &HashMap<Handle<Node>, Handle<Node>>) {
+        for track in self.tracks.iter_mut() {
+            if let Some(&new_node) = mapping.get(&track.node) {
+                track.node = new_node;
+            }
+        }
+        self.pose.reset();
+    }
+
     pub fn add_signal(&mut self, signal: AnimationSignal) -> &mut Self {
         self.signals.push(signal);
         self
@@ -670,6 +785,7 @@ impl Animation {
                                 == data.get_scene().graph[ref_track.get_node()].name()
                             {
                                 track.set_key_frames(ref_track.get_key_frames());
+                                track.set_weight_key_frames(ref_track.get_weight_key_frames());
                                 found = true;
                                 break;
                             }
@@ -830,6 +946,13 @@ impl AnimationContainer {
         self.pool.retain(pred)
     }
 
+    /// Remaps handles of animated nodes in every animation, see [`Animation::remap_handles`].
+    pub fn remap_handles(&mut self, mapping: &HashMap<Handle<Node>, Handle<Node>>) {
+        for animation in self.pool.iter_mut() {
+            animation.remap_handles(mapping);
+        }
+    }
+
     pub fn resolve(&mut self, graph: &Graph) {
         Log::writeln(
             MessageKind::Information,
@@ -893,3 +1016,65 @@ impl IndexMut<Handle<Animation>> for AnimationContainer {
         &mut self.pool[index]
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        animation::{AnimationPose, KeyFrame, Track, WeightKeyFrame},
+        core::{
+            algebra::{UnitQuaternion, Vector3},
+            pool::Handle,
+        },
+    };
+
+    fn track_with_weights(weight_frames: &[(f32, Vec<f32>)]) -> Track {
+        let mut track = Track::new();
+        track.add_key_frame(KeyFrame::new(
+            0.0,
+            Vector3::default(),
+            Vector3::new(1.0, 1.0, 1.0),
+            UnitQuaternion::identity(),
+        ));
+        for (time, weights) in weight_frames {
+            track.add_weight_key_frame(WeightKeyFrame::new(*time, weights.clone()));
+        }
+        track
+    }
+
+    #[test]
+    fn test_morph_weights_interpolation() {
+        let track = track_with_weights(&[(2.0, vec![1.0]), (0.0, vec![0.0, 1.0])]);
+        assert_eq!(track.get_weight_key_frames()[0].time, 0.0);
+
+        assert_eq!(track.get_local_pose(0.0).unwrap().weights(), &[0.0, 1.0]);
+        // Missing weights are treated as zero.
+        assert_eq!(track.get_local_pose(0.5).unwrap().weights(), &[0.25, 0.75]);
+        assert_eq!(track.get_local_pose(3.0).unwrap().weights(), &[1.0]);
+
+        let mut track = Track::new();
+        track.add_key_frame(KeyFrame::default());
+        assert!(track.get_local_pose(0.0).unwrap().weights().is_empty());
+    }
+
+    #[test]
+    fn test_morph_weights_blending() {
+        let node = Handle::new(1, 1);
+
+        let mut first = track_with_weights(&[(0.0, vec![1.0, 0.0])]);
+        first.set_node(node);
+
+        let mut second = track_with_weights(&[(0.0, vec![0.0, 1.0, 0.5])]);
+        second.set_node(node);
+
+        let mut first_pose = AnimationPose::default();
+        first_pose.add_local_pose(first.get_local_pose(0.0).unwrap());
+        let mut second_pose = AnimationPose::default();
+        second_pose.add_local_pose(second.get_local_pose(0.0).unwrap());
+
+        // The same way as blend nodes of animation machine do.
+        let mut pose = AnimationPose::default();
+        pose.blend_with(&first_pose, 0.25);
+        pose.blend_with(&second_pose, 0.75);
+        assert_eq!(pose.local_poses[&node].weights(), &[0.25, 0.75, 0.375]);
+    }
+}
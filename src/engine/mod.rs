@@ -12,6 +12,7 @@ use crate::{
         algebra::Vector2,
         instant,
         pool::Handle,
+        profiler,
         visitor::{Visit, VisitResult, Visitor},
     },
     engine::{error::EngineError, resource_manager::ResourceManager},
@@ -197,6 +198,8 @@ impl<M: MessageData, C: Control<M, C>> Engine<M, C> {
             });
 
             scene.update(frame_size, dt);
+
+            profiler::record_counters(&scene.performance_statistics);
         }
 
         for scene in self.scenes2d.iter_mut().filter(|s| s.enabled) {
@@ -209,6 +212,8 @@ impl<M: MessageData, C: Control<M, C>> Engine<M, C> {
             });
 
             scene.update(render_target_size, dt);
+
+            profiler::record_counters(&scene.performance_statistics);
         }
 
         let time = instant::Instant::now();
@@ -223,22 +228,23 @@ impl<M: MessageData, C: Control<M, C>> Engine<M, C> {
         self.user_interface.draw();
 
         #[cfg(not(target_arch = "wasm32"))]
-        {
-            self.renderer.render_and_swap_buffers(
-                &self.scenes,
-                self.user_interface.get_drawing_context(),
-                &self.scenes2d,
-                &self.context,
-            )
-        }
+        let result = self.renderer.render_and_swap_buffers(
+            &self.scenes,
+            self.user_interface.get_drawing_context(),
+            &self.scenes2d,
+            &self.context,
+        );
         #[cfg(target_arch = "wasm32")]
-        {
-            self.renderer.render_and_swap_buffers(
-                &self.scenes,
-                &self.user_interface.get_drawing_context(),
-                &self.scenes2d,
-            )
-        }
+        let result = self.renderer.render_and_swap_buffers(
+            &self.scenes,
+            &self.user_interface.get_drawing_context(),
+            &self.scenes2d,
+        );
+
+        profiler::record_counters(&self.renderer.get_statistics());
+        profiler::end_frame();
+
+        result
     }
 }
 
@@ -410,6 +416,21 @@ impl<N> PhysicsBinder<N> {
         });
         self.forward_map.retain(f);
     }
+
+    /// Replaces handles of bound nodes using given old-to-new mapping, handles that are not in
+    /// the mapping are left as is.
+    pub fn remap_handles(&mut self, mapping: &HashMap<Handle<N>, Handle<N>>) {
+        self.forward_map = self
+            .forward_map
+            .drain()
+            .map(|(node, body)| (mapping.get(&node).cloned().unwrap_or(node), body))
+            .collect();
+        for node in self.backward_map.values_mut() {
+            if let Some(&new_node) = mapping.get(node) {
+                *node = new_node;
+            }
+        }
+    }
 }
 
 impl<N> Visit for PhysicsBinder<N> {
@@ -67,7 +67,7 @@ impl BatchStorage {
         for (handle, node) in graph.pair_iter() {
             match node {
                 Node::Mesh(mesh) => {
-                    for surface in mesh.surfaces().iter() {
+                    for (surface_index, surface) in mesh.surfaces().iter().enumerate() {
                         let is_skinned = !surface.bones.is_empty();
 
                         let world = if is_skinned {
@@ -76,8 +76,15 @@ impl BatchStorage {
                             mesh.global_transform()
                         };
 
-                        let data = surface.data();
-                        let key = surface.batch_id();
+                        // Deformed data is unique for each mesh, so it cannot be shared with
+                        // other instances.
+                        let (data, key) = match mesh.morphed_data(surface_index) {
+                            Some(data) => {
+                                let key = &*data as *const _ as u64;
+                                (data, key)
+                            }
+                            None => (surface.data(), surface.batch_id()),
+                        };
 
                         let batch = if let Some(&batch_index) = self.batch_map.get(&key) {
                             self.batches.get_mut(batch_index).unwrap()
@@ -42,6 +42,7 @@ use crate::{
         instant,
         math::Rect,
         pool::Handle,
+        profiler::CounterSource,
         scope_profile,
     },
     gui::{draw::DrawingContext, message::MessageData, Control, UserInterface},
@@ -132,6 +133,37 @@ impl Display for Statistics {
     }
 }
 
+impl CounterSource for Statistics {
+    fn for_each_counter(&self, func: &mut dyn FnMut(&'static str, f64)) {
+        func("Draw Calls", self.geometry.draw_calls as f64);
+        func("Triangles Rendered", self.geometry.triangles_rendered as f64);
+        func(
+            "Lights Rendered",
+            (self.lighting.point_lights_rendered
+                + self.lighting.spot_lights_rendered
+                + self.lighting.directional_lights_rendered) as f64,
+        );
+        func(
+            "Shadow Maps Rendered",
+            (self.lighting.point_shadow_maps_rendered + self.lighting.spot_shadow_maps_rendered)
+                as f64,
+        );
+        func(
+            "Texture Binding Changes",
+            self.pipeline.texture_binding_changes as f64,
+        );
+        func(
+            "Program Binding Changes",
+            self.pipeline.program_binding_changes as f64,
+        );
+        func(
+            "Framebuffer Binding Changes",
+            self.pipeline.framebuffer_binding_changes as f64,
+        );
+        func("Pure Frame Time (ms)", self.pure_frame_time as f64 * 1000.0);
+    }
+}
+
 /// GPU statistics for single frame.
 #[derive(Copy, Clone)]
 pub struct RenderPassStatistics {
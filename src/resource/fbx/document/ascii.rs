@@ -50,8 +50,9 @@ where
             continue;
         }
 
-        // Parse string
-        let mut read_value = false;
+        // Parse string. Lines without a colon are continuation of values of previous node,
+        // for example long arrays or base64-encoded embedded media split across lines.
+        let mut read_value = !buffer.contains(&b':');
         name.clear();
         for (i, symbol) in buffer.iter().enumerate() {
             let symbol = *symbol;
@@ -109,3 +110,28 @@ where
         root: root_handle,
     })
 }
+
+#[cfg(test)]
+mod test {
+    use crate::resource::fbx::document::FbxDocument;
+
+    #[test]
+    fn test_line_without_colon_is_continuation() {
+        let document = FbxDocument::from_bytes(
+            b"Node: 1,2,\n  3,4\nContent: ,\n  \"aGVs\",\n  \"bG8=\"\n".to_vec(),
+        )
+        .unwrap();
+        let values = |name| {
+            document
+                .nodes()
+                .get_by_name(document.root(), name)
+                .unwrap()
+                .attributes()
+                .iter()
+                .map(|attribute| attribute.as_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(values("Node"), ["1", "2", "3", "4"]);
+        assert_eq!(values("Content"), ["", "aGVs", "bG8="]);
+    }
+}
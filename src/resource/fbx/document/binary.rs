@@ -6,7 +6,7 @@ use crate::{
         error::FbxError,
     },
 };
-use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::io::{Cursor, ErrorKind, Read, Seek, SeekFrom};
 
 fn read_attribute<R>(type_code: u8, file: &mut R) -> Result<FbxAttribute, FbxError>
 where
@@ -148,9 +148,18 @@ where
                 .attributes
                 .push(read_string(file)?),
             b'R' => {
-                // Ignore Raw data
-                let length = i64::from(file.read_u32::<LittleEndian>()?);
-                file.seek(SeekFrom::Current(length))?;
+                // Raw data is used to store embedded media (textures, etc.)
+                let length = file.read_u32::<LittleEndian>()? as u64;
+                // Length is not trusted, so the data is read in chunks instead of allocating
+                // whole buffer at once.
+                let mut data = Vec::new();
+                file.by_ref().take(length).read_to_end(&mut data)?;
+                if data.len() as u64 != length {
+                    return Err(FbxError::Io(ErrorKind::UnexpectedEof.into()));
+                }
+                pool.borrow_mut(node_handle)
+                    .attributes
+                    .push(FbxAttribute::RawData(data));
             }
             _ => (),
         }
@@ -116,13 +116,16 @@ pub struct FbxDocument {
 
 fn is_binary(data: &[u8]) -> bool {
     let fbx_magic = b"Kaydara FBX Binary";
-    &data[0..18] == fbx_magic
+    data.starts_with(fbx_magic)
 }
 
 impl FbxDocument {
     pub async fn new<P: AsRef<Path>>(path: P) -> Result<FbxDocument, FbxError> {
-        let data = io::load_file(path).await?;
+        Self::from_bytes(io::load_file(path).await?)
+    }
 
+    /// Reads document from content of binary or ASCII FBX file.
+    pub(in crate::resource::fbx) fn from_bytes(data: Vec<u8>) -> Result<FbxDocument, FbxError> {
         let is_bin = is_binary(&data);
 
         let mut reader = Cursor::new(data);
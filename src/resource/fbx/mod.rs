@@ -5,6 +5,26 @@
 //!
 //! Normally you should never use methods from this module directly, use resource manager to load
 //! models and create their instances.
+//!
+//! # Layers
+//!
+//! Besides first set of texture coordinates, loader imports second set of texture coordinates
+//! which is usually used for lightmaps, it is put in [`VertexAttributeUsage::TexCoord1`]. Vertex
+//! colors are put in [`VertexAttributeUsage::TexCoord2`] as RGBA vector of four `f32`. Both
+//! attributes are added to vertex buffer only if a geometry has them.
+//!
+//! # Embedded media
+//!
+//! Textures embedded in FBX are extracted directly into texture resources, so there is no need
+//! to have separate image files for them. Each embedded texture is created only once per model
+//! and shared across all materials that use it.
+//!
+//! # Blend shapes
+//!
+//! Each channel of blend shapes of a model becomes a morph target of the mesh with the same index
+//! in every surface of it (see [`crate::scene::mesh::surface::MorphTarget`]). Only the final shape
+//! of a channel is used, in-between shapes are ignored. Weights of morph targets are initialized
+//! from `DeformPercent` of channels and are animated if there are respective animation curves.
 
 mod document;
 pub mod error;
@@ -12,30 +32,36 @@ mod scene;
 
 use crate::material::shader::SamplerFallback;
 use crate::{
-    animation::{Animation, AnimationContainer, KeyFrame, Track},
+    animation::{Animation, AnimationContainer, KeyFrame, Track, WeightKeyFrame},
     core::{
         algebra::{Matrix4, Point3, UnitQuaternion, Vector2, Vector3, Vector4},
         instant::Instant,
-        io,
         math::{self, triangulator::triangulate, RotationOrder},
         pool::Handle,
     },
     engine::resource_manager::{MaterialSearchOptions, ResourceManager},
     material::PropertyValue,
-    resource::fbx::{
-        document::FbxDocument,
-        error::FbxError,
-        scene::{
-            animation::FbxAnimationCurveNodeType, geometry::FbxGeometry, model::FbxModel,
-            FbxComponent, FbxMapping, FbxScene,
+    resource::{
+        fbx::{
+            document::FbxDocument,
+            error::FbxError,
+            scene::{
+                animation::FbxAnimationCurveNodeType, blend_shape::FbxBlendShapeChannel,
+                geometry::FbxGeometry, model::FbxModel, FbxComponent, FbxMapping, FbxScene,
+            },
         },
+        find_texture,
+        texture::{CompressionOptions, Texture},
     },
     scene::{
         base::BaseBuilder,
         graph::Graph,
         mesh::{
-            buffer::{VertexAttributeUsage, VertexWriteTrait},
-            surface::{Surface, SurfaceData, VertexWeightSet},
+            buffer::{
+                VertexAttributeDataType, VertexAttributeDescriptor, VertexAttributeUsage,
+                VertexWriteTrait,
+            },
+            surface::{MorphTarget, Surface, SurfaceData, VertexWeightSet},
             vertex::{AnimatedVertex, StaticVertex},
             MeshBuilder,
         },
@@ -44,17 +70,18 @@ use crate::{
         Scene,
     },
     utils::{
+        hash_as_bytes,
         log::{Log, MessageKind},
-        raw_mesh::RawMeshBuilder,
+        raw_mesh::{RawMesh, RawMeshBuilder},
     },
 };
 use std::{
     cmp::Ordering,
     collections::{HashMap, HashSet},
+    hash::{Hash, Hasher},
     path::Path,
     sync::{Arc, RwLock},
 };
-use walkdir::WalkDir;
 
 /// Input angles in degrees
 fn quat_from_euler(euler: Vector3<f32>) -> UnitQuaternion<f32> {
@@ -140,14 +167,64 @@ fn prepare_next_face(
 struct UnpackedVertex {
     // Index of surface this vertex belongs to.
     surface: usize,
+    // Index of control point of geometry this vertex was made of, zero if the geometry has no
+    // blend shapes.
+    control_point: usize,
     position: Vector3<f32>,
     normal: Vector3<f32>,
     tangent: Vector3<f32>,
     uv: Vector2<f32>,
+    second_uv: Vector2<f32>,
+    color: Vector4<f32>,
     // Set of weights for skinning.
     weights: Option<VertexWeightSet>,
 }
 
+/// Vertex with attributes that are optional in FBX, they're not part of any engine vertex format
+/// and added to vertex buffer only when geometry has them.
+#[derive(Copy, Clone)]
+struct FbxVertex<T> {
+    vertex: T,
+    second_uv: Vector2<f32>,
+    color: Vector4<f32>,
+    // Vertices of different control points must not be merged, because they could be
+    // deformed differently by blend shapes. Zero for geometries without blend shapes, so
+    // vertices are merged as usual.
+    control_point: usize,
+}
+
+impl<T: PartialEq> PartialEq for FbxVertex<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.vertex == other.vertex
+            && self.second_uv == other.second_uv
+            && self.color == other.color
+            && self.control_point == other.control_point
+    }
+}
+
+impl<T: Hash> Hash for FbxVertex<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.vertex.hash(state);
+        hash_as_bytes(&self.second_uv, state);
+        hash_as_bytes(&self.color, state);
+        self.control_point.hash(state);
+    }
+}
+
+impl<T> From<UnpackedVertex> for FbxVertex<T>
+where
+    UnpackedVertex: Into<T>,
+{
+    fn from(vertex: UnpackedVertex) -> Self {
+        Self {
+            second_uv: vertex.second_uv,
+            color: vertex.color,
+            control_point: vertex.control_point,
+            vertex: vertex.into(),
+        }
+    }
+}
+
 impl Into<AnimatedVertex> for UnpackedVertex {
     fn into(self) -> AnimatedVertex {
         AnimatedVertex {
@@ -199,6 +276,16 @@ fn convert_vertex(
         None => Vector2::default(),
     };
 
+    let second_uv = match geom.second_uvs.as_ref() {
+        Some(uvs) => *uvs.get(index, index_in_polygon)?,
+        None => Vector2::default(),
+    };
+
+    let color = match geom.colors.as_ref() {
+        Some(colors) => *colors.get(index, index_in_polygon)?,
+        None => Vector4::new(1.0, 1.0, 1.0, 1.0),
+    };
+
     let material = match geom.materials.as_ref() {
         Some(materials) => *materials.get(material_index, index_in_polygon)?,
         None => 0,
@@ -211,7 +298,14 @@ fn convert_vertex(
         normal: geometric_transform.transform_vector(&normal),
         tangent: geometric_transform.transform_vector(&tangent),
         uv: Vector2::new(uv.x, 1.0 - uv.y), // Invert Y because OpenGL has origin at left *bottom* corner.
+        second_uv: Vector2::new(second_uv.x, 1.0 - second_uv.y),
+        color,
         surface: material as usize,
+        control_point: if geom.blend_shapes.is_empty() {
+            0
+        } else {
+            index
+        },
         weights: if geom.deformers.is_empty() {
             None
         } else {
@@ -222,19 +316,202 @@ fn convert_vertex(
 
 #[derive(Clone)]
 enum FbxMeshBuilder {
-    Static(RawMeshBuilder<StaticVertex>),
-    Animated(RawMeshBuilder<AnimatedVertex>),
+    Static(RawMeshBuilder<FbxVertex<StaticVertex>>),
+    Animated(RawMeshBuilder<FbxVertex<AnimatedVertex>>),
+}
+
+/// Offsets of control points of a geometry made by a blend shape channel. Offsets are empty if
+/// the channel belongs to other geometry of a model.
+struct FbxMorphOffsets<'a> {
+    name: &'a str,
+    positions: Vec<Vector3<f32>>,
+    normals: Vec<Vector3<f32>>,
+}
+
+impl<'a> FbxMorphOffsets<'a> {
+    fn read(
+        fbx_scene: &FbxScene,
+        channel: &'a FbxBlendShapeChannel,
+        geom: &FbxGeometry,
+        geometric_transform: &Matrix4<f32>,
+    ) -> Result<Self, FbxError> {
+        let mut offsets = Self {
+            name: &channel.name,
+            positions: Default::default(),
+            normals: Default::default(),
+        };
+
+        // In-between shapes are not supported, use the final shape only.
+        if let Some(&shape_handle) = channel.shapes.last() {
+            let shape = fbx_scene.get(shape_handle).as_shape()?;
+            offsets.positions = vec![Vector3::default(); geom.vertices.len()];
+            if !shape.normals.is_empty() {
+                offsets.normals = vec![Vector3::default(); geom.vertices.len()];
+            }
+            for (i, &index) in shape.indices.iter().enumerate() {
+                let control_point = index as usize;
+                *offsets
+                    .positions
+                    .get_mut(control_point)
+                    .ok_or(FbxError::IndexOutOfBounds)? =
+                    geometric_transform.transform_vector(&shape.vertices[i]);
+                if let Some(normal) = shape.normals.get(i) {
+                    offsets.normals[control_point] = geometric_transform.transform_vector(normal);
+                }
+            }
+        }
+
+        Ok(offsets)
+    }
+
+    fn gather(offsets: &[Vector3<f32>], control_points: &[usize]) -> Vec<Vector3<f32>> {
+        if offsets.is_empty() {
+            Default::default()
+        } else {
+            control_points.iter().map(|&i| offsets[i]).collect()
+        }
+    }
+}
+
+/// Collects blend shape channels of every geometry of the model together with handles of
+/// geometries. Index of a channel is the index of respective morph target of a mesh.
+fn collect_blend_shape_channels<'a>(
+    fbx_scene: &'a FbxScene,
+    model: &FbxModel,
+) -> Result<Vec<(Handle<FbxComponent>, &'a FbxBlendShapeChannel)>, FbxError> {
+    let mut channels = Vec::new();
+    for &geom_handle in model.geoms.iter() {
+        let geom = fbx_scene.get(geom_handle).as_geometry()?;
+        for &blend_shape_handle in geom.blend_shapes.iter() {
+            let blend_shape = fbx_scene.get(blend_shape_handle).as_blend_shape()?;
+            for &channel_handle in blend_shape.channels.iter() {
+                channels.push((
+                    geom_handle,
+                    fbx_scene.get(channel_handle).as_blend_shape_channel()?,
+                ));
+            }
+        }
+    }
+    Ok(channels)
+}
+
+/// Reads offsets of every blend shape channel of the model for the geometry, so every surface of
+/// a mesh has the same set of morph targets.
+fn read_morph_offsets<'a>(
+    fbx_scene: &FbxScene,
+    channels: &[(Handle<FbxComponent>, &'a FbxBlendShapeChannel)],
+    geom_handle: Handle<FbxComponent>,
+    geometric_transform: &Matrix4<f32>,
+) -> Result<Vec<FbxMorphOffsets<'a>>, FbxError> {
+    let geom = fbx_scene.get(geom_handle).as_geometry()?;
+    let mut morph_offsets = Vec::with_capacity(channels.len());
+    for &(channel_geom_handle, channel) in channels.iter() {
+        morph_offsets.push(if channel_geom_handle == geom_handle {
+            FbxMorphOffsets::read(fbx_scene, channel, geom, geometric_transform)?
+        } else {
+            FbxMorphOffsets {
+                name: &channel.name,
+                positions: Default::default(),
+                normals: Default::default(),
+            }
+        });
+    }
+    Ok(morph_offsets)
+}
+
+/// Returns initial weights of morph targets, FBX stores them in percents.
+fn initial_morph_weights(channels: &[(Handle<FbxComponent>, &FbxBlendShapeChannel)]) -> Vec<f32> {
+    channels
+        .iter()
+        .map(|(_, channel)| channel.deform_percent / 100.0)
+        .collect()
+}
+
+/// Creates surface data from the mesh and appends optional attributes to its vertex buffer.
+fn build_surface_data<T: Copy>(
+    raw_mesh: RawMesh<FbxVertex<T>>,
+    layout: &[VertexAttributeDescriptor],
+    geom: &FbxGeometry,
+    morph_offsets: &[FbxMorphOffsets],
+) -> SurfaceData {
+    let mut data = SurfaceData::from_raw_mesh(
+        RawMesh {
+            vertices: raw_mesh.vertices.iter().map(|v| v.vertex).collect(),
+            triangles: raw_mesh.triangles,
+        },
+        layout,
+        false,
+    );
+
+    let mut vertex_buffer_mut = data.vertex_buffer.modify();
+    if geom.second_uvs.is_some() {
+        vertex_buffer_mut
+            .add_attribute(
+                VertexAttributeDescriptor {
+                    usage: VertexAttributeUsage::TexCoord1,
+                    data_type: VertexAttributeDataType::F32,
+                    size: 2,
+                    divisor: 0,
+                    shader_location: 6, // HACK: GBuffer renderer expects it to be at 6
+                },
+                Vector2::<f32>::default(),
+            )
+            .unwrap();
+        for (mut view, vertex) in vertex_buffer_mut.iter_mut().zip(raw_mesh.vertices.iter()) {
+            view.write_2_f32(VertexAttributeUsage::TexCoord1, vertex.second_uv)
+                .unwrap();
+        }
+    }
+    if geom.colors.is_some() {
+        vertex_buffer_mut
+            .add_attribute(
+                VertexAttributeDescriptor {
+                    usage: VertexAttributeUsage::TexCoord2,
+                    data_type: VertexAttributeDataType::F32,
+                    size: 4,
+                    divisor: 0,
+                    shader_location: 7,
+                },
+                Vector4::<f32>::default(),
+            )
+            .unwrap();
+        for (mut view, vertex) in vertex_buffer_mut.iter_mut().zip(raw_mesh.vertices.iter()) {
+            view.write_4_f32(VertexAttributeUsage::TexCoord2, vertex.color)
+                .unwrap();
+        }
+    }
+    drop(vertex_buffer_mut);
+
+    let control_points = raw_mesh
+        .vertices
+        .iter()
+        .map(|v| v.control_point)
+        .collect::<Vec<_>>();
+    data.morph_targets = morph_offsets
+        .iter()
+        .map(|offsets| MorphTarget {
+            name: offsets.name.to_owned(),
+            position_deltas: FbxMorphOffsets::gather(&offsets.positions, &control_points),
+            normal_deltas: FbxMorphOffsets::gather(&offsets.normals, &control_points),
+            tangent_deltas: Default::default(),
+        })
+        .collect();
+
+    data
 }
 
 impl FbxMeshBuilder {
-    fn build(self) -> SurfaceData {
+    fn build(self, geom: &FbxGeometry, morph_offsets: &[FbxMorphOffsets]) -> SurfaceData {
         match self {
             FbxMeshBuilder::Static(builder) => {
-                SurfaceData::from_raw_mesh(builder.build(), StaticVertex::layout(), false)
-            }
-            FbxMeshBuilder::Animated(builder) => {
-                SurfaceData::from_raw_mesh(builder.build(), AnimatedVertex::layout(), false)
+                build_surface_data(builder.build(), StaticVertex::layout(), geom, morph_offsets)
             }
+            FbxMeshBuilder::Animated(builder) => build_surface_data(
+                builder.build(),
+                AnimatedVertex::layout(),
+                geom,
+                morph_offsets,
+            ),
         }
     }
 }
@@ -245,13 +522,37 @@ struct FbxSurfaceData {
     skin_data: Vec<VertexWeightSet>,
 }
 
+/// Creates textures from embedded media of every video in the scene.
+fn create_embedded_textures(fbx_scene: &FbxScene) -> HashMap<Handle<FbxComponent>, Texture> {
+    let mut textures = HashMap::new();
+    for (handle, component) in fbx_scene.pair_iter() {
+        if let FbxComponent::Video(video) = component {
+            if !video.content.is_empty() {
+                match Texture::load_from_memory(&video.content, CompressionOptions::NoCompression) {
+                    Ok(texture) => {
+                        textures.insert(handle, texture);
+                    }
+                    Err(e) => Log::writeln(
+                        MessageKind::Error,
+                        format!("Unable to load embedded FBX texture! Reason: {:?}", e),
+                    ),
+                }
+            }
+        }
+    }
+    textures
+}
+
 async fn create_surfaces(
     fbx_scene: &FbxScene,
     data_set: Vec<FbxSurfaceData>,
     resource_manager: ResourceManager,
     model: &FbxModel,
+    geom: &FbxGeometry,
+    morph_offsets: &[FbxMorphOffsets],
     model_path: &Path,
     material_search_options: &MaterialSearchOptions,
+    embedded_textures: &HashMap<Handle<FbxComponent>, Texture>,
 ) -> Result<Vec<Surface>, FbxError> {
     let mut surfaces = Vec::new();
 
@@ -259,92 +560,73 @@ async fn create_surfaces(
     if model.materials.is_empty() {
         assert_eq!(data_set.len(), 1);
         let data = data_set.into_iter().next().unwrap();
-        let mut surface = Surface::new(Arc::new(RwLock::new(data.builder.build())));
+        let mut surface = Surface::new(Arc::new(RwLock::new(
+            data.builder.build(geom, morph_offsets),
+        )));
         surface.vertex_weights = data.skin_data;
         surfaces.push(surface);
     } else {
         assert_eq!(data_set.len(), model.materials.len());
         for (&material_handle, data) in model.materials.iter().zip(data_set.into_iter()) {
-            let mut surface = Surface::new(Arc::new(RwLock::new(data.builder.build())));
+            let mut surface = Surface::new(Arc::new(RwLock::new(
+                data.builder.build(geom, morph_offsets),
+            )));
             surface.vertex_weights = data.skin_data;
             let material = fbx_scene.get(material_handle).as_material()?;
             for (name, texture_handle) in material.textures.iter() {
-                let texture = fbx_scene.get(*texture_handle).as_texture()?;
-                let path = texture.get_file_path();
-                if let Some(filename) = path.file_name() {
-                    let texture_path = match material_search_options {
-                        MaterialSearchOptions::MaterialsDirectory(directory) => {
-                            directory.join(filename)
-                        }
-                        MaterialSearchOptions::RecursiveUp => {
-                            let mut texture_path = Default::default();
-                            let mut path = model_path.to_owned();
-                            while let Some(parent) = path.parent() {
-                                let candidate = parent.join(filename);
-                                if io::exists(&candidate).await {
-                                    texture_path = candidate;
-                                    break;
-                                }
-                                path.pop();
-                            }
-                            texture_path
-                        }
-                        MaterialSearchOptions::WorkingDirectory => {
-                            let mut texture_path = Default::default();
-                            for dir in WalkDir::new(".").into_iter().flatten() {
-                                if dir.path().is_dir() {
-                                    let candidate = dir.path().join(filename);
-                                    if candidate.exists() {
-                                        texture_path = candidate;
-                                        break;
-                                    }
-                                }
-                            }
-                            texture_path
-                        }
-                        MaterialSearchOptions::UsePathDirectly => path.clone(),
-                    };
-
-                    let texture = resource_manager.request_texture(texture_path.as_path(), None);
-
-                    // Make up your mind, Autodesk.
-                    // Handle all possible combinations of links to auto-import materials.
-                    let name_usage = if name.contains("AmbientColor")
-                        || name.contains("ambient_color")
-                    {
-                        Some(("aoTexture", SamplerFallback::White))
-                    } else if name.contains("DiffuseColor") || name.contains("diffuse_color") {
-                        Some(("diffuseTexture", SamplerFallback::White))
-                    } else if name.contains("MetalnessMap") || name.contains("metalness_map") {
-                        Some(("metallicTexture", SamplerFallback::Black))
-                    } else if name.contains("RoughnessMap") || name.contains("roughness_map") {
-                        Some(("roughnessTexture", SamplerFallback::White))
-                    } else if name.contains("Bump")
-                        || name.contains("bump_map")
-                        || name.contains("NormalMap")
-                        || name.contains("normal_map")
-                    {
-                        Some(("normalTexture", SamplerFallback::Normal))
-                    } else if name.contains("DisplacementColor")
-                        || name.contains("displacement_map")
-                    {
-                        Some(("heightTexture", SamplerFallback::Black))
-                    } else if name.contains("EmissiveColor") || name.contains("emit_color_map") {
-                        Some(("emissionTexture", SamplerFallback::Black))
-                    } else {
-                        None
-                    };
-
-                    if let Some((property_name, usage)) = name_usage {
-                        if let Err(e) = surface.material().lock().unwrap().set_property(
-                            property_name,
-                            PropertyValue::Sampler {
-                                value: Some(texture),
-                                fallback: usage,
-                            },
-                        ) {
-                            Log::writeln(MessageKind::Error, format!("Unable to set material property {} for FBX material! Reason: {:?}", property_name, e));
-                        }
+                let fbx_texture = fbx_scene.get(*texture_handle).as_texture()?;
+                let path = fbx_texture.get_file_path();
+                let texture = if let Some(texture) = embedded_textures.get(&fbx_texture.video) {
+                    texture.clone()
+                } else if path.file_name().is_some() {
+                    let texture_path =
+                        find_texture(&path, model_path, material_search_options).await;
+
+                    resource_manager.request_texture(texture_path.as_path(), None)
+                } else {
+                    continue;
+                };
+
+                // Make up your mind, Autodesk.
+                // Handle all possible combinations of links to auto-import materials.
+                let name_usage = if name.contains("AmbientColor") || name.contains("ambient_color")
+                {
+                    Some(("aoTexture", SamplerFallback::White))
+                } else if name.contains("DiffuseColor") || name.contains("diffuse_color") {
+                    Some(("diffuseTexture", SamplerFallback::White))
+                } else if name.contains("MetalnessMap") || name.contains("metalness_map") {
+                    Some(("metallicTexture", SamplerFallback::Black))
+                } else if name.contains("RoughnessMap") || name.contains("roughness_map") {
+                    Some(("roughnessTexture", SamplerFallback::White))
+                } else if name.contains("Bump")
+                    || name.contains("bump_map")
+                    || name.contains("NormalMap")
+                    || name.contains("normal_map")
+                {
+                    Some(("normalTexture", SamplerFallback::Normal))
+                } else if name.contains("DisplacementColor") || name.contains("displacement_map") {
+                    Some(("heightTexture", SamplerFallback::Black))
+                } else if name.contains("EmissiveColor") || name.contains("emit_color_map") {
+                    Some(("emissionTexture", SamplerFallback::Black))
+                } else {
+                    None
+                };
+
+                if let Some((property_name, usage)) = name_usage {
+                    if let Err(e) = surface.material().lock().unwrap().set_property(
+                        property_name,
+                        PropertyValue::Sampler {
+                            value: Some(texture),
+                            fallback: usage,
+                        },
+                    ) {
+                        Log::writeln(
+                            MessageKind::Error,
+                            format!(
+                                "Unable to set material property {} for FBX material! Reason: {:?}",
+                                property_name, e
+                            ),
+                        );
                     }
                 }
             }
@@ -363,6 +645,7 @@ async fn convert_mesh(
     graph: &mut Graph,
     model_path: &Path,
     material_search_options: &MaterialSearchOptions,
+    embedded_textures: &HashMap<Handle<FbxComponent>, Texture>,
 ) -> Result<Handle<Node>, FbxError> {
     let geometric_transform = Matrix4::new_translation(&model.geometric_translation)
         * quat_from_euler(model.geometric_rotation).to_homogeneous()
@@ -375,11 +658,16 @@ async fn convert_mesh(
     // triangulated polygon.
     let mut face_triangles = Vec::new();
 
+    let channels = collect_blend_shape_channels(fbx_scene, model)?;
+
     let mut mesh_surfaces = Vec::new();
     for &geom_handle in &model.geoms {
         let geom = fbx_scene.get(geom_handle).as_geometry()?;
         let skin_data = geom.get_skin_data(fbx_scene)?;
 
+        let morph_offsets =
+            read_morph_offsets(fbx_scene, &channels, geom_handle, &geometric_transform)?;
+
         let mut data_set = vec![
             FbxSurfaceData {
                 builder: if geom.deformers.is_empty() {
@@ -439,8 +727,11 @@ async fn convert_mesh(
             data_set,
             resource_manager.clone(),
             model,
+            geom,
+            &morph_offsets,
             model_path,
             material_search_options,
+            embedded_textures,
         )
         .await?;
 
@@ -462,6 +753,7 @@ async fn convert_mesh(
 
     Ok(MeshBuilder::new(base)
         .with_surfaces(mesh_surfaces)
+        .with_morph_weights(initial_morph_weights(&channels))
         .build(graph))
 }
 
@@ -493,6 +785,7 @@ async fn convert_model(
     animation_handle: Handle<Animation>,
     model_path: &Path,
     material_search_options: &MaterialSearchOptions,
+    embedded_textures: &HashMap<Handle<FbxComponent>, Texture>,
 ) -> Result<Handle<Node>, FbxError> {
     let base = convert_model_to_base(model);
 
@@ -506,6 +799,7 @@ async fn convert_model(
             graph,
             model_path,
             material_search_options,
+            embedded_textures,
         )
         .await?
     } else if model.light.is_some() {
@@ -514,8 +808,24 @@ async fn convert_model(
         base.build(graph)
     };
 
+    // Weights of blend shape channels could be animated too.
+    let mut weight_curves = Vec::new();
+    for (_, channel) in collect_blend_shape_channels(fbx_scene, model)? {
+        let curve = if channel.deform_percent_curve.is_some() {
+            Some(
+                fbx_scene
+                    .get(channel.deform_percent_curve)
+                    .as_animation_curve_node()?,
+            )
+        } else {
+            None
+        };
+        weight_curves.push((channel, curve));
+    }
+    let has_animated_weights = weight_curves.iter().any(|(_, curve)| curve.is_some());
+
     // Convert animations
-    if !model.animation_curve_nodes.is_empty() {
+    if !model.animation_curve_nodes.is_empty() || has_animated_weights {
         // Find supported curve nodes (translation, rotation, scale)
         let mut lcl_translation = None;
         let mut lcl_rotation = None;
@@ -555,8 +865,25 @@ async fn convert_model(
 
             track.add_key_frame(KeyFrame::new(time, translation, scale, rotation));
 
+            if has_animated_weights {
+                let weights = weight_curves
+                    .iter()
+                    .map(|(channel, curve)| {
+                        curve
+                            .map(|curve| curve.eval_f32(fbx_scene, time))
+                            .unwrap_or(channel.deform_percent)
+                            / 100.0
+                    })
+                    .collect();
+                track.add_weight_key_frame(WeightKeyFrame::new(time, weights));
+            }
+
             let mut next_time = f32::MAX;
-            for node in [lcl_translation, lcl_rotation, lcl_scale].iter().flatten() {
+            for node in [lcl_translation, lcl_rotation, lcl_scale]
+                .iter()
+                .flatten()
+                .chain(weight_curves.iter().filter_map(|(_, curve)| curve.as_ref()))
+            {
                 for &curve_handle in node.curves.iter() {
                     let curve_component = fbx_scene.get(curve_handle);
                     if let FbxComponent::AnimationCurve(curve) = curve_component {
@@ -597,6 +924,7 @@ async fn convert(
 ) -> Result<(), FbxError> {
     let root = scene.graph.get_root();
     let animation_handle = scene.animations.add(Animation::default());
+    let embedded_textures = create_embedded_textures(fbx_scene);
     let mut fbx_model_to_node_map = HashMap::new();
     for (component_handle, component) in fbx_scene.pair_iter() {
         if let FbxComponent::Model(model) = component {
@@ -609,6 +937,7 @@ async fn convert(
                 animation_handle,
                 model_path,
                 material_search_options,
+                &embedded_textures,
             )
             .await?;
             scene.graph.link_nodes(node, root);
@@ -741,3 +1070,128 @@ pub async fn load_to_scene<P: AsRef<Path>>(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::{Matrix4, Vector3},
+        resource::fbx::{
+            collect_blend_shape_channels, convert_vertex,
+            document::FbxDocument,
+            initial_morph_weights, read_morph_offsets,
+            scene::{FbxComponent, FbxScene},
+            FbxMeshBuilder,
+        },
+        utils::raw_mesh::RawMeshBuilder,
+    };
+
+    const MODEL: &str = r#"
+FBXHeaderExtension:  {
+    FBXVersion: 7400
+}
+Objects:  {
+    Model: 1, "Model::Face", "Mesh" {
+        Properties70:  {
+        }
+    }
+    Geometry: 2, "Geometry::Face", "Mesh" {
+        Vertices: *9 {
+            a: 0,0,0,1,0,0,0,1,0
+        }
+        PolygonVertexIndex: *3 {
+            a: 0,1,-3
+        }
+    }
+    Deformer: 3, "Deformer::Face", "BlendShape" {
+    }
+    Deformer: 4, "SubDeformer::Smile", "BlendShapeChannel" {
+        Properties70:  {
+            P: "DeformPercent", "Number", "", "A",25
+        }
+    }
+    Geometry: 5, "Geometry::Smile", "Shape" {
+        Indexes: *2 {
+            a: 0,2
+        }
+        Vertices: *6 {
+            a: 0,0,1,0,0,2
+        }
+        Normals: *6 {
+            a: 1,0,0,0,1,0
+        }
+    }
+    AnimationCurveNode: 6, "AnimCurveNode::DeformPercent", "" {
+    }
+    AnimationCurve: 7, "AnimCurve::", "" {
+        KeyTime: *2 {
+            a: 0,46186158000
+        }
+        KeyValueFloat: *2 {
+            a: 0,100
+        }
+    }
+}
+Connections:  {
+    C: "OO",1,0
+    C: "OO",2,1
+    C: "OO",3,2
+    C: "OO",4,3
+    C: "OO",5,4
+    C: "OP",6,4, "DeformPercent"
+    C: "OP",7,6, "d|DeformPercent"
+}
+"#;
+
+    #[test]
+    fn test_blend_shapes() {
+        let document = FbxDocument::from_bytes(MODEL.as_bytes().to_vec()).unwrap();
+        let fbx_scene = FbxScene::new(&document).unwrap();
+        let model = fbx_scene
+            .pair_iter()
+            .find_map(|(_, component)| match component {
+                FbxComponent::Model(model) => Some(model),
+                _ => None,
+            })
+            .unwrap();
+        let geom_handle = model.geoms[0];
+        let geom = fbx_scene.get(geom_handle).as_geometry().unwrap();
+
+        let channels = collect_blend_shape_channels(&fbx_scene, model).unwrap();
+        assert_eq!(channels.len(), 1);
+        assert_eq!(channels[0].0, geom_handle);
+        assert_eq!(initial_morph_weights(&channels), [0.25]);
+
+        let deform_percent = fbx_scene
+            .get(channels[0].1.deform_percent_curve)
+            .as_animation_curve_node()
+            .unwrap();
+        assert_eq!(deform_percent.eval_f32(&fbx_scene, 0.5), 50.0);
+
+        let morph_offsets =
+            read_morph_offsets(&fbx_scene, &channels, geom_handle, &Matrix4::identity()).unwrap();
+
+        let mut builder = RawMeshBuilder::new(3, 3);
+        for index in 0..3 {
+            let vertex = convert_vertex(geom, &Matrix4::identity(), 0, index, index, &[]).unwrap();
+            builder.insert(vertex.into());
+        }
+        let data = FbxMeshBuilder::Static(builder).build(geom, &morph_offsets);
+
+        assert_eq!(data.morph_targets.len(), 1);
+        let target = &data.morph_targets[0];
+        assert_eq!(target.name, "Smile");
+        assert_eq!(
+            target.position_deltas,
+            [
+                Vector3::new(0.0, 0.0, 1.0),
+                Vector3::default(),
+                Vector3::new(0.0, 0.0, 2.0)
+            ]
+        );
+        assert_eq!(
+            target.normal_deltas,
+            [Vector3::x(), Vector3::default(), Vector3::y()]
+        );
+        assert!(target.tangent_deltas.is_empty());
+    }
+}
@@ -69,9 +69,8 @@ impl FbxAnimationCurve {
         // Do linear search for span
         for i in 0..(self.keys.len() - 1) {
             let cur = &self.keys[i];
-            if cur.time >= time {
-                let next = &self.keys[i + 1];
-
+            let next = &self.keys[i + 1];
+            if next.time >= time {
                 // calculate interpolation coefficient
                 let time_span = next.time - cur.time;
                 let k = (time - cur.time) / time_span;
@@ -93,6 +92,7 @@ pub enum FbxAnimationCurveNodeType {
     Translation,
     Rotation,
     Scale,
+    DeformPercent,
 }
 
 pub struct FbxAnimationCurveNode {
@@ -108,6 +108,9 @@ impl FbxAnimationCurveNode {
                 "T" | "AnimCurveNode::T" => FbxAnimationCurveNodeType::Translation,
                 "R" | "AnimCurveNode::R" => FbxAnimationCurveNodeType::Rotation,
                 "S" | "AnimCurveNode::S" => FbxAnimationCurveNodeType::Scale,
+                "DeformPercent" | "AnimCurveNode::DeformPercent" => {
+                    FbxAnimationCurveNodeType::DeformPercent
+                }
                 _ => FbxAnimationCurveNodeType::Unknown,
             },
             curves: Vec::new(),
@@ -140,7 +143,35 @@ impl FbxAnimationCurveNode {
         }
     }
 
+    pub fn eval_f32(&self, scene: &FbxScene, time: f32) -> f32 {
+        match self.curves.first().map(|&curve| scene.get(curve)) {
+            Some(FbxComponent::AnimationCurve(curve)) => curve.eval(time),
+            _ => 0.0,
+        }
+    }
+
     pub fn eval_quat(&self, scene: &FbxScene, time: f32) -> UnitQuaternion<f32> {
         quat_from_euler(self.eval_vec3(scene, time))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::resource::fbx::scene::animation::{FbxAnimationCurve, FbxTimeValuePair};
+
+    #[test]
+    fn test_curve_eval() {
+        let curve = FbxAnimationCurve {
+            keys: [(0.0, 0.0), (1.0, 10.0), (2.0, 30.0)]
+                .iter()
+                .map(|&(time, value)| FbxTimeValuePair { time, value })
+                .collect(),
+        };
+        assert_eq!(curve.eval(-1.0), 0.0);
+        // Time between first two keys must not be extrapolated from the next span.
+        assert_eq!(curve.eval(0.5), 5.0);
+        assert_eq!(curve.eval(1.0), 10.0);
+        assert_eq!(curve.eval(1.5), 20.0);
+        assert_eq!(curve.eval(3.0), 30.0);
+    }
+}
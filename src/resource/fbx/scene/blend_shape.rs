@@ -0,0 +1,137 @@
+use crate::{
+    core::{algebra::Vector3, pool::Handle},
+    resource::fbx::{
+        document::{FbxNode, FbxNodeContainer},
+        scene::FbxComponent,
+    },
+};
+
+/// Blend shape is a deformer that attached to a geometry, it has a set of channels where
+/// each channel deforms the geometry towards one or more target shapes.
+pub struct FbxBlendShape {
+    pub channels: Vec<Handle<FbxComponent>>,
+}
+
+impl FbxBlendShape {
+    pub(in crate::resource::fbx) fn read(
+        _blend_shape_node_handle: Handle<FbxNode>,
+        _nodes: &FbxNodeContainer,
+    ) -> Self {
+        Self {
+            channels: Default::default(),
+        }
+    }
+}
+
+pub struct FbxBlendShapeChannel {
+    pub name: String,
+    /// Default weight of the channel in [0; 100] range.
+    pub deform_percent: f32,
+    /// Weights at which respective shapes are fully applied, in [0; 100] range. There is
+    /// more than one weight only if channel has in-between shapes.
+    pub full_weights: Vec<f32>,
+    pub shapes: Vec<Handle<FbxComponent>>,
+    /// Animation curve node of the weight of the channel, could be none.
+    pub deform_percent_curve: Handle<FbxComponent>,
+}
+
+impl FbxBlendShapeChannel {
+    pub(in crate::resource::fbx) fn read(
+        channel_node_handle: Handle<FbxNode>,
+        nodes: &FbxNodeContainer,
+    ) -> Result<Self, String> {
+        let channel_node = nodes.get(channel_node_handle);
+
+        let mut name = match channel_node.get_attrib(1) {
+            Ok(name_attrib) => name_attrib.as_string(),
+            Err(_) => String::from("Unnamed"),
+        };
+        // Remove prefix
+        if name.starts_with("SubDeformer::") {
+            name = name.chars().skip(13).collect();
+        }
+
+        let mut channel = Self {
+            name,
+            deform_percent: 0.0,
+            full_weights: Default::default(),
+            shapes: Default::default(),
+            deform_percent_curve: Default::default(),
+        };
+
+        if let Ok(props) = nodes.get_by_name(channel_node_handle, "Properties70") {
+            for prop_handle in props.children() {
+                let prop = nodes.get(*prop_handle);
+                if prop.get_attrib(0)?.as_string() == "DeformPercent" {
+                    channel.deform_percent = prop.get_attrib(4)?.as_f32()?;
+                }
+            }
+        }
+
+        if let Ok(full_weights_handle) = nodes.find(channel_node_handle, "FullWeights") {
+            let full_weights = nodes.get_by_name(full_weights_handle, "a")?;
+            for weight in full_weights.attributes() {
+                channel.full_weights.push(weight.as_f32()?);
+            }
+        }
+
+        Ok(channel)
+    }
+}
+
+/// Shape is a special kind of geometry that contains sparse set of offsets for vertices
+/// of base geometry.
+pub struct FbxShape {
+    /// Indices of vertices of base geometry.
+    pub indices: Vec<i32>,
+    /// Offsets of vertex positions, there is exactly one offset per index.
+    pub vertices: Vec<Vector3<f32>>,
+    /// Offsets of vertex normals, there is exactly one offset per index. Could be empty.
+    pub normals: Vec<Vector3<f32>>,
+}
+
+fn read_vec3_array(
+    shape_node_handle: Handle<FbxNode>,
+    nodes: &FbxNodeContainer,
+    name: &str,
+) -> Result<Vec<Vector3<f32>>, String> {
+    let array_node_handle = nodes.find(shape_node_handle, name)?;
+    let array_node = nodes.get_by_name(array_node_handle, "a")?;
+    let mut array = Vec::with_capacity(array_node.attrib_count() / 3);
+    for v in array_node.attributes().chunks_exact(3) {
+        array.push(Vector3::new(v[0].as_f32()?, v[1].as_f32()?, v[2].as_f32()?));
+    }
+    Ok(array)
+}
+
+impl FbxShape {
+    pub(in crate::resource::fbx) fn read(
+        shape_node_handle: Handle<FbxNode>,
+        nodes: &FbxNodeContainer,
+    ) -> Result<Self, String> {
+        let indices_handle = nodes.find(shape_node_handle, "Indexes")?;
+        let indices_node = nodes.get_by_name(indices_handle, "a")?;
+        let mut indices = Vec::with_capacity(indices_node.attrib_count());
+        for index in indices_node.attributes() {
+            indices.push(index.as_i32()?);
+        }
+
+        let vertices = read_vec3_array(shape_node_handle, nodes, "Vertices")?;
+        if vertices.len() != indices.len() {
+            return Err(String::from(
+                "invalid shape, vertex count does not match index count",
+            ));
+        }
+
+        let mut normals = read_vec3_array(shape_node_handle, nodes, "Normals").unwrap_or_default();
+        if normals.len() != indices.len() {
+            normals.clear();
+        }
+
+        Ok(Self {
+            indices,
+            vertices,
+            normals,
+        })
+    }
+}
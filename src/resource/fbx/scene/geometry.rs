@@ -1,4 +1,4 @@
-use crate::core::algebra::{Vector2, Vector3};
+use crate::core::algebra::{Vector2, Vector3, Vector4};
 use crate::{
     core::pool::Handle,
     resource::{
@@ -20,11 +20,38 @@ pub struct FbxGeometry {
     // Normals, UVs, etc. are optional.
     pub normals: Option<FbxContainer<Vector3<f32>>>,
     pub uvs: Option<FbxContainer<Vector2<f32>>>,
+    // Second set of UVs is usually used for lightmaps.
+    pub second_uvs: Option<FbxContainer<Vector2<f32>>>,
+    pub colors: Option<FbxContainer<Vector4<f32>>>,
     pub materials: Option<FbxContainer<i32>>,
     pub tangents: Option<FbxContainer<Vector3<f32>>>,
     pub binormals: Option<FbxContainer<Vector3<f32>>>,
 
     pub deformers: Vec<Handle<FbxComponent>>,
+    pub blend_shapes: Vec<Handle<FbxComponent>>,
+}
+
+/// Searches layer element with given name and layer index among children of geometry node.
+fn find_layer_element(
+    geom_node_handle: Handle<FbxNode>,
+    nodes: &FbxNodeContainer,
+    name: &str,
+    layer_index: i32,
+) -> Option<Handle<FbxNode>> {
+    nodes
+        .get(geom_node_handle)
+        .children()
+        .iter()
+        .copied()
+        .find(|&handle| {
+            let node = nodes.get(handle);
+            // Layer index could be omitted, in this case it is the first layer.
+            let index = node
+                .get_attrib(0)
+                .and_then(|attrib| attrib.as_i32())
+                .unwrap_or(0);
+            node.name() == name && index == layer_index
+        })
 }
 
 fn read_vertices(
@@ -106,8 +133,11 @@ fn read_binormals(
 fn read_uvs(
     geom_node_handle: Handle<FbxNode>,
     nodes: &FbxNodeContainer,
+    layer_index: i32,
 ) -> Result<Option<FbxContainer<Vector2<f32>>>, FbxError> {
-    if let Ok(layer_element_uv) = nodes.find(geom_node_handle, "LayerElementUV") {
+    if let Some(layer_element_uv) =
+        find_layer_element(geom_node_handle, nodes, "LayerElementUV", layer_index)
+    {
         Ok(Some(FbxContainer::new(
             nodes,
             layer_element_uv,
@@ -125,6 +155,35 @@ fn read_uvs(
     }
 }
 
+fn read_colors(
+    geom_node_handle: Handle<FbxNode>,
+    nodes: &FbxNodeContainer,
+) -> Result<Option<FbxContainer<Vector4<f32>>>, FbxError> {
+    if let Some(layer_element_color) =
+        find_layer_element(geom_node_handle, nodes, "LayerElementColor", 0)
+    {
+        Ok(Some(FbxContainer::new(
+            nodes,
+            layer_element_color,
+            "Colors",
+            |attributes| {
+                let mut colors = Vec::with_capacity(attributes.len() / 4);
+                for color in attributes.chunks_exact(4) {
+                    colors.push(Vector4::new(
+                        color[0].as_f32()?,
+                        color[1].as_f32()?,
+                        color[2].as_f32()?,
+                        color[3].as_f32()?,
+                    ));
+                }
+                Ok(colors)
+            },
+        )?))
+    } else {
+        Ok(None)
+    }
+}
+
 fn read_materials(
     geom_node_handle: Handle<FbxNode>,
     nodes: &FbxNodeContainer,
@@ -158,11 +217,14 @@ impl FbxGeometry {
             vertices: read_vertices(geom_node_handle, nodes)?,
             indices: read_indices(geom_node_handle, nodes)?,
             normals: read_normals(geom_node_handle, nodes)?,
-            uvs: read_uvs(geom_node_handle, nodes)?,
+            uvs: read_uvs(geom_node_handle, nodes, 0)?,
+            second_uvs: read_uvs(geom_node_handle, nodes, 1)?,
+            colors: read_colors(geom_node_handle, nodes)?,
             materials: read_materials(geom_node_handle, nodes)?,
             tangents: read_tangents(geom_node_handle, nodes)?,
             binormals: read_binormals(geom_node_handle, nodes)?,
             deformers: Vec::new(),
+            blend_shapes: Vec::new(),
         })
     }
 
@@ -196,3 +258,71 @@ impl FbxGeometry {
         Ok(out)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::{Vector2, Vector4},
+        resource::fbx::{
+            document::FbxDocument,
+            scene::geometry::{find_layer_element, read_colors, read_uvs},
+        },
+    };
+
+    const GEOMETRY: &str = r#"
+Geometry: 1 {
+    LayerElementUV: 0 {
+        MappingInformationType: "ByPolygonVertex"
+        ReferenceInformationType: "Direct"
+        UV: *4 {
+            a: 0,0,0,1
+        }
+    }
+    LayerElementUV: 1 {
+        MappingInformationType: "ByPolygonVertex"
+        ReferenceInformationType: "IndexToDirect"
+        UV: *4 {
+            a: 0.5,0.5,1,1
+        }
+        UVIndex: *3 {
+            a: 1,0,1
+        }
+    }
+    LayerElementColor: 0 {
+        MappingInformationType: "ByPolygonVertex"
+        ReferenceInformationType: "IndexToDirect"
+        Colors: *8 {
+            a: 1,0,0,1,0,1,0,1
+        }
+        ColorIndex: *3 {
+            a: 1,1,0
+        }
+    }
+}
+"#;
+
+    #[test]
+    fn test_layer_elements() {
+        let document = FbxDocument::from_bytes(GEOMETRY.as_bytes().to_vec()).unwrap();
+        let nodes = document.nodes();
+        let geometry = nodes.find(document.root(), "Geometry").unwrap();
+
+        let second_layer = find_layer_element(geometry, nodes, "LayerElementUV", 1).unwrap();
+        assert_eq!(
+            nodes.get(second_layer).get_attrib(0).unwrap().as_i32(),
+            Ok(1)
+        );
+        assert!(find_layer_element(geometry, nodes, "LayerElementUV", 2).is_none());
+
+        let uvs = read_uvs(geometry, nodes, 0).unwrap().unwrap();
+        assert_eq!(*uvs.get(0, 1).unwrap(), Vector2::new(0.0, 1.0));
+        let second_uvs = read_uvs(geometry, nodes, 1).unwrap().unwrap();
+        assert_eq!(*second_uvs.get(0, 0).unwrap(), Vector2::new(1.0, 1.0));
+        assert_eq!(*second_uvs.get(0, 1).unwrap(), Vector2::new(0.5, 0.5));
+
+        let colors = read_colors(geometry, nodes).unwrap().unwrap();
+        assert_eq!(colors.index, [1, 1, 0]);
+        assert_eq!(*colors.get(0, 1).unwrap(), Vector4::new(0.0, 1.0, 0.0, 1.0));
+        assert_eq!(*colors.get(0, 2).unwrap(), Vector4::new(1.0, 0.0, 0.0, 1.0));
+    }
+}
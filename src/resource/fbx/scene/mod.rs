@@ -9,16 +9,18 @@ use crate::{
         fix_index,
         scene::{
             animation::{FbxAnimationCurve, FbxAnimationCurveNode},
+            blend_shape::{FbxBlendShape, FbxBlendShapeChannel, FbxShape},
             geometry::FbxGeometry,
             light::FbxLight,
             model::FbxModel,
-            texture::FbxTexture,
+            texture::{FbxTexture, FbxVideo},
         },
     },
 };
 use std::collections::HashMap;
 
 pub mod animation;
+pub mod blend_shape;
 pub mod geometry;
 pub mod light;
 pub mod model;
@@ -53,9 +55,15 @@ impl FbxScene {
             let mut component_handle: Handle<FbxComponent> = Handle::NONE;
             match object.name() {
                 "Geometry" => {
-                    component_handle = components.spawn(FbxComponent::Geometry(Box::new(
-                        FbxGeometry::read(*object_handle, nodes)?,
-                    )));
+                    if object.attrib_count() > 2 && object.get_attrib(2)?.as_string() == "Shape" {
+                        component_handle = components.spawn(FbxComponent::Shape(Box::new(
+                            FbxShape::read(*object_handle, nodes)?,
+                        )));
+                    } else {
+                        component_handle = components.spawn(FbxComponent::Geometry(Box::new(
+                            FbxGeometry::read(*object_handle, nodes)?,
+                        )));
+                    }
                 }
                 "Model" => {
                     component_handle = components.spawn(FbxComponent::Model(Box::new(
@@ -72,6 +80,10 @@ impl FbxScene {
                         nodes,
                     )?));
                 }
+                "Video" => {
+                    component_handle = components
+                        .spawn(FbxComponent::Video(FbxVideo::read(*object_handle, nodes)?));
+                }
                 "NodeAttribute" => {
                     if object.attrib_count() > 2 && object.get_attrib(2)?.as_string() == "Light" {
                         component_handle = components
@@ -99,6 +111,16 @@ impl FbxScene {
                             FbxDeformer::read(*object_handle, nodes),
                         ));
                     }
+                    "BlendShape" => {
+                        component_handle = components.spawn(FbxComponent::BlendShape(
+                            FbxBlendShape::read(*object_handle, nodes),
+                        ));
+                    }
+                    "BlendShapeChannel" => {
+                        component_handle = components.spawn(FbxComponent::BlendShapeChannel(
+                            FbxBlendShapeChannel::read(*object_handle, nodes)?,
+                        ));
+                    }
                     _ => (),
                 },
                 _ => (),
@@ -161,6 +183,12 @@ fn link_child_with_parent_component(
                 material.textures.push((property, child_handle));
             }
         }
+        // Link texture with video
+        FbxComponent::Texture(texture) => {
+            if let FbxComponent::Video(_) = child {
+                texture.video = child_handle;
+            }
+        }
         // Link animation curve node with animation curve
         FbxComponent::AnimationCurveNode(anim_curve_node) => {
             if let FbxComponent::AnimationCurve(_) = child {
@@ -173,12 +201,24 @@ fn link_child_with_parent_component(
                 deformer.sub_deformers.push(child_handle);
             }
         }
-        // Link geometry with deformers
-        FbxComponent::Geometry(geometry) => {
-            if let FbxComponent::Deformer(_) = child {
-                geometry.deformers.push(child_handle);
+        // Link geometry with deformers and blend shapes
+        FbxComponent::Geometry(geometry) => match child {
+            FbxComponent::Deformer(_) => geometry.deformers.push(child_handle),
+            FbxComponent::BlendShape(_) => geometry.blend_shapes.push(child_handle),
+            _ => (),
+        },
+        // Link blend shape with channels
+        FbxComponent::BlendShape(blend_shape) => {
+            if let FbxComponent::BlendShapeChannel(_) = child {
+                blend_shape.channels.push(child_handle);
             }
         }
+        // Link blend shape channel with shapes and animation of its weight
+        FbxComponent::BlendShapeChannel(channel) => match child {
+            FbxComponent::Shape(_) => channel.shapes.push(child_handle),
+            FbxComponent::AnimationCurveNode(_) => channel.deform_percent_curve = child_handle,
+            _ => (),
+        },
         // Link sub-deformer with model
         FbxComponent::SubDeformer(sub_deformer) => {
             if let FbxComponent::Model(model) = child {
@@ -201,6 +241,10 @@ pub enum FbxComponent {
     AnimationCurveNode(FbxAnimationCurveNode),
     AnimationCurve(FbxAnimationCurve),
     Geometry(Box<FbxGeometry>),
+    Video(FbxVideo),
+    BlendShape(FbxBlendShape),
+    BlendShapeChannel(FbxBlendShapeChannel),
+    Shape(Box<FbxShape>),
 }
 
 macro_rules! define_as {
@@ -222,6 +266,20 @@ impl FbxComponent {
     define_as!(self, as_light, FbxLight, Light);
     define_as!(self, as_material, FbxMaterial, Material);
     define_as!(self, as_geometry, FbxGeometry, Geometry);
+    define_as!(self, as_blend_shape, FbxBlendShape, BlendShape);
+    define_as!(
+        self,
+        as_blend_shape_channel,
+        FbxBlendShapeChannel,
+        BlendShapeChannel
+    );
+    define_as!(self, as_shape, FbxShape, Shape);
+    define_as!(
+        self,
+        as_animation_curve_node,
+        FbxAnimationCurveNode,
+        AnimationCurveNode
+    );
 }
 
 // https://help.autodesk.com/view/FBX/2016/ENU/?guid=__cpp_ref_class_fbx_anim_curve_html
@@ -388,10 +446,12 @@ impl<T> FbxContainer<T> {
         // See: https://developer.blender.org/D402
         if data_name.as_ref() != "Materials" {
             if reference == FbxReference::IndexToDirect {
-                let index_node = nodes.find(
-                    container_node,
-                    format!("{}Index", data_name.as_ref()).as_str(),
-                )?;
+                // Every index array is named as data array with "Index" suffix, except colors.
+                let index_name = match data_name.as_ref() {
+                    "Colors" => String::from("ColorIndex"),
+                    name => format!("{}Index", name),
+                };
+                let index_node = nodes.find(container_node, index_name.as_str())?;
                 let index_array_node = nodes.get_by_name(index_node, "a")?;
                 for attribute in index_array_node.attributes() {
                     let idx = attribute.as_i32()?;
@@ -1,11 +1,16 @@
 use crate::{
     core::pool::Handle,
-    resource::fbx::document::{FbxNode, FbxNodeContainer},
+    resource::fbx::{
+        document::{attribute::FbxAttribute, FbxNode, FbxNodeContainer},
+        scene::FbxComponent,
+    },
 };
 use std::path::PathBuf;
 
 pub struct FbxTexture {
     filename: PathBuf,
+    /// Handle to video component which may contain embedded image.
+    pub video: Handle<FbxComponent>,
 }
 
 impl FbxTexture {
@@ -15,6 +20,7 @@ impl FbxTexture {
     ) -> Result<Self, String> {
         let mut texture = FbxTexture {
             filename: PathBuf::new(),
+            video: Handle::NONE,
         };
         if let Ok(relative_file_name_node) =
             nodes.get_by_name(texture_node_handle, "RelativeFilename")
@@ -35,3 +41,51 @@ impl FbxTexture {
         &self.filename
     }
 }
+
+/// Video is a container for media that is used by textures. Its content is the file content
+/// of an image which was embedded into FBX by exporter, content is empty if the media is stored
+/// in separate file.
+pub struct FbxVideo {
+    pub content: Vec<u8>,
+}
+
+impl FbxVideo {
+    pub(in crate::resource::fbx) fn read(
+        video_node_handle: Handle<FbxNode>,
+        nodes: &FbxNodeContainer,
+    ) -> Result<Self, String> {
+        let mut content = Vec::new();
+        if let Ok(content_node) = nodes.get_by_name(video_node_handle, "Content") {
+            let mut base64_content = String::new();
+            for attribute in content_node.attributes() {
+                match attribute {
+                    FbxAttribute::RawData(data) => content.extend_from_slice(data),
+                    // ASCII FBX stores content as a set of base64-encoded strings.
+                    FbxAttribute::String(string) => base64_content.push_str(string),
+                    _ => (),
+                }
+            }
+            if !base64_content.is_empty() {
+                content = base64::decode(base64_content)
+                    .map_err(|e| format!("Invalid embedded content of video. Reason: {}", e))?;
+            }
+        }
+        Ok(Self { content })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::resource::fbx::{document::FbxDocument, scene::texture::FbxVideo};
+
+    #[test]
+    fn test_video_content_from_base64() {
+        let document = FbxDocument::from_bytes(
+            b"Video: 1 {\n  Content: ,\n    \"aGVs\",\n    \"bG8=\"\n}\n".to_vec(),
+        )
+        .unwrap();
+        let video = document.nodes().find(document.root(), "Video").unwrap();
+        let video = FbxVideo::read(video, document.nodes()).unwrap();
+        assert_eq!(video.content, b"hello");
+    }
+}
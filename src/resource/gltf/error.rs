@@ -0,0 +1,77 @@
+//! Contains all possible errors that can occur during glTF loading, conversion and export.
+
+use rg3d_core::io::FileLoadError;
+use std::fmt::Formatter;
+
+/// See module docs.
+#[derive(Debug)]
+pub enum GltfError {
+    /// The document is malformed or does not pass validation.
+    Gltf(gltf::Error),
+    /// An error occurred during file loading.
+    FileLoadError(FileLoadError),
+    /// Embedded data has invalid base64 encoding.
+    Base64(base64::DecodeError),
+    /// Data URI is not base64-encoded or binary chunk of `.glb` file is missing.
+    UnsupportedUri(String),
+    /// A buffer has less bytes than declared in the document.
+    InvalidBuffer(usize),
+    /// A primitive of a mesh with given index has no vertex positions.
+    MissingPositions(usize),
+    /// A primitive of a mesh with given index has out-of-bounds vertex index.
+    InvalidIndex(usize),
+    /// A primitive of a mesh with given index is affected by more joints than a vertex can
+    /// reference (256).
+    TooManyJoints(usize),
+    /// An error occurred during writing of exported files.
+    Io(std::io::Error),
+    /// Exported document cannot be serialized.
+    Json(gltf::json::Error),
+}
+
+impl std::fmt::Display for GltfError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::Gltf(e) => write!(f, "glTF error: {}", e),
+            Self::FileLoadError(e) => write!(f, "File load error: {:?}", e),
+            Self::Base64(e) => write!(f, "Invalid base64 data: {}", e),
+            Self::UnsupportedUri(uri) => write!(f, "Unsupported uri {}", uri),
+            Self::InvalidBuffer(i) => write!(f, "Buffer {} is too short", i),
+            Self::MissingPositions(i) => write!(f, "Mesh {} has no vertex positions", i),
+            Self::InvalidIndex(i) => write!(f, "Mesh {} has invalid vertex index", i),
+            Self::TooManyJoints(i) => write!(f, "Mesh {} is affected by too many joints", i),
+            Self::Io(e) => write!(f, "Io error: {}", e),
+            Self::Json(e) => write!(f, "JSON serialization error: {}", e),
+        }
+    }
+}
+
+impl From<gltf::Error> for GltfError {
+    fn from(err: gltf::Error) -> Self {
+        GltfError::Gltf(err)
+    }
+}
+
+impl From<FileLoadError> for GltfError {
+    fn from(err: FileLoadError) -> Self {
+        GltfError::FileLoadError(err)
+    }
+}
+
+impl From<base64::DecodeError> for GltfError {
+    fn from(err: base64::DecodeError) -> Self {
+        GltfError::Base64(err)
+    }
+}
+
+impl From<std::io::Error> for GltfError {
+    fn from(err: std::io::Error) -> Self {
+        GltfError::Io(err)
+    }
+}
+
+impl From<gltf::json::Error> for GltfError {
+    fn from(err: gltf::json::Error) -> Self {
+        GltfError::Json(err)
+    }
+}
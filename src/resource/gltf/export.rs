@@ -0,0 +1,1342 @@
+//! Contains methods to export graph of a scene to glTF 2.0 format.
+//!
+//! The exporter writes node hierarchy, meshes with skinning, materials of the standard shader,
+//! perspective cameras, lights (`KHR_lights_punctual` extension) and animations of node
+//! transforms. Output format is defined by extension of the file: `.glb` produces single binary
+//! file, any other extension produces JSON document with a `.bin` buffer next to it.
+//!
+//! # Limitations
+//!
+//! - Textures that were loaded from files are referenced by relative paths, procedural textures
+//!   (for example lightmaps) are embedded as PNG images. Compressed procedural textures are skipped.
+//! - Metallic and roughness textures are merged into a single texture, so their data must be
+//!   loaded at the moment of export.
+//! - glTF has no lightmaps, second set of texture coordinates is written as `TEXCOORD_1` and
+//!   lightmap texture is stored in `extras` of a material as `lightmapTexture` texture info.
+//! - Sprites, particle systems, terrains and decals are written as empty nodes.
+//! - Shear of transforms with pivots and pre/post rotations is lost, such transforms are
+//!   decomposed into translation, rotation and scale.
+
+use crate::{
+    asset::ResourceState,
+    core::{
+        algebra::{Matrix3, Matrix4, Rotation3, UnitQuaternion, Vector3, Vector4},
+        color::LinearColor,
+        pool::Handle,
+    },
+    material::{shader::SamplerFallback, Material, PropertyValue},
+    resource::{
+        gltf::error::GltfError,
+        texture::{
+            Texture, TextureData, TextureKind, TextureMagnificationFilter,
+            TextureMinificationFilter, TexturePixelKind, TextureWrapMode,
+        },
+    },
+    scene::{
+        camera::Camera,
+        graph::Graph,
+        light::Light,
+        mesh::{
+            buffer::{VertexAttributeUsage, VertexReadTrait},
+            surface::SurfaceData,
+            Mesh,
+        },
+        node::Node,
+        transform::Transform,
+        Scene,
+    },
+    utils::log::{Log, MessageKind},
+};
+use gltf::{
+    binary::{Glb, Header},
+    json::{
+        self,
+        accessor::{ComponentType, GenericComponentType, Type},
+        animation::{Interpolation, Property},
+        buffer::Target,
+        extensions::scene::khr_lights_punctual,
+        material::{
+            AlphaMode, EmissiveFactor, NormalTexture, OcclusionTexture, PbrBaseColorFactor,
+            StrengthFactor,
+        },
+        mesh::{Mode, Semantic},
+        texture::{MagFilter, MinFilter, WrappingMode},
+        validation::Checked,
+        Index,
+    },
+};
+use image::{png::PngEncoder, ColorType};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
+};
+
+const LIGHTS_EXTENSION: &str = "KHR_lights_punctual";
+
+fn vec3(v: &Vector3<f32>) -> [f32; 3] {
+    [v.x, v.y, v.z]
+}
+
+fn quat(q: &UnitQuaternion<f32>) -> [f32; 4] {
+    [q.i, q.j, q.k, q.w]
+}
+
+fn f32_bytes(values: &[f32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// Pads a buffer with zeros to 4-byte boundary, glTF requires aligned data.
+fn align(buffer: &mut Vec<u8>) {
+    buffer.resize((buffer.len() + 3) & !3, 0);
+}
+
+fn is_trs(transform: &Transform) -> bool {
+    **transform.pre_rotation() == UnitQuaternion::identity()
+        && **transform.post_rotation() == UnitQuaternion::identity()
+        && **transform.rotation_offset() == Vector3::default()
+        && **transform.rotation_pivot() == Vector3::default()
+        && **transform.scaling_offset() == Vector3::default()
+        && **transform.scaling_pivot() == Vector3::default()
+}
+
+/// Splits a matrix into translation, rotation and scale, shear is lost.
+fn decompose(m: &Matrix4<f32>) -> (Vector3<f32>, UnitQuaternion<f32>, Vector3<f32>) {
+    let translation = Vector3::new(m[(0, 3)], m[(1, 3)], m[(2, 3)]);
+    let mut basis: Matrix3<f32> = m.fixed_slice::<3, 3>(0, 0).into_owned();
+    let mut scale = Vector3::new(
+        basis.column(0).norm(),
+        basis.column(1).norm(),
+        basis.column(2).norm(),
+    );
+    if basis.determinant() < 0.0 {
+        scale.x = -scale.x;
+    }
+    for (i, s) in scale.iter().enumerate() {
+        if *s != 0.0 {
+            basis.column_mut(i).unscale_mut(*s);
+        }
+    }
+    let rotation = UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix_unchecked(basis));
+    (translation, rotation, scale)
+}
+
+/// Returns local translation, rotation and scale of a transform with given values of its
+/// animated components.
+fn trs(
+    transform: &Transform,
+    position: Vector3<f32>,
+    rotation: UnitQuaternion<f32>,
+    scale: Vector3<f32>,
+) -> (Vector3<f32>, UnitQuaternion<f32>, Vector3<f32>) {
+    if is_trs(transform) {
+        (position, rotation, scale)
+    } else {
+        let mut transform = transform.clone();
+        transform
+            .set_position(position)
+            .set_rotation(rotation)
+            .set_scale(scale);
+        decompose(&transform.matrix())
+    }
+}
+
+fn convert_min_filter(filter: TextureMinificationFilter) -> MinFilter {
+    match filter {
+        TextureMinificationFilter::Nearest => MinFilter::Nearest,
+        TextureMinificationFilter::Linear => MinFilter::Linear,
+        TextureMinificationFilter::NearestMipMapNearest => MinFilter::NearestMipmapNearest,
+        TextureMinificationFilter::LinearMipMapNearest => MinFilter::LinearMipmapNearest,
+        TextureMinificationFilter::NearestMipMapLinear => MinFilter::NearestMipmapLinear,
+        TextureMinificationFilter::LinearMipMapLinear => MinFilter::LinearMipmapLinear,
+    }
+}
+
+fn convert_mag_filter(filter: TextureMagnificationFilter) -> MagFilter {
+    match filter {
+        TextureMagnificationFilter::Nearest => MagFilter::Nearest,
+        TextureMagnificationFilter::Linear => MagFilter::Linear,
+    }
+}
+
+fn convert_wrap_mode(mode: TextureWrapMode) -> WrappingMode {
+    match mode {
+        TextureWrapMode::Repeat => WrappingMode::Repeat,
+        TextureWrapMode::MirroredRepeat => WrappingMode::MirroredRepeat,
+        TextureWrapMode::ClampToEdge
+        | TextureWrapMode::ClampToBorder
+        | TextureWrapMode::MirrorClampToEdge => WrappingMode::ClampToEdge,
+    }
+}
+
+fn fallback_value(fallback: SamplerFallback) -> u8 {
+    match fallback {
+        SamplerFallback::White => 255,
+        SamplerFallback::Normal => 128,
+        SamplerFallback::Black => 0,
+    }
+}
+
+/// Encodes first mip level of a rectangle texture as PNG image.
+fn encode_png(data: &TextureData) -> Option<Vec<u8>> {
+    let (width, height) = match data.kind() {
+        TextureKind::Rectangle { width, height } => (width, height),
+        _ => return None,
+    };
+    let (color_type, bytes_per_pixel) = match data.pixel_kind() {
+        TexturePixelKind::R8 => (ColorType::L8, 1),
+        TexturePixelKind::RG8 => (ColorType::La8, 2),
+        TexturePixelKind::RGB8 | TexturePixelKind::BGR8 => (ColorType::Rgb8, 3),
+        TexturePixelKind::RGBA8 | TexturePixelKind::BGRA8 => (ColorType::Rgba8, 4),
+        TexturePixelKind::R16 => (ColorType::L16, 2),
+        TexturePixelKind::RG16 => (ColorType::La16, 4),
+        TexturePixelKind::RGB16 => (ColorType::Rgb16, 6),
+        TexturePixelKind::RGBA16 => (ColorType::Rgba16, 8),
+        TexturePixelKind::DXT1RGB
+        | TexturePixelKind::DXT1RGBA
+        | TexturePixelKind::DXT3RGBA
+        | TexturePixelKind::DXT5RGBA
+        | TexturePixelKind::R8RGTC
+        | TexturePixelKind::RG8RGTC => return None,
+    };
+    let mut pixels = data
+        .data()
+        .get(..(width * height) as usize * bytes_per_pixel)?
+        .to_vec();
+    match data.pixel_kind() {
+        TexturePixelKind::BGR8 | TexturePixelKind::BGRA8 => {
+            for pixel in pixels.chunks_exact_mut(bytes_per_pixel) {
+                pixel.swap(0, 2);
+            }
+        }
+        // PNG stores 16-bit samples in big-endian order.
+        TexturePixelKind::R16
+        | TexturePixelKind::RG16
+        | TexturePixelKind::RGB16
+        | TexturePixelKind::RGBA16 => {
+            for sample in pixels.chunks_exact_mut(2) {
+                sample.swap(0, 1);
+            }
+        }
+        _ => (),
+    }
+    let mut png = Vec::new();
+    PngEncoder::new(&mut png)
+        .encode(&pixels, width, height, color_type)
+        .ok()?;
+    Some(png)
+}
+
+/// Extracts red channel of first mip level of a rectangle texture, 16-bit channels are reduced
+/// to 8 bits.
+fn red_channel(data: &TextureData) -> Option<(u32, u32, Vec<u8>)> {
+    let (width, height) = match data.kind() {
+        TextureKind::Rectangle { width, height } => (width, height),
+        _ => return None,
+    };
+    // Size of a pixel and offset of the most significant byte of red channel.
+    let (stride, offset) = match data.pixel_kind() {
+        TexturePixelKind::R8 => (1, 0),
+        TexturePixelKind::RG8 => (2, 0),
+        TexturePixelKind::RGB8 => (3, 0),
+        TexturePixelKind::RGBA8 => (4, 0),
+        TexturePixelKind::BGR8 => (3, 2),
+        TexturePixelKind::BGRA8 => (4, 2),
+        TexturePixelKind::R16 => (2, 1),
+        TexturePixelKind::RG16 => (4, 1),
+        TexturePixelKind::RGB16 => (6, 1),
+        TexturePixelKind::RGBA16 => (8, 1),
+        _ => return None,
+    };
+    let bytes = data.data().get(..(width * height) as usize * stride)?;
+    let channel = bytes.chunks_exact(stride).map(|p| p[offset]).collect();
+    Some((width, height, channel))
+}
+
+/// Packs metallic and roughness into blue and green channels of a single RGB image as glTF
+/// requires, channels of different size are resampled to the biggest one.
+fn merge_metallic_roughness(
+    metallic: &(u32, u32, Vec<u8>),
+    roughness: &(u32, u32, Vec<u8>),
+) -> (u32, u32, Vec<u8>) {
+    let width = metallic.0.max(roughness.0);
+    let height = metallic.1.max(roughness.1);
+    let sample = |(w, h, data): &(u32, u32, Vec<u8>), x: u32, y: u32| {
+        data[(y * *h / height * *w + x * *w / width) as usize]
+    };
+    let mut pixels = Vec::with_capacity((width * height * 3) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            pixels.extend_from_slice(&[0, sample(roughness, x, y), sample(metallic, x, y)]);
+        }
+    }
+    (width, height, pixels)
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    let path = if path.is_absolute() {
+        path.to_owned()
+    } else {
+        std::env::current_dir().unwrap_or_default().join(path)
+    };
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                result.pop();
+            }
+            _ => result.push(component),
+        }
+    }
+    result
+}
+
+/// Escapes every character of a path that is not allowed in relative URI reference.
+fn escape_uri(path: &str) -> String {
+    let mut uri = String::new();
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// Makes URI of a file relative to given directory.
+fn relative_uri(base_dir: &Path, path: &Path) -> String {
+    let base_dir = normalize(base_dir);
+    let path = normalize(path);
+    let common = base_dir
+        .components()
+        .zip(path.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    if common == 0 {
+        // Different roots (drives), relative path is impossible.
+        return escape_uri(&path.to_string_lossy().replace('\\', "/"));
+    }
+    let parts = base_dir
+        .components()
+        .skip(common)
+        .map(|_| Cow::Borrowed(".."))
+        .chain(
+            path.components()
+                .skip(common)
+                .map(|c| c.as_os_str().to_string_lossy()),
+        )
+        .collect::<Vec<_>>();
+    escape_uri(&parts.join("/"))
+}
+
+fn texture_info(index: Index<json::Texture>, tex_coord: u32) -> json::texture::Info {
+    json::texture::Info {
+        index,
+        tex_coord,
+        extensions: None,
+        extras: Default::default(),
+    }
+}
+
+fn sampler_ref<'a>(material: &'a Material, name: &str) -> (Option<&'a Texture>, SamplerFallback) {
+    match material.property_ref(name) {
+        Some(PropertyValue::Sampler { value, fallback }) => (value.as_ref(), *fallback),
+        _ => (None, SamplerFallback::White),
+    }
+}
+
+struct PendingSkin {
+    node: Index<json::Node>,
+    joints: Vec<Handle<Node>>,
+}
+
+struct Exporter<'a> {
+    graph: &'a Graph,
+    base_dir: PathBuf,
+    root: json::Root,
+    buffer: Vec<u8>,
+    nodes: HashMap<Handle<Node>, Index<json::Node>>,
+    // Keys are addresses of shared data, each material and geometry is written once.
+    materials: HashMap<usize, Index<json::Material>>,
+    primitives: HashMap<usize, json::mesh::Primitive>,
+    textures: HashMap<usize, Option<Index<json::Texture>>>,
+    metallic_roughness: HashMap<(usize, usize), Option<Index<json::Texture>>>,
+    skins: Vec<PendingSkin>,
+}
+
+impl<'a> Exporter<'a> {
+    fn push_view(&mut self, data: &[u8], target: Option<Target>) -> Index<json::buffer::View> {
+        align(&mut self.buffer);
+        let view = json::buffer::View {
+            buffer: Index::new(0),
+            byte_length: data.len() as u32,
+            byte_offset: Some(self.buffer.len() as u32),
+            byte_stride: None,
+            name: None,
+            target: target.map(Checked::Valid),
+            extensions: None,
+            extras: Default::default(),
+        };
+        self.buffer.extend_from_slice(data);
+        self.root.buffer_views.push(view);
+        Index::new(self.root.buffer_views.len() as u32 - 1)
+    }
+
+    fn push_accessor(
+        &mut self,
+        data: &[u8],
+        count: usize,
+        component_type: ComponentType,
+        type_: Type,
+        target: Option<Target>,
+        bounds: Option<(json::Value, json::Value)>,
+    ) -> Index<json::Accessor> {
+        let view = self.push_view(data, target);
+        let (min, max) = match bounds {
+            Some((min, max)) => (Some(min), Some(max)),
+            None => (None, None),
+        };
+        self.root.accessors.push(json::Accessor {
+            buffer_view: Some(view),
+            byte_offset: 0,
+            count: count as u32,
+            component_type: Checked::Valid(GenericComponentType(component_type)),
+            extensions: None,
+            extras: Default::default(),
+            type_: Checked::Valid(type_),
+            min,
+            max,
+            name: None,
+            normalized: false,
+            sparse: None,
+        });
+        Index::new(self.root.accessors.len() as u32 - 1)
+    }
+
+    fn push_floats(
+        &mut self,
+        values: &[f32],
+        type_: Type,
+        target: Option<Target>,
+        with_bounds: bool,
+    ) -> Index<json::Accessor> {
+        let components = type_.multiplicity();
+        let bounds = if with_bounds {
+            let mut min = vec![f32::MAX; components];
+            let mut max = vec![f32::MIN; components];
+            for element in values.chunks_exact(components) {
+                for (i, v) in element.iter().enumerate() {
+                    min[i] = min[i].min(*v);
+                    max[i] = max[i].max(*v);
+                }
+            }
+            Some((json::Value::from(min), json::Value::from(max)))
+        } else {
+            None
+        };
+        self.push_accessor(
+            &f32_bytes(values),
+            values.len() / components,
+            ComponentType::F32,
+            type_,
+            target,
+            bounds,
+        )
+    }
+
+    fn push_image(&mut self, image: json::Image) -> Index<json::Image> {
+        self.root.images.push(image);
+        Index::new(self.root.images.len() as u32 - 1)
+    }
+
+    fn push_embedded_image(&mut self, png: &[u8]) -> Index<json::Image> {
+        let view = self.push_view(png, None);
+        self.push_image(json::Image {
+            buffer_view: Some(view),
+            mime_type: Some(json::image::MimeType("image/png".to_owned())),
+            name: None,
+            uri: None,
+            extensions: None,
+            extras: Default::default(),
+        })
+    }
+
+    fn push_texture(
+        &mut self,
+        source: Index<json::Image>,
+        data: Option<&TextureData>,
+    ) -> Index<json::Texture> {
+        let sampler = data.map(|data| {
+            self.root.samplers.push(json::texture::Sampler {
+                mag_filter: Some(Checked::Valid(convert_mag_filter(
+                    data.magnification_filter(),
+                ))),
+                min_filter: Some(Checked::Valid(convert_min_filter(
+                    data.minification_filter(),
+                ))),
+                name: None,
+                wrap_s: Checked::Valid(convert_wrap_mode(data.s_wrap_mode())),
+                wrap_t: Checked::Valid(convert_wrap_mode(data.t_wrap_mode())),
+                extensions: None,
+                extras: Default::default(),
+            });
+            Index::new(self.root.samplers.len() as u32 - 1)
+        });
+        self.root.textures.push(json::Texture {
+            name: None,
+            sampler,
+            source,
+            extensions: None,
+            extras: Default::default(),
+        });
+        Index::new(self.root.textures.len() as u32 - 1)
+    }
+
+    fn texture(&mut self, texture: &Texture) -> Option<Index<json::Texture>> {
+        if let Some(index) = self.textures.get(&texture.key()) {
+            return *index;
+        }
+
+        let state = texture.state();
+        let index = match &*state {
+            ResourceState::Ok(data) => {
+                let source = if !data.is_procedural() && !state.path().as_os_str().is_empty() {
+                    let uri = relative_uri(&self.base_dir, &state.path());
+                    Some(self.push_image(json::Image {
+                        buffer_view: None,
+                        mime_type: None,
+                        name: None,
+                        uri: Some(uri),
+                        extensions: None,
+                        extras: Default::default(),
+                    }))
+                } else if let Some(png) = encode_png(data) {
+                    Some(self.push_embedded_image(&png))
+                } else {
+                    Log::writeln(
+                        MessageKind::Warning,
+                        format!(
+                            "glTF: Unable to encode texture {:?} with {:?} pixels, skipping!",
+                            state.path(),
+                            data.pixel_kind()
+                        ),
+                    );
+                    None
+                };
+                source.map(|source| self.push_texture(source, Some(data)))
+            }
+            ResourceState::Pending { path, .. } | ResourceState::LoadError { path, .. } => {
+                let uri = relative_uri(&self.base_dir, path);
+                let source = self.push_image(json::Image {
+                    buffer_view: None,
+                    mime_type: None,
+                    name: None,
+                    uri: Some(uri),
+                    extensions: None,
+                    extras: Default::default(),
+                });
+                Some(self.push_texture(source, None))
+            }
+        };
+
+        self.textures.insert(texture.key(), index);
+        index
+    }
+
+    fn metallic_roughness_texture(
+        &mut self,
+        metallic: (Option<&Texture>, SamplerFallback),
+        roughness: (Option<&Texture>, SamplerFallback),
+    ) -> Option<Index<json::Texture>> {
+        let key = (
+            metallic.0.map(Texture::key).unwrap_or_default(),
+            roughness.0.map(Texture::key).unwrap_or_default(),
+        );
+        if let Some(index) = self.metallic_roughness.get(&key) {
+            return *index;
+        }
+
+        let channel = |(texture, fallback): (Option<&Texture>, SamplerFallback)| match texture {
+            Some(texture) => match &*texture.state() {
+                ResourceState::Ok(data) => red_channel(data),
+                _ => None,
+            },
+            None => Some((1, 1, vec![fallback_value(fallback)])),
+        };
+        let index = match (channel(metallic), channel(roughness)) {
+            (Some(metallic), Some(roughness)) => {
+                let (width, height, pixels) = merge_metallic_roughness(&metallic, &roughness);
+                let mut png = Vec::new();
+                PngEncoder::new(&mut png)
+                    .encode(&pixels, width, height, ColorType::Rgb8)
+                    .ok()
+                    .map(|_| {
+                        let source = self.push_embedded_image(&png);
+                        self.push_texture(source, None)
+                    })
+            }
+            _ => {
+                Log::writeln(
+                    MessageKind::Warning,
+                    "glTF: Metallic or roughness texture is not loaded or has unsupported \
+                    format, skipping!"
+                        .to_owned(),
+                );
+                None
+            }
+        };
+
+        self.metallic_roughness.insert(key, index);
+        index
+    }
+
+    fn material(&mut self, material: &Arc<Mutex<Material>>) -> Index<json::Material> {
+        let key = Arc::as_ptr(material) as usize;
+        if let Some(index) = self.materials.get(&key) {
+            return *index;
+        }
+
+        let material = material.lock().unwrap();
+        let mut result = json::Material::default();
+
+        // glTF colors are in linear space.
+        if let Some(PropertyValue::Color(color)) = material.property_ref("diffuseColor") {
+            result.pbr_metallic_roughness.base_color_factor =
+                PbrBaseColorFactor(Vector4::from(LinearColor::from(*color)).into());
+            if color.a < 255 {
+                result.alpha_mode = Checked::Valid(AlphaMode::Blend);
+            }
+        }
+        if let (Some(texture), _) = sampler_ref(&material, "diffuseTexture") {
+            result.pbr_metallic_roughness.base_color_texture =
+                self.texture(texture).map(|index| texture_info(index, 0));
+        }
+        if let (Some(texture), _) = sampler_ref(&material, "normalTexture") {
+            result.normal_texture = self.texture(texture).map(|index| NormalTexture {
+                index,
+                scale: 1.0,
+                tex_coord: 0,
+                extensions: None,
+                extras: Default::default(),
+            });
+        }
+        if let (Some(texture), _) = sampler_ref(&material, "aoTexture") {
+            result.occlusion_texture = self.texture(texture).map(|index| OcclusionTexture {
+                index,
+                strength: StrengthFactor(1.0),
+                tex_coord: 0,
+                extensions: None,
+                extras: Default::default(),
+            });
+        }
+
+        let emission = sampler_ref(&material, "emissionTexture");
+        let strength = match material.property_ref("emissionStrength") {
+            Some(PropertyValue::Vector3(strength)) => *strength,
+            _ => Vector3::new(1.0, 1.0, 1.0),
+        };
+        // glTF limits emission factor to [0; 1] range.
+        let strength = strength.map(|s| s.clamp(0.0, 1.0));
+        match emission {
+            (Some(texture), _) => {
+                result.emissive_texture = self.texture(texture).map(|index| texture_info(index, 0));
+                if result.emissive_texture.is_some() {
+                    result.emissive_factor = EmissiveFactor(vec3(&strength));
+                }
+            }
+            (None, fallback) => {
+                let value = fallback_value(fallback) as f32 / 255.0;
+                result.emissive_factor = EmissiveFactor(vec3(&(strength * value)));
+            }
+        }
+
+        let metallic = sampler_ref(&material, "metallicTexture");
+        let roughness = sampler_ref(&material, "roughnessTexture");
+        if metallic.0.is_none() && roughness.0.is_none() {
+            let pbr = &mut result.pbr_metallic_roughness;
+            pbr.metallic_factor = StrengthFactor(fallback_value(metallic.1) as f32 / 255.0);
+            pbr.roughness_factor = StrengthFactor(fallback_value(roughness.1) as f32 / 255.0);
+        } else if let Some(index) = self.metallic_roughness_texture(metallic, roughness) {
+            result.pbr_metallic_roughness.metallic_roughness_texture = Some(texture_info(index, 0));
+        }
+
+        if let (Some(texture), _) = sampler_ref(&material, "lightmapTexture") {
+            if let Some(index) = self.texture(texture) {
+                result.extras = json::extras::RawValue::from_string(format!(
+                    r#"{{"lightmapTexture":{{"index":{},"texCoord":1}}}}"#,
+                    index.value()
+                ))
+                .ok();
+            }
+        }
+
+        self.root.materials.push(result);
+        let index = Index::new(self.root.materials.len() as u32 - 1);
+        self.materials.insert(key, index);
+        index
+    }
+
+    fn primitive(
+        &mut self,
+        data: &Arc<RwLock<SurfaceData>>,
+        bones: &[Handle<Node>],
+        joints: &[Handle<Node>],
+    ) -> Option<json::mesh::Primitive> {
+        let key = Arc::as_ptr(data) as usize;
+        if bones.is_empty() {
+            if let Some(primitive) = self.primitives.get(&key) {
+                return Some(primitive.clone());
+            }
+        }
+
+        let data = data.read().unwrap();
+        let vertices = &data.vertex_buffer;
+        let indices = data
+            .geometry_buffer
+            .iter()
+            .flat_map(|triangle| triangle.0.iter())
+            .flat_map(|i| i.to_le_bytes())
+            .collect::<Vec<_>>();
+        if vertices.vertex_count() == 0 || indices.is_empty() {
+            return None;
+        }
+
+        let mut attributes = HashMap::new();
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut tangents = Vec::new();
+        let mut tex_coords = [Vec::new(), Vec::new()];
+        let mut bone_indices = Vec::new();
+        let mut bone_weights = Vec::new();
+        for vertex in vertices.iter() {
+            if let Ok(position) = vertex.read_3_f32(VertexAttributeUsage::Position) {
+                positions.extend_from_slice(&vec3(&position));
+            }
+            if let Ok(normal) = vertex.read_3_f32(VertexAttributeUsage::Normal) {
+                // glTF requires unit normals.
+                let normal = normal
+                    .try_normalize(f32::EPSILON)
+                    .unwrap_or_else(Vector3::y);
+                normals.extend_from_slice(&vec3(&normal));
+            }
+            if let Ok(tangent) = vertex.read_4_f32(VertexAttributeUsage::Tangent) {
+                let xyz = tangent
+                    .xyz()
+                    .try_normalize(f32::EPSILON)
+                    .unwrap_or_else(Vector3::x);
+                let w = if tangent.w < 0.0 { -1.0 } else { 1.0 };
+                tangents.extend_from_slice(&[xyz.x, xyz.y, xyz.z, w]);
+            }
+            for (tex_coords, usage) in tex_coords.iter_mut().zip([
+                VertexAttributeUsage::TexCoord0,
+                VertexAttributeUsage::TexCoord1,
+            ]) {
+                if let Ok(uv) = vertex.read_2_f32(usage) {
+                    tex_coords.extend_from_slice(&[uv.x, uv.y]);
+                }
+            }
+            if !bones.is_empty() {
+                if let (Ok(indices), Ok(weights)) = (
+                    vertex.read_4_u8(VertexAttributeUsage::BoneIndices),
+                    vertex.read_4_f32(VertexAttributeUsage::BoneWeight),
+                ) {
+                    for (index, weight) in indices.iter().zip(weights.iter()) {
+                        // Bone indices of a surface are remapped to the joints of the skin.
+                        let joint = bones
+                            .get(*index as usize)
+                            .and_then(|bone| joints.iter().position(|joint| joint == bone))
+                            .filter(|_| *weight > 0.0);
+                        bone_indices.extend_from_slice(&(joint.unwrap_or(0) as u16).to_le_bytes());
+                        bone_weights.push(if joint.is_some() { *weight } else { 0.0 });
+                    }
+                }
+            }
+        }
+
+        let vertex_count = vertices.vertex_count() as usize;
+        if positions.len() != vertex_count * 3 {
+            return None;
+        }
+        let accessor = self.push_floats(&positions, Type::Vec3, Some(Target::ArrayBuffer), true);
+        attributes.insert(Checked::Valid(Semantic::Positions), accessor);
+        if normals.len() == vertex_count * 3 {
+            let accessor = self.push_floats(&normals, Type::Vec3, Some(Target::ArrayBuffer), false);
+            attributes.insert(Checked::Valid(Semantic::Normals), accessor);
+        }
+        if tangents.len() == vertex_count * 4 {
+            let accessor =
+                self.push_floats(&tangents, Type::Vec4, Some(Target::ArrayBuffer), false);
+            attributes.insert(Checked::Valid(Semantic::Tangents), accessor);
+        }
+        for (set, tex_coords) in tex_coords.iter().enumerate() {
+            if tex_coords.len() == vertex_count * 2 {
+                let accessor =
+                    self.push_floats(tex_coords, Type::Vec2, Some(Target::ArrayBuffer), false);
+                attributes.insert(Checked::Valid(Semantic::TexCoords(set as u32)), accessor);
+            }
+        }
+        if bone_weights.len() == vertex_count * 4 {
+            // Weights must be normalized.
+            for weights in bone_weights.chunks_exact_mut(4) {
+                let sum = weights.iter().sum::<f32>();
+                if sum > 0.0 {
+                    weights.iter_mut().for_each(|w| *w /= sum);
+                }
+            }
+            let accessor = self.push_accessor(
+                &bone_indices,
+                vertex_count,
+                ComponentType::U16,
+                Type::Vec4,
+                Some(Target::ArrayBuffer),
+                None,
+            );
+            attributes.insert(Checked::Valid(Semantic::Joints(0)), accessor);
+            let accessor =
+                self.push_floats(&bone_weights, Type::Vec4, Some(Target::ArrayBuffer), false);
+            attributes.insert(Checked::Valid(Semantic::Weights(0)), accessor);
+        }
+
+        let indices = self.push_accessor(
+            &indices,
+            indices.len() / 4,
+            ComponentType::U32,
+            Type::Scalar,
+            Some(Target::ElementArrayBuffer),
+            None,
+        );
+
+        let primitive = json::mesh::Primitive {
+            attributes,
+            extensions: None,
+            extras: Default::default(),
+            indices: Some(indices),
+            material: None,
+            mode: Checked::Valid(Mode::Triangles),
+            targets: None,
+        };
+        if bones.is_empty() {
+            self.primitives.insert(key, primitive.clone());
+        }
+        Some(primitive)
+    }
+
+    /// Writes every surface of a mesh as a primitive, returns index of the mesh and joints of
+    /// its skin.
+    fn mesh(&mut self, mesh: &Mesh) -> (Option<Index<json::Mesh>>, Vec<Handle<Node>>) {
+        let mut joints = Vec::new();
+        for surface in mesh.surfaces() {
+            for bone in surface.bones() {
+                if !joints.contains(bone) {
+                    joints.push(*bone);
+                }
+            }
+        }
+
+        let mut primitives = Vec::new();
+        for surface in mesh.surfaces() {
+            if let Some(mut primitive) = self.primitive(&surface.data(), surface.bones(), &joints) {
+                primitive.material = Some(self.material(surface.material()));
+                primitives.push(primitive);
+            }
+        }
+        if primitives.is_empty() {
+            return (None, Vec::new());
+        }
+        if !primitives.iter().any(|p| {
+            p.attributes
+                .contains_key(&Checked::Valid(Semantic::Joints(0)))
+        }) {
+            joints.clear();
+        }
+
+        self.root.meshes.push(json::Mesh {
+            extensions: None,
+            extras: Default::default(),
+            name: Some(mesh.name_owned()),
+            primitives,
+            weights: None,
+        });
+        (Some(Index::new(self.root.meshes.len() as u32 - 1)), joints)
+    }
+
+    fn camera(&mut self, camera: &Camera) -> Index<json::Camera> {
+        self.root.cameras.push(json::Camera {
+            name: Some(camera.name_owned()),
+            orthographic: None,
+            perspective: Some(json::camera::Perspective {
+                aspect_ratio: None,
+                yfov: camera.fov(),
+                zfar: Some(camera.z_far()),
+                znear: camera.z_near(),
+                extensions: None,
+                extras: Default::default(),
+            }),
+            type_: Checked::Valid(json::camera::Type::Perspective),
+            extensions: None,
+            extras: Default::default(),
+        });
+        Index::new(self.root.cameras.len() as u32 - 1)
+    }
+
+    fn light(&mut self, light: &Light) -> Index<khr_lights_punctual::Light> {
+        let (type_, range, spot) = match light {
+            Light::Directional(_) => (khr_lights_punctual::Type::Directional, None, None),
+            Light::Point(point) => (khr_lights_punctual::Type::Point, Some(point.radius()), None),
+            Light::Spot(spot) => {
+                // glTF angles are measured from the axis of the cone.
+                let outer = (0.5 * spot.full_cone_angle()).min(std::f32::consts::FRAC_PI_2);
+                let inner = (0.5 * spot.hotspot_cone_angle()).min(outer);
+                (
+                    khr_lights_punctual::Type::Spot,
+                    Some(spot.distance()),
+                    Some(khr_lights_punctual::Spot {
+                        inner_cone_angle: inner,
+                        outer_cone_angle: outer,
+                    }),
+                )
+            }
+        };
+        let color = LinearColor::from(light.color());
+        let lights = &mut self
+            .root
+            .extensions
+            .get_or_insert_with(Default::default)
+            .khr_lights_punctual
+            .get_or_insert_with(|| json::extensions::root::KhrLightsPunctual { lights: Vec::new() })
+            .lights;
+        lights.push(khr_lights_punctual::Light {
+            color: [color.r, color.g, color.b],
+            extensions: None,
+            extras: Default::default(),
+            intensity: light.intensity(),
+            name: Some(light.name_owned()),
+            range,
+            spot,
+            type_: Checked::Valid(type_),
+        });
+        Index::new(lights.len() as u32 - 1)
+    }
+
+    fn push_node(&mut self, node: json::Node) -> Index<json::Node> {
+        self.root.nodes.push(node);
+        Index::new(self.root.nodes.len() as u32 - 1)
+    }
+
+    fn node(&mut self, handle: Handle<Node>) -> Index<json::Node> {
+        let graph = self.graph;
+        let node = &graph[handle];
+        let transform = node.local_transform();
+        let (translation, rotation, scale) = trs(
+            transform,
+            **transform.position(),
+            **transform.rotation(),
+            **transform.scale(),
+        );
+        let mut result = json::Node {
+            camera: None,
+            children: None,
+            extensions: None,
+            extras: Default::default(),
+            matrix: None,
+            mesh: None,
+            name: Some(node.name_owned()),
+            rotation: Some(json::scene::UnitQuaternion(quat(&rotation))),
+            scale: Some(vec3(&scale)),
+            translation: Some(vec3(&translation)),
+            skin: None,
+            weights: None,
+        };
+        let mut children = Vec::new();
+
+        // Cameras and lights of glTF look along -Z, so they are attached to child nodes which
+        // compensate difference in orientation.
+        let attachment = |rotation: UnitQuaternion<f32>, suffix: &str| json::Node {
+            camera: None,
+            children: None,
+            extensions: None,
+            extras: Default::default(),
+            matrix: None,
+            mesh: None,
+            name: Some(format!("{}_{}", node.name(), suffix)),
+            rotation: Some(json::scene::UnitQuaternion(quat(&rotation))),
+            scale: None,
+            translation: None,
+            skin: None,
+            weights: None,
+        };
+        match node {
+            Node::Mesh(mesh) => {
+                let (mesh, joints) = self.mesh(mesh);
+                result.mesh = mesh;
+                if !joints.is_empty() {
+                    self.skins.push(PendingSkin {
+                        node: Index::new(self.root.nodes.len() as u32),
+                        joints,
+                    });
+                }
+            }
+            Node::Camera(camera) => {
+                // Cameras of the engine look along +Z.
+                let mut child = attachment(
+                    UnitQuaternion::from_axis_angle(&Vector3::y_axis(), std::f32::consts::PI),
+                    "Camera",
+                );
+                child.camera = Some(self.camera(camera));
+                children.push(self.push_node(child));
+            }
+            Node::Light(light) => {
+                // Lights of the engine emit light along -Y.
+                let mut child = attachment(
+                    UnitQuaternion::from_axis_angle(
+                        &Vector3::x_axis(),
+                        -std::f32::consts::FRAC_PI_2,
+                    ),
+                    "Light",
+                );
+                child.extensions = Some(json::extensions::scene::Node {
+                    khr_lights_punctual: Some(khr_lights_punctual::KhrLightsPunctual {
+                        light: self.light(light),
+                    }),
+                });
+                children.push(self.push_node(child));
+            }
+            _ => (),
+        }
+
+        let index = self.push_node(result);
+        self.nodes.insert(handle, index);
+        for &child in node.children() {
+            children.push(self.node(child));
+        }
+        if !children.is_empty() {
+            self.root.nodes[index.value()].children = Some(children);
+        }
+        index
+    }
+
+    fn skins(&mut self) {
+        for skin in std::mem::take(&mut self.skins) {
+            let joints = skin
+                .joints
+                .iter()
+                .filter_map(|joint| self.nodes.get(joint).cloned())
+                .collect::<Vec<_>>();
+            if joints.len() != skin.joints.len() {
+                Log::writeln(
+                    MessageKind::Warning,
+                    format!(
+                        "glTF: Skin of node {} has bones outside of the graph, skipping!",
+                        skin.node.value()
+                    ),
+                );
+                continue;
+            }
+            let matrices = skin
+                .joints
+                .iter()
+                .flat_map(|joint| {
+                    self.graph[*joint]
+                        .inv_bind_pose_transform()
+                        .as_slice()
+                        .to_vec()
+                })
+                .collect::<Vec<_>>();
+            let inverse_bind_matrices = self.push_floats(&matrices, Type::Mat4, None, false);
+            self.root.skins.push(json::Skin {
+                extensions: None,
+                extras: Default::default(),
+                inverse_bind_matrices: Some(inverse_bind_matrices),
+                joints,
+                name: None,
+                skeleton: None,
+            });
+            self.root.nodes[skin.node.value()].skin =
+                Some(Index::new(self.root.skins.len() as u32 - 1));
+        }
+    }
+
+    fn animations(&mut self, scene: &Scene) {
+        for (i, animation) in scene.animations.iter().enumerate() {
+            let mut result = json::Animation {
+                extensions: None,
+                extras: Default::default(),
+                channels: Vec::new(),
+                name: Some(format!("Animation{}", i)),
+                samplers: Vec::new(),
+            };
+            for track in animation.get_tracks() {
+                let node = match self.nodes.get(&track.get_node()) {
+                    Some(node) if !track.get_key_frames().is_empty() => *node,
+                    _ => continue,
+                };
+                let transform = self.graph[track.get_node()].local_transform();
+                let mut times = Vec::new();
+                let mut translations = Vec::new();
+                let mut rotations = Vec::new();
+                let mut scales = Vec::new();
+                for key in track.get_key_frames() {
+                    let (translation, rotation, scale) =
+                        trs(transform, key.position, key.rotation, key.scale);
+                    times.push(key.time);
+                    translations.extend_from_slice(&vec3(&translation));
+                    rotations.extend_from_slice(&quat(&rotation));
+                    scales.extend_from_slice(&vec3(&scale));
+                }
+
+                // Times are shared between samplers of the track.
+                let input = self.push_floats(&times, Type::Scalar, None, true);
+                for (values, type_, path) in [
+                    (translations, Type::Vec3, Property::Translation),
+                    (rotations, Type::Vec4, Property::Rotation),
+                    (scales, Type::Vec3, Property::Scale),
+                ] {
+                    let output = self.push_floats(&values, type_, None, false);
+                    result.samplers.push(json::animation::Sampler {
+                        extensions: None,
+                        extras: Default::default(),
+                        input,
+                        interpolation: Checked::Valid(Interpolation::Linear),
+                        output,
+                    });
+                    result.channels.push(json::animation::Channel {
+                        sampler: Index::new(result.samplers.len() as u32 - 1),
+                        target: json::animation::Target {
+                            extensions: None,
+                            extras: Default::default(),
+                            node,
+                            path: Checked::Valid(path),
+                        },
+                        extensions: None,
+                        extras: Default::default(),
+                    });
+                }
+            }
+            if !result.channels.is_empty() {
+                self.root.animations.push(result);
+            }
+        }
+    }
+}
+
+/// Converts graph of a scene into glTF document and its binary buffer. Paths of textures are
+/// made relative to given directory.
+fn export(scene: &Scene, base_dir: &Path) -> (json::Root, Vec<u8>) {
+    let mut exporter = Exporter {
+        graph: &scene.graph,
+        base_dir: base_dir.to_owned(),
+        root: Default::default(),
+        buffer: Default::default(),
+        nodes: Default::default(),
+        materials: Default::default(),
+        primitives: Default::default(),
+        textures: Default::default(),
+        metallic_roughness: Default::default(),
+        skins: Default::default(),
+    };
+    exporter.root.asset = json::Asset {
+        copyright: None,
+        extensions: None,
+        extras: Default::default(),
+        generator: Some(format!("rg3d {}", env!("CARGO_PKG_VERSION"))),
+        min_version: None,
+        version: "2.0".to_owned(),
+    };
+
+    let graph = &scene.graph;
+    let nodes = graph[graph.get_root()]
+        .children()
+        .iter()
+        .map(|child| exporter.node(*child))
+        .collect();
+    exporter.root.scenes.push(json::Scene {
+        extensions: None,
+        extras: Default::default(),
+        name: None,
+        nodes,
+    });
+    exporter.root.scene = Some(Index::new(0));
+    exporter.skins();
+    exporter.animations(scene);
+
+    if exporter.root.extensions.is_some() {
+        exporter
+            .root
+            .extensions_used
+            .push(LIGHTS_EXTENSION.to_owned());
+    }
+    align(&mut exporter.buffer);
+    if !exporter.buffer.is_empty() {
+        exporter.root.buffers.push(json::Buffer {
+            byte_length: exporter.buffer.len() as u32,
+            name: None,
+            uri: None,
+            extensions: None,
+            extras: Default::default(),
+        });
+    }
+    (exporter.root, exporter.buffer)
+}
+
+/// Converts graph of a scene into binary glTF (`.glb`). Textures that were loaded from files
+/// are referenced by paths relative to working directory.
+pub fn export_to_glb(scene: &Scene) -> Result<Vec<u8>, GltfError> {
+    let (root, buffer) = export(scene, Path::new(""));
+    make_glb(&root, buffer)
+}
+
+fn make_glb(root: &json::Root, buffer: Vec<u8>) -> Result<Vec<u8>, GltfError> {
+    let json = root.to_vec()?;
+    let glb = Glb {
+        // Length is calculated by the writer.
+        header: Header {
+            magic: *b"glTF",
+            version: 2,
+            length: 0,
+        },
+        json: Cow::Owned(json),
+        bin: if buffer.is_empty() {
+            None
+        } else {
+            Some(Cow::Owned(buffer))
+        },
+    };
+    Ok(glb.to_vec()?)
+}
+
+/// Saves graph of a scene to a glTF file. If the file has `.glb` extension, binary format is
+/// used, otherwise the document is written as JSON and its binary buffer is written to a `.bin`
+/// file with the same name. Textures that were loaded from files are referenced by paths
+/// relative to the directory of the output file.
+pub fn save<P: AsRef<Path>>(scene: &Scene, path: P) -> Result<(), GltfError> {
+    let path = path.as_ref();
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let (mut root, buffer) = export(scene, base_dir);
+
+    let is_binary = path
+        .extension()
+        .map(|ext| ext.eq_ignore_ascii_case("glb"))
+        .unwrap_or_default();
+    if is_binary {
+        std::fs::write(path, make_glb(&root, buffer)?)?;
+    } else {
+        if let Some(json_buffer) = root.buffers.first_mut() {
+            let buffer_path = path.with_extension("bin");
+            let file_name = buffer_path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            json_buffer.uri = Some(escape_uri(&file_name));
+            std::fs::write(buffer_path, buffer)?;
+        }
+        std::fs::write(path, root.to_vec_pretty()?)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        animation::{Animation, KeyFrame, Track},
+        core::algebra::{Matrix4, UnitQuaternion, Vector3},
+        resource::gltf::export::{decompose, export_to_glb, relative_uri},
+        scene::{
+            base::BaseBuilder,
+            camera::CameraBuilder,
+            light::{point::PointLightBuilder, BaseLightBuilder},
+            mesh::{
+                surface::{Surface, SurfaceData},
+                MeshBuilder,
+            },
+            Scene,
+        },
+    };
+    use std::{
+        path::Path,
+        sync::{Arc, RwLock},
+    };
+
+    #[test]
+    fn test_decompose() {
+        let rotation = UnitQuaternion::from_euler_angles(0.1, 0.2, 0.3);
+        let matrix = Matrix4::new_translation(&Vector3::new(1.0, 2.0, 3.0))
+            * rotation.to_homogeneous()
+            * Matrix4::new_nonuniform_scaling(&Vector3::new(2.0, 3.0, -4.0));
+        let (t, r, s) = decompose(&matrix);
+        let result =
+            Matrix4::new_translation(&t) * r.to_homogeneous() * Matrix4::new_nonuniform_scaling(&s);
+        assert!((result - matrix).abs().max() < 1.0e-5);
+        assert_eq!(t, Vector3::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn test_relative_uri() {
+        assert_eq!(
+            relative_uri(
+                Path::new("data/export"),
+                Path::new("data/textures/my wall.png")
+            ),
+            "../textures/my%20wall.png"
+        );
+        assert_eq!(
+            relative_uri(Path::new(""), Path::new("./data/a.png")),
+            "data/a.png"
+        );
+    }
+
+    #[test]
+    fn test_export_to_glb() {
+        let mut scene = Scene::new();
+        let cube = MeshBuilder::new(BaseBuilder::new().with_name("Cube"))
+            .with_surfaces(vec![Surface::new(Arc::new(RwLock::new(
+                SurfaceData::make_cube(Matrix4::identity()),
+            )))])
+            .build(&mut scene.graph);
+        let light =
+            PointLightBuilder::new(BaseLightBuilder::new(BaseBuilder::new().with_name("Light")))
+                .with_radius(5.0)
+                .build(&mut scene.graph);
+        scene.graph.link_nodes(light, cube);
+        CameraBuilder::new(BaseBuilder::new().with_name("Camera")).build(&mut scene.graph);
+
+        let mut track = Track::new();
+        track.set_node(cube);
+        for time in [0.0, 1.0] {
+            track.add_key_frame(KeyFrame::new(
+                time,
+                Vector3::new(time, 0.0, 0.0),
+                Vector3::new(1.0, 1.0, 1.0),
+                UnitQuaternion::identity(),
+            ));
+        }
+        let mut animation = Animation::default();
+        animation.add_track(track);
+        scene.animations.add(animation);
+
+        let glb = export_to_glb(&scene).unwrap();
+        let gltf = gltf::Gltf::from_slice(&glb).unwrap();
+        assert_eq!(gltf.meshes().len(), 1);
+        assert_eq!(gltf.materials().len(), 1);
+        assert_eq!(gltf.cameras().len(), 1);
+        assert_eq!(gltf.lights().unwrap().count(), 1);
+        assert_eq!(gltf.animations().len(), 1);
+        assert_eq!(gltf.animations().next().unwrap().channels().count(), 3);
+
+        // Root nodes are the cube and the camera, the light is attached to the cube by child
+        // node that holds its orientation.
+        let scene = gltf.default_scene().unwrap();
+        assert_eq!(scene.nodes().count(), 2);
+        let cube = scene.nodes().find(|n| n.name() == Some("Cube")).unwrap();
+        let primitive = cube.mesh().unwrap().primitives().next().unwrap();
+        assert!(primitive.get(&gltf::Semantic::Positions).is_some());
+        assert!(primitive.get(&gltf::Semantic::TexCoords(0)).is_some());
+        let light = cube.children().next().unwrap();
+        assert_eq!(light.name(), Some("Light"));
+        assert!(light.children().next().unwrap().light().is_some());
+    }
+}
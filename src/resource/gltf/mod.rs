@@ -0,0 +1,1234 @@
+//! Contains all methods to load and convert glTF 2.0 model format.
+//!
+//! glTF is an open format for transmission of 3D scenes, most of 3D editors can export it. Both
+//! variants of the format are supported: `.gltf` (JSON document with external or embedded buffers)
+//! and `.glb` (single binary file). The loader converts node hierarchy, meshes with skinning,
+//! metallic-roughness materials, cameras, punctual lights (`KHR_lights_punctual` extension) and
+//! animations of node transforms.
+//!
+//! # Limitations
+//!
+//! - Only first set of texture coordinates is used, vertex colors are ignored.
+//! - Morph targets and their animations are ignored.
+//! - Orthographic cameras are converted to perspective cameras.
+//! - Light intensities are used as is, the engine does not use physical units for lights.
+//!
+//! Normally you should never use methods from this module directly, use resource manager to load
+//! models and create their instances. Scenes can be saved back to glTF using [`export`] module.
+
+pub mod error;
+pub mod export;
+
+use crate::{
+    animation::{Animation, KeyFrame, Track},
+    core::{
+        algebra::{Matrix4, Quaternion, UnitQuaternion, Vector2, Vector3, Vector4},
+        color::{Color, LinearColor},
+        instant::Instant,
+        io,
+        math::TriangleDefinition,
+        pool::Handle,
+    },
+    engine::resource_manager::{MaterialSearchOptions, ResourceManager, TextureImportOptions},
+    material::{shader::SamplerFallback, Material, PropertyValue},
+    resource::{
+        find_texture,
+        gltf::error::GltfError,
+        texture::{
+            CompressionOptions, Texture, TextureKind, TextureMagnificationFilter,
+            TextureMinificationFilter, TexturePixelKind, TextureWrapMode,
+        },
+    },
+    scene::{
+        base::BaseBuilder,
+        camera::CameraBuilder,
+        graph::Graph,
+        light::{
+            directional::DirectionalLightBuilder, point::PointLightBuilder, spot::SpotLightBuilder,
+            BaseLightBuilder,
+        },
+        mesh::{
+            buffer::{GeometryBuffer, VertexBuffer},
+            surface::{Surface, SurfaceData, VertexWeight, VertexWeightSet},
+            vertex::{AnimatedVertex, StaticVertex},
+            MeshBuilder,
+        },
+        node::Node,
+        transform::TransformBuilder,
+        Scene,
+    },
+    utils::log::{Log, MessageKind},
+};
+use gltf::{
+    animation::{util::ReadOutputs, Interpolation, Property},
+    camera::Projection,
+    khr_lights_punctual::Kind,
+    mesh::Mode,
+    texture::{MagFilter, MinFilter, WrappingMode},
+    Gltf,
+};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap, HashSet},
+    convert::TryFrom,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
+};
+
+/// Range of point and spot lights which have infinite range in glTF.
+const DEFAULT_LIGHT_RANGE: f32 = 10.0;
+
+/// Size of bone matrices array of the standard shader.
+const MAX_BONES: usize = 60;
+
+/// Amount of samples per segment of a cubic spline curve, the engine interpolates key frames
+/// linearly, so cubic curves are baked.
+const CUBIC_SPLINE_SAMPLES: usize = 4;
+
+/// Time offset of an extra key frame which emulates step interpolation.
+const STEP_EPSILON: f32 = 1.0e-4;
+
+/// Decodes percent-encoded characters of a relative URI.
+fn decode_uri(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(byte) = uri
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8(decoded).unwrap_or_else(|_| uri.to_owned())
+}
+
+async fn load_uri(uri: &str, base_path: &Path) -> Result<Vec<u8>, GltfError> {
+    if let Some(data) = uri.strip_prefix("data:") {
+        match data.split_once(";base64,") {
+            Some((_, data)) => Ok(base64::decode(data)?),
+            None => Err(GltfError::UnsupportedUri(uri.to_owned())),
+        }
+    } else {
+        Ok(io::load_file(base_path.join(decode_uri(uri))).await?)
+    }
+}
+
+async fn load_buffers(gltf: &mut Gltf, base_path: &Path) -> Result<Vec<Vec<u8>>, GltfError> {
+    let mut blob = gltf.blob.take();
+    let mut buffers = Vec::new();
+    for buffer in gltf.buffers() {
+        let data = match buffer.source() {
+            gltf::buffer::Source::Bin => blob
+                .take()
+                .ok_or_else(|| GltfError::UnsupportedUri("BIN".to_owned()))?,
+            gltf::buffer::Source::Uri(uri) => load_uri(uri, base_path).await?,
+        };
+        if data.len() < buffer.length() {
+            return Err(GltfError::InvalidBuffer(buffer.index()));
+        }
+        buffers.push(data);
+    }
+    Ok(buffers)
+}
+
+enum ImageData {
+    File(PathBuf),
+    Embedded(Vec<u8>),
+}
+
+async fn load_image(
+    image: gltf::Image<'_>,
+    buffers: &[Vec<u8>],
+    model_path: &Path,
+    base_path: &Path,
+    material_search_options: &MaterialSearchOptions,
+) -> Result<ImageData, GltfError> {
+    match image.source() {
+        gltf::image::Source::View { view, .. } => {
+            let buffer = view.buffer().index();
+            buffers
+                .get(buffer)
+                .and_then(|data| data.get(view.offset()..view.offset() + view.length()))
+                .map(|data| ImageData::Embedded(data.to_vec()))
+                .ok_or(GltfError::InvalidBuffer(buffer))
+        }
+        gltf::image::Source::Uri { uri, .. } => {
+            if uri.starts_with("data:") {
+                Ok(ImageData::Embedded(load_uri(uri, base_path).await?))
+            } else {
+                // glTF requires paths to be relative to the model file, but textures still
+                // could be moved somewhere else.
+                let path = base_path.join(decode_uri(uri));
+                Ok(ImageData::File(
+                    find_texture(&path, model_path, material_search_options).await,
+                ))
+            }
+        }
+    }
+}
+
+fn convert_min_filter(filter: MinFilter) -> TextureMinificationFilter {
+    match filter {
+        MinFilter::Nearest => TextureMinificationFilter::Nearest,
+        MinFilter::Linear => TextureMinificationFilter::Linear,
+        MinFilter::NearestMipmapNearest => TextureMinificationFilter::NearestMipMapNearest,
+        MinFilter::LinearMipmapNearest => TextureMinificationFilter::LinearMipMapNearest,
+        MinFilter::NearestMipmapLinear => TextureMinificationFilter::NearestMipMapLinear,
+        MinFilter::LinearMipmapLinear => TextureMinificationFilter::LinearMipMapLinear,
+    }
+}
+
+fn convert_mag_filter(filter: MagFilter) -> TextureMagnificationFilter {
+    match filter {
+        MagFilter::Nearest => TextureMagnificationFilter::Nearest,
+        MagFilter::Linear => TextureMagnificationFilter::Linear,
+    }
+}
+
+fn convert_wrap_mode(mode: WrappingMode) -> TextureWrapMode {
+    match mode {
+        WrappingMode::ClampToEdge => TextureWrapMode::ClampToEdge,
+        WrappingMode::MirroredRepeat => TextureWrapMode::MirroredRepeat,
+        WrappingMode::Repeat => TextureWrapMode::Repeat,
+    }
+}
+
+/// Returns import options for a texture with non-default sampler, default import options of
+/// resource manager are used otherwise.
+fn import_options(sampler: &gltf::texture::Sampler) -> Option<TextureImportOptions> {
+    if sampler.min_filter().is_none()
+        && sampler.mag_filter().is_none()
+        && sampler.wrap_s() == WrappingMode::Repeat
+        && sampler.wrap_t() == WrappingMode::Repeat
+    {
+        return None;
+    }
+
+    let mut options = TextureImportOptions::default()
+        .with_s_wrap_mode(convert_wrap_mode(sampler.wrap_s()))
+        .with_t_wrap_mode(convert_wrap_mode(sampler.wrap_t()));
+    if let Some(filter) = sampler.min_filter() {
+        options = options.with_minification_filter(convert_min_filter(filter));
+    }
+    if let Some(filter) = sampler.mag_filter() {
+        options = options.with_magnification_filter(convert_mag_filter(filter));
+    }
+    Some(options)
+}
+
+fn constant_texture(value: f32) -> Option<Texture> {
+    Texture::from_bytes(
+        TextureKind::Rectangle {
+            width: 1,
+            height: 1,
+        },
+        TexturePixelKind::R8,
+        vec![(value.clamp(0.0, 1.0) * 255.0).round() as u8],
+        false,
+    )
+}
+
+/// Splits packed metallic-roughness texture of glTF into separate metallic and roughness
+/// textures which are used by the standard shader.
+fn split_metallic_roughness(
+    data: &[u8],
+    metallic_factor: f32,
+    roughness_factor: f32,
+) -> Option<(Texture, Texture)> {
+    let image = match image::load_from_memory(data) {
+        Ok(image) => image.to_rgba8(),
+        Err(e) => {
+            Log::writeln(
+                MessageKind::Error,
+                format!(
+                    "glTF: Unable to decode metallic-roughness texture! Reason: {:?}",
+                    e
+                ),
+            );
+            return None;
+        }
+    };
+
+    let (width, height) = image.dimensions();
+    let mut metallic = Vec::with_capacity((width * height) as usize);
+    let mut roughness = Vec::with_capacity((width * height) as usize);
+    for pixel in image.pixels() {
+        // Roughness is stored in green channel, metalness - in blue.
+        roughness.push((pixel[1] as f32 * roughness_factor.clamp(0.0, 1.0)) as u8);
+        metallic.push((pixel[2] as f32 * metallic_factor.clamp(0.0, 1.0)) as u8);
+    }
+
+    let kind = TextureKind::Rectangle { width, height };
+    Some((
+        Texture::from_bytes(kind, TexturePixelKind::R8, metallic, false)?,
+        Texture::from_bytes(kind, TexturePixelKind::R8, roughness, false)?,
+    ))
+}
+
+fn set_property(material: &mut Material, name: &str, value: PropertyValue) {
+    if let Err(e) = material.set_property(name, value) {
+        Log::writeln(
+            MessageKind::Error,
+            format!(
+                "Unable to set material property {} for glTF material! Reason: {:?}",
+                name, e
+            ),
+        );
+    }
+}
+
+fn quat_from_xyzw(v: Vector4<f32>) -> UnitQuaternion<f32> {
+    if v.norm_squared() < f32::EPSILON {
+        UnitQuaternion::identity()
+    } else {
+        UnitQuaternion::from_quaternion(Quaternion::new(v.w, v.x, v.y, v.z))
+    }
+}
+
+fn rest_transform(node: &gltf::Node) -> (Vector3<f32>, UnitQuaternion<f32>, Vector3<f32>) {
+    let (translation, rotation, scale) = node.transform().decomposed();
+    (
+        Vector3::from(translation),
+        quat_from_xyzw(Vector4::from(rotation)),
+        Vector3::from(scale),
+    )
+}
+
+fn node_name(node: &gltf::Node) -> String {
+    match node.name() {
+        Some(name) => name.to_owned(),
+        None => format!("Node{}", node.index()),
+    }
+}
+
+/// Converts triangle list, strip or fan into a list of triangles.
+fn make_triangles(mode: Mode, indices: &[u32]) -> Option<Vec<TriangleDefinition>> {
+    let triangles = match mode {
+        Mode::Triangles => indices
+            .chunks_exact(3)
+            .map(|t| TriangleDefinition([t[0], t[1], t[2]]))
+            .collect(),
+        Mode::TriangleStrip => (0..indices.len().saturating_sub(2))
+            .map(|i| {
+                // Keep winding order of every second triangle.
+                if i % 2 == 0 {
+                    TriangleDefinition([indices[i], indices[i + 1], indices[i + 2]])
+                } else {
+                    TriangleDefinition([indices[i + 1], indices[i], indices[i + 2]])
+                }
+            })
+            .collect(),
+        Mode::TriangleFan => (1..indices.len().saturating_sub(1))
+            .map(|i| TriangleDefinition([indices[0], indices[i], indices[i + 1]]))
+            .collect(),
+        Mode::Points | Mode::Lines | Mode::LineLoop | Mode::LineStrip => return None,
+    };
+    Some(triangles)
+}
+
+/// Animation curve of a single property of a node.
+struct Curve {
+    times: Vec<f32>,
+    // Cubic spline curves store (in-tangent, value, out-tangent) triples.
+    values: Vec<Vector4<f32>>,
+    interpolation: Interpolation,
+}
+
+impl Curve {
+    fn value(&self, i: usize) -> Vector4<f32> {
+        match self.interpolation {
+            Interpolation::CubicSpline => self.values[3 * i + 1],
+            Interpolation::Linear | Interpolation::Step => self.values[i],
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        let values_per_key = match self.interpolation {
+            Interpolation::CubicSpline => 3,
+            Interpolation::Linear | Interpolation::Step => 1,
+        };
+        !self.times.is_empty() && self.values.len() == values_per_key * self.times.len()
+    }
+
+    fn sample(&self, time: f32, is_rotation: bool) -> Vector4<f32> {
+        let last = self.times.len() - 1;
+        if time <= self.times[0] {
+            return self.value(0);
+        } else if time >= self.times[last] {
+            return self.value(last);
+        }
+
+        let next = self.times.partition_point(|&t| t <= time);
+        let prev = next - 1;
+        let dt = self.times[next] - self.times[prev];
+        if dt <= f32::EPSILON {
+            return self.value(prev);
+        }
+        let k = (time - self.times[prev]) / dt;
+
+        match self.interpolation {
+            Interpolation::Step => self.value(prev),
+            Interpolation::Linear => {
+                if is_rotation {
+                    let a = quat_from_xyzw(self.value(prev));
+                    let b = quat_from_xyzw(self.value(next));
+                    a.try_slerp(&b, k, f32::EPSILON)
+                        .unwrap_or(a)
+                        .into_inner()
+                        .coords
+                } else {
+                    self.value(prev).lerp(&self.value(next), k)
+                }
+            }
+            Interpolation::CubicSpline => {
+                let p0 = self.values[3 * prev + 1];
+                let m0 = self.values[3 * prev + 2] * dt;
+                let p1 = self.values[3 * next + 1];
+                let m1 = self.values[3 * next] * dt;
+                let k2 = k * k;
+                let k3 = k2 * k;
+                p0 * (2.0 * k3 - 3.0 * k2 + 1.0)
+                    + m0 * (k3 - 2.0 * k2 + k)
+                    + p1 * (-2.0 * k3 + 3.0 * k2)
+                    + m1 * (k3 - k2)
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct NodeCurves {
+    translation: Option<Curve>,
+    rotation: Option<Curve>,
+    scale: Option<Curve>,
+}
+
+impl NodeCurves {
+    /// Returns sorted times of key frames, which are enough to reproduce every curve with linear
+    /// interpolation between key frames.
+    fn key_times(&self) -> Vec<f32> {
+        let mut times = Vec::new();
+        for curve in [&self.translation, &self.rotation, &self.scale]
+            .iter()
+            .filter_map(|curve| curve.as_ref())
+        {
+            for (i, &time) in curve.times.iter().enumerate() {
+                times.push(time);
+                if let Some(&next) = curve.times.get(i + 1) {
+                    match curve.interpolation {
+                        Interpolation::Step => times.push((next - STEP_EPSILON).max(time)),
+                        Interpolation::CubicSpline => {
+                            for s in 1..CUBIC_SPLINE_SAMPLES {
+                                let t = s as f32 / CUBIC_SPLINE_SAMPLES as f32;
+                                times.push(time + (next - time) * t);
+                            }
+                        }
+                        Interpolation::Linear => (),
+                    }
+                }
+            }
+        }
+        times.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        times.dedup_by(|a, b| (*a - *b).abs() < 0.5 * STEP_EPSILON);
+        times
+    }
+}
+
+#[derive(Default)]
+struct SkinnedSurface {
+    // Indices of joints in a skin, position of a joint in the list is the index of the bone in
+    // the surface.
+    joints: Vec<usize>,
+    // Bone indices and weights of every vertex.
+    weights: Vec<([u8; 4], [f32; 4])>,
+}
+
+struct PendingSkin {
+    mesh: Handle<Node>,
+    skin: usize,
+    surfaces: Vec<SkinnedSurface>,
+}
+
+struct Loader<'a> {
+    gltf: &'a Gltf,
+    buffers: Vec<Vec<u8>>,
+    images: Vec<ImageData>,
+    textures: HashMap<usize, Option<Texture>>,
+    materials: Vec<Arc<Mutex<Material>>>,
+    resource_manager: ResourceManager,
+}
+
+impl<'a> Loader<'a> {
+    fn texture(&mut self, texture: gltf::Texture) -> Option<Texture> {
+        if let Some(result) = self.textures.get(&texture.index()) {
+            return result.clone();
+        }
+
+        let sampler = texture.sampler();
+        let result = match self.images.get(texture.source().index())? {
+            ImageData::File(path) => Some(
+                self.resource_manager
+                    .request_texture(path, import_options(&sampler)),
+            ),
+            ImageData::Embedded(data) => {
+                match Texture::load_from_memory(data, CompressionOptions::NoCompression) {
+                    Ok(result) => {
+                        let mut data = result.data_ref();
+                        data.set_s_wrap_mode(convert_wrap_mode(sampler.wrap_s()));
+                        data.set_t_wrap_mode(convert_wrap_mode(sampler.wrap_t()));
+                        if let Some(filter) = sampler.min_filter() {
+                            data.set_minification_filter(convert_min_filter(filter));
+                        }
+                        if let Some(filter) = sampler.mag_filter() {
+                            data.set_magnification_filter(convert_mag_filter(filter));
+                        }
+                        drop(data);
+                        Some(result)
+                    }
+                    Err(e) => {
+                        Log::writeln(
+                            MessageKind::Error,
+                            format!(
+                                "glTF: Unable to load embedded texture {}! Reason: {:?}",
+                                texture.index(),
+                                e
+                            ),
+                        );
+                        None
+                    }
+                }
+            }
+        };
+
+        self.textures.insert(texture.index(), result.clone());
+        result
+    }
+
+    async fn image_bytes(&self, index: usize) -> Option<Vec<u8>> {
+        match self.images.get(index)? {
+            ImageData::File(path) => match io::load_file(path).await {
+                Ok(data) => Some(data),
+                Err(e) => {
+                    Log::writeln(
+                        MessageKind::Error,
+                        format!("glTF: Unable to load {}! Reason: {:?}", path.display(), e),
+                    );
+                    None
+                }
+            },
+            ImageData::Embedded(data) => Some(data.clone()),
+        }
+    }
+
+    fn sampler(
+        &mut self,
+        texture: Option<gltf::Texture>,
+        fallback: SamplerFallback,
+    ) -> PropertyValue {
+        PropertyValue::Sampler {
+            value: texture.and_then(|texture| self.texture(texture)),
+            fallback,
+        }
+    }
+
+    async fn convert_material(&mut self, material: gltf::Material<'_>) -> Material {
+        let mut result = Material::standard();
+        let pbr = material.pbr_metallic_roughness();
+
+        // glTF colors are in linear space.
+        let diffuse_color = LinearColor::from(Vector4::from(pbr.base_color_factor()));
+        set_property(
+            &mut result,
+            "diffuseColor",
+            PropertyValue::Color(Color::from(diffuse_color)),
+        );
+        let diffuse_texture = pbr.base_color_texture().map(|info| info.texture());
+        let value = self.sampler(diffuse_texture, SamplerFallback::White);
+        set_property(&mut result, "diffuseTexture", value);
+
+        let normal_texture = material.normal_texture().map(|info| info.texture());
+        let value = self.sampler(normal_texture, SamplerFallback::Normal);
+        set_property(&mut result, "normalTexture", value);
+
+        let ao_texture = material.occlusion_texture().map(|info| info.texture());
+        let value = self.sampler(ao_texture, SamplerFallback::White);
+        set_property(&mut result, "aoTexture", value);
+
+        let emissive_factor = Vector3::from(material.emissive_factor());
+        let emissive_texture = material.emissive_texture().map(|info| info.texture());
+        if emissive_texture.is_some() || emissive_factor != Vector3::default() {
+            // Emission without a texture is defined by the factor only.
+            let value = self.sampler(emissive_texture, SamplerFallback::White);
+            set_property(&mut result, "emissionTexture", value);
+            set_property(
+                &mut result,
+                "emissionStrength",
+                PropertyValue::Vector3(emissive_factor),
+            );
+        }
+
+        let (metallic_factor, roughness_factor) = (pbr.metallic_factor(), pbr.roughness_factor());
+        let mut textures = None;
+        if let Some(info) = pbr.metallic_roughness_texture() {
+            if let Some(data) = self.image_bytes(info.texture().source().index()).await {
+                textures = split_metallic_roughness(&data, metallic_factor, roughness_factor);
+            }
+        }
+        let (metallic, roughness) = match textures {
+            Some((metallic, roughness)) => (Some(metallic), Some(roughness)),
+            None => (
+                constant_texture(metallic_factor),
+                constant_texture(roughness_factor),
+            ),
+        };
+        set_property(
+            &mut result,
+            "metallicTexture",
+            PropertyValue::Sampler {
+                value: metallic,
+                fallback: SamplerFallback::Black,
+            },
+        );
+        set_property(
+            &mut result,
+            "roughnessTexture",
+            PropertyValue::Sampler {
+                value: roughness,
+                fallback: SamplerFallback::White,
+            },
+        );
+
+        result
+    }
+
+    fn convert_primitive(
+        &self,
+        mesh: &gltf::Mesh,
+        primitive: &gltf::Primitive,
+        joint_count: Option<usize>,
+    ) -> Result<Option<(SurfaceData, SkinnedSurface)>, GltfError> {
+        let reader = primitive.reader(|buffer| self.buffers.get(buffer.index()).map(Vec::as_slice));
+
+        let positions = reader
+            .read_positions()
+            .ok_or_else(|| GltfError::MissingPositions(mesh.index()))?
+            .collect::<Vec<_>>();
+        let indices = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect::<Vec<_>>(),
+            None => (0..positions.len() as u32).collect(),
+        };
+        if indices.iter().any(|&i| i as usize >= positions.len()) {
+            return Err(GltfError::InvalidIndex(mesh.index()));
+        }
+        let triangles = match make_triangles(primitive.mode(), &indices) {
+            Some(triangles) => triangles,
+            None => {
+                Log::writeln(
+                    MessageKind::Warning,
+                    format!(
+                        "glTF: Primitive of mesh {} has unsupported mode {:?}, skipping!",
+                        mesh.index(),
+                        primitive.mode()
+                    ),
+                );
+                return Ok(None);
+            }
+        };
+
+        let normals = reader.read_normals().map(|n| n.collect::<Vec<_>>());
+        let tangents = reader.read_tangents().map(|t| t.collect::<Vec<_>>());
+        let tex_coords = reader
+            .read_tex_coords(0)
+            .map(|t| t.into_f32().collect::<Vec<_>>());
+
+        let vertex = |i: usize| StaticVertex {
+            position: Vector3::from(positions[i]),
+            tex_coord: tex_coords
+                .as_ref()
+                .and_then(|t| t.get(i))
+                .map(|&t| Vector2::from(t))
+                .unwrap_or_default(),
+            normal: normals
+                .as_ref()
+                .and_then(|n| n.get(i))
+                .map(|&n| Vector3::from(n))
+                .unwrap_or_else(Vector3::y),
+            tangent: tangents
+                .as_ref()
+                .and_then(|t| t.get(i))
+                .map(|&t| Vector4::from(t))
+                .unwrap_or_else(|| Vector4::new(1.0, 0.0, 0.0, 1.0)),
+        };
+
+        let joints = reader
+            .read_joints(0)
+            .map(|j| j.into_u16().collect::<Vec<_>>());
+        let weights = reader
+            .read_weights(0)
+            .map(|w| w.into_f32().collect::<Vec<_>>());
+
+        let mut skin = SkinnedSurface::default();
+        let mut data = match (joint_count, joints, weights) {
+            (Some(joint_count), Some(joints), Some(weights)) => {
+                let mut vertices = Vec::with_capacity(positions.len());
+                for i in 0..positions.len() {
+                    let mut bone_indices = [0u8; 4];
+                    let mut bone_weights = [0.0f32; 4];
+                    if let (Some(vertex_joints), Some(vertex_weights)) =
+                        (joints.get(i), weights.get(i))
+                    {
+                        let total = vertex_weights.iter().sum::<f32>();
+                        for (k, (&joint, &weight)) in
+                            vertex_joints.iter().zip(vertex_weights.iter()).enumerate()
+                        {
+                            let joint = joint as usize;
+                            if weight <= 0.0 || total <= 0.0 {
+                                continue;
+                            }
+                            if joint >= joint_count {
+                                return Err(GltfError::InvalidIndex(mesh.index()));
+                            }
+                            let bone = match skin.joints.iter().position(|&j| j == joint) {
+                                Some(bone) => bone,
+                                None => {
+                                    skin.joints.push(joint);
+                                    skin.joints.len() - 1
+                                }
+                            };
+                            bone_indices[k] = u8::try_from(bone)
+                                .map_err(|_| GltfError::TooManyJoints(mesh.index()))?;
+                            bone_weights[k] = weight / total;
+                        }
+                    }
+                    skin.weights.push((bone_indices, bone_weights));
+
+                    let static_vertex = vertex(i);
+                    vertices.push(AnimatedVertex {
+                        position: static_vertex.position,
+                        tex_coord: static_vertex.tex_coord,
+                        normal: static_vertex.normal,
+                        tangent: static_vertex.tangent,
+                        bone_weights,
+                        bone_indices,
+                    });
+                }
+
+                if skin.joints.len() > MAX_BONES {
+                    Log::writeln(
+                        MessageKind::Error,
+                        format!(
+                            "glTF: Primitive of mesh {} is affected by {} bones, but only {} \
+                            bones are supported!",
+                            mesh.index(),
+                            skin.joints.len(),
+                            MAX_BONES
+                        ),
+                    );
+                }
+
+                SurfaceData::new(
+                    VertexBuffer::new(vertices.len(), AnimatedVertex::layout(), vertices).unwrap(),
+                    GeometryBuffer::new(triangles),
+                    false,
+                )
+            }
+            _ => {
+                let vertices = (0..positions.len()).map(vertex).collect::<Vec<_>>();
+                SurfaceData::new(
+                    VertexBuffer::new(vertices.len(), StaticVertex::layout(), vertices).unwrap(),
+                    GeometryBuffer::new(triangles),
+                    false,
+                )
+            }
+        };
+
+        if normals.is_none() {
+            data.calculate_normals().unwrap();
+        }
+        if tangents.is_none() {
+            data.calculate_tangents().unwrap();
+        }
+
+        Ok(Some((data, skin)))
+    }
+
+    fn convert_mesh(
+        &self,
+        base: BaseBuilder,
+        mesh: gltf::Mesh,
+        skin: Option<gltf::Skin>,
+        graph: &mut Graph,
+        pending_skins: &mut Vec<PendingSkin>,
+    ) -> Result<Handle<Node>, GltfError> {
+        let joint_count = skin.as_ref().map(|skin| skin.joints().count());
+
+        let mut surfaces = Vec::new();
+        let mut skinned_surfaces = Vec::new();
+        for primitive in mesh.primitives() {
+            if let Some((data, skinned_surface)) =
+                self.convert_primitive(&mesh, &primitive, joint_count)?
+            {
+                let mut surface = Surface::new(Arc::new(RwLock::new(data)));
+                if let Some(material) = primitive
+                    .material()
+                    .index()
+                    .and_then(|i| self.materials.get(i))
+                {
+                    surface.set_material(material.clone());
+                }
+                surfaces.push(surface);
+                skinned_surfaces.push(skinned_surface);
+            }
+        }
+
+        let handle = MeshBuilder::new(base).with_surfaces(surfaces).build(graph);
+
+        if let Some(skin) = skin {
+            pending_skins.push(PendingSkin {
+                mesh: handle,
+                skin: skin.index(),
+                surfaces: skinned_surfaces,
+            });
+        }
+
+        Ok(handle)
+    }
+
+    fn convert_node(
+        &self,
+        node: &gltf::Node,
+        graph: &mut Graph,
+        pending_skins: &mut Vec<PendingSkin>,
+    ) -> Result<Handle<Node>, GltfError> {
+        let name = node_name(node);
+        let (position, rotation, scale) = rest_transform(node);
+        let base = BaseBuilder::new()
+            .with_name(name.as_str())
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(position)
+                    .with_local_rotation(rotation)
+                    .with_local_scale(scale)
+                    .build(),
+            );
+
+        let handle = match node.mesh() {
+            Some(mesh) => self.convert_mesh(base, mesh, node.skin(), graph, pending_skins)?,
+            None => base.build(graph),
+        };
+
+        // Cameras and lights are attached to separate child nodes, because their orientation
+        // must be corrected without affecting children of the node.
+        if let Some(camera) = node.camera() {
+            let camera = convert_camera(camera, format!("{}_Camera", name), graph);
+            graph.link_nodes(camera, handle);
+        }
+        if let Some(light) = node.light() {
+            let light = convert_light(light, format!("{}_Light", name), graph);
+            graph.link_nodes(light, handle);
+        }
+
+        Ok(handle)
+    }
+
+    fn convert_animations(&self, node_handles: &[Handle<Node>], scene: &mut Scene) {
+        let get_buffer = |buffer: gltf::Buffer| self.buffers.get(buffer.index()).map(Vec::as_slice);
+
+        for animation in self.gltf.animations() {
+            // Sorted map keeps order of tracks stable.
+            let mut node_curves = BTreeMap::<usize, NodeCurves>::new();
+            for channel in animation.channels() {
+                let reader = channel.reader(get_buffer);
+                let times = match reader.read_inputs() {
+                    Some(inputs) => inputs.collect::<Vec<_>>(),
+                    None => continue,
+                };
+                let values = match reader.read_outputs() {
+                    Some(ReadOutputs::Translations(translations)) => translations
+                        .map(|t| Vector4::new(t[0], t[1], t[2], 0.0))
+                        .collect::<Vec<_>>(),
+                    Some(ReadOutputs::Rotations(rotations)) => {
+                        rotations.into_f32().map(Vector4::from).collect()
+                    }
+                    Some(ReadOutputs::Scales(scales)) => scales
+                        .map(|s| Vector4::new(s[0], s[1], s[2], 0.0))
+                        .collect(),
+                    // Morph targets are not supported.
+                    Some(ReadOutputs::MorphTargetWeights(_)) | None => continue,
+                };
+                let curve = Curve {
+                    times,
+                    values,
+                    interpolation: channel.sampler().interpolation(),
+                };
+                if !curve.is_valid() {
+                    Log::writeln(
+                        MessageKind::Warning,
+                        format!(
+                            "glTF: Animation {} has invalid channel, skipping!",
+                            animation.index()
+                        ),
+                    );
+                    continue;
+                }
+
+                let target = channel.target();
+                let curves = node_curves.entry(target.node().index()).or_default();
+                match target.property() {
+                    Property::Translation => curves.translation = Some(curve),
+                    Property::Rotation => curves.rotation = Some(curve),
+                    Property::Scale => curves.scale = Some(curve),
+                    Property::MorphTargetWeights => (),
+                }
+            }
+
+            let mut result = Animation::default();
+            for (node_index, curves) in node_curves {
+                let handle = node_handles[node_index];
+                if handle.is_none() {
+                    continue;
+                }
+
+                let node = self.gltf.nodes().nth(node_index).unwrap();
+                let (position, rotation, scale) = rest_transform(&node);
+
+                let mut track = Track::new();
+                track.set_node(handle);
+                for time in curves.key_times() {
+                    track.add_key_frame(KeyFrame::new(
+                        time,
+                        curves
+                            .translation
+                            .as_ref()
+                            .map(|c| c.sample(time, false).xyz())
+                            .unwrap_or(position),
+                        curves
+                            .scale
+                            .as_ref()
+                            .map(|c| c.sample(time, false).xyz())
+                            .unwrap_or(scale),
+                        curves
+                            .rotation
+                            .as_ref()
+                            .map(|c| quat_from_xyzw(c.sample(time, true)))
+                            .unwrap_or(rotation),
+                    ));
+                }
+                result.add_track(track);
+            }
+            scene.animations.add(result);
+        }
+    }
+
+    fn convert(&self, scene: &mut Scene) -> Result<(), GltfError> {
+        let gltf = self.gltf;
+
+        let roots = match gltf.default_scene().or_else(|| gltf.scenes().next()) {
+            Some(gltf_scene) => gltf_scene.nodes().collect::<Vec<_>>(),
+            None => {
+                // There are no scenes, so every node without parent is a root.
+                let children = gltf
+                    .nodes()
+                    .flat_map(|node| node.children().map(|child| child.index()))
+                    .collect::<HashSet<_>>();
+                gltf.nodes()
+                    .filter(|node| !children.contains(&node.index()))
+                    .collect()
+            }
+        };
+
+        let root = scene.graph.get_root();
+        let mut node_handles = vec![Handle::NONE; gltf.nodes().count()];
+        let mut pending_skins = Vec::new();
+        let mut stack = roots
+            .into_iter()
+            .rev()
+            .map(|node| (node, root))
+            .collect::<Vec<_>>();
+        while let Some((node, parent)) = stack.pop() {
+            let handle = self.convert_node(&node, &mut scene.graph, &mut pending_skins)?;
+            scene.graph.link_nodes(handle, parent);
+            node_handles[node.index()] = handle;
+            let children = node.children().collect::<Vec<_>>();
+            stack.extend(children.into_iter().rev().map(|child| (child, handle)));
+        }
+
+        let get_buffer = |buffer: gltf::Buffer| self.buffers.get(buffer.index()).map(Vec::as_slice);
+        for skin in gltf.skins() {
+            let reader = skin.reader(get_buffer);
+            if let Some(matrices) = reader.read_inverse_bind_matrices() {
+                for (joint, matrix) in skin.joints().zip(matrices) {
+                    let handle = node_handles[joint.index()];
+                    if handle.is_some() {
+                        scene.graph[handle].inv_bind_pose_transform = Matrix4::from(matrix);
+                    }
+                }
+            }
+        }
+
+        for pending_skin in pending_skins {
+            let skin = gltf.skins().nth(pending_skin.skin).unwrap();
+            let joints = skin
+                .joints()
+                .map(|joint| node_handles[joint.index()])
+                .collect::<Vec<_>>();
+            if let Node::Mesh(mesh) = &mut scene.graph[pending_skin.mesh] {
+                for (surface, skinned_surface) in
+                    mesh.surfaces_mut().iter_mut().zip(pending_skin.surfaces)
+                {
+                    let bones = skinned_surface
+                        .joints
+                        .iter()
+                        .map(|&joint| joints[joint])
+                        .collect::<Vec<_>>();
+                    surface.vertex_weights = skinned_surface
+                        .weights
+                        .iter()
+                        .map(|(indices, weights)| {
+                            let mut weight_set = VertexWeightSet::default();
+                            for (&index, &value) in indices.iter().zip(weights.iter()) {
+                                if value > 0.0 {
+                                    weight_set.push(VertexWeight {
+                                        value,
+                                        effector: bones[index as usize].into(),
+                                    });
+                                }
+                            }
+                            weight_set
+                        })
+                        .collect();
+                    surface.bones = bones;
+                }
+            }
+        }
+
+        self.convert_animations(&node_handles, scene);
+
+        scene.graph.update_hierarchical_data();
+
+        Ok(())
+    }
+}
+
+fn convert_camera(camera: gltf::Camera, name: String, graph: &mut Graph) -> Handle<Node> {
+    // glTF cameras look along -Z, cameras of the engine - along +Z.
+    let base = BaseBuilder::new().with_name(name).with_local_transform(
+        TransformBuilder::new()
+            .with_local_rotation(UnitQuaternion::from_axis_angle(
+                &Vector3::y_axis(),
+                std::f32::consts::PI,
+            ))
+            .build(),
+    );
+
+    let mut builder = CameraBuilder::new(base);
+    match camera.projection() {
+        Projection::Perspective(perspective) => {
+            builder = builder
+                .with_fov(perspective.yfov())
+                .with_z_near(perspective.znear());
+            if let Some(z_far) = perspective.zfar() {
+                builder = builder.with_z_far(z_far);
+            }
+        }
+        Projection::Orthographic(orthographic) => {
+            Log::writeln(
+                MessageKind::Warning,
+                format!(
+                    "glTF: Orthographic camera {} is not supported, fallback to perspective!",
+                    camera.index()
+                ),
+            );
+            builder = builder
+                .with_z_near(orthographic.znear())
+                .with_z_far(orthographic.zfar());
+        }
+    }
+    builder.build(graph)
+}
+
+fn convert_light(
+    light: gltf::khr_lights_punctual::Light,
+    name: String,
+    graph: &mut Graph,
+) -> Handle<Node> {
+    // glTF lights emit light along -Z, lights of the engine - along -Y.
+    let base = BaseBuilder::new().with_name(name).with_local_transform(
+        TransformBuilder::new()
+            .with_local_rotation(UnitQuaternion::from_axis_angle(
+                &Vector3::x_axis(),
+                std::f32::consts::FRAC_PI_2,
+            ))
+            .build(),
+    );
+
+    let [r, g, b] = light.color();
+    let base_light = BaseLightBuilder::new(base)
+        .with_color(Color::from(LinearColor::opaque(r, g, b)))
+        .with_intensity(light.intensity());
+    let range = light.range().unwrap_or(DEFAULT_LIGHT_RANGE);
+
+    match light.kind() {
+        Kind::Directional => DirectionalLightBuilder::new(base_light).build(graph),
+        Kind::Point => PointLightBuilder::new(base_light)
+            .with_radius(range)
+            .build(graph),
+        Kind::Spot {
+            inner_cone_angle,
+            outer_cone_angle,
+        } => SpotLightBuilder::new(base_light)
+            .with_distance(range)
+            // glTF angles are measured from the axis of the cone.
+            .with_hotspot_cone_angle(2.0 * inner_cone_angle)
+            .with_falloff_angle_delta(2.0 * (outer_cone_angle - inner_cone_angle))
+            .build(graph),
+    }
+}
+
+/// Tries to load and convert glTF from given path.
+///
+/// Normally you should never use this method, use resource manager to load models.
+pub async fn load_to_scene<P: AsRef<Path>>(
+    scene: &mut Scene,
+    resource_manager: ResourceManager,
+    path: P,
+    material_search_options: &MaterialSearchOptions,
+) -> Result<(), GltfError> {
+    let start_time = Instant::now();
+
+    Log::writeln(
+        MessageKind::Information,
+        format!("Trying to load {:?}", path.as_ref()),
+    );
+
+    let now = Instant::now();
+    let data = io::load_file(path.as_ref()).await?;
+    let mut gltf = Gltf::from_slice(&data)?;
+    let base_path = path.as_ref().parent().unwrap_or_else(|| Path::new(""));
+    let buffers = load_buffers(&mut gltf, base_path).await?;
+    let mut images = Vec::new();
+    for image in gltf.images() {
+        images.push(
+            load_image(
+                image,
+                &buffers,
+                path.as_ref(),
+                base_path,
+                material_search_options,
+            )
+            .await?,
+        );
+    }
+    let parsing_time = now.elapsed().as_millis();
+
+    let now = Instant::now();
+    let mut loader = Loader {
+        gltf: &gltf,
+        buffers,
+        images,
+        textures: Default::default(),
+        materials: Default::default(),
+        resource_manager,
+    };
+    for material in gltf.materials() {
+        let material = loader.convert_material(material).await;
+        loader.materials.push(Arc::new(Mutex::new(material)));
+    }
+    loader.convert(scene)?;
+    let conversion_time = now.elapsed().as_millis();
+
+    Log::writeln(
+        MessageKind::Information,
+        format!(
+            "glTF {:?} loaded in {} ms\n\t- Parsing - {} ms\n\t- Conversion - {} ms",
+            path.as_ref(),
+            start_time.elapsed().as_millis(),
+            parsing_time,
+            conversion_time
+        ),
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::Vector4,
+        resource::gltf::{decode_uri, make_triangles, Curve, NodeCurves},
+    };
+    use gltf::{animation::Interpolation, mesh::Mode};
+
+    #[test]
+    fn test_decode_uri() {
+        assert_eq!(
+            decode_uri("textures/my%20texture.png"),
+            "textures/my texture.png"
+        );
+        assert_eq!(decode_uri("100%"), "100%");
+    }
+
+    #[test]
+    fn test_make_triangles() {
+        let strip = make_triangles(Mode::TriangleStrip, &[0, 1, 2, 3]).unwrap();
+        assert_eq!(strip.len(), 2);
+        assert_eq!(strip[1].0, [2, 1, 3]);
+        let fan = make_triangles(Mode::TriangleFan, &[0, 1, 2, 3]).unwrap();
+        assert_eq!(fan[1].0, [0, 2, 3]);
+        assert!(make_triangles(Mode::Lines, &[0, 1]).is_none());
+    }
+
+    #[test]
+    fn test_curves() {
+        let values = vec![
+            Vector4::new(0.0, 0.0, 0.0, 0.0),
+            Vector4::new(2.0, 0.0, 0.0, 0.0),
+        ];
+        let linear = Curve {
+            times: vec![0.0, 1.0],
+            values: values.clone(),
+            interpolation: Interpolation::Linear,
+        };
+        assert_eq!(linear.sample(0.5, false).x, 1.0);
+        assert_eq!(linear.sample(2.0, false).x, 2.0);
+
+        let step = Curve {
+            times: vec![0.0, 1.0],
+            values,
+            interpolation: Interpolation::Step,
+        };
+        assert_eq!(step.sample(0.9, false).x, 0.0);
+        let curves = NodeCurves {
+            translation: Some(step),
+            ..Default::default()
+        };
+        // Extra key frame keeps value constant until the next key.
+        assert_eq!(curves.key_times().len(), 3);
+
+        let zero = Vector4::default();
+        let cubic = Curve {
+            times: vec![0.0, 1.0],
+            values: vec![
+                zero,
+                zero,
+                zero,
+                zero,
+                Vector4::new(1.0, 0.0, 0.0, 0.0),
+                zero,
+            ],
+            interpolation: Interpolation::CubicSpline,
+        };
+        assert!(cubic.is_valid());
+        // Hermite basis with zero tangents is a smoothstep.
+        assert_eq!(cubic.sample(0.5, false).x, 0.5);
+        assert!((cubic.sample(0.25, false).x - 0.15625).abs() < 1.0e-6);
+    }
+}
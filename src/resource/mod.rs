@@ -3,5 +3,74 @@
 #![warn(missing_docs)]
 
 pub mod fbx;
+pub mod gltf;
 pub mod model;
+pub mod obj;
 pub mod texture;
+
+use crate::{core::io, engine::resource_manager::MaterialSearchOptions};
+use std::path::{Path, PathBuf};
+
+/// Finds a texture referenced by a model using given search options. `path` is the path to the
+/// texture as it is stored in the model (relative paths must be already resolved), it is used as
+/// is with [`MaterialSearchOptions::UsePathDirectly`] and checked first with
+/// [`MaterialSearchOptions::RecursiveUp`]. Other options search a file with the same name.
+/// Returns `path` if the texture was not found, so an error will be reported for that path.
+pub(in crate) async fn find_texture(
+    path: &Path,
+    model_path: &Path,
+    material_search_options: &MaterialSearchOptions,
+) -> PathBuf {
+    let file_name = match path.file_name() {
+        Some(file_name) => file_name,
+        None => return path.to_owned(),
+    };
+    let found = match material_search_options {
+        MaterialSearchOptions::MaterialsDirectory(directory) => Some(directory.join(file_name)),
+        MaterialSearchOptions::RecursiveUp => {
+            if io::exists(path).await {
+                Some(path.to_owned())
+            } else {
+                let mut found = None;
+                let mut directory = model_path.parent();
+                while let Some(current) = directory {
+                    let candidate = current.join(file_name);
+                    if io::exists(&candidate).await {
+                        found = Some(candidate);
+                        break;
+                    }
+                    directory = current.parent();
+                }
+                found
+            }
+        }
+        MaterialSearchOptions::WorkingDirectory => {
+            search_working_directory(PathBuf::from(file_name)).await
+        }
+        MaterialSearchOptions::UsePathDirectly => Some(path.to_owned()),
+    };
+    found.unwrap_or_else(|| path.to_owned())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn search_working_directory(file_name: PathBuf) -> Option<PathBuf> {
+    // Walking whole directory tree could take a lot of time, so it is done on separate thread
+    // to not block the executor.
+    let (sender, receiver) = crate::core::futures::channel::oneshot::channel();
+    std::thread::spawn(move || {
+        let found = walkdir::WalkDir::new(".")
+            .into_iter()
+            .flatten()
+            .filter(|entry| entry.file_type().is_dir())
+            .map(|entry| entry.path().join(&file_name))
+            .find(|candidate| candidate.exists());
+        let _ = sender.send(found);
+    });
+    receiver.await.ok().flatten()
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn search_working_directory(_file_name: PathBuf) -> Option<PathBuf> {
+    // There is no file system to walk.
+    None
+}
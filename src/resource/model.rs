@@ -15,25 +15,39 @@
 //!
 //! # Supported formats
 //!
-//! Currently only FBX (common format in game industry for storing complex 3d models)
-//! and RGS (native rusty-editor format) formats are supported.
+//! Currently only FBX (common format in game industry for storing complex 3d models),
+//! glTF 2.0 (both `.gltf` and `.glb`), Wavefront OBJ (with MTL material libraries) and RGS
+//! (native rusty-editor format) formats are supported.
 use crate::{
     animation::Animation,
     asset::{define_new_resource, Resource, ResourceData},
     core::{
         pool::Handle,
+        uuid::Uuid,
         visitor::{Visit, VisitError, VisitResult, Visitor},
     },
     engine::resource_manager::{MaterialSearchOptions, ResourceManager},
-    resource::fbx::{self, error::FbxError},
-    scene::{node::Node, Scene},
+    resource::{
+        fbx::{self, error::FbxError},
+        gltf::{self, error::GltfError},
+        obj::{self, error::ObjError},
+    },
+    scene::{
+        base::{derive_uuid, instance_uuid},
+        graph::Graph,
+        node::Node,
+        Scene,
+    },
     utils::log::{Log, MessageKind},
 };
 use std::{
     borrow::Cow,
+    collections::HashMap,
     path::{Path, PathBuf},
 };
 
+/// Defines how to find original nodes of instances of a model that were saved before persistent
+/// identifiers of nodes were introduced, otherwise nodes are matched by identifiers.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 #[repr(u32)]
 pub(in crate) enum NodeMapping {
@@ -79,9 +93,17 @@ impl Model {
             stack.extend_from_slice(node.children());
         }
 
-        // Fill original handles to instances.
+        // Fill original handles to instances and derive persistent identifiers of instantiated
+        // nodes from identifiers of resource nodes, so instantiated nodes will be found by them
+        // when a save file is resolved.
+        let root_uuid = dest_scene.graph[root].uuid();
         for (&old, &new) in old_to_new.iter() {
             dest_scene.graph[new].original_handle_in_resource = old;
+            if new != root {
+                dest_scene
+                    .graph
+                    .set_uuid(new, instance_uuid(root_uuid, data.scene.graph[old].uuid()));
+            }
         }
 
         // Embed navmeshes.
@@ -232,6 +254,10 @@ pub enum ModelLoadError {
     NotSupported(String),
     /// An error occurred while loading FBX file.
     Fbx(FbxError),
+    /// An error occurred while loading glTF file.
+    Gltf(GltfError),
+    /// An error occurred while loading OBJ file.
+    Obj(ObjError),
 }
 
 impl From<FbxError> for ModelLoadError {
@@ -240,12 +266,55 @@ impl From<FbxError> for ModelLoadError {
     }
 }
 
+impl From<GltfError> for ModelLoadError {
+    fn from(gltf: GltfError) -> Self {
+        ModelLoadError::Gltf(gltf)
+    }
+}
+
+impl From<ObjError> for ModelLoadError {
+    fn from(obj: ObjError) -> Self {
+        ModelLoadError::Obj(obj)
+    }
+}
+
 impl From<VisitError> for ModelLoadError {
     fn from(e: VisitError) -> Self {
         ModelLoadError::Visit(e)
     }
 }
 
+/// Formats other than native one have no persistent identifiers of nodes, so identifiers are derived
+/// from paths of names of nodes to be the same every time when a model is loaded. Nodes with same
+/// paths are distinguished by their order in the graph.
+fn assign_uuids_from_names(graph: &mut Graph) {
+    let mut occurrences = HashMap::new();
+    let mut stack = vec![(graph.get_root(), None)];
+    while let Some((handle, parent_path)) = stack.pop() {
+        let path = match parent_path {
+            Some(parent_path) => {
+                let path = format!("{}/{}", parent_path, graph[handle].name());
+                let occurrence = occurrences.entry(path.clone()).or_insert(0usize);
+                *occurrence += 1;
+                if *occurrence > 1 {
+                    format!("{}#{}", path, *occurrence - 1)
+                } else {
+                    path
+                }
+            }
+            // Root is named after a file, it must not affect identifiers.
+            None => String::new(),
+        };
+        graph.set_uuid(handle, derive_uuid(Uuid::nil(), path.as_bytes()));
+        stack.extend(
+            graph[handle]
+                .children()
+                .iter()
+                .map(|&child| (child, Some(path.clone()))),
+        );
+    }
+}
+
 impl ModelData {
     pub(in crate) async fn load<P: AsRef<Path>>(
         path: P,
@@ -259,7 +328,7 @@ impl ModelData {
             .to_string_lossy()
             .as_ref()
             .to_lowercase();
-        let (scene, mapping) = match extension.as_ref() {
+        let (mut scene, mapping) = match extension.as_ref() {
             "fbx" => {
                 let mut scene = Scene::new();
                 if let Some(filename) = path.as_ref().file_name() {
@@ -277,6 +346,38 @@ impl ModelData {
                 // any persistent unique ids, and we have to use names.
                 (scene, NodeMapping::UseNames)
             }
+            "gltf" | "glb" => {
+                let mut scene = Scene::new();
+                if let Some(filename) = path.as_ref().file_name() {
+                    let root = scene.graph.get_root();
+                    scene.graph[root].set_name(filename.to_string_lossy().to_string());
+                }
+                gltf::load_to_scene(
+                    &mut scene,
+                    resource_manager,
+                    path.as_ref(),
+                    &material_search_options,
+                )
+                .await?;
+                // glTF nodes have indices, but they change every time when a model is
+                // re-exported, so names are more reliable.
+                (scene, NodeMapping::UseNames)
+            }
+            "obj" => {
+                let mut scene = Scene::new();
+                if let Some(filename) = path.as_ref().file_name() {
+                    let root = scene.graph.get_root();
+                    scene.graph[root].set_name(filename.to_string_lossy().to_string());
+                }
+                obj::load_to_scene(
+                    &mut scene,
+                    resource_manager,
+                    path.as_ref(),
+                    &material_search_options,
+                )
+                .await?;
+                (scene, NodeMapping::UseNames)
+            }
             // Scene can be used directly as model resource. Such scenes can be created from
             // rusty-editor (https://github.com/mrDIMAS/rusty-editor) for example.
             "rgs" => (
@@ -292,6 +393,10 @@ impl ModelData {
             }
         };
 
+        if mapping == NodeMapping::UseNames {
+            assign_uuids_from_names(&mut scene.graph);
+        }
+
         Ok(Self {
             path: path.as_ref().to_owned(),
             scene,
@@ -319,3 +424,63 @@ impl ModelData {
         &self.material_search_options
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        asset::{Resource, ResourceState},
+        core::pool::Handle,
+        resource::model::{assign_uuids_from_names, Model, ModelData, NodeMapping},
+        scene::{base::BaseBuilder, graph::Graph, node::Node, Scene},
+    };
+    use std::path::PathBuf;
+
+    fn build_hierarchy(graph: &mut Graph) -> (Handle<Node>, Handle<Node>) {
+        let b = BaseBuilder::new().with_name("B").build(graph);
+        let a = BaseBuilder::new()
+            .with_name("A")
+            .with_children(&[b])
+            .build(graph);
+        (a, b)
+    }
+
+    #[test]
+    fn test_instance_uuids() {
+        let mut scene = Scene::new();
+        let (a, b) = build_hierarchy(&mut scene.graph);
+        assign_uuids_from_names(&mut scene.graph);
+
+        // Identifiers do not depend on loading.
+        let mut other = Graph::new();
+        let (other_a, other_b) = build_hierarchy(&mut other);
+        assign_uuids_from_names(&mut other);
+        assert_eq!(scene.graph[a].uuid(), other[other_a].uuid());
+        assert_eq!(scene.graph[b].uuid(), other[other_b].uuid());
+        assert_ne!(scene.graph[a].uuid(), scene.graph[b].uuid());
+
+        let model = Model(Resource::new(ResourceState::Ok(ModelData {
+            path: PathBuf::from("test.fbx"),
+            mapping: NodeMapping::UseNames,
+            material_search_options: Default::default(),
+            scene,
+        })));
+
+        let mut dest_scene = Scene::new();
+        let first = model.instantiate_geometry(&mut dest_scene);
+        let second = model.instantiate_geometry(&mut dest_scene);
+
+        let first_b = dest_scene.graph.find_by_name(first, "B");
+        let second_b = dest_scene.graph.find_by_name(second, "B");
+        assert_ne!(
+            dest_scene.graph[first_b].uuid(),
+            dest_scene.graph[second_b].uuid()
+        );
+
+        // Instantiated nodes are matched with resource nodes by identifiers, not names.
+        dest_scene.graph[first_b].set_name("Renamed");
+        dest_scene.graph[first_b].original_handle_in_resource = Handle::NONE;
+        dest_scene.graph.resolve();
+        assert_eq!(dest_scene.graph[first_b].original_handle_in_resource, b);
+        assert!(dest_scene.graph.find_by_name(first, "B").is_none());
+    }
+}
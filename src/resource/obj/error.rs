@@ -0,0 +1,31 @@
+//! Contains all possible errors that can occur during OBJ and MTL parsing.
+
+use rg3d_core::io::FileLoadError;
+use std::fmt::Formatter;
+
+/// See module docs.
+#[derive(Debug)]
+pub enum ObjError {
+    /// An error occurred during file loading.
+    FileLoadError(FileLoadError),
+    /// A statement at given line has a malformed or missing number.
+    InvalidNumber(usize),
+    /// A face at given line references non-existing vertex attribute.
+    InvalidIndex(usize),
+}
+
+impl std::fmt::Display for ObjError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::FileLoadError(e) => write!(f, "File load error: {:?}", e),
+            Self::InvalidNumber(line) => write!(f, "Invalid number at line {}", line),
+            Self::InvalidIndex(line) => write!(f, "Invalid vertex index at line {}", line),
+        }
+    }
+}
+
+impl From<FileLoadError> for ObjError {
+    fn from(err: FileLoadError) -> Self {
+        ObjError::FileLoadError(err)
+    }
+}
@@ -0,0 +1,753 @@
+//! Contains all methods to load and convert Wavefront OBJ model format and its MTL material
+//! libraries.
+//!
+//! OBJ is a simple text format that stores only geometry, every object (`o` or `g` statement) of
+//! a file is converted to a separate mesh, and every material group (`usemtl` statement) of an
+//! object is converted to a separate surface. Normals are generated for faces without them using
+//! smoothing groups (`s` statement), tangents are always generated.
+//!
+//! Parsing and conversion of geometry does not need a renderer, only textures of materials are
+//! requested from resource manager.
+//!
+//! # Limitations
+//!
+//! - Polygonal lines, points, free-form curves and surfaces are ignored.
+//! - The standard shader uses metallic-roughness workflow, so specular map (`map_Ks`) is used as
+//!   metalness map, unless there is `map_Pm` from PBR extension of MTL.
+//! - Texture options (`-s`, `-o`, `-bm`, etc.) are ignored.
+//!
+//! Normally you should never use methods from this module directly, use resource manager to load
+//! models and create their instances.
+
+pub mod error;
+
+use crate::{
+    core::{
+        algebra::{Vector2, Vector3, Vector4},
+        color::Color,
+        instant::Instant,
+        io,
+        math::triangulator::triangulate,
+    },
+    engine::resource_manager::{MaterialSearchOptions, ResourceManager},
+    material::{shader::SamplerFallback, Material, PropertyValue},
+    resource::{find_texture, obj::error::ObjError},
+    scene::{
+        base::BaseBuilder,
+        mesh::{
+            surface::{Surface, SurfaceData},
+            vertex::StaticVertex,
+            MeshBuilder,
+        },
+        Scene,
+    },
+    utils::{
+        log::{Log, MessageKind},
+        raw_mesh::RawMeshBuilder,
+    },
+};
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex, RwLock},
+};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct ObjVertex {
+    position: usize,
+    tex_coord: Option<usize>,
+    normal: Option<usize>,
+}
+
+struct ObjFace {
+    vertices: Vec<ObjVertex>,
+    // Zero means that smoothing is off.
+    smoothing_group: u32,
+}
+
+struct ObjGroup {
+    material: Option<String>,
+    faces: Vec<ObjFace>,
+}
+
+struct ObjObject {
+    name: String,
+    groups: Vec<ObjGroup>,
+}
+
+impl ObjObject {
+    fn new(name: String, material: Option<String>) -> Self {
+        Self {
+            name,
+            groups: vec![ObjGroup {
+                material,
+                faces: Default::default(),
+            }],
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.groups.iter().all(|group| group.faces.is_empty())
+    }
+}
+
+#[derive(Default)]
+struct ObjDocument {
+    positions: Vec<Vector3<f32>>,
+    tex_coords: Vec<Vector2<f32>>,
+    normals: Vec<Vector3<f32>>,
+    objects: Vec<ObjObject>,
+    material_libraries: Vec<String>,
+}
+
+/// Splits text into statements, joins lines which end with a backslash and removes comments.
+/// Every statement is returned with its line number (starting from one).
+fn statements(text: &str) -> Vec<(usize, String)> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut start_line = 1;
+    for (i, line) in text.lines().enumerate() {
+        if current.is_empty() {
+            start_line = i + 1;
+        }
+        let line = match line.find('#') {
+            Some(comment) => &line[..comment],
+            None => line,
+        };
+        match line.trim_end().strip_suffix('\\') {
+            Some(line) => {
+                current += line;
+                current.push(' ');
+            }
+            None => {
+                current += line;
+                statements.push((start_line, std::mem::take(&mut current)));
+            }
+        }
+    }
+    if !current.is_empty() {
+        statements.push((start_line, current));
+    }
+    statements
+}
+
+fn parse_f32(token: Option<&&str>, line: usize) -> Result<f32, ObjError> {
+    token
+        .and_then(|token| token.parse().ok())
+        .ok_or(ObjError::InvalidNumber(line))
+}
+
+fn parse_vector3(args: &[&str], line: usize) -> Result<Vector3<f32>, ObjError> {
+    Ok(Vector3::new(
+        parse_f32(args.first(), line)?,
+        parse_f32(args.get(1), line)?,
+        parse_f32(args.get(2), line)?,
+    ))
+}
+
+/// Converts one-based (or negative relative) index into zero-based index.
+fn parse_index(token: &str, count: usize, line: usize) -> Result<usize, ObjError> {
+    let index = token
+        .parse::<i64>()
+        .map_err(|_| ObjError::InvalidNumber(line))?;
+    let resolved = if index > 0 {
+        index - 1
+    } else {
+        count as i64 + index
+    };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        Err(ObjError::InvalidIndex(line))
+    } else {
+        Ok(resolved as usize)
+    }
+}
+
+impl ObjDocument {
+    fn current_object(&mut self, material: &Option<String>) -> &mut ObjObject {
+        if self.objects.is_empty() {
+            self.objects
+                .push(ObjObject::new(Default::default(), material.clone()));
+        }
+        self.objects.last_mut().unwrap()
+    }
+
+    fn parse_vertex(&self, token: &str, line: usize) -> Result<ObjVertex, ObjError> {
+        let mut indices = token.split('/');
+        let position = parse_index(indices.next().unwrap_or(""), self.positions.len(), line)?;
+        let tex_coord = match indices.next() {
+            Some(index) if !index.is_empty() => {
+                Some(parse_index(index, self.tex_coords.len(), line)?)
+            }
+            _ => None,
+        };
+        let normal = match indices.next() {
+            Some(index) if !index.is_empty() => Some(parse_index(index, self.normals.len(), line)?),
+            _ => None,
+        };
+        Ok(ObjVertex {
+            position,
+            tex_coord,
+            normal,
+        })
+    }
+
+    fn parse(text: &str) -> Result<Self, ObjError> {
+        let mut document = Self::default();
+        let mut material = None;
+        let mut smoothing_group = 0;
+
+        for (line, statement) in statements(text) {
+            let mut tokens = statement.split_whitespace();
+            let keyword = match tokens.next() {
+                Some(keyword) => keyword,
+                None => continue,
+            };
+            let args = tokens.collect::<Vec<_>>();
+
+            match keyword {
+                "v" => document.positions.push(parse_vector3(&args, line)?),
+                "vt" => document.tex_coords.push(Vector2::new(
+                    parse_f32(args.first(), line)?,
+                    // Second coordinate is optional.
+                    match args.get(1) {
+                        Some(_) => parse_f32(args.get(1), line)?,
+                        None => 0.0,
+                    },
+                )),
+                "vn" => document.normals.push(parse_vector3(&args, line)?),
+                "f" => {
+                    let vertices = args
+                        .iter()
+                        .map(|token| document.parse_vertex(token, line))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    // Silently ignore invalid faces.
+                    if vertices.len() >= 3 {
+                        let object = document.current_object(&material);
+                        object.groups.last_mut().unwrap().faces.push(ObjFace {
+                            vertices,
+                            smoothing_group,
+                        });
+                    }
+                }
+                "o" | "g" => {
+                    let name = args.join(" ");
+                    match document.objects.last_mut() {
+                        Some(object) if object.is_empty() => object.name = name,
+                        _ => document
+                            .objects
+                            .push(ObjObject::new(name, material.clone())),
+                    }
+                }
+                "usemtl" => {
+                    material = Some(args.join(" "));
+                    let object = document.current_object(&material);
+                    let group = object.groups.last_mut().unwrap();
+                    if group.faces.is_empty() {
+                        group.material = material.clone();
+                    } else {
+                        object.groups.push(ObjGroup {
+                            material: material.clone(),
+                            faces: Default::default(),
+                        });
+                    }
+                }
+                "s" => {
+                    smoothing_group = match args.first() {
+                        Some(&"off") | None => 0,
+                        Some(&"on") => 1,
+                        Some(group) => group.parse().map_err(|_| ObjError::InvalidNumber(line))?,
+                    }
+                }
+                "mtllib" => document
+                    .material_libraries
+                    .extend(args.iter().map(|library| library.to_string())),
+                // Lines, points, curves, etc. are not supported.
+                _ => (),
+            }
+        }
+
+        Ok(document)
+    }
+
+    // Unnormalized normal of a polygon, its length is twice the area of the polygon.
+    fn face_normal(&self, face: &ObjFace) -> Vector3<f32> {
+        // Newell's method works for non-planar polygons too.
+        let mut normal = Vector3::default();
+        for (i, a) in face.vertices.iter().enumerate() {
+            let b = &face.vertices[(i + 1) % face.vertices.len()];
+            let a = self.positions[a.position];
+            let b = self.positions[b.position];
+            normal.x += (a.y - b.y) * (a.z + b.z);
+            normal.y += (a.z - b.z) * (a.x + b.x);
+            normal.z += (a.x - b.x) * (a.y + b.y);
+        }
+        normal
+    }
+
+    /// Converts every material group of an object into surface data, returns pairs of material
+    /// name and surface data.
+    fn build_surfaces<'a>(&self, object: &'a ObjObject) -> Vec<(Option<&'a str>, SurfaceData)> {
+        // Normals for vertices without explicit normals are averaged per smoothing group.
+        let mut smooth_normals = HashMap::<(usize, u32), Vector3<f32>>::new();
+        for face in object.groups.iter().flat_map(|group| group.faces.iter()) {
+            if face.smoothing_group != 0 {
+                let normal = self.face_normal(face);
+                for vertex in face.vertices.iter() {
+                    *smooth_normals
+                        .entry((vertex.position, face.smoothing_group))
+                        .or_default() += normal;
+                }
+            }
+        }
+
+        let mut temp_vertices = Vec::new();
+        let mut triangles = Vec::new();
+        let mut surfaces = Vec::new();
+        for group in object.groups.iter().filter(|group| !group.faces.is_empty()) {
+            let mut builder =
+                RawMeshBuilder::<StaticVertex>::new(group.faces.len() * 3, group.faces.len() * 3);
+            for face in group.faces.iter() {
+                let face_normal = self.face_normal(face);
+
+                if face.vertices.len() == 3 {
+                    triangles.clear();
+                    triangles.push([0, 1, 2]);
+                } else {
+                    temp_vertices.clear();
+                    temp_vertices.extend(face.vertices.iter().map(|v| self.positions[v.position]));
+                    triangulate(&temp_vertices, &mut triangles);
+                    if triangles.is_empty() {
+                        // Degenerate polygon, fallback to a fan.
+                        triangles.extend((1..face.vertices.len() - 1).map(|i| [0, i, i + 1]));
+                    }
+                }
+
+                for &index in triangles.iter().flatten() {
+                    let vertex = face.vertices[index];
+                    let normal = match vertex.normal {
+                        Some(normal) => self.normals[normal],
+                        None if face.smoothing_group != 0 => {
+                            smooth_normals[&(vertex.position, face.smoothing_group)]
+                        }
+                        None => face_normal,
+                    };
+                    let tex_coord = vertex
+                        .tex_coord
+                        .map(|tex_coord| self.tex_coords[tex_coord])
+                        .unwrap_or_default();
+                    builder.insert(StaticVertex {
+                        position: self.positions[vertex.position],
+                        // Invert Y because OpenGL has origin at left *bottom* corner.
+                        tex_coord: Vector2::new(tex_coord.x, 1.0 - tex_coord.y),
+                        normal: normal
+                            .try_normalize(f32::EPSILON)
+                            .unwrap_or_else(Vector3::y),
+                        tangent: Vector4::default(),
+                    });
+                }
+            }
+
+            let mut data =
+                SurfaceData::from_raw_mesh(builder.build(), StaticVertex::layout(), false);
+            data.calculate_tangents().unwrap();
+            surfaces.push((group.material.as_deref(), data));
+        }
+        surfaces
+    }
+}
+
+#[derive(Default, Debug)]
+struct MtlMaterial {
+    name: String,
+    diffuse_color: Option<Vector3<f32>>,
+    emission_color: Option<Vector3<f32>>,
+    opacity: Option<f32>,
+    diffuse_texture: Option<String>,
+    normal_texture: Option<String>,
+    specular_texture: Option<String>,
+    metallic_texture: Option<String>,
+    roughness_texture: Option<String>,
+    emission_texture: Option<String>,
+    height_texture: Option<String>,
+}
+
+/// Extracts path of a texture from arguments of a texture statement, skipping texture options.
+fn parse_texture_path(args: &[&str]) -> Option<String> {
+    let mut i = 0;
+    while let Some(option) = args.get(i).filter(|arg| arg.starts_with('-')) {
+        i += 1;
+        match *option {
+            // Up to three numbers.
+            "-o" | "-s" | "-t" => {
+                let count = args[i..]
+                    .iter()
+                    .take(3)
+                    .take_while(|arg| arg.parse::<f32>().is_ok())
+                    .count();
+                i += count;
+            }
+            "-mm" => i += 2,
+            _ => i += 1,
+        }
+    }
+    if i < args.len() {
+        Some(args[i..].join(" "))
+    } else {
+        None
+    }
+}
+
+fn parse_mtl(text: &str) -> Result<Vec<MtlMaterial>, ObjError> {
+    let mut materials = Vec::<MtlMaterial>::new();
+    for (line, statement) in statements(text) {
+        let mut tokens = statement.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword.to_lowercase(),
+            None => continue,
+        };
+        let args = tokens.collect::<Vec<_>>();
+
+        if keyword == "newmtl" {
+            materials.push(MtlMaterial {
+                name: args.join(" "),
+                ..Default::default()
+            });
+            continue;
+        }
+
+        // Statements before the first material are ignored.
+        let material = match materials.last_mut() {
+            Some(material) => material,
+            None => continue,
+        };
+        match keyword.as_str() {
+            "kd" => material.diffuse_color = Some(parse_vector3(&args, line)?),
+            "ke" => material.emission_color = Some(parse_vector3(&args, line)?),
+            "d" => material.opacity = Some(parse_f32(args.last(), line)?),
+            "tr" => material.opacity = Some(1.0 - parse_f32(args.last(), line)?),
+            "map_kd" => material.diffuse_texture = parse_texture_path(&args),
+            "map_bump" | "bump" | "norm" => material.normal_texture = parse_texture_path(&args),
+            "map_ks" => material.specular_texture = parse_texture_path(&args),
+            "map_pm" => material.metallic_texture = parse_texture_path(&args),
+            "map_pr" => material.roughness_texture = parse_texture_path(&args),
+            "map_ke" => material.emission_texture = parse_texture_path(&args),
+            "disp" | "map_disp" => material.height_texture = parse_texture_path(&args),
+            _ => (),
+        }
+    }
+    Ok(materials)
+}
+
+async fn convert_material(
+    mtl: &MtlMaterial,
+    library_directory: &Path,
+    model_path: &Path,
+    resource_manager: &ResourceManager,
+    material_search_options: &MaterialSearchOptions,
+) -> Material {
+    let mut material = Material::standard();
+
+    let mut set_property = |name: &str, value: PropertyValue| {
+        if let Err(e) = material.set_property(name, value) {
+            Log::writeln(
+                MessageKind::Error,
+                format!(
+                    "Unable to set material property {} for OBJ material! Reason: {:?}",
+                    name, e
+                ),
+            );
+        }
+    };
+
+    let diffuse_color = mtl
+        .diffuse_color
+        .unwrap_or_else(|| Vector3::new(1.0, 1.0, 1.0));
+    set_property(
+        "diffuseColor",
+        PropertyValue::Color(Color::from(Vector4::new(
+            diffuse_color.x,
+            diffuse_color.y,
+            diffuse_color.z,
+            mtl.opacity.unwrap_or(1.0),
+        ))),
+    );
+
+    if let Some(emission_color) = mtl.emission_color {
+        if emission_color != Vector3::default() {
+            set_property("emissionStrength", PropertyValue::Vector3(emission_color));
+            if mtl.emission_texture.is_none() {
+                // Emission is defined by the color only.
+                set_property(
+                    "emissionTexture",
+                    PropertyValue::Sampler {
+                        value: None,
+                        fallback: SamplerFallback::White,
+                    },
+                );
+            }
+        }
+    }
+
+    let textures = [
+        (
+            mtl.diffuse_texture.as_ref(),
+            "diffuseTexture",
+            SamplerFallback::White,
+        ),
+        (
+            mtl.normal_texture.as_ref(),
+            "normalTexture",
+            SamplerFallback::Normal,
+        ),
+        (
+            mtl.metallic_texture
+                .as_ref()
+                .or(mtl.specular_texture.as_ref()),
+            "metallicTexture",
+            SamplerFallback::Black,
+        ),
+        (
+            mtl.roughness_texture.as_ref(),
+            "roughnessTexture",
+            SamplerFallback::White,
+        ),
+        (
+            mtl.emission_texture.as_ref(),
+            "emissionTexture",
+            SamplerFallback::Black,
+        ),
+        (
+            mtl.height_texture.as_ref(),
+            "heightTexture",
+            SamplerFallback::Black,
+        ),
+    ];
+    for (texture, property_name, fallback) in textures.iter() {
+        if let Some(texture) = texture {
+            // Paths in MTL files are relative to the library.
+            let path = find_texture(
+                &library_directory.join(texture),
+                model_path,
+                material_search_options,
+            )
+            .await;
+            let texture = resource_manager.request_texture(path, None);
+            set_property(
+                property_name,
+                PropertyValue::Sampler {
+                    value: Some(texture),
+                    fallback: *fallback,
+                },
+            );
+        }
+    }
+
+    material
+}
+
+/// Tries to load and convert OBJ from given path.
+///
+/// Normally you should never use this method, use resource manager to load models.
+pub async fn load_to_scene<P: AsRef<Path>>(
+    scene: &mut Scene,
+    resource_manager: ResourceManager,
+    path: P,
+    material_search_options: &MaterialSearchOptions,
+) -> Result<(), ObjError> {
+    let start_time = Instant::now();
+
+    Log::writeln(
+        MessageKind::Information,
+        format!("Trying to load {:?}", path.as_ref()),
+    );
+
+    let now = Instant::now();
+    let data = io::load_file(path.as_ref()).await?;
+    let document = ObjDocument::parse(&String::from_utf8_lossy(&data))?;
+    let base_path = path.as_ref().parent().unwrap_or_else(|| Path::new(""));
+    let mut libraries = Vec::new();
+    for library in document.material_libraries.iter() {
+        let library_path = base_path.join(library);
+        match io::load_file(&library_path).await {
+            Ok(data) => libraries.push((library_path, parse_mtl(&String::from_utf8_lossy(&data))?)),
+            // Geometry is still useful without materials.
+            Err(e) => Log::writeln(
+                MessageKind::Warning,
+                format!(
+                    "Unable to load material library {:?}! Reason: {:?}",
+                    library_path, e
+                ),
+            ),
+        }
+    }
+    let parsing_time = now.elapsed().as_millis();
+
+    let now = Instant::now();
+    let mut materials = HashMap::new();
+    for (library_path, library) in libraries.iter() {
+        let library_directory = library_path.parent().unwrap_or_else(|| Path::new(""));
+        for mtl in library.iter() {
+            let material = convert_material(
+                mtl,
+                library_directory,
+                path.as_ref(),
+                &resource_manager,
+                material_search_options,
+            )
+            .await;
+            materials.insert(mtl.name.as_str(), Arc::new(Mutex::new(material)));
+        }
+    }
+
+    let root = scene.graph.get_root();
+    for (i, object) in document.objects.iter().enumerate() {
+        let mut surfaces = Vec::new();
+        for (material_name, data) in document.build_surfaces(object) {
+            let mut surface = Surface::new(Arc::new(RwLock::new(data)));
+            if let Some(material_name) = material_name {
+                match materials.get(material_name) {
+                    Some(material) => surface.set_material(material.clone()),
+                    None => Log::writeln(
+                        MessageKind::Warning,
+                        format!("OBJ: Material {} is not found!", material_name),
+                    ),
+                }
+            }
+            surfaces.push(surface);
+        }
+        if surfaces.is_empty() {
+            continue;
+        }
+
+        let name = if object.name.is_empty() {
+            format!("Object{}", i)
+        } else {
+            object.name.clone()
+        };
+        let mesh = MeshBuilder::new(BaseBuilder::new().with_name(name))
+            .with_surfaces(surfaces)
+            .build(&mut scene.graph);
+        scene.graph.link_nodes(mesh, root);
+    }
+    scene.graph.update_hierarchical_data();
+    let conversion_time = now.elapsed().as_millis();
+
+    Log::writeln(
+        MessageKind::Information,
+        format!(
+            "OBJ {:?} loaded in {} ms\n\t- Parsing - {} ms\n\t- Conversion - {} ms",
+            path.as_ref(),
+            start_time.elapsed().as_millis(),
+            parsing_time,
+            conversion_time
+        ),
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::Vector3,
+        resource::obj::{error::ObjError, parse_mtl, ObjDocument},
+        scene::mesh::buffer::{VertexAttributeUsage, VertexReadTrait},
+    };
+
+    const QUAD: &str = "
+mtllib scene.mtl
+o Quad
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0 # Comment
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+usemtl Red
+f 1/1 2/2 3/3 4/4
+usemtl Green
+f -4/-4 -2/-2 \\
+  -1/-1
+";
+
+    #[test]
+    fn test_parse_obj() {
+        let document = ObjDocument::parse(QUAD).unwrap();
+        assert_eq!(document.material_libraries, vec!["scene.mtl".to_owned()]);
+        assert_eq!(document.objects.len(), 1);
+        let object = &document.objects[0];
+        assert_eq!(object.name, "Quad");
+
+        let surfaces = document.build_surfaces(object);
+        assert_eq!(surfaces.len(), 2);
+        assert_eq!(surfaces[0].0, Some("Red"));
+        assert_eq!(surfaces[0].1.geometry_buffer.len(), 2);
+        assert_eq!(surfaces[0].1.vertex_buffer.vertex_count(), 4);
+        assert_eq!(surfaces[1].0, Some("Green"));
+        assert_eq!(surfaces[1].1.geometry_buffer.len(), 1);
+
+        // Flat normals are generated, because there are no normals and smoothing is off.
+        let vertex = surfaces[1].1.vertex_buffer.get(0).unwrap();
+        assert_eq!(
+            vertex.read_3_f32(VertexAttributeUsage::Normal).unwrap(),
+            Vector3::new(0.0, 0.0, 1.0)
+        );
+
+        assert!(matches!(
+            ObjDocument::parse("v 0 0 0\nf 1 2 3"),
+            Err(ObjError::InvalidIndex(2))
+        ));
+        assert!(matches!(
+            ObjDocument::parse("v 0 0 x"),
+            Err(ObjError::InvalidNumber(1))
+        ));
+    }
+
+    #[test]
+    fn test_smoothing_groups() {
+        // Two triangles with a shared edge, bent by 90 degrees.
+        let text = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 1\ns 1\nf 1 2 3\nf 1 4 2";
+        let document = ObjDocument::parse(text).unwrap();
+        let surfaces = document.build_surfaces(&document.objects[0]);
+        let data = &surfaces[0].1;
+        // Shared vertices are merged, because their normals are the same.
+        assert_eq!(data.vertex_buffer.vertex_count(), 4);
+        let normal = data
+            .vertex_buffer
+            .get(0)
+            .unwrap()
+            .read_3_f32(VertexAttributeUsage::Normal)
+            .unwrap();
+        let expected = Vector3::new(0.0, 1.0, 1.0).normalize();
+        assert!((normal - expected).norm() < 1.0e-6);
+    }
+
+    #[test]
+    fn test_parse_mtl() {
+        let text = "
+newmtl Red
+Kd 1 0 0
+d 0.5
+map_Kd -s 2 2 1 -bm 0.5 textures/red diffuse.png
+map_Bump normal.png
+map_Ks specular.png
+newmtl Green
+Kd 0 1 0
+";
+        let materials = parse_mtl(text).unwrap();
+        assert_eq!(materials.len(), 2);
+        let red = &materials[0];
+        assert_eq!(red.name, "Red");
+        assert_eq!(red.diffuse_color, Some(Vector3::new(1.0, 0.0, 0.0)));
+        assert_eq!(red.opacity, Some(0.5));
+        assert_eq!(
+            red.diffuse_texture.as_deref(),
+            Some("textures/red diffuse.png")
+        );
+        assert_eq!(red.normal_texture.as_deref(), Some("normal.png"));
+        assert_eq!(red.specular_texture.as_deref(), Some("specular.png"));
+        assert!(materials[1].diffuse_texture.is_none());
+    }
+}
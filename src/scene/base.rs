@@ -11,6 +11,8 @@ use crate::{
         algebra::{Matrix4, Vector3},
         math::Matrix4Ext,
         pool::Handle,
+        reflect::Reflect,
+        uuid::Uuid,
         visitor::{Visit, VisitError, VisitResult, Visitor},
     },
     resource::model::Model,
@@ -159,7 +161,7 @@ impl Visit for LodGroup {
 
 /// Mobility defines a group for scene node which has direct impact on performance
 /// and capabilities of nodes.
-#[derive(Copy, Clone, PartialOrd, PartialEq, Ord, Eq, Debug)]
+#[derive(Copy, Clone, PartialOrd, PartialEq, Ord, Eq, Debug, Reflect)]
 #[repr(u32)]
 pub enum Mobility {
     /// Transform cannot be changed.
@@ -222,35 +224,49 @@ impl Visit for Mobility {
 }
 
 /// See module docs.
-#[derive(Debug)]
+#[derive(Debug, Reflect)]
 pub struct Base {
     name: String,
     local_transform: Transform,
     visibility: bool,
+    #[reflect(skip)]
     pub(in crate) global_visibility: Cell<bool>,
+    #[reflect(skip)]
     pub(in crate) parent: Handle<Node>,
+    #[reflect(skip)]
     pub(in crate) children: Vec<Handle<Node>>,
+    #[reflect(skip)]
     pub(in crate) global_transform: Cell<Matrix4<f32>>,
     /// Bone-specific matrix. Non-serializable.
+    #[reflect(skip)]
     pub(in crate) inv_bind_pose_transform: Matrix4<f32>,
     /// A resource from which this node was instantiated from, can work in pair
     /// with `original` handle to get corresponding node from resource.
+    #[reflect(skip)]
     pub(in crate) resource: Option<Model>,
     /// Handle to node in scene of model resource from which this node
     /// was instantiated from.
+    #[reflect(skip)]
     pub(in crate) original_handle_in_resource: Handle<Node>,
     /// When `true` it means that this node is instance of `resource`.
     /// More precisely - this node is root of whole descendant nodes
     /// hierarchy which was instantiated from resource.
+    #[reflect(skip)]
     pub(in crate) is_resource_instance_root: bool,
     /// Maximum amount of Some(time) that node will "live" or None
     /// if node has undefined lifetime.
     pub(in crate) lifetime: Option<f32>,
     depth_offset: f32,
+    #[reflect(skip)]
     lod_group: Option<LodGroup>,
     mobility: Mobility,
     tag: String,
+    #[reflect(skip)]
     pub(in crate) physics_binding: PhysicsBinding,
+    /// Persistent identifier of the node, unlike handle it does not change on save/load
+    /// or when graph is compacted.
+    #[reflect(skip)]
+    pub(in crate) uuid: Uuid,
 }
 
 impl Base {
@@ -457,6 +473,23 @@ impl Base {
         self.physics_binding = binding;
     }
 
+    /// Returns persistent identifier of the node. Unlike handle, it stays the same across
+    /// save/load, so it can be used to store references to nodes in save files for example.
+    /// Use [`NodeRef`](crate::scene::node::NodeRef) to store such references.
+    ///
+    /// # Notes
+    ///
+    /// Copies of a node get new identifiers, there are two exceptions:
+    ///
+    /// - [`Graph::clone`](crate::scene::graph::Graph::clone) preserves identifiers because copy
+    /// of the graph lives separately from the original.
+    /// - Descendants of an instance of a model resource get identifiers derived from identifiers
+    /// of respective nodes in the resource and identifier of the instance root, so they are the
+    /// same every time when the instance is resolved after loading.
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+
     /// Shallow copy of node data. You should never use this directly, shallow copy
     /// will produce invalid node in most cases!
     pub fn raw_copy(&self) -> Self {
@@ -480,6 +513,31 @@ impl Base {
     }
 }
 
+/// Derives persistent identifier from another identifier and arbitrary data, the result is always
+/// the same for the same input.
+pub(in crate) fn derive_uuid(namespace: Uuid, data: &[u8]) -> Uuid {
+    // 128-bit FNV-1a, unlike std hashers it is guaranteed to be the same on every platform and
+    // version, identifiers derived by it are stored in save files.
+    const OFFSET_BASIS: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+
+    Uuid::from_u128(
+        namespace
+            .as_bytes()
+            .iter()
+            .chain(data)
+            .fold(OFFSET_BASIS, |hash, &byte| {
+                (hash ^ byte as u128).wrapping_mul(PRIME)
+            }),
+    )
+}
+
+/// Returns identifier of a node of a model instance, that was made of a resource node with given
+/// identifier.
+pub(in crate) fn instance_uuid(instance_root: Uuid, resource_node: Uuid) -> Uuid {
+    derive_uuid(instance_root, resource_node.as_bytes())
+}
+
 impl Default for Base {
     fn default() -> Self {
         BaseBuilder::new().build_base()
@@ -506,6 +564,7 @@ impl Visit for Base {
             .visit("Original", visitor)?;
         self.tag.visit("Tag", visitor)?;
         self.physics_binding.visit("PhysicsBinding", visitor)?;
+        let _ = self.uuid.visit("Uuid", visitor); // Backward compatibility.
 
         visitor.leave_region()
     }
@@ -523,6 +582,7 @@ pub struct BaseBuilder {
     mobility: Mobility,
     inv_bind_pose_transform: Matrix4<f32>,
     tag: String,
+    uuid: Option<Uuid>,
 }
 
 impl Default for BaseBuilder {
@@ -545,6 +605,7 @@ impl BaseBuilder {
             mobility: Mobility::Dynamic,
             inv_bind_pose_transform: Matrix4::identity(),
             tag: Default::default(),
+            uuid: None,
         }
     }
 
@@ -615,6 +676,14 @@ impl BaseBuilder {
         self
     }
 
+    /// Sets desired persistent identifier, it could be useful to re-create a node from a save
+    /// file, so references to the node will remain valid. If not set, random one will be
+    /// generated. Identifier must be unique across the graph.
+    pub fn with_uuid(mut self, uuid: Uuid) -> Self {
+        self.uuid = Some(uuid);
+        self
+    }
+
     pub(in crate) fn build_base(self) -> Base {
         Base {
             name: self.name,
@@ -634,6 +703,7 @@ impl BaseBuilder {
             mobility: self.mobility,
             tag: self.tag,
             physics_binding: PhysicsBinding::NodeWithBody,
+            uuid: self.uuid.unwrap_or_else(Uuid::new_v4),
         }
     }
 
@@ -21,6 +21,7 @@ use crate::scene::graph::Graph;
 use crate::{
     core::{
         math::{ray::Ray, Rect},
+        reflect::Reflect,
         visitor::{Visit, VisitResult, Visitor},
     },
     resource::texture::Texture,
@@ -36,7 +37,7 @@ use std::sync::Arc;
 
 /// Exposure is a parameter that describes how many light should be collected for one
 /// frame. The higher the value, the more brighter the final frame will be and vice versa.
-#[derive(Visit, Copy, Clone, PartialEq, Debug)]
+#[derive(Visit, Reflect, Copy, Clone, PartialEq, Debug)]
 pub enum Exposure {
     /// Automatic exposure based on the frame luminance. High luminance values will result
     /// in lower exposure levels and vice versa. This is default option.
@@ -68,7 +69,7 @@ impl Default for Exposure {
 }
 
 /// See module docs.
-#[derive(Debug, Visit)]
+#[derive(Debug, Visit, Reflect)]
 pub struct Camera {
     base: Base,
     fov: f32,
@@ -76,21 +77,27 @@ pub struct Camera {
     z_far: f32,
     viewport: Rect<f32>,
     #[visit(skip)]
+    #[reflect(skip)]
     view_matrix: Matrix4<f32>,
     #[visit(skip)]
+    #[reflect(skip)]
     projection_matrix: Matrix4<f32>,
     enabled: bool,
+    #[reflect(skip)]
     sky_box: Option<Box<SkyBox>>,
+    #[reflect(skip)]
     environment: Option<Texture>,
     #[visit(optional)] // Backward compatibility.
     exposure: Exposure,
     #[visit(optional)] // Backward compatibility.
+    #[reflect(skip)]
     color_grading_lut: Option<ColorGradingLut>,
     #[visit(optional)] // Backward compatibility.
     color_grading_enabled: bool,
 
     /// Visibility cache allows you to quickly check if object is visible from the camera or not.
     #[visit(skip)]
+    #[reflect(skip)]
     pub visibility_cache: VisibilityCache,
 }
 
@@ -15,7 +15,7 @@
 //! means that unused decals (bullet holes for example) must be removed after some time.
 
 use crate::{
-    core::{color::Color, pool::Handle, visitor::prelude::*},
+    core::{color::Color, pool::Handle, reflect::Reflect, visitor::prelude::*},
     resource::texture::Texture,
     scene::{
         base::{Base, BaseBuilder},
@@ -26,10 +26,12 @@ use crate::{
 use std::ops::{Deref, DerefMut};
 
 /// See module docs.
-#[derive(Debug, Visit, Default)]
+#[derive(Debug, Visit, Reflect, Default)]
 pub struct Decal {
     base: Base,
+    #[reflect(skip)]
     diffuse_texture: Option<Texture>,
+    #[reflect(skip)]
     normal_texture: Option<Texture>,
     #[visit(optional)] // Backward compatibility
     color: Color,
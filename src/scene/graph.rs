@@ -31,11 +31,12 @@ use crate::{
             Handle, Pool, PoolIterator, PoolIteratorMut, PoolPairIterator, PoolPairIteratorMut,
             Ticket,
         },
+        uuid::Uuid,
         visitor::{Visit, VisitResult, Visitor},
         VecExtensions,
     },
     resource::model::NodeMapping,
-    scene::{node::Node, transform::TransformBuilder, VisibilityCache},
+    scene::{base::instance_uuid, node::Node, transform::TransformBuilder, VisibilityCache},
     utils::log::{Log, MessageKind},
 };
 use std::{
@@ -49,6 +50,7 @@ pub struct Graph {
     root: Handle<Node>,
     pool: Pool<Node>,
     stack: Vec<Handle<Node>>,
+    uuid_map: HashMap<Uuid, Handle<Node>>,
 }
 
 impl Default for Graph {
@@ -57,6 +59,7 @@ impl Default for Graph {
             root: Handle::NONE,
             pool: Pool::new(),
             stack: Vec::new(),
+            uuid_map: Default::default(),
         }
     }
 }
@@ -115,14 +118,50 @@ impl Graph {
         let mut pool = Pool::new();
         let mut root = Node::Base(Default::default());
         root.set_name("__ROOT__");
+        let uuid = root.uuid();
         let root = pool.spawn(root);
+        let mut uuid_map = HashMap::new();
+        uuid_map.insert(uuid, root);
         Self {
             stack: Vec::new(),
             root,
             pool,
+            uuid_map,
         }
     }
 
+    fn register_uuid(&mut self, handle: Handle<Node>) {
+        let uuid = self.pool[handle].uuid();
+        if let Some(old) = self.uuid_map.insert(uuid, handle) {
+            if old != handle && self.pool.is_valid_handle(old) {
+                Log::writeln(
+                    MessageKind::Warning,
+                    format!(
+                        "Node {:?} has same uuid {} as node {:?}, only latter can be found by uuid!",
+                        old, uuid, handle
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Changes persistent identifier of a node, see [`Base::uuid`](crate::scene::base::Base::uuid).
+    pub(in crate) fn set_uuid(&mut self, handle: Handle<Node>, uuid: Uuid) {
+        let old = std::mem::replace(&mut self.pool[handle].uuid, uuid);
+        if self.uuid_map.get(&old) == Some(&handle) {
+            self.uuid_map.remove(&old);
+        }
+        self.register_uuid(handle);
+    }
+
+    fn rebuild_uuid_map(&mut self) {
+        self.uuid_map = self
+            .pool
+            .pair_iter()
+            .map(|(handle, node)| (node.uuid(), handle))
+            .collect();
+    }
+
     /// Adds new node to the graph. Node will be transferred into implementation-defined
     /// storage and you'll get a handle to the node. Node will be automatically attached
     /// to root node of graph, it is required because graph can contain only one root.
@@ -131,6 +170,7 @@ impl Graph {
         let children = node.children.clone();
         node.children.clear();
         let handle = self.pool.spawn(node);
+        self.register_uuid(handle);
         if self.root.is_some() {
             self.link_nodes(handle, self.root);
         }
@@ -196,7 +236,10 @@ impl Graph {
             for &child in self.pool[handle].children().iter() {
                 self.stack.push(child);
             }
-            self.pool.free(handle);
+            let node = self.pool.free(handle);
+            if self.uuid_map.get(&node.uuid()) == Some(&handle) {
+                self.uuid_map.remove(&node.uuid());
+            }
         }
     }
 
@@ -286,6 +329,17 @@ impl Graph {
         self.find_by_name(self.root, name)
     }
 
+    /// Searches node with specified persistent identifier (see [`Base::uuid`](crate::scene::base::Base::uuid)).
+    /// Unlike other search methods it does not traverse the graph, but uses an index instead. If
+    /// nothing was found, `Handle::NONE` is returned.
+    pub fn find_by_uuid(&self, uuid: Uuid) -> Handle<Node> {
+        let handle = self.uuid_map.get(&uuid).cloned().unwrap_or_default();
+        match self.pool.try_borrow(handle) {
+            Some(node) if node.uuid() == uuid => handle,
+            _ => Handle::NONE,
+        }
+    }
+
     /// Searches node using specified compare closure starting from root. If nothing was found,
     /// `Handle::NONE` is returned.
     pub fn find_from_root<C>(&self, cmp: &mut C) -> Handle<Node>
@@ -466,45 +520,116 @@ impl Graph {
         model_root_handle
     }
 
+    /// Finds original nodes in resources for instantiated nodes using their persistent
+    /// identifiers. Identifier of a node of an instance is derived from identifier of the instance
+    /// root, so ancestors that are instance roots of the same resource are checked from nearest to
+    /// farthest. Instance root itself corresponds to the root of the resource if it isn't a part
+    /// of other instance.
+    fn find_originals_by_uuid(&self) -> HashMap<Handle<Node>, Handle<Node>> {
+        let mut instance_uuids: HashMap<Handle<Node>, HashMap<Uuid, Handle<Node>>> = HashMap::new();
+        let mut originals = HashMap::new();
+
+        for (handle, node) in self.pool.pair_iter() {
+            let model = match node.resource() {
+                Some(model) => model,
+                None => continue,
+            };
+            let state = model.state();
+            let resource_graph = match *state {
+                ResourceState::Ok(ref data) => &data.get_scene().graph,
+                _ => continue,
+            };
+
+            let mut original = Handle::NONE;
+            let mut ancestor = node.parent();
+            while ancestor.is_some() {
+                let ancestor_node = &self.pool[ancestor];
+                match ancestor_node.resource() {
+                    Some(ancestor_model) if ancestor_model.key() == model.key() => (),
+                    _ => break,
+                }
+                if ancestor_node.is_resource_instance_root {
+                    let uuids = instance_uuids.entry(ancestor).or_insert_with(|| {
+                        resource_graph
+                            .pair_iter()
+                            .map(|(resource_handle, resource_node)| {
+                                (
+                                    instance_uuid(ancestor_node.uuid(), resource_node.uuid()),
+                                    resource_handle,
+                                )
+                            })
+                            .collect()
+                    });
+                    if let Some(&resource_handle) = uuids.get(&node.uuid()) {
+                        original = resource_handle;
+                        break;
+                    }
+                }
+                ancestor = ancestor_node.parent();
+            }
+
+            if original.is_none() && node.is_resource_instance_root {
+                original = resource_graph.root;
+            }
+
+            if original.is_some() {
+                originals.insert(handle, original);
+            }
+        }
+
+        originals
+    }
+
     pub(in crate) fn resolve(&mut self) {
         Log::writeln(MessageKind::Information, "Resolving graph...".to_owned());
 
         self.update_hierarchical_data();
 
+        let originals = self.find_originals_by_uuid();
+
         // Iterate over each node in the graph and resolve original handles. Original handle is a handle
         // to a node in resource from which a node was instantiated from. Also sync templated properties
         // if needed and copy surfaces from originals.
-        for node in self.pool.iter_mut() {
+        for (handle, node) in self.pool.pair_iter_mut() {
             if let Some(model) = node.resource() {
                 let model = model.state();
                 match *model {
                     ResourceState::Ok(ref data) => {
                         let resource_graph = &data.get_scene().graph;
 
-                        let resource_node = match data.mapping {
-                            NodeMapping::UseNames => {
-                                // For some models we can resolve it only by names of nodes, but this is not
-                                // reliable way of doing this, because some editors allow nodes to have same
-                                // names for objects, but here we'll assume that modellers will not create
-                                // models with duplicated names and user of the engine reads log messages.
-                                resource_graph
-                                    .pair_iter()
-                                    .find_map(|(handle, resource_node)| {
-                                        if resource_node.name() == node.name() {
-                                            Some((resource_node, handle))
-                                        } else {
-                                            None
-                                        }
-                                    })
-                            }
-                            NodeMapping::UseHandles => {
-                                // Use original handle directly.
-                                resource_graph
-                                    .pool
-                                    .try_borrow(node.original_handle_in_resource)
-                                    .map(|resource_node| {
-                                        (resource_node, node.original_handle_in_resource)
-                                    })
+                        let resource_node = if let Some(&original) = originals.get(&handle) {
+                            resource_graph
+                                .pool
+                                .try_borrow(original)
+                                .map(|resource_node| (resource_node, original))
+                        } else {
+                            // Nodes saved before persistent identifiers were introduced have
+                            // random ones, so use previous ways of mapping for them.
+                            match data.mapping {
+                                NodeMapping::UseNames => {
+                                    // For some models we can resolve it only by names of nodes, but this is not
+                                    // reliable way of doing this, because some editors allow nodes to have same
+                                    // names for objects, but here we'll assume that modellers will not create
+                                    // models with duplicated names and user of the engine reads log messages.
+                                    resource_graph.pair_iter().find_map(
+                                        |(handle, resource_node)| {
+                                            if resource_node.name() == node.name() {
+                                                Some((resource_node, handle))
+                                            } else {
+                                                None
+                                            }
+                                        },
+                                    )
+                                }
+                                NodeMapping::UseHandles => {
+                                    // Use original handle directly.
+                                    resource_graph
+                                        .pool
+                                        .try_borrow(node.original_handle_in_resource)
+                                        .map(|resource_node| {
+                                            (resource_node, node.original_handle_in_resource)
+                                        })
+                                }
                             }
                         };
 
@@ -636,6 +761,19 @@ impl Graph {
                     continue;
                 }
 
+                let instance_root_uuid = self.pool[instance].uuid();
+                // Nodes saved before persistent identifiers were introduced can be found only by
+                // their names.
+                let find_instance_node = |graph: &Graph, resource_node: &Node| {
+                    let handle =
+                        graph.find_by_uuid(instance_uuid(instance_root_uuid, resource_node.uuid()));
+                    if handle.is_some() {
+                        handle
+                    } else {
+                        graph.find_by_name(instance, resource_node.name())
+                    }
+                };
+
                 let mut traverse_stack = vec![original];
                 while let Some(resource_node_handle) = traverse_stack.pop() {
                     let resource_node = &resource_graph[resource_node_handle];
@@ -643,7 +781,7 @@ impl Graph {
                     // Root of the resource is not belongs to resource, it is just a convenient way of
                     // consolidation all descendants under a single node.
                     if resource_node_handle != resource_graph.root
-                        && self.find_by_name(instance, resource_node.name()).is_none()
+                        && find_instance_node(self, resource_node).is_none()
                     {
                         Log::writeln(
                             MessageKind::Warning,
@@ -659,6 +797,16 @@ impl Graph {
 
                         restored_count += mapping.len();
 
+                        for (&resource_handle, &handle) in mapping.iter() {
+                            self.set_uuid(
+                                handle,
+                                instance_uuid(
+                                    instance_root_uuid,
+                                    resource_graph[resource_handle].uuid(),
+                                ),
+                            );
+                        }
+
                         let mut stack = vec![copy];
                         while let Some(node_handle) = stack.pop() {
                             let node = &mut self.pool[node_handle];
@@ -668,10 +816,8 @@ impl Graph {
 
                         // Link it with existing node.
                         if resource_node.parent().is_some() {
-                            let parent = self.find_by_name(
-                                instance,
-                                resource_graph[resource_node.parent()].name(),
-                            );
+                            let parent =
+                                find_instance_node(self, &resource_graph[resource_node.parent()]);
 
                             if parent.is_some() {
                                 self.link_nodes(copy, parent);
@@ -834,6 +980,7 @@ impl Graph {
                         }
                         Node::ParticleSystem(particle_system) => particle_system.update(dt),
                         Node::Terrain(terrain) => terrain.update(),
+                        Node::Mesh(mesh) => mesh.update(),
                         _ => (),
                     }
                 }
@@ -916,6 +1063,7 @@ impl Graph {
     /// Puts node back by given ticket. Attaches back to root node of graph.
     pub fn put_back(&mut self, ticket: Ticket<Node>, node: Node) -> Handle<Node> {
         let handle = self.pool.put_back(ticket, node);
+        self.register_uuid(handle);
         self.link_nodes(handle, self.root);
         handle
     }
@@ -950,7 +1098,8 @@ impl Graph {
     /// parent.
     pub fn put_sub_graph_back(&mut self, sub_graph: SubGraph) -> Handle<Node> {
         for (ticket, node) in sub_graph.descendants {
-            self.pool.put_back(ticket, node);
+            let handle = self.pool.put_back(ticket, node);
+            self.register_uuid(handle);
         }
 
         let (ticket, node) = sub_graph.root;
@@ -1010,9 +1159,58 @@ impl Graph {
         let mut copy = Self::default();
         let (root, old_new_map) = self.copy_node(self.root, &mut copy, filter);
         copy.root = root;
+        // Copy lives separately, so it can keep persistent identifiers of the nodes.
+        for (&old, &new) in old_new_map.iter() {
+            copy.pool[new].uuid = self.pool[old].uuid;
+        }
+        copy.rebuild_uuid_map();
         (copy, old_new_map)
     }
 
+    /// Moves all nodes to the beginning of internal pool, so it no longer contains holes left by
+    /// removed nodes, and remaps every handle stored in the graph. Returns mapping from old handles
+    /// to new ones for every moved node, handles of other nodes stay valid.
+    ///
+    /// # Remarks
+    ///
+    /// Handles to nodes stored outside of the graph must be remapped using returned mapping, use
+    /// [`Scene::compact`](crate::scene::Scene::compact) to remap handles in whole scene.
+    pub fn compact(&mut self) -> HashMap<Handle<Node>, Handle<Node>> {
+        let mapping = self.pool.compact();
+        self.remap_handles(&mapping);
+        mapping
+    }
+
+    /// Replaces every node handle stored in the graph (parents, children, bones, LODs) using
+    /// given old-to-new mapping, handles that are not in the mapping are left as is.
+    pub fn remap_handles(&mut self, mapping: &HashMap<Handle<Node>, Handle<Node>>) {
+        let remap = |handle: &mut Handle<Node>| {
+            if let Some(&new_handle) = mapping.get(handle) {
+                *handle = new_handle;
+            }
+        };
+
+        remap(&mut self.root);
+        self.uuid_map.values_mut().for_each(remap);
+
+        for node in self.pool.iter_mut() {
+            remap(&mut node.parent);
+            node.children.iter_mut().for_each(remap);
+
+            if let Node::Mesh(mesh) = node {
+                for surface in mesh.surfaces_mut() {
+                    surface.bones.iter_mut().for_each(remap);
+                }
+            }
+
+            if let Some(lod_group) = node.lod_group_mut() {
+                for level in lod_group.levels.iter_mut() {
+                    level.objects.iter_mut().for_each(remap);
+                }
+            }
+        }
+    }
+
     /// Returns local transformation matrix of a node without scale.
     pub fn local_transform_no_scale(&self, node: Handle<Node>) -> Matrix4<f32> {
         let mut transform = self[node].local_transform().clone();
@@ -1176,6 +1374,10 @@ impl Visit for Graph {
         self.root.visit("Root", visitor)?;
         self.pool.visit("Pool", visitor)?;
 
+        if visitor.is_reading() {
+            self.rebuild_uuid_map();
+        }
+
         visitor.leave_region()
     }
 }
@@ -1183,8 +1385,12 @@ impl Visit for Graph {
 #[cfg(test)]
 mod test {
     use crate::{
-        core::pool::Handle,
-        scene::{base::Base, graph::Graph, node::Node},
+        core::{pool::Handle, uuid::Uuid},
+        scene::{
+            base::{Base, BaseBuilder},
+            graph::Graph,
+            node::{Node, NodeRef},
+        },
     };
 
     #[test]
@@ -1202,4 +1408,29 @@ mod test {
         graph.add_node(Node::Base(Base::default()));
         assert_eq!(graph.pool.alive_count(), 4);
     }
+
+    #[test]
+    fn graph_find_by_uuid_test() {
+        let mut graph = Graph::new();
+        let a = BaseBuilder::new().build(&mut graph);
+        let uuid = Uuid::new_v4();
+        let b = BaseBuilder::new().with_uuid(uuid).build(&mut graph);
+        assert_eq!(graph.find_by_uuid(uuid), b);
+        assert_eq!(graph.find_by_uuid(graph[a].uuid()), a);
+
+        let mut node_ref = NodeRef::new(&graph, b);
+        graph.remove_node(a);
+        let mapping = graph.compact();
+        assert_ne!(mapping[&b], b);
+        assert_eq!(node_ref.resolve(&graph), mapping[&b]);
+        assert_eq!(graph.find_by_uuid(uuid), mapping[&b]);
+
+        let (copy, _) = graph.clone(&mut |_, _| true);
+        assert_eq!(node_ref.resolve(&copy), copy.find_by_uuid(uuid));
+        assert!(copy.find_by_uuid(uuid).is_some());
+
+        graph.remove_node(mapping[&b]);
+        assert_eq!(graph.find_by_uuid(uuid), Handle::NONE);
+        assert_eq!(node_ref.resolve(&graph), Handle::NONE);
+    }
 }
@@ -10,6 +10,7 @@
 use crate::{
     core::{
         pool::Handle,
+        reflect::Reflect,
         visitor::{Visit, VisitResult, Visitor},
     },
     scene::{
@@ -21,7 +22,7 @@ use crate::{
 use std::ops::{Deref, DerefMut};
 
 /// See module docs.
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Reflect)]
 pub struct DirectionalLight {
     base_light: BaseLight,
 }
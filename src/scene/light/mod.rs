@@ -21,6 +21,7 @@ use crate::{
         algebra::Vector3,
         color::Color,
         define_is_as,
+        reflect::Reflect,
         visitor::{Visit, VisitResult, Visitor},
     },
     scene::{
@@ -28,7 +29,10 @@ use crate::{
         light::{directional::DirectionalLight, point::PointLight, spot::SpotLight},
     },
 };
-use std::ops::{Deref, DerefMut};
+use std::{
+    any::Any,
+    ops::{Deref, DerefMut},
+};
 
 pub mod directional;
 pub mod point;
@@ -139,10 +143,58 @@ impl Visit for Light {
     }
 }
 
+// Exposes fields of actual light directly, so paths look like `base_light.intensity` instead of
+// `0.base_light.intensity`.
+impl Reflect for Light {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_reflect(&self) -> &dyn Reflect {
+        self
+    }
+
+    fn as_reflect_mut(&mut self) -> &mut dyn Reflect {
+        self
+    }
+
+    fn fields(&self) -> Vec<(&'static str, &dyn Reflect)> {
+        match self {
+            Self::Directional(v) => v.fields(),
+            Self::Spot(v) => v.fields(),
+            Self::Point(v) => v.fields(),
+        }
+    }
+
+    fn fields_mut(&mut self) -> Vec<(&'static str, &mut dyn Reflect)> {
+        match self {
+            Self::Directional(v) => v.fields_mut(),
+            Self::Spot(v) => v.fields_mut(),
+            Self::Point(v) => v.fields_mut(),
+        }
+    }
+
+    fn variant_name(&self) -> Option<&'static str> {
+        Some(match self {
+            Self::Directional(_) => "Directional",
+            Self::Spot(_) => "Spot",
+            Self::Point(_) => "Point",
+        })
+    }
+
+    fn variant_names(&self) -> &'static [&'static str] {
+        &["Directional", "Spot", "Point"]
+    }
+}
+
 /// Light scene node. It contains common properties of light such as color,
 /// scattering factor (per color channel) and other useful properties. Exact
 /// behavior defined by specific light kind.
-#[derive(Debug)]
+#[derive(Debug, Reflect)]
 pub struct BaseLight {
     base: Base,
     color: Color,
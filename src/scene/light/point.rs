@@ -20,6 +20,7 @@
 use crate::{
     core::{
         pool::Handle,
+        reflect::Reflect,
         visitor::{Visit, VisitResult, Visitor},
     },
     scene::{
@@ -31,7 +32,7 @@ use crate::{
 use std::ops::{Deref, DerefMut};
 
 /// See module docs.
-#[derive(Debug)]
+#[derive(Debug, Reflect)]
 pub struct PointLight {
     base_light: BaseLight,
     shadow_bias: f32,
@@ -25,6 +25,7 @@
 use crate::{
     core::{
         pool::Handle,
+        reflect::Reflect,
         visitor::{Visit, VisitResult, Visitor},
     },
     resource::texture::Texture,
@@ -37,13 +38,14 @@ use crate::{
 use std::ops::{Deref, DerefMut};
 
 /// See module docs.
-#[derive(Debug)]
+#[derive(Debug, Reflect)]
 pub struct SpotLight {
     base_light: BaseLight,
     hotspot_cone_angle: f32,
     falloff_angle_delta: f32,
     shadow_bias: f32,
     distance: f32,
+    #[reflect(skip)]
     cookie_texture: Option<Texture>,
 }
 
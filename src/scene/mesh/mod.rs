@@ -7,12 +7,22 @@
 //! Usually there is no need to manually create meshes, it is much easier to make one in 3d
 //! modelling software or just download some model you like and load it in engine. But since
 //! 3d model can contain multiple nodes, 3d model loading discussed in model resource section.
+//!
+//! # Morph targets
+//!
+//! Mesh holds a weight for every morph target of its surfaces (see [`surface::MorphTarget`]), a
+//! weight with some index is applied to morph targets with the same index of every surface. Weights
+//! could be changed manually or by animation, see
+//! [`Track::add_weight_key_frame`](crate::animation::Track::add_weight_key_frame). Deformed copies
+//! of surface data are updated on CPU only when weights are changed and are used for rendering
+//! instead of the original data. Bounding box of a mesh does not include deformations.
 
 use crate::{
     core::{
         algebra::{Matrix4, Point3, Vector3},
         math::{aabb::AxisAlignedBoundingBox, frustum::Frustum},
         pool::Handle,
+        reflect::Reflect,
         visitor::{Visit, VisitResult, Visitor},
     },
     scene::{
@@ -20,7 +30,7 @@ use crate::{
         graph::Graph,
         mesh::{
             buffer::{VertexAttributeUsage, VertexReadTrait},
-            surface::Surface,
+            surface::{Surface, SurfaceData},
         },
         node::Node,
     },
@@ -28,6 +38,7 @@ use crate::{
 use std::{
     cell::Cell,
     ops::{Deref, DerefMut},
+    sync::{Arc, RwLock},
 };
 
 pub mod buffer;
@@ -35,7 +46,7 @@ pub mod surface;
 pub mod vertex;
 
 /// Defines a path that should be used to render a mesh.
-#[derive(Copy, Clone, PartialOrd, PartialEq, Eq, Ord, Hash, Debug, Visit)]
+#[derive(Copy, Clone, PartialOrd, PartialEq, Eq, Ord, Hash, Debug, Visit, Reflect)]
 #[repr(u32)]
 pub enum RenderPath {
     /// Deferred rendering has much better performance than Forward, but it does not support transparent
@@ -65,15 +76,24 @@ impl RenderPath {
 }
 
 /// See module docs.
-#[derive(Debug)]
+#[derive(Debug, Reflect)]
 pub struct Mesh {
     base: Base,
+    #[reflect(skip)]
     surfaces: Vec<Surface>,
+    #[reflect(skip)]
     bounding_box: Cell<AxisAlignedBoundingBox>,
+    #[reflect(skip)]
     bounding_box_dirty: Cell<bool>,
     cast_shadows: bool,
     render_path: RenderPath,
     decal_layer_index: u8,
+    #[reflect(skip)]
+    morph_weights: Vec<f32>,
+    #[reflect(skip)]
+    morphed_data: Vec<Option<Arc<RwLock<SurfaceData>>>>,
+    #[reflect(skip)]
+    morphed_data_dirty: bool,
 }
 
 impl Default for Mesh {
@@ -86,6 +106,9 @@ impl Default for Mesh {
             cast_shadows: true,
             render_path: RenderPath::Deferred,
             decal_layer_index: 0,
+            morph_weights: Default::default(),
+            morphed_data: Default::default(),
+            morphed_data_dirty: true,
         }
     }
 }
@@ -111,6 +134,7 @@ impl Visit for Mesh {
         self.base.visit("Common", visitor)?;
         self.cast_shadows.visit("CastShadows", visitor)?;
         let _ = self.decal_layer_index.visit("DecalLayerIndex", visitor);
+        let _ = self.morph_weights.visit("MorphWeights", visitor); // Backward compatibility.
 
         let mut render_path = self.render_path as u32;
         render_path.visit("RenderPath", visitor)?;
@@ -136,6 +160,7 @@ impl Mesh {
     /// Returns mutable reference to array of surfaces.
     #[inline]
     pub fn surfaces_mut(&mut self) -> &mut [Surface] {
+        self.morphed_data_dirty = true;
         &mut self.surfaces
     }
 
@@ -144,6 +169,7 @@ impl Mesh {
     pub fn clear_surfaces(&mut self) {
         self.surfaces.clear();
         self.bounding_box_dirty.set(true);
+        self.morphed_data_dirty = true;
     }
 
     /// Adds new surface into mesh, can be used to procedurally generate meshes.
@@ -151,6 +177,90 @@ impl Mesh {
     pub fn add_surface(&mut self, surface: Surface) {
         self.surfaces.push(surface);
         self.bounding_box_dirty.set(true);
+        self.morphed_data_dirty = true;
+    }
+
+    /// Returns weights of morph targets.
+    pub fn morph_weights(&self) -> &[f32] {
+        &self.morph_weights
+    }
+
+    /// Sets new weights of morph targets, there should be one weight per morph target. Missing
+    /// weights are treated as zero.
+    pub fn set_morph_weights(&mut self, weights: &[f32]) {
+        if self.morph_weights != weights {
+            self.morph_weights.clear();
+            self.morph_weights.extend_from_slice(weights);
+            self.morphed_data_dirty = true;
+        }
+    }
+
+    /// Sets new weight of a morph target with given index.
+    pub fn set_morph_weight(&mut self, index: usize, weight: f32) {
+        if self.morph_weights.len() <= index {
+            self.morph_weights.resize(index + 1, 0.0);
+        }
+        if self.morph_weights[index] != weight {
+            self.morph_weights[index] = weight;
+            self.morphed_data_dirty = true;
+        }
+    }
+
+    /// Searches for an index of a morph target with given name in the surfaces of the mesh.
+    pub fn morph_target_index(&self, name: &str) -> Option<usize> {
+        self.surfaces.iter().find_map(|surface| {
+            surface
+                .data()
+                .read()
+                .unwrap()
+                .morph_targets
+                .iter()
+                .position(|target| target.name == name)
+        })
+    }
+
+    /// Returns surface data deformed by morph targets for a surface with given index. Returns
+    /// `None` if the surface has no morph targets or all weights are zero, in this case original
+    /// data of the surface should be used. Deformed data is updated by [`Self::update`].
+    pub fn morphed_data(&self, surface_index: usize) -> Option<Arc<RwLock<SurfaceData>>> {
+        self.morphed_data.get(surface_index).cloned().flatten()
+    }
+
+    /// Updates deformed copies of surface data if weights of morph targets or surfaces were
+    /// changed. There is no need to call it manually, it is called by the graph every frame.
+    pub fn update(&mut self) {
+        if !self.morphed_data_dirty {
+            return;
+        }
+        self.morphed_data_dirty = false;
+
+        let is_morphed = self.morph_weights.iter().any(|w| *w != 0.0);
+        self.morphed_data.resize(self.surfaces.len(), None);
+        for (surface, morphed_data) in self.surfaces.iter().zip(self.morphed_data.iter_mut()) {
+            let data = surface.data();
+            let data = data.read().unwrap();
+            if !is_morphed || data.morph_targets.is_empty() {
+                *morphed_data = None;
+                continue;
+            }
+            match morphed_data {
+                Some(morphed_data)
+                    if morphed_data.read().unwrap().vertex_buffer.vertex_count()
+                        == data.vertex_buffer.vertex_count() =>
+                {
+                    data.apply_morph_targets(
+                        &self.morph_weights,
+                        &mut morphed_data.write().unwrap().vertex_buffer,
+                    )
+                    .unwrap();
+                }
+                _ => {
+                    *morphed_data = Some(Arc::new(RwLock::new(
+                        data.morphed(&self.morph_weights).unwrap(),
+                    )));
+                }
+            }
+        }
     }
 
     /// Returns true if mesh should cast shadows, false - otherwise.
@@ -314,6 +424,10 @@ impl Mesh {
             cast_shadows: self.cast_shadows,
             render_path: self.render_path,
             decal_layer_index: self.decal_layer_index,
+            morph_weights: self.morph_weights.clone(),
+            // Deformed data must not be shared between copies.
+            morphed_data: Default::default(),
+            morphed_data_dirty: true,
         }
     }
 }
@@ -325,6 +439,7 @@ pub struct MeshBuilder {
     cast_shadows: bool,
     render_path: RenderPath,
     decal_layer_index: u8,
+    morph_weights: Vec<f32>,
 }
 
 impl MeshBuilder {
@@ -336,6 +451,7 @@ impl MeshBuilder {
             cast_shadows: true,
             render_path: RenderPath::Deferred,
             decal_layer_index: 0,
+            morph_weights: Default::default(),
         }
     }
 
@@ -364,6 +480,12 @@ impl MeshBuilder {
         self
     }
 
+    /// Sets desired weights of morph targets.
+    pub fn with_morph_weights(mut self, morph_weights: Vec<f32>) -> Self {
+        self.morph_weights = morph_weights;
+        self
+    }
+
     /// Creates new mesh.
     pub fn build_node(self) -> Node {
         Node::Mesh(Mesh {
@@ -374,6 +496,9 @@ impl MeshBuilder {
             bounding_box_dirty: Cell::new(true),
             render_path: self.render_path,
             decal_layer_index: self.decal_layer_index,
+            morph_weights: self.morph_weights,
+            morphed_data: Default::default(),
+            morphed_data_dirty: true,
         })
     }
 
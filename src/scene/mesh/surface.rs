@@ -3,11 +3,22 @@
 //!
 //! Surfaces can use the same data source across many instances, this is a memory optimization for
 //! being able to re-use data when you need to draw the same mesh in many places.
+//!
+//! # Morph targets
+//!
+//! Surface data can have a set of [morph targets](MorphTarget) (also known as blend shapes), each
+//! target holds offsets of vertices which are added to the vertex buffer with some weight. Weights
+//! are stored per mesh instance (see [`Mesh::set_morph_weights`](super::Mesh::set_morph_weights)),
+//! so the same data could be deformed differently by each instance. Morphing is done before
+//! skinning, so morph targets can be used together with bones, for example for facial animation.
 
 use crate::{
     core::{
         algebra::{Matrix4, Point3, Vector2, Vector3, Vector4},
-        math::TriangleDefinition,
+        math::{
+            decimation::{decimate, DecimationError},
+            TriangleDefinition,
+        },
         pool::{ErasedHandle, Handle},
         visitor::{Visit, VisitResult, Visitor},
     },
@@ -22,7 +33,10 @@ use crate::{
         },
         node::Node,
     },
-    utils::raw_mesh::{RawMesh, RawMeshBuilder},
+    utils::{
+        log::{Log, MessageKind},
+        raw_mesh::{RawMesh, RawMeshBuilder},
+    },
 };
 use std::sync::Mutex;
 use std::{
@@ -31,6 +45,27 @@ use std::{
     sync::{Arc, RwLock},
 };
 
+/// An error that may occur during decimation of surface data.
+#[derive(Debug)]
+pub enum SurfaceDecimationError {
+    /// Unable to read positions of vertices.
+    VertexFetch(VertexFetchError),
+    /// Triangles of the data are invalid.
+    Decimation(DecimationError),
+}
+
+impl From<VertexFetchError> for SurfaceDecimationError {
+    fn from(e: VertexFetchError) -> Self {
+        Self::VertexFetch(e)
+    }
+}
+
+impl From<DecimationError> for SurfaceDecimationError {
+    fn from(e: DecimationError) -> Self {
+        Self::Decimation(e)
+    }
+}
+
 /// Data source of a surface. Each surface can share same data source, this is used
 /// in instancing technique to render multiple instances of same model at different
 /// places.
@@ -40,6 +75,9 @@ pub struct SurfaceData {
     pub vertex_buffer: VertexBuffer,
     /// Current geometry buffer.
     pub geometry_buffer: GeometryBuffer,
+    /// A set of morph targets of the data. Every target must either have exactly one offset
+    /// per vertex, or no offsets at all.
+    pub morph_targets: Vec<MorphTarget>,
     // If true - indicates that surface was generated and does not have reference
     // resource. Procedural data will be serialized.
     is_procedural: bool,
@@ -50,6 +88,7 @@ impl Default for SurfaceData {
         Self {
             vertex_buffer: Default::default(),
             geometry_buffer: Default::default(),
+            morph_targets: Default::default(),
             is_procedural: false,
         }
     }
@@ -65,6 +104,7 @@ impl SurfaceData {
         Self {
             vertex_buffer,
             geometry_buffer: triangles,
+            morph_targets: Default::default(),
             is_procedural,
         }
     }
@@ -108,10 +148,127 @@ impl SurfaceData {
         Self {
             vertex_buffer: VertexBuffer::new(raw.vertices.len(), layout, raw.vertices).unwrap(),
             geometry_buffer: GeometryBuffer::new(raw.triangles),
+            morph_targets: Default::default(),
             is_procedural,
         }
     }
 
+    /// Deforms vertices of the data by morph targets with given weights (one weight per target,
+    /// missing weights are treated as zero) and writes the result into given vertex buffer. The
+    /// buffer must be a copy of the vertex buffer of the data, only positions, normals and
+    /// tangents are overwritten. Normals and tangents are re-normalized after deformation.
+    /// Targets with wrong amount of offsets are skipped, the buffer is left untouched if its
+    /// amount of vertices differs from the data. Both cases are reported to the log.
+    ///
+    /// This is CPU implementation of morphing, its results could be used as a reference.
+    pub fn apply_morph_targets(
+        &self,
+        weights: &[f32],
+        dest: &mut VertexBuffer,
+    ) -> Result<(), VertexFetchError> {
+        let vertex_count = self.vertex_buffer.vertex_count() as usize;
+        if dest.vertex_count() as usize != vertex_count {
+            Log::writeln(
+                MessageKind::Error,
+                format!(
+                    "Unable to apply morph targets: buffer has {} vertices, {} expected.",
+                    dest.vertex_count(),
+                    vertex_count
+                ),
+            );
+            return Ok(());
+        }
+
+        let mut position_offsets = vec![Vector3::default(); vertex_count];
+        let mut normal_offsets = vec![Vector3::default(); vertex_count];
+        let mut tangent_offsets = vec![Vector3::default(); vertex_count];
+        let mut has_normal_offsets = false;
+        let mut has_tangent_offsets = false;
+        for (target, &weight) in self.morph_targets.iter().zip(weights.iter()) {
+            if weight == 0.0 {
+                continue;
+            }
+            if !target.matches_vertex_count(vertex_count) {
+                Log::writeln(
+                    MessageKind::Warning,
+                    format!(
+                        "Morph target {} is skipped: its offsets do not match {} vertices.",
+                        target.name, vertex_count
+                    ),
+                );
+                continue;
+            }
+            for (offset, delta) in position_offsets.iter_mut().zip(&target.position_deltas) {
+                *offset += delta.scale(weight);
+            }
+            for (offset, delta) in normal_offsets.iter_mut().zip(&target.normal_deltas) {
+                *offset += delta.scale(weight);
+                has_normal_offsets = true;
+            }
+            for (offset, delta) in tangent_offsets.iter_mut().zip(&target.tangent_deltas) {
+                *offset += delta.scale(weight);
+                has_tangent_offsets = true;
+            }
+        }
+
+        let has_normals = has_normal_offsets
+            && self
+                .vertex_buffer
+                .has_attribute(VertexAttributeUsage::Normal);
+        let has_tangents = has_tangent_offsets
+            && self
+                .vertex_buffer
+                .has_attribute(VertexAttributeUsage::Tangent);
+
+        let mut dest_mut = dest.modify();
+        for (i, (source, mut view)) in self
+            .vertex_buffer
+            .iter()
+            .zip(dest_mut.iter_mut())
+            .enumerate()
+        {
+            let position = source.read_3_f32(VertexAttributeUsage::Position)?;
+            view.write_3_f32(
+                VertexAttributeUsage::Position,
+                position + position_offsets[i],
+            )?;
+            if has_normals {
+                let normal = source.read_3_f32(VertexAttributeUsage::Normal)?;
+                view.write_3_f32(
+                    VertexAttributeUsage::Normal,
+                    (normal + normal_offsets[i])
+                        .try_normalize(f32::EPSILON)
+                        .unwrap_or(normal),
+                )?;
+            }
+            if has_tangents {
+                let tangent = source.read_4_f32(VertexAttributeUsage::Tangent)?;
+                let new_tangent = (tangent.xyz() + tangent_offsets[i])
+                    .try_normalize(f32::EPSILON)
+                    .unwrap_or_else(|| tangent.xyz());
+                // Keep sign (W).
+                view.write_4_f32(
+                    VertexAttributeUsage::Tangent,
+                    Vector4::new(new_tangent.x, new_tangent.y, new_tangent.z, tangent.w),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates a copy of the data deformed by morph targets with given weights, see
+    /// [`Self::apply_morph_targets`] for more info. The copy has no morph targets.
+    pub fn morphed(&self, weights: &[f32]) -> Result<Self, VertexFetchError> {
+        let mut vertex_buffer = self.vertex_buffer.clone();
+        self.apply_morph_targets(weights, &mut vertex_buffer)?;
+        Ok(Self::new(
+            vertex_buffer,
+            self.geometry_buffer.clone(),
+            self.is_procedural,
+        ))
+    }
+
     /// Calculates tangents of surface. Tangents are needed for correct lighting, you will
     /// get incorrect lighting if tangents of your surface are invalid! When engine loads
     /// a mesh from "untrusted" source, it automatically calculates tangents for you, so
@@ -781,6 +938,104 @@ impl SurfaceData {
         data
     }
 
+    /// Creates decimated copy of the data with `target_triangle_count` triangles (or more, if the
+    /// next edge collapse would exceed `max_error`), it could be used to generate levels of detail
+    /// for [`LodGroup`](crate::scene::base::LodGroup). See [`decimate`] for more info. Unused
+    /// vertices are removed, remaining vertices keep every attribute and offsets of morph targets
+    /// as is. Offsets of targets with wrong amount of offsets are dropped.
+    pub fn decimated(
+        &self,
+        target_triangle_count: usize,
+        max_error: f32,
+    ) -> Result<Self, SurfaceDecimationError> {
+        let positions = self
+            .vertex_buffer
+            .iter()
+            .map(|view| view.read_3_f32(VertexAttributeUsage::Position))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut triangles = decimate(
+            &positions,
+            self.geometry_buffer.triangles_ref(),
+            target_triangle_count,
+            max_error,
+        )?;
+
+        let vertex_size = self.vertex_buffer.vertex_size() as usize;
+        let raw_data = self.vertex_buffer.raw_data();
+        let mut remap = vec![None; positions.len()];
+        let mut data = Vec::new();
+        // Old indices of kept vertices in new order.
+        let mut kept = Vec::new();
+        for triangle in triangles.iter_mut() {
+            for index in triangle.0.iter_mut() {
+                let old_index = *index as usize;
+                *index = *remap[old_index].get_or_insert_with(|| {
+                    data.extend_from_slice(
+                        &raw_data[old_index * vertex_size..(old_index + 1) * vertex_size],
+                    );
+                    kept.push(old_index);
+                    kept.len() as u32 - 1
+                });
+            }
+        }
+
+        // Targets are never removed, because weights of mesh instances refer to them by index.
+        let morph_targets = self
+            .morph_targets
+            .iter()
+            .map(|target| {
+                if !target.matches_vertex_count(positions.len()) {
+                    Log::writeln(
+                        MessageKind::Warning,
+                        format!(
+                            "Morph target {} is emptied: its offsets do not match {} vertices.",
+                            target.name,
+                            positions.len()
+                        ),
+                    );
+                    return MorphTarget {
+                        name: target.name.clone(),
+                        ..Default::default()
+                    };
+                }
+                let remap_deltas = |deltas: &[Vector3<f32>]| {
+                    if deltas.is_empty() {
+                        Vec::new()
+                    } else {
+                        kept.iter().map(|&i| deltas[i]).collect()
+                    }
+                };
+                MorphTarget {
+                    name: target.name.clone(),
+                    position_deltas: remap_deltas(&target.position_deltas),
+                    normal_deltas: remap_deltas(&target.normal_deltas),
+                    tangent_deltas: remap_deltas(&target.tangent_deltas),
+                }
+            })
+            .collect();
+
+        let layout = self
+            .vertex_buffer
+            .layout()
+            .iter()
+            .map(|attribute| VertexAttributeDescriptor {
+                usage: attribute.usage,
+                data_type: attribute.data_type,
+                size: attribute.size,
+                divisor: attribute.divisor,
+                shader_location: attribute.shader_location,
+            })
+            .collect::<Vec<_>>();
+
+        let mut decimated = Self::new(
+            VertexBuffer::new(kept.len(), &layout, data).unwrap(),
+            GeometryBuffer::new(triangles),
+            true,
+        );
+        decimated.morph_targets = morph_targets;
+        Ok(decimated)
+    }
+
     /// Calculates hash based on contents of surface shared data.
     pub fn content_hash(&self) -> u64 {
         let mut hasher = DefaultHasher::new();
@@ -821,12 +1076,54 @@ impl Visit for SurfaceData {
                 triangles.visit("Triangles", visitor)?;
                 self.geometry_buffer = GeometryBuffer::new(triangles);
             }
+            let _ = self.morph_targets.visit("MorphTargets", visitor); // Backward compatibility.
         }
 
         visitor.leave_region()
     }
 }
 
+/// Morph target (blend shape) is a named set of per-vertex offsets of position, normal and tangent.
+/// Offsets are scaled by a weight of the target and added to vertices of surface data. Any set of
+/// offsets could be empty, which means that the target does not affect respective attribute.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MorphTarget {
+    /// Name of the target, it could be used to find a weight of the target by name.
+    pub name: String,
+    /// Offsets of vertex positions.
+    pub position_deltas: Vec<Vector3<f32>>,
+    /// Offsets of vertex normals.
+    pub normal_deltas: Vec<Vector3<f32>>,
+    /// Offsets of vertex tangents (W component of a tangent is not affected).
+    pub tangent_deltas: Vec<Vector3<f32>>,
+}
+
+impl MorphTarget {
+    /// Returns true if every non-empty set of offsets has exactly one offset per vertex.
+    pub fn matches_vertex_count(&self, vertex_count: usize) -> bool {
+        [
+            &self.position_deltas,
+            &self.normal_deltas,
+            &self.tangent_deltas,
+        ]
+        .iter()
+        .all(|deltas| deltas.is_empty() || deltas.len() == vertex_count)
+    }
+}
+
+impl Visit for MorphTarget {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.name.visit("Name", visitor)?;
+        self.position_deltas.visit("PositionDeltas", visitor)?;
+        self.normal_deltas.visit("NormalDeltas", visitor)?;
+        self.tangent_deltas.visit("TangentDeltas", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
 /// Vertex weight is a pair of (bone; weight) that affects vertex.
 #[derive(Copy, Clone, Debug)]
 pub struct VertexWeight {
@@ -1035,3 +1332,175 @@ impl SurfaceBuilder {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::{Matrix4, Vector3},
+        scene::{
+            base::BaseBuilder,
+            graph::Graph,
+            mesh::{
+                buffer::{VertexAttributeUsage, VertexBuffer, VertexReadTrait},
+                surface::{MorphTarget, Surface, SurfaceData},
+                MeshBuilder,
+            },
+        },
+    };
+    use std::sync::{Arc, RwLock};
+
+    fn read_vec3(vertex_buffer: &VertexBuffer, usage: VertexAttributeUsage) -> Vec<Vector3<f32>> {
+        vertex_buffer
+            .iter()
+            .map(|v| v.read_3_f32(usage).unwrap())
+            .collect()
+    }
+
+    fn make_morphed_cube() -> SurfaceData {
+        let mut data = SurfaceData::make_cube(Matrix4::identity());
+        let vertex_count = data.vertex_buffer.vertex_count() as usize;
+        data.morph_targets = vec![
+            MorphTarget {
+                name: "Up".to_owned(),
+                position_deltas: vec![Vector3::new(0.0, 2.0, 0.0); vertex_count],
+                ..Default::default()
+            },
+            MorphTarget {
+                name: "Flip".to_owned(),
+                position_deltas: vec![Vector3::new(1.0, 0.0, 0.0); vertex_count],
+                normal_deltas: read_vec3(&data.vertex_buffer, VertexAttributeUsage::Normal)
+                    .into_iter()
+                    .map(|n| n.scale(-2.0))
+                    .collect(),
+                ..Default::default()
+            },
+        ];
+        data
+    }
+
+    #[test]
+    fn test_apply_morph_targets() {
+        let data = make_morphed_cube();
+        let positions = read_vec3(&data.vertex_buffer, VertexAttributeUsage::Position);
+        let normals = read_vec3(&data.vertex_buffer, VertexAttributeUsage::Normal);
+
+        let morphed = data.morphed(&[0.5]).unwrap();
+        assert!(morphed.morph_targets.is_empty());
+        for (p, original) in read_vec3(&morphed.vertex_buffer, VertexAttributeUsage::Position)
+            .iter()
+            .zip(positions.iter())
+        {
+            assert_eq!(*p, original + Vector3::new(0.0, 1.0, 0.0));
+        }
+        // First target does not affect normals.
+        assert_eq!(
+            read_vec3(&morphed.vertex_buffer, VertexAttributeUsage::Normal),
+            normals
+        );
+
+        let morphed = data.morphed(&[1.0, 1.0]).unwrap();
+        for (p, original) in read_vec3(&morphed.vertex_buffer, VertexAttributeUsage::Position)
+            .iter()
+            .zip(positions.iter())
+        {
+            assert_eq!(*p, original + Vector3::new(1.0, 2.0, 0.0));
+        }
+        // Normals are re-normalized after deformation.
+        for (n, original) in read_vec3(&morphed.vertex_buffer, VertexAttributeUsage::Normal)
+            .iter()
+            .zip(normals.iter())
+        {
+            assert_eq!(*n, -original);
+        }
+
+        // Source data must stay untouched.
+        assert_eq!(
+            read_vec3(&data.vertex_buffer, VertexAttributeUsage::Position),
+            positions
+        );
+    }
+
+    #[test]
+    fn test_apply_invalid_morph_targets() {
+        let mut data = make_morphed_cube();
+        let positions = read_vec3(&data.vertex_buffer, VertexAttributeUsage::Position);
+        data.morph_targets[1].position_deltas.pop();
+        assert!(!data.morph_targets[1].matches_vertex_count(positions.len()));
+
+        // Target with missing offsets is skipped.
+        let morphed = data.morphed(&[1.0, 1.0]).unwrap();
+        for (p, original) in read_vec3(&morphed.vertex_buffer, VertexAttributeUsage::Position)
+            .iter()
+            .zip(positions.iter())
+        {
+            assert_eq!(*p, original + Vector3::new(0.0, 2.0, 0.0));
+        }
+
+        // Buffer of wrong size is left untouched.
+        let mut vertex_buffer = SurfaceData::make_quad(&Matrix4::identity()).vertex_buffer;
+        let quad_positions = read_vec3(&vertex_buffer, VertexAttributeUsage::Position);
+        data.apply_morph_targets(&[1.0], &mut vertex_buffer).unwrap();
+        assert_eq!(
+            read_vec3(&vertex_buffer, VertexAttributeUsage::Position),
+            quad_positions
+        );
+    }
+
+    #[test]
+    fn test_decimated_morph_targets() {
+        let mut data = make_morphed_cube();
+        // Offsets that differ per vertex, so wrong remapping would be noticed.
+        data.morph_targets[0].position_deltas =
+            read_vec3(&data.vertex_buffer, VertexAttributeUsage::Position);
+
+        let decimated = data.decimated(6, f32::MAX).unwrap();
+        assert_eq!(decimated.morph_targets.len(), 2);
+        assert_eq!(decimated.morph_targets[1].name, "Flip");
+        let positions = read_vec3(&decimated.vertex_buffer, VertexAttributeUsage::Position);
+        let normals = read_vec3(&decimated.vertex_buffer, VertexAttributeUsage::Normal);
+        assert_eq!(decimated.morph_targets[0].position_deltas, positions);
+        assert!(decimated.morph_targets[0].normal_deltas.is_empty());
+        assert_eq!(
+            decimated.morph_targets[1].normal_deltas,
+            normals.iter().map(|n| n.scale(-2.0)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_mesh_morph_weights() {
+        let mut graph = Graph::new();
+        let data = Arc::new(RwLock::new(make_morphed_cube()));
+        let positions = read_vec3(
+            &data.read().unwrap().vertex_buffer,
+            VertexAttributeUsage::Position,
+        );
+        let handle = MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![Surface::new(data)])
+            .build(&mut graph);
+        let mesh = graph[handle].as_mesh_mut();
+
+        assert_eq!(mesh.morph_target_index("Flip"), Some(1));
+        assert_eq!(mesh.morph_target_index("Frown"), None);
+
+        mesh.update();
+        assert!(mesh.morphed_data(0).is_none());
+
+        mesh.set_morph_weight(0, 0.25);
+        assert_eq!(mesh.morph_weights(), &[0.25]);
+        mesh.update();
+        let morphed = mesh.morphed_data(0).unwrap();
+        for (p, original) in read_vec3(
+            &morphed.read().unwrap().vertex_buffer,
+            VertexAttributeUsage::Position,
+        )
+        .iter()
+        .zip(positions.iter())
+        {
+            assert_eq!(*p, original + Vector3::new(0.0, 0.5, 0.0));
+        }
+
+        mesh.set_morph_weights(&[0.0, 0.0]);
+        mesh.update();
+        assert!(mesh.morphed_data(0).is_none());
+    }
+}
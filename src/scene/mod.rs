@@ -28,6 +28,8 @@ use crate::{
         instant,
         math::{aabb::AxisAlignedBoundingBox, frustum::Frustum, Matrix4Ext},
         pool::{Handle, Pool, PoolIterator, PoolIteratorMut, Ticket},
+        profiler::CounterSource,
+        reflect::Reflect,
         visitor::{Visit, VisitError, VisitResult, Visitor},
     },
     engine::{resource_manager::ResourceManager, PhysicsBinder},
@@ -47,6 +49,7 @@ use crate::{
     utils::{lightmap::Lightmap, log::Log, log::MessageKind, navmesh::Navmesh},
 };
 use std::{
+    any::Any,
     collections::HashMap,
     fmt::{Display, Formatter},
     ops::{Deref, Index, IndexMut, Range},
@@ -979,6 +982,18 @@ impl Display for PerformanceStatistics {
     }
 }
 
+impl CounterSource for PerformanceStatistics {
+    fn for_each_counter(&self, func: &mut dyn FnMut(&'static str, f64)) {
+        self.physics.for_each_counter(func);
+        func("Graph Update Time (ms)", self.graph_update_time as f64 * 1000.0);
+        func(
+            "Animations Update Time (ms)",
+            self.animations_update_time as f64 * 1000.0,
+        );
+        func("Sound Update Time (ms)", self.sound_update_time as f64 * 1000.0);
+    }
+}
+
 impl Scene {
     /// Creates new scene with single root node.
     ///
@@ -1005,7 +1020,8 @@ impl Scene {
         }
     }
 
-    /// Tries to load scene from given file. File can contain any scene in native engine format.
+    /// Tries to load scene from given file. File can contain any scene in native engine format,
+    /// either binary or text one.
     /// Such scenes can be made in rusty editor.
     ///
     /// # Important notes
@@ -1020,7 +1036,7 @@ impl Scene {
     ) -> Result<Self, VisitError> {
         let mut scene = Scene::default();
         {
-            let mut visitor = Visitor::load_binary(path.as_ref()).await?;
+            let mut visitor = Visitor::load_from_file(path.as_ref()).await?;
             scene.visit("Scene", &mut visitor)?;
         }
 
@@ -1413,6 +1429,31 @@ impl Scene {
             old_new_map,
         )
     }
+
+    /// Removes holes left by removed nodes from the graph (see [`Graph::compact`]) and remaps
+    /// node handles stored in animations, physics binder and lightmap. Useful to shrink a scene
+    /// before saving it. Returns mapping from old handles to new ones for every moved node, so
+    /// handles stored elsewhere (in game logic for example) can be remapped too.
+    pub fn compact(&mut self) -> HashMap<Handle<Node>, Handle<Node>> {
+        let mapping = self.graph.compact();
+        self.animations.remap_handles(&mapping);
+        self.physics_binder.remap_handles(&mapping);
+        if let Some(lightmap) = self.lightmap.as_mut() {
+            lightmap.map = lightmap
+                .map
+                .drain()
+                .map(|(node, entries)| (mapping.get(&node).cloned().unwrap_or(node), entries))
+                .collect();
+            for entry in lightmap.map.values_mut().flatten() {
+                for light in entry.lights.iter_mut() {
+                    if let Some(&new_light) = mapping.get(light) {
+                        *light = new_light;
+                    }
+                }
+            }
+        }
+        mapping
+    }
 }
 
 impl Visit for Scene {
@@ -1745,3 +1786,53 @@ where
         visitor.leave_region()
     }
 }
+
+// Transparent: reflects wrapped value and raises `custom` flag on every mutable access.
+impl<T: Reflect> Reflect for TemplateVariable<T> {
+    fn type_name(&self) -> &'static str {
+        self.value.type_name()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self.value.as_any()
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self.custom = true;
+        self.value.as_any_mut()
+    }
+
+    fn as_reflect(&self) -> &dyn Reflect {
+        self
+    }
+
+    fn as_reflect_mut(&mut self) -> &mut dyn Reflect {
+        self
+    }
+
+    fn fields(&self) -> Vec<(&'static str, &dyn Reflect)> {
+        self.value.fields()
+    }
+
+    fn fields_mut(&mut self) -> Vec<(&'static str, &mut dyn Reflect)> {
+        self.custom = true;
+        self.value.fields_mut()
+    }
+
+    fn items(&self) -> Vec<&dyn Reflect> {
+        self.value.items()
+    }
+
+    fn items_mut(&mut self) -> Vec<&mut dyn Reflect> {
+        self.custom = true;
+        self.value.items_mut()
+    }
+
+    fn variant_name(&self) -> Option<&'static str> {
+        self.value.variant_name()
+    }
+
+    fn variant_names(&self) -> &'static [&'static str] {
+        self.value.variant_names()
+    }
+}
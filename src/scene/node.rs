@@ -6,13 +6,22 @@ use crate::scene::decal::Decal;
 use crate::scene::terrain::Terrain;
 use crate::{
     core::define_is_as,
-    core::visitor::{Visit, VisitResult, Visitor},
+    core::{
+        pool::Handle,
+        reflect::Reflect,
+        uuid::Uuid,
+        visitor::{Visit, VisitResult, Visitor},
+    },
     scene::{
-        base::Base, camera::Camera, light::Light, mesh::Mesh, particle_system::ParticleSystem,
-        sprite::Sprite,
+        base::Base, camera::Camera, graph::Graph, light::Light, mesh::Mesh,
+        particle_system::ParticleSystem, sprite::Sprite,
     },
 };
-use std::ops::{Deref, DerefMut};
+use std::{
+    any::Any,
+    hash::{Hash, Hasher},
+    ops::{Deref, DerefMut},
+};
 
 /// Helper macros to reduce code bloat - its purpose it to dispatch
 /// specified call by actual enum variant.
@@ -79,6 +88,59 @@ macro_rules! static_dispatch_deref {
     };
 }
 
+// Exposes fields of actual node directly, so paths look like `base.name` instead of `0.base.name`.
+impl Reflect for Node {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_reflect(&self) -> &dyn Reflect {
+        self
+    }
+
+    fn as_reflect_mut(&mut self) -> &mut dyn Reflect {
+        self
+    }
+
+    fn fields(&self) -> Vec<(&'static str, &dyn Reflect)> {
+        static_dispatch!(self, fields,)
+    }
+
+    fn fields_mut(&mut self) -> Vec<(&'static str, &mut dyn Reflect)> {
+        static_dispatch!(self, fields_mut,)
+    }
+
+    fn variant_name(&self) -> Option<&'static str> {
+        Some(match self {
+            Node::Base(_) => "Base",
+            Node::Light(_) => "Light",
+            Node::Camera(_) => "Camera",
+            Node::Mesh(_) => "Mesh",
+            Node::Sprite(_) => "Sprite",
+            Node::ParticleSystem(_) => "ParticleSystem",
+            Node::Terrain(_) => "Terrain",
+            Node::Decal(_) => "Decal",
+        })
+    }
+
+    fn variant_names(&self) -> &'static [&'static str] {
+        &[
+            "Base",
+            "Light",
+            "Camera",
+            "Mesh",
+            "Sprite",
+            "ParticleSystem",
+            "Terrain",
+            "Decal",
+        ]
+    }
+}
+
 impl Deref for Node {
     type Target = Base;
 
@@ -153,3 +215,132 @@ impl Node {
     define_is_as!(Node : Terrain -> ref Terrain => fn is_terrain, fn as_terrain, fn as_terrain_mut);
     define_is_as!(Node : Decal -> ref Decal => fn is_decal, fn as_decal, fn as_decal_mut);
 }
+
+/// Persistent reference to a node. Unlike [`Handle`], it stays valid across save/load, graph
+/// compaction and re-instantiation of the graph, because it refers to a node by its persistent
+/// identifier (see [`Base::uuid`]). Handle of referenced node is cached, so resolving the
+/// reference is cheap in most cases.
+///
+/// # Serialization
+///
+/// Only identifier is serialized, cached handle is reset on load and will be fetched
+/// on first [`resolve`](Self::resolve).
+///
+/// # Example
+///
+/// ```no_run
+/// use rg3d::scene::{graph::Graph, node::NodeRef, base::BaseBuilder};
+///
+/// let mut graph = Graph::new();
+/// let handle = BaseBuilder::new().build(&mut graph);
+/// let mut node_ref = NodeRef::new(&graph, handle);
+/// assert_eq!(node_ref.resolve(&graph), handle);
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NodeRef {
+    uuid: Uuid,
+    handle: Handle<Node>,
+}
+
+impl NodeRef {
+    /// Reference that does not point to any node.
+    pub const NONE: NodeRef = NodeRef {
+        uuid: Uuid::nil(),
+        handle: Handle::NONE,
+    };
+
+    /// Creates new reference to a node with given handle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if handle is invalid.
+    pub fn new(graph: &Graph, handle: Handle<Node>) -> Self {
+        Self {
+            uuid: graph[handle].uuid(),
+            handle,
+        }
+    }
+
+    /// Creates new reference to a node with given persistent identifier, the node does not need
+    /// to exist at the moment of creation.
+    pub fn from_uuid(uuid: Uuid) -> Self {
+        Self {
+            uuid,
+            handle: Handle::NONE,
+        }
+    }
+
+    /// Returns persistent identifier of referenced node.
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    /// Returns true if the reference does not point to any node.
+    pub fn is_none(&self) -> bool {
+        self.uuid.is_nil()
+    }
+
+    /// Returns true if the reference points to some node (which may not exist).
+    pub fn is_some(&self) -> bool {
+        !self.is_none()
+    }
+
+    /// Returns handle of referenced node in given graph without updating cached handle. Returns
+    /// [`Handle::NONE`] if there is no such node in the graph.
+    pub fn handle(&self, graph: &Graph) -> Handle<Node> {
+        if self.is_none() {
+            return Handle::NONE;
+        }
+
+        match graph.try_get(self.handle) {
+            Some(node) if node.uuid() == self.uuid => self.handle,
+            _ => graph.find_by_uuid(self.uuid),
+        }
+    }
+
+    /// Returns handle of referenced node in given graph and caches it, so next call will be
+    /// cheap. Returns [`Handle::NONE`] if there is no such node in the graph.
+    pub fn resolve(&mut self, graph: &Graph) -> Handle<Node> {
+        self.handle = self.handle(graph);
+        self.handle
+    }
+
+    /// Tries to borrow referenced node from given graph.
+    pub fn try_get<'a>(&self, graph: &'a Graph) -> Option<&'a Node> {
+        graph.try_get(self.handle(graph))
+    }
+
+    /// Tries to borrow referenced node from given graph as mutable.
+    pub fn try_get_mut<'a>(&self, graph: &'a mut Graph) -> Option<&'a mut Node> {
+        let handle = self.handle(graph);
+        graph.try_get_mut(handle)
+    }
+}
+
+impl PartialEq for NodeRef {
+    fn eq(&self, other: &Self) -> bool {
+        self.uuid == other.uuid
+    }
+}
+
+impl Eq for NodeRef {}
+
+impl Hash for NodeRef {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.uuid.hash(state)
+    }
+}
+
+impl Visit for NodeRef {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.uuid.visit("Uuid", visitor)?;
+
+        if visitor.is_reading() {
+            self.handle = Handle::NONE;
+        }
+
+        visitor.leave_region()
+    }
+}
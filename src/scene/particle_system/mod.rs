@@ -78,6 +78,7 @@ use crate::{
         color_gradient::ColorGradient,
         math::TriangleDefinition,
         pool::Handle,
+        reflect::Reflect,
         visitor::prelude::*,
     },
     resource::texture::Texture,
@@ -135,16 +136,21 @@ impl Visit for ParticleLimit {
 }
 
 /// See module docs.
-#[derive(Debug, Visit)]
+#[derive(Debug, Visit, Reflect)]
 pub struct ParticleSystem {
     base: Base,
+    #[reflect(skip)]
     particles: Vec<Particle>,
+    #[reflect(skip)]
     free_particles: Vec<u32>,
     /// List of emitters of the particle system.
+    #[reflect(skip)]
     pub emitters: Vec<Emitter>,
+    #[reflect(skip)]
     texture: Option<Texture>,
     acceleration: Vector3<f32>,
     #[visit(rename = "ColorGradient")]
+    #[reflect(skip)]
     color_over_lifetime: Option<ColorGradient>,
     #[visit(optional)] // Backward compatibility.
     soft_boundary_sharpness_factor: f32,
@@ -8,6 +8,7 @@ use crate::{
         instant,
         math::{aabb::AxisAlignedBoundingBox, ray::Ray},
         pool::Handle,
+        profiler::CounterSource,
         visitor::prelude::*,
         BiDirHashMap,
     },
@@ -252,6 +253,19 @@ impl Display for PhysicsPerformanceStatistics {
     }
 }
 
+impl CounterSource for PhysicsPerformanceStatistics {
+    fn for_each_counter(&self, func: &mut dyn FnMut(&'static str, f64)) {
+        func(
+            "physics3d.Step Time (ms)",
+            self.step_time.as_secs_f64() * 1000.0,
+        );
+        func(
+            "physics3d.Ray Cast Time (ms)",
+            self.total_ray_cast_time.get().as_secs_f64() * 1000.0,
+        );
+    }
+}
+
 impl PhysicsPerformanceStatistics {
     pub(in crate) fn reset(&mut self) {
         *self = Default::default();
@@ -14,6 +14,7 @@ use crate::scene::node::Node;
 use crate::{
     core::{
         color::Color,
+        reflect::Reflect,
         visitor::{Visit, VisitResult, Visitor},
     },
     resource::texture::Texture,
@@ -22,9 +23,10 @@ use crate::{
 use std::ops::{Deref, DerefMut};
 
 /// See module docs.
-#[derive(Debug)]
+#[derive(Debug, Reflect)]
 pub struct Sprite {
     base: Base,
+    #[reflect(skip)]
     texture: Option<Texture>,
     color: Color,
     size: f32,
@@ -9,6 +9,7 @@ use crate::{
             aabb::AxisAlignedBoundingBox, ray::Ray, ray_rect_intersection, Rect, TriangleDefinition,
         },
         pool::Handle,
+        reflect::Reflect,
         visitor::{prelude::*, PodVecView},
     },
     resource::texture::{Texture, TextureKind, TexturePixelKind, TextureWrapMode},
@@ -265,17 +266,27 @@ pub struct TerrainRayCastResult {
 }
 
 /// See module docs.
-#[derive(Visit, Debug, Default)]
+#[derive(Visit, Reflect, Debug, Default)]
 pub struct Terrain {
+    // Geometry parameters can't be changed after terrain is built.
+    #[reflect(skip)]
     width: f32,
+    #[reflect(skip)]
     length: f32,
+    #[reflect(skip)]
     mask_resolution: f32,
+    #[reflect(skip)]
     height_map_resolution: f32,
     base: Base,
+    #[reflect(skip)]
     chunks: Vec<Chunk>,
+    #[reflect(skip)]
     width_chunks: u32,
+    #[reflect(skip)]
     length_chunks: u32,
+    #[reflect(skip)]
     bounding_box_dirty: Cell<bool>,
+    #[reflect(skip)]
     bounding_box: Cell<AxisAlignedBoundingBox>,
     #[visit(optional)] // Backward compatibility
     decal_layer_index: u8,
@@ -49,12 +49,13 @@
 use crate::{
     core::{
         algebra::{Matrix3, Matrix4, UnitQuaternion, Vector3},
+        reflect::Reflect,
         visitor::{Visit, VisitResult, Visitor},
     },
     scene::TemplateVariable,
     utils::log::{Log, MessageKind},
 };
-use std::cell::Cell;
+use std::{any::Any, cell::Cell};
 
 /// See module docs.
 #[derive(Clone, Debug)]
@@ -120,6 +121,55 @@ impl Default for Transform {
     }
 }
 
+// Implemented manually to mark transform as dirty on every mutable access. Post-rotation is
+// read-only, because its matrix must be rebuilt on change - use `set_post_rotation` instead.
+impl Reflect for Transform {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self.dirty.set(true);
+        self
+    }
+
+    fn as_reflect(&self) -> &dyn Reflect {
+        self
+    }
+
+    fn as_reflect_mut(&mut self) -> &mut dyn Reflect {
+        self
+    }
+
+    fn fields(&self) -> Vec<(&'static str, &dyn Reflect)> {
+        vec![
+            ("local_scale", &self.local_scale),
+            ("local_position", &self.local_position),
+            ("local_rotation", &self.local_rotation),
+            ("pre_rotation", &self.pre_rotation),
+            ("post_rotation", &self.post_rotation),
+            ("rotation_offset", &self.rotation_offset),
+            ("rotation_pivot", &self.rotation_pivot),
+            ("scaling_offset", &self.scaling_offset),
+            ("scaling_pivot", &self.scaling_pivot),
+        ]
+    }
+
+    fn fields_mut(&mut self) -> Vec<(&'static str, &mut dyn Reflect)> {
+        self.dirty.set(true);
+        vec![
+            ("local_scale", &mut self.local_scale),
+            ("local_position", &mut self.local_position),
+            ("local_rotation", &mut self.local_rotation),
+            ("pre_rotation", &mut self.pre_rotation),
+            ("rotation_offset", &mut self.rotation_offset),
+            ("rotation_pivot", &mut self.rotation_pivot),
+            ("scaling_offset", &mut self.scaling_offset),
+            ("scaling_pivot", &mut self.scaling_pivot),
+        ]
+    }
+}
+
 fn build_post_rotation_matrix(post_rotation: UnitQuaternion<f32>) -> Matrix3<f32> {
     post_rotation
         .to_rotation_matrix()
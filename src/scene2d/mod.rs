@@ -4,6 +4,7 @@ use crate::{
         color::Color,
         instant,
         pool::{Handle, Pool},
+        profiler::CounterSource,
         visitor::prelude::*,
     },
     engine::PhysicsBinder,
@@ -40,6 +41,21 @@ pub struct PerformanceStatistics {
     pub sound_update_time: f32,
 }
 
+impl CounterSource for PerformanceStatistics {
+    fn for_each_counter(&self, func: &mut dyn FnMut(&'static str, f64)) {
+        self.physics.for_each_counter(func);
+        // Prefixed to not be summed with counters of 3D scenes.
+        func(
+            "scene2d.Graph Update Time (ms)",
+            self.graph_update_time as f64 * 1000.0,
+        );
+        func(
+            "scene2d.Sound Update Time (ms)",
+            self.sound_update_time as f64 * 1000.0,
+        );
+    }
+}
+
 #[derive(Visit, Default)]
 pub struct Scene2d {
     pub graph: Graph,
@@ -11,6 +11,7 @@ use crate::{
         instant,
         math::ray::Ray,
         pool::ErasedHandle,
+        profiler::CounterSource,
         uuid::Uuid,
         visitor::prelude::*,
         BiDirHashMap,
@@ -207,6 +208,19 @@ impl Display for PhysicsPerformanceStatistics {
     }
 }
 
+impl CounterSource for PhysicsPerformanceStatistics {
+    fn for_each_counter(&self, func: &mut dyn FnMut(&'static str, f64)) {
+        func(
+            "physics2d.Step Time (ms)",
+            self.step_time.as_secs_f64() * 1000.0,
+        );
+        func(
+            "physics2d.Ray Cast Time (ms)",
+            self.total_ray_cast_time.get().as_secs_f64() * 1000.0,
+        );
+    }
+}
+
 impl PhysicsPerformanceStatistics {
     pub(in crate) fn reset(&mut self) {
         *self = Default::default();